@@ -94,6 +94,12 @@ impl BM25Index {
         })
     }
 
+    /// Number of chunks currently in the index
+    pub fn num_docs(&self) -> Result<u64> {
+        self.reader.reload().context("Failed to reload index reader")?;
+        Ok(self.reader.searcher().num_docs())
+    }
+
     /// Index a batch of chunks
     pub fn index_chunks(&self, chunks: &[ChunkInput]) -> Result<()> {
         let mut writer = self.writer.lock().unwrap();
@@ -116,6 +122,18 @@ impl BM25Index {
         Ok(())
     }
 
+    /// Commit any pending writes and reload the reader
+    ///
+    /// Acts as a durability barrier: once this returns, every chunk passed to
+    /// `index_chunks` is on disk and visible to searchers.
+    pub fn commit(&self) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.commit().context("Failed to commit tantivy index")?;
+        drop(writer); // Release lock before reload
+        self.reader.reload().context("Failed to reload index reader")?;
+        Ok(())
+    }
+
     /// Search for chunks matching the query
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<BM25Result>> {
         let searcher = self.reader.searcher();
@@ -209,6 +227,64 @@ impl BM25Index {
         Ok(results)
     }
 
+    /// IDs among `chunk_ids` that are in the committed index
+    pub fn indexed_chunk_ids(&self, chunk_ids: &[String]) -> Result<Vec<String>> {
+        let searcher = self.reader.searcher();
+        let mut indexed = Vec::new();
+        for id in chunk_ids {
+            if self.has_chunk(&searcher, id)? {
+                indexed.push(id.clone());
+            }
+        }
+        Ok(indexed)
+    }
+
+    fn has_chunk(&self, searcher: &tantivy::Searcher, chunk_id: &str) -> Result<bool> {
+        let count = searcher
+            .search(
+                &tantivy::query::TermQuery::new(
+                    tantivy::Term::from_field_text(self.chunk_id_field, chunk_id),
+                    tantivy::schema::IndexRecordOption::Basic,
+                ),
+                &tantivy::collector::Count,
+            )
+            .context("Tantivy lookup failed")?;
+        Ok(count > 0)
+    }
+
+    /// Re-index chunks that are already in the index (e.g. after a source change)
+    ///
+    /// Each existing chunk is replaced by the given input. Chunks not present in
+    /// the index are skipped so deduplicated chunks stay out of BM25.
+    /// Returns the number of chunks re-indexed.
+    pub fn reindex_existing(&self, chunks: &[ChunkInput]) -> Result<usize> {
+        let searcher = self.reader.searcher();
+        let mut writer = self.writer.lock().unwrap();
+        let mut reindexed = 0;
+
+        for chunk in chunks {
+            if !self.has_chunk(&searcher, &chunk.id)? {
+                continue;
+            }
+
+            writer.delete_term(tantivy::Term::from_field_text(self.chunk_id_field, &chunk.id));
+            let mut doc = TantivyDocument::default();
+            doc.add_text(self.chunk_id_field, &chunk.id);
+            doc.add_text(self.source_id_field, &chunk.source_id);
+            doc.add_text(self.content_field, &chunk.content);
+            if let Some(ref title) = chunk.title {
+                doc.add_text(self.title_field, title);
+            }
+            writer.add_document(doc)?;
+            reindexed += 1;
+        }
+
+        writer.commit().context("Failed to commit tantivy index")?;
+        drop(writer); // Release lock before reload
+        self.reader.reload().context("Failed to reload index reader")?;
+        Ok(reindexed)
+    }
+
     /// Delete all chunks for a source
     pub fn delete_source(&self, source_id: &str) -> Result<()> {
         let mut writer = self.writer.lock().unwrap();
@@ -231,6 +307,18 @@ impl BM25Index {
         Ok(())
     }
 
+    /// Delete several chunks by ID in a single commit
+    pub fn delete_chunks(&self, chunk_ids: &[String]) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        for chunk_id in chunk_ids {
+            writer.delete_term(tantivy::Term::from_field_text(self.chunk_id_field, chunk_id));
+        }
+        writer.commit().context("Failed to commit deletion")?;
+        drop(writer); // Release lock before reload
+        self.reader.reload().context("Failed to reload index reader")?;
+        Ok(())
+    }
+
     /// Clear all documents from the index
     pub fn reset(&self) -> Result<()> {
         let mut writer = self.writer.lock().unwrap();
@@ -341,4 +429,24 @@ mod tests {
         let results = index.search("test document", 10).unwrap();
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_bm25_indexed_chunk_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = BM25Index::open(temp_dir.path()).unwrap();
+
+        let chunks = vec![ChunkInput {
+            id: "chunk1".to_string(),
+            source_id: "docs".to_string(),
+            content: "Test document one".to_string(),
+            title: None,
+        }];
+        index.index_chunks(&chunks).unwrap();
+
+        let ids = vec!["chunk1".to_string(), "chunk2".to_string()];
+        assert_eq!(index.indexed_chunk_ids(&ids).unwrap(), vec!["chunk1".to_string()]);
+
+        index.delete_chunks(&["chunk1".to_string()]).unwrap();
+        assert!(index.indexed_chunk_ids(&ids).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,463 @@
+//! Code Chunker
+//!
+//! Splits source files on top-level definitions (functions, classes, impl
+//! blocks) so each chunk holds whole definitions. Boundaries come from a
+//! brace/indentation heuristic: a definition starts on an unindented line
+//! outside any open brace. Definitions larger than the target size, and files
+//! with no recognizable definitions, fall back to line-based splitting.
+
+use super::fallback::FallbackChunker;
+use super::{create_chunk, Chunk, ChunkMetadata, ChunkSizes, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use crate::config::OverlapStrategy;
+use std::path::Path;
+
+/// Languages the boundary heuristic understands
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+}
+
+impl Language {
+    fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "ts" | "tsx" => Some(Self::JavaScript),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    /// Python blocks are delimited by indentation alone
+    fn uses_braces(self) -> bool {
+        self != Self::Python
+    }
+
+    /// Prefixes that may precede a definition keyword
+    fn modifiers(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["pub(crate) ", "pub(super) ", "pub ", "async ", "unsafe ", "const ", "extern \"C\" "],
+            Self::Python => &["async "],
+            Self::JavaScript => &["export default ", "export ", "async "],
+            Self::Go => &[],
+        }
+    }
+
+    /// Keywords that open a top-level definition
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["fn", "struct", "enum", "trait", "impl", "mod", "union", "macro_rules!"],
+            Self::Python => &["def", "class"],
+            Self::JavaScript => &["function*", "function", "class", "const", "let"],
+            Self::Go => &["func", "type"],
+        }
+    }
+
+    /// Lines directly above a definition that belong to it (docs, attributes, decorators)
+    fn is_preamble(self, line: &str) -> bool {
+        match self {
+            Self::Rust => line.starts_with("//") || line.starts_with("#["),
+            Self::Python => line.starts_with('@') || line.starts_with('#'),
+            Self::JavaScript => {
+                line.starts_with("//") || line.starts_with("/*") || line.starts_with(" *") || line.starts_with('@')
+            }
+            Self::Go => line.starts_with("//"),
+        }
+    }
+}
+
+/// Name of the definition opened by `line`, if it opens one
+fn definition_name(line: &str, lang: Language) -> Option<String> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut rest = line.trim_end();
+    while let Some(stripped) = lang.modifiers().iter().find_map(|m| rest.strip_prefix(m)) {
+        rest = stripped;
+    }
+
+    let (keyword, after) = lang.keywords().iter().find_map(|kw| {
+        let after = rest.strip_prefix(kw)?;
+        let boundary = kw.ends_with('!') || after.starts_with(|c: char| c.is_whitespace() || c == '<' || c == '(');
+        boundary.then_some((*kw, after.trim_start()))
+    })?;
+
+    let ident = |s: &str| -> Option<String> {
+        let name: String = s
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+            .collect();
+        (!name.is_empty()).then_some(name)
+    };
+
+    match (lang, keyword) {
+        // `impl<T> Display for Wrapper<T>` names the whole header
+        (Language::Rust, "impl") => {
+            let header = rest.split('{').next().unwrap_or(rest);
+            let header = header.split(" where").next().unwrap_or(header).trim();
+            Some(header.to_string())
+        }
+        // Only function-valued bindings count as definitions
+        (Language::JavaScript, "const" | "let") => {
+            if rest.contains("=>") || rest.contains("function") {
+                ident(after)
+            } else {
+                None
+            }
+        }
+        // `func (s *Server) Start()` becomes `Server.Start`
+        (Language::Go, "func") if after.starts_with('(') => {
+            let close = after.find(')')?;
+            let receiver = after[1..close].split_whitespace().last()?.trim_start_matches('*');
+            let method = ident(after[close + 1..].trim_start())?;
+            Some(format!("{}.{}", receiver, method))
+        }
+        _ => ident(after),
+    }
+}
+
+/// Net change in brace depth over one line, ignoring `//` comments
+fn brace_delta(line: &str) -> i32 {
+    let code = line.split("//").next().unwrap_or(line);
+    code.chars().fold(0, |depth, c| match c {
+        '{' => depth + 1,
+        '}' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// A run of lines `[start, end)` holding one definition (or the file header)
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    start: usize,
+    end: usize,
+    name: Option<String>,
+}
+
+/// Split lines at top-level definitions (empty if none are found)
+fn split_definitions(lines: &[&str], lang: Language) -> Vec<Segment> {
+    let mut starts: Vec<(usize, String)> = Vec::new();
+    let mut depth = 0i32;
+
+    for (i, line) in lines.iter().enumerate() {
+        if depth == 0 || !lang.uses_braces() {
+            if let Some(name) = definition_name(line, lang) {
+                starts.push((i, name));
+            }
+        }
+        if lang.uses_braces() {
+            depth += brace_delta(line);
+            // An unindented closing brace ends a top-level item; resync there
+            // in case braces inside strings threw the count off
+            if line.starts_with('}') {
+                depth = 0;
+            }
+            depth = depth.max(0);
+        }
+    }
+
+    if starts.is_empty() {
+        return Vec::new();
+    }
+
+    // Pull doc comments, attributes and decorators into the definition
+    let mut floor = 0;
+    for (start, _) in starts.iter_mut() {
+        while *start > floor && lang.is_preamble(lines[*start - 1]) {
+            *start -= 1;
+        }
+        floor = *start + 1;
+    }
+
+    let mut segments = Vec::new();
+    if starts[0].0 > 0 {
+        segments.push(Segment { start: 0, end: starts[0].0, name: None });
+    }
+    for (i, (start, name)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map(|(s, _)| *s).unwrap_or(lines.len());
+        segments.push(Segment { start: *start, end, name: Some(name.clone()) });
+    }
+    segments
+}
+
+/// Fold segments shorter than `min_size` into their neighbour
+///
+/// A short segment joins the one after it (keeping the first name); a short
+/// last segment joins the one before it.
+fn merge_small_segments(lines: &[&str], segments: Vec<Segment>, min_size: usize) -> Vec<Segment> {
+    let size = |s: &Segment| lines[s.start..s.end].join("\n").trim().len();
+
+    let mut merged: Vec<Segment> = Vec::new();
+    let mut pending: Option<Segment> = None;
+    for segment in segments {
+        let current = match pending.take() {
+            Some(p) => Segment { start: p.start, end: segment.end, name: p.name.or(segment.name) },
+            None => segment,
+        };
+        if size(&current) < min_size {
+            pending = Some(current);
+        } else {
+            merged.push(current);
+        }
+    }
+
+    if let Some(p) = pending {
+        match merged.last_mut() {
+            Some(last) => last.end = p.end,
+            None => merged.push(p),
+        }
+    }
+    merged
+}
+
+/// Definition-aware chunker for source code
+pub struct CodeChunker {
+    target_size: usize,
+    min_size: usize,
+    lines: FallbackChunker,
+}
+
+impl CodeChunker {
+    pub fn new() -> Self {
+        Self::with_strategy(OverlapStrategy::default())
+    }
+
+    /// Create a chunker whose line-based fallback uses the given overlap strategy
+    pub fn with_strategy(strategy: OverlapStrategy) -> Self {
+        Self {
+            target_size: TARGET_SIZE,
+            min_size: MIN_CHUNK,
+            lines: FallbackChunker::with_sizes_and_strategy(TARGET_SIZE, OVERLAP, strategy),
+        }
+    }
+
+    pub fn with_sizes(target_size: usize, overlap: usize) -> Self {
+        Self {
+            target_size,
+            min_size: MIN_CHUNK,
+            lines: FallbackChunker::with_sizes(target_size, overlap),
+        }
+    }
+
+    /// Use the given target, overlap and minimum chunk sizes
+    pub fn with_chunk_sizes(mut self, sizes: ChunkSizes) -> Self {
+        self.target_size = sizes.target;
+        self.min_size = sizes.min;
+        self.lines = self.lines.with_chunk_sizes(sizes);
+        self
+    }
+
+    /// Line-split text starting at `first_line` (1-based) into code chunks
+    fn split_lines(&self, text: &str, first_line: u32, base_metadata: &ChunkMetadata) -> Vec<Chunk> {
+        let mut chunks = self.lines.chunk_by_lines(text, base_metadata);
+        for chunk in &mut chunks {
+            chunk.metadata.line_start += first_line - 1;
+            chunk.metadata.line_end += first_line - 1;
+            chunk.metadata.has_code = true;
+        }
+        chunks
+    }
+}
+
+impl Default for CodeChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for CodeChunker {
+    fn chunk(&self, content: &str, metadata: &DocMetadata) -> Vec<Chunk> {
+        if content.trim().is_empty() {
+            return Vec::new();
+        }
+
+        let title = metadata.file_path.as_ref().and_then(|p| {
+            Path::new(p)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        });
+        let base_metadata = ChunkMetadata::new(metadata).with_title(title);
+
+        let lines: Vec<&str> = content.lines().collect();
+        let segments = metadata
+            .file_path
+            .as_deref()
+            .and_then(Language::from_path)
+            .map(|lang| split_definitions(&lines, lang))
+            .unwrap_or_default();
+        if segments.is_empty() {
+            return self.split_lines(content, 1, &base_metadata);
+        }
+
+        let mut chunks = Vec::new();
+        for segment in merge_small_segments(&lines, segments, self.min_size) {
+            let text = lines[segment.start..segment.end].join("\n");
+            let meta = base_metadata.clone().with_section(segment.name);
+            let first_line = segment.start as u32 + 1;
+
+            if text.len() > self.target_size {
+                chunks.extend(self.split_lines(&text, first_line, &meta));
+            } else if text.trim().len() >= self.min_size {
+                let meta = meta.with_lines(first_line, segment.end as u32).with_code(true);
+                chunks.push(create_chunk(text, meta));
+            }
+        }
+        chunks
+    }
+
+    fn supported_extensions(&self) -> &[&str] {
+        &["rs", "py", "js", "jsx", "mjs", "ts", "tsx", "go"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(path: &str) -> DocMetadata {
+        DocMetadata {
+            document_id: "doc1".to_string(),
+            source_id: "src1".to_string(),
+            file_path: Some(path.to_string()),
+        }
+    }
+
+    const RUST_FILE: &str = r#"use std::collections::HashMap;
+use std::fmt;
+
+/// Parse a configuration string into key/value pairs.
+pub fn parse_config(input: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for line in input.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            map.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    map
+}
+
+/// Render key/value pairs back into configuration text.
+pub fn render_config(map: &HashMap<String, String>) -> String {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    keys.iter()
+        .map(|k| format!("{} = {}", k, map[*k]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug)]
+pub struct Wrapper {
+    inner: HashMap<String, String>,
+    label: String,
+    created_at: u64,
+}
+
+impl fmt::Display for Wrapper {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} entries)", self.label, self.inner.len())
+    }
+}
+"#;
+
+    #[test]
+    fn test_rust_file_one_chunk_per_definition() {
+        let chunker = CodeChunker::new();
+        let chunks = chunker.chunk(RUST_FILE, &doc("src/config.rs"));
+
+        let sections: Vec<_> = chunks.iter().map(|c| c.metadata.section.as_deref()).collect();
+        assert_eq!(
+            sections,
+            vec![
+                Some("parse_config"),
+                Some("render_config"),
+                Some("Wrapper"),
+                Some("impl fmt::Display for Wrapper"),
+            ]
+        );
+        assert!(chunks.iter().all(|c| c.metadata.has_code));
+
+        // Imports fold into the first function; doc comments stay with theirs
+        assert!(chunks[0].content.starts_with("use std::collections::HashMap;"));
+        assert!(chunks[1].content.starts_with("/// Render key/value pairs"));
+        assert!(chunks[2].content.starts_with("#[derive(Debug)]"));
+        assert!(chunks[1].content.trim_end().ends_with('}'));
+        assert_eq!(chunks[1].metadata.line_start, 15);
+    }
+
+    #[test]
+    fn test_nested_functions_are_not_boundaries() {
+        let content = "fn outer() {\nfn inner() -> u32 {\n    1\n}\n    let total = inner() + inner() + inner() + inner() + inner() + inner() + inner();\n    println!(\"{}\", total);\n}\n";
+        let lines: Vec<&str> = content.lines().collect();
+        let segments = split_definitions(&lines, Language::Rust);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].name.as_deref(), Some("outer"));
+    }
+
+    #[test]
+    fn test_python_decorators_and_classes() {
+        let content = r#"import functools
+
+
+@functools.lru_cache(maxsize=None)
+def fibonacci(n):
+    """Return the n-th Fibonacci number, memoized across calls."""
+    if n < 2:
+        return n
+    return fibonacci(n - 1) + fibonacci(n - 2)
+
+
+class Greeter:
+    """Greets people by name, optionally in a louder voice than usual."""
+
+    def greet(self, name, loud=False):
+        message = f"Hello, {name}!"
+        return message.upper() if loud else message
+"#;
+        let chunks = CodeChunker::new().chunk(content, &doc("lib.py"));
+        let sections: Vec<_> = chunks.iter().map(|c| c.metadata.section.as_deref()).collect();
+        assert_eq!(sections, vec![Some("fibonacci"), Some("Greeter")]);
+        assert!(chunks[0].content.contains("@functools.lru_cache"));
+        assert!(chunks[1].content.contains("def greet"));
+    }
+
+    #[test]
+    fn test_definition_names() {
+        assert_eq!(definition_name("export async function load(url) {", Language::JavaScript).as_deref(), Some("load"));
+        assert_eq!(definition_name("const handler = async (req) => {", Language::JavaScript).as_deref(), Some("handler"));
+        assert_eq!(definition_name("const LIMIT = 10;", Language::JavaScript), None);
+        assert_eq!(definition_name("func (s *Server) Start() error {", Language::Go).as_deref(), Some("Server.Start"));
+        assert_eq!(definition_name("type Config struct {", Language::Go).as_deref(), Some("Config"));
+        assert_eq!(definition_name("pub(crate) async fn run() {", Language::Rust).as_deref(), Some("run"));
+        assert_eq!(definition_name("pub const MAX: usize = 3;", Language::Rust), None);
+        assert_eq!(definition_name("    fn indented() {}", Language::Rust), None);
+    }
+
+    #[test]
+    fn test_large_definition_falls_back_to_lines() {
+        let body: String = (0..40)
+            .map(|i| format!("    let value_{} = compute_something_expensive({});\n", i, i))
+            .collect();
+        let content = format!("fn huge() {{\n{}}}\n", body);
+        let chunks = CodeChunker::with_sizes(500, 50).chunk(&content, &doc("big.rs"));
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.metadata.section.as_deref() == Some("huge")));
+        assert!(chunks.iter().all(|c| c.metadata.has_code));
+        assert_eq!(chunks[0].metadata.line_start, 1);
+    }
+
+    #[test]
+    fn test_no_definitions_falls_back_to_lines() {
+        let content = (1..=20).map(|i| format!("let value_{} = {};", i, i * 3)).collect::<Vec<_>>().join("\n");
+        let chunks = CodeChunker::new().chunk(&content, &doc("script.rs"));
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].metadata.section.is_none());
+        assert!(chunks[0].metadata.has_code);
+    }
+}
@@ -3,19 +3,28 @@
 //! Simple character-based chunking for unknown file types.
 //! Uses line boundaries when possible.
 
-use super::{create_chunk, Chunk, ChunkMetadata, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use super::{create_chunk, Chunk, ChunkMetadata, ChunkSizes, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use crate::config::OverlapStrategy;
 
 /// Fallback chunker for unknown file types
 pub struct FallbackChunker {
     target_size: usize,
     overlap: usize,
+    min_size: usize,
+    strategy: OverlapStrategy,
 }
 
 impl FallbackChunker {
     pub fn new() -> Self {
+        Self::with_strategy(OverlapStrategy::default())
+    }
+
+    pub fn with_strategy(strategy: OverlapStrategy) -> Self {
         Self {
             target_size: TARGET_SIZE,
             overlap: OVERLAP,
+            min_size: MIN_CHUNK,
+            strategy,
         }
     }
 
@@ -23,11 +32,30 @@ impl FallbackChunker {
         Self {
             target_size,
             overlap,
+            min_size: MIN_CHUNK,
+            strategy: OverlapStrategy::default(),
+        }
+    }
+
+    pub fn with_sizes_and_strategy(target_size: usize, overlap: usize, strategy: OverlapStrategy) -> Self {
+        Self {
+            target_size,
+            overlap,
+            min_size: MIN_CHUNK,
+            strategy,
         }
     }
 
+    /// Use the given target, overlap and minimum chunk sizes
+    pub fn with_chunk_sizes(mut self, sizes: ChunkSizes) -> Self {
+        self.target_size = sizes.target;
+        self.overlap = sizes.overlap;
+        self.min_size = sizes.min;
+        self
+    }
+
     /// Split content into lines, respecting chunk size limits
-    fn chunk_by_lines(&self, content: &str, base_metadata: &ChunkMetadata) -> Vec<Chunk> {
+    pub(super) fn chunk_by_lines(&self, content: &str, base_metadata: &ChunkMetadata) -> Vec<Chunk> {
         let mut chunks = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
 
@@ -47,7 +75,7 @@ impl FallbackChunker {
                 && !current_chunk.is_empty()
             {
                 // Create chunk if it's big enough
-                if current_chunk.len() >= MIN_CHUNK {
+                if current_chunk.len() >= self.min_size {
                     let meta = base_metadata
                         .clone()
                         .with_lines(chunk_start_line, current_line - 1)
@@ -67,7 +95,7 @@ impl FallbackChunker {
         }
 
         // Last chunk
-        if current_chunk.trim().len() >= MIN_CHUNK {
+        if current_chunk.trim().len() >= self.min_size {
             let meta = base_metadata
                 .clone()
                 .with_lines(chunk_start_line, current_line - 1)
@@ -79,25 +107,58 @@ impl FallbackChunker {
         chunks
     }
 
-    /// Find the start position for overlap, preferring line boundaries
+    /// Find the start position for overlap according to the overlap strategy
     fn find_overlap_start(&self, content: &str) -> usize {
-        if content.len() <= self.overlap {
-            return 0;
+        if self.strategy == OverlapStrategy::None {
+            return content.len();
         }
 
-        let target_start = content.len() - self.overlap;
-
-        // Find the nearest newline after target_start
-        if let Some(newline_pos) = content[target_start..].find('\n') {
-            return target_start + newline_pos + 1;
+        if content.len() <= self.overlap {
+            return 0;
         }
 
-        // No newline found, use char boundary
-        content
+        // Char boundary at or after the target
+        let target_start = content
             .char_indices()
             .map(|(i, _)| i)
-            .find(|&i| i >= target_start)
-            .unwrap_or(0)
+            .find(|&i| i >= content.len() - self.overlap)
+            .unwrap_or(0);
+
+        match self.strategy {
+            OverlapStrategy::None => content.len(),
+            OverlapStrategy::Chars => target_start,
+            OverlapStrategy::Lines => {
+                // Find the nearest newline after target_start
+                match content[target_start..].find('\n') {
+                    Some(newline_pos) => target_start + newline_pos + 1,
+                    None => target_start,
+                }
+            }
+            OverlapStrategy::Sentence => {
+                // Start at the first sentence after target_start; no overlap
+                // rather than a partial sentence
+                Self::find_sentence_start(content, target_start).unwrap_or(content.len())
+            }
+        }
+    }
+
+    /// Find the start of the first sentence beginning at or after `from`
+    fn find_sentence_start(content: &str, from: usize) -> Option<usize> {
+        let bytes = content.as_bytes();
+        let mut i = from.max(1);
+        while i < bytes.len() {
+            let prev = bytes[i - 1];
+            if (prev == b' ' || prev == b'\n') && i >= 2 && matches!(bytes[i - 2], b'.' | b'!' | b'?') {
+                // Skip any further whitespace
+                let start = i + content[i..].len() - content[i..].trim_start().len();
+                return (start < content.len()).then_some(start);
+            }
+            if prev == b'\n' && i >= 2 && bytes[i - 2] == b'\n' {
+                return Some(i);
+            }
+            i += 1;
+        }
+        None
     }
 }
 
@@ -193,4 +254,53 @@ mod tests {
             assert_eq!(chunks[0].metadata.title, Some("test.txt".to_string()));
         }
     }
+
+    fn sentence_content() -> String {
+        (1..=30)
+            .map(|i| format!("Sentence {} talks about topic {}. It has a second part {}.", i, i, i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_no_overlap_strategy() {
+        let chunker = FallbackChunker::with_sizes_and_strategy(200, 50, OverlapStrategy::None);
+        let content = (1..=50).map(|i| format!("This is line number {} with content", i)).collect::<Vec<_>>().join("\n");
+        let chunks = chunker.chunk(&content, &test_doc());
+
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            assert!(
+                pair[1].metadata.line_start > pair[0].metadata.line_end,
+                "Chunks should not share lines: {:?} / {:?}",
+                (pair[0].metadata.line_start, pair[0].metadata.line_end),
+                (pair[1].metadata.line_start, pair[1].metadata.line_end)
+            );
+        }
+
+        // Nothing is repeated: chunks add up to the original lines
+        let total_lines: usize = chunks.iter().map(|c| c.content.lines().count()).sum();
+        assert_eq!(total_lines, content.lines().count());
+    }
+
+    #[test]
+    fn test_sentence_overlap_strategy() {
+        let chunker = FallbackChunker::with_sizes_and_strategy(300, 80, OverlapStrategy::Sentence);
+        let content = sentence_content();
+        let chunks = chunker.chunk(&content, &test_doc());
+
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            let next = &pair[1].content;
+            // Overlapping chunks start at the beginning of a sentence
+            assert!(
+                next.starts_with("Sentence") || next.starts_with("It has"),
+                "Chunk should start on a sentence boundary: {:?}",
+                &next[..next.len().min(40)]
+            );
+            // ...that the previous chunk also contains
+            let first_sentence = &next[..next.find('.').unwrap() + 1];
+            assert!(pair[0].content.contains(first_sentence));
+        }
+    }
 }
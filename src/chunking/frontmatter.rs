@@ -0,0 +1,173 @@
+//! Frontmatter parsing for Markdown
+//!
+//! Recognises YAML (`---`) and TOML (`+++`) blocks at the very top of a file.
+//! Keys become document tags so metadata is searchable by filter rather than
+//! embedded as chunk text.
+
+/// Keys whose values are tags themselves rather than `key:value` pairs
+const TAG_KEYS: &[&str] = &["tags", "tag", "keywords", "categories", "category"];
+
+/// Parsed frontmatter block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frontmatter {
+    /// Tags derived from the frontmatter keys
+    pub tags: Vec<String>,
+    /// Byte offset where the body starts
+    pub body_start: usize,
+    /// Number of lines taken by the block (including fences)
+    pub lines: u32,
+}
+
+impl Frontmatter {
+    /// Content after the frontmatter block
+    pub fn body<'a>(&self, content: &'a str) -> &'a str {
+        &content[self.body_start..]
+    }
+}
+
+/// Parse a frontmatter block at the start of `content`, if there is one
+pub fn parse_frontmatter(content: &str) -> Option<Frontmatter> {
+    let content_start = content.strip_prefix('\u{feff}').map_or(0, |_| 3);
+    let first_line = content[content_start..].lines().next()?.trim_end();
+    let fence = match first_line {
+        "---" => "---",
+        "+++" => "+++",
+        _ => return None,
+    };
+
+    // Find the closing fence
+    let mut offset = content_start + content[content_start..].find('\n')? + 1;
+    let block_start = offset;
+    let mut lines = 1u32;
+    loop {
+        let rest = &content[offset..];
+        let line_len = rest.find('\n').map_or(rest.len(), |i| i + 1);
+        if line_len == 0 {
+            return None;
+        }
+        lines += 1;
+        if rest[..line_len].trim_end() == fence {
+            let block = &content[block_start..offset];
+            let pairs = if fence == "---" { parse_yaml(block) } else { parse_toml(block) };
+            return Some(Frontmatter {
+                tags: to_tags(pairs),
+                body_start: offset + line_len,
+                lines,
+            });
+        }
+        offset += line_len;
+    }
+}
+
+/// Turn (key, values) pairs into tags
+fn to_tags(pairs: Vec<(String, Vec<String>)>) -> Vec<String> {
+    let mut tags = Vec::new();
+    for (key, values) in pairs {
+        let is_tag_key = TAG_KEYS.contains(&key.to_lowercase().as_str());
+        for value in values.into_iter().filter(|v| !v.is_empty()) {
+            let tag = if is_tag_key { value } else { format!("{}:{}", key, value) };
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Minimal YAML: `key: value`, `key: [a, b]` and `- item` lists
+fn parse_yaml(block: &str) -> Vec<(String, Vec<String>)> {
+    let mut pairs: Vec<(String, Vec<String>)> = Vec::new();
+
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // List item belonging to the previous key
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some((_, values)) = pairs.last_mut() {
+                values.push(unquote(item));
+            }
+            continue;
+        }
+
+        // Nested mappings are skipped; only top-level keys become tags
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let values = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(list) => list.split(',').map(unquote).collect(),
+            None if value.is_empty() => Vec::new(),
+            None => vec![unquote(value)],
+        };
+        pairs.push((key.trim().to_string(), values));
+    }
+
+    pairs
+}
+
+/// TOML frontmatter via the toml crate; tables are skipped
+fn parse_toml(block: &str) -> Vec<(String, Vec<String>)> {
+    let Ok(table) = block.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    let scalar = |v: &toml::Value| match v {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Table(_) | toml::Value::Array(_) => None,
+        other => Some(other.to_string()),
+    };
+
+    table
+        .iter()
+        .filter_map(|(key, value)| {
+            let values = match value {
+                toml::Value::Array(items) => items.iter().filter_map(scalar).collect(),
+                other => vec![scalar(other)?],
+            };
+            Some((key.clone(), values))
+        })
+        .collect()
+}
+
+fn unquote(s: &str) -> String {
+    s.trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_frontmatter() {
+        let content = "---\ntitle: \"Setup Guide\"\ntags: [rust, cli]\ncategories:\n  - tools\n---\n# Body\n";
+        let fm = parse_frontmatter(content).unwrap();
+        assert_eq!(fm.tags, vec!["title:Setup Guide", "rust", "cli", "tools"]);
+        assert_eq!(fm.lines, 6);
+        assert_eq!(fm.body(content), "# Body\n");
+    }
+
+    #[test]
+    fn test_toml_frontmatter() {
+        let content = "+++\ntitle = \"Notes\"\ndraft = true\ntags = [\"a\", \"b\"]\n+++\nBody";
+        let fm = parse_frontmatter(content).unwrap();
+        assert!(fm.tags.contains(&"title:Notes".to_string()));
+        assert!(fm.tags.contains(&"draft:true".to_string()));
+        assert!(fm.tags.contains(&"a".to_string()));
+        assert!(fm.tags.contains(&"b".to_string()));
+        assert_eq!(fm.body(content), "Body");
+    }
+
+    #[test]
+    fn test_no_frontmatter() {
+        assert!(parse_frontmatter("# Title\n---\nnot frontmatter").is_none());
+        // Unclosed block is treated as content
+        assert!(parse_frontmatter("---\ntitle: x\n# Body").is_none());
+    }
+}
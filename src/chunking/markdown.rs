@@ -4,7 +4,8 @@
 //! Uses pulldown-cmark to parse and extract structure.
 //! Tracks H1/H2/H3 headers for hierarchical metadata.
 
-use super::{create_chunk, Chunk, ChunkMetadata, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use super::frontmatter::parse_frontmatter;
+use super::{create_chunk, Chunk, ChunkMetadata, ChunkSizes, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
 // Note: pulldown-cmark imported for future use with proper AST parsing
 // Currently using simple string-based header detection
 
@@ -13,6 +14,10 @@ pub struct MarkdownChunker {
     target_size: usize,
     #[allow(dead_code)]
     overlap: usize,
+    /// Sections and chunks shorter than this are merged or dropped
+    min_size: usize,
+    /// Leave YAML/TOML frontmatter out of chunk content
+    strip_frontmatter: bool,
 }
 
 /// Current section context while parsing
@@ -21,9 +26,35 @@ struct SectionContext {
     title: Option<String>,      // H1
     section: Option<String>,    // H2
     subsection: Option<String>, // H3
+    deeper: Vec<(usize, String)>, // H4-H6, only in the hierarchy
 }
 
 impl SectionContext {
+    /// Enter a heading, closing any open headings at the same or a deeper level
+    fn enter(&mut self, level: usize, text: String) {
+        match level {
+            1 => {
+                self.title = Some(text);
+                self.section = None;
+                self.subsection = None;
+                self.deeper.clear();
+            }
+            2 => {
+                self.section = Some(text);
+                self.subsection = None;
+                self.deeper.clear();
+            }
+            3 => {
+                self.subsection = Some(text);
+                self.deeper.clear();
+            }
+            _ => {
+                self.deeper.retain(|(l, _)| *l < level);
+                self.deeper.push((level, text));
+            }
+        }
+    }
+
     fn to_hierarchy(&self) -> Vec<String> {
         let mut h = Vec::new();
         if let Some(t) = &self.title {
@@ -35,6 +66,7 @@ impl SectionContext {
         if let Some(ss) = &self.subsection {
             h.push(ss.clone());
         }
+        h.extend(self.deeper.iter().map(|(_, text)| text.clone()));
         h
     }
 }
@@ -44,6 +76,8 @@ impl MarkdownChunker {
         Self {
             target_size: TARGET_SIZE,
             overlap: OVERLAP,
+            min_size: MIN_CHUNK,
+            strip_frontmatter: true,
         }
     }
 
@@ -51,9 +85,25 @@ impl MarkdownChunker {
         Self {
             target_size,
             overlap,
+            min_size: MIN_CHUNK,
+            strip_frontmatter: true,
         }
     }
 
+    /// Use the given target, overlap and minimum chunk sizes
+    pub fn with_chunk_sizes(mut self, sizes: ChunkSizes) -> Self {
+        self.target_size = sizes.target;
+        self.overlap = sizes.overlap;
+        self.min_size = sizes.min;
+        self
+    }
+
+    /// Keep (false) or strip (true, default) frontmatter from chunk content
+    pub fn with_frontmatter_stripping(mut self, strip: bool) -> Self {
+        self.strip_frontmatter = strip;
+        self
+    }
+
     /// Check if content contains code blocks
     fn has_code_blocks(content: &str) -> bool {
         content.contains("```")
@@ -125,23 +175,7 @@ impl MarkdownChunker {
             }
 
             // Check for headers (only outside code blocks)
-            if trimmed.starts_with("# ") && !trimmed.starts_with("##") {
-                // H1 - Title
-                if !current_content.trim().is_empty() {
-                    sections.push((
-                        current_context.clone(),
-                        current_content.clone(),
-                        current_start_line,
-                        current_line - 1,
-                    ));
-                }
-                current_context.title = Some(trimmed[2..].trim().to_string());
-                current_context.section = None;
-                current_context.subsection = None;
-                current_content = format!("{}\n", line);
-                current_start_line = current_line;
-            } else if trimmed.starts_with("## ") && !trimmed.starts_with("###") {
-                // H2 - Section
+            if let Some(level) = heading_level(trimmed) {
                 if !current_content.trim().is_empty() {
                     sections.push((
                         current_context.clone(),
@@ -150,21 +184,7 @@ impl MarkdownChunker {
                         current_line - 1,
                     ));
                 }
-                current_context.section = Some(trimmed[3..].trim().to_string());
-                current_context.subsection = None;
-                current_content = format!("{}\n", line);
-                current_start_line = current_line;
-            } else if trimmed.starts_with("### ") {
-                // H3 - Subsection
-                if !current_content.trim().is_empty() {
-                    sections.push((
-                        current_context.clone(),
-                        current_content.clone(),
-                        current_start_line,
-                        current_line - 1,
-                    ));
-                }
-                current_context.subsection = Some(trimmed[4..].trim().to_string());
+                current_context.enter(level, trimmed[level..].trim().to_string());
                 current_content = format!("{}\n", line);
                 current_start_line = current_line;
             } else {
@@ -189,6 +209,41 @@ impl MarkdownChunker {
         sections
     }
 
+    /// Fold sections shorter than `min_size` into the next one
+    ///
+    /// A merged section takes the context of the section it was folded into;
+    /// a short last section is appended to the one before it.
+    fn merge_small_sections(
+        sections: Vec<(SectionContext, String, u32, u32)>,
+        min_size: usize,
+    ) -> Vec<(SectionContext, String, u32, u32)> {
+        let mut merged: Vec<(SectionContext, String, u32, u32)> = Vec::new();
+        let mut pending: Option<(SectionContext, String, u32, u32)> = None;
+
+        for (context, content, start, end) in sections {
+            let (content, start) = match pending.take() {
+                Some((_, prefix, pending_start, _)) => (prefix + &content, pending_start),
+                None => (content, start),
+            };
+            if content.len() < min_size {
+                pending = Some((context, content, start, end));
+            } else {
+                merged.push((context, content, start, end));
+            }
+        }
+
+        if let Some(short) = pending {
+            match merged.last_mut() {
+                Some(last) => {
+                    last.1.push_str(&short.1);
+                    last.3 = short.3;
+                }
+                None => merged.push(short),
+            }
+        }
+        merged
+    }
+
     /// Further split a section if it exceeds target size
     fn split_large_section(
         &self,
@@ -224,7 +279,7 @@ impl MarkdownChunker {
             if !in_code_block
                 && !is_code_fence
                 && current_chunk.len() + line_with_newline.len() > self.target_size
-                && current_chunk.len() >= MIN_CHUNK
+                && current_chunk.len() >= self.min_size
             {
                 let has_code = Self::has_code_blocks(&current_chunk);
                 let hierarchy = context.to_hierarchy();
@@ -252,7 +307,7 @@ impl MarkdownChunker {
         }
 
         // Last chunk
-        if current_chunk.len() >= MIN_CHUNK {
+        if current_chunk.len() >= self.min_size {
             let has_code = Self::has_code_blocks(&current_chunk);
             let hierarchy = context.to_hierarchy();
 
@@ -273,6 +328,57 @@ impl MarkdownChunker {
     }
 }
 
+/// One part of a Markdown file split by `split_sections`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownSection {
+    /// Heading text, or `None` for content before the first split heading
+    pub title: Option<String>,
+    /// The heading line and everything up to the next split heading
+    pub content: String,
+}
+
+/// Level of an ATX heading line (`## Title` is 2), if it is one
+fn heading_level(trimmed: &str) -> Option<usize> {
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let is_heading = (1..=6).contains(&level) && trimmed[level..].starts_with(' ');
+    is_heading.then_some(level)
+}
+
+/// Split Markdown into one part per heading at `level` or above
+///
+/// With level 2, both H1 and H2 start a new part. Headings inside fenced
+/// code blocks are ignored, and blank parts are dropped.
+pub fn split_sections(content: &str, level: usize) -> Vec<MarkdownSection> {
+    let mut sections = Vec::new();
+    let mut current = MarkdownSection { title: None, content: String::new() };
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+
+        let splits = !in_code_block && heading_level(trimmed).is_some_and(|l| l <= level);
+        if splits {
+            let title = trimmed.trim_start_matches('#').trim().to_string();
+            let next = MarkdownSection { title: Some(title), content: String::new() };
+            let done = std::mem::replace(&mut current, next);
+            if !done.content.trim().is_empty() {
+                sections.push(done);
+            }
+        }
+
+        current.content.push_str(line);
+        current.content.push('\n');
+    }
+
+    if !current.content.trim().is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
 impl Default for MarkdownChunker {
     fn default() -> Self {
         Self::new()
@@ -281,17 +387,33 @@ impl Default for MarkdownChunker {
 
 impl Chunker for MarkdownChunker {
     fn chunk(&self, content: &str, metadata: &DocMetadata) -> Vec<Chunk> {
+        // Chunk only the body; line numbers still refer to the original file
+        if self.strip_frontmatter {
+            if let Some(frontmatter) = parse_frontmatter(content) {
+                let mut chunks = Self {
+                    strip_frontmatter: false,
+                    ..*self
+                }
+                .chunk(frontmatter.body(content), metadata);
+                for chunk in &mut chunks {
+                    chunk.metadata.line_start += frontmatter.lines;
+                    chunk.metadata.line_end += frontmatter.lines;
+                }
+                return chunks;
+            }
+        }
+
         if content.trim().is_empty() {
             return Vec::new();
         }
 
-        let sections = Self::split_into_sections(content);
+        let sections = Self::merge_small_sections(Self::split_into_sections(content), self.min_size);
         let mut chunks = Vec::new();
 
         for (context, section_content, start_line, end_line) in sections {
             if section_content.len() <= self.target_size {
                 // Section fits in one chunk
-                if section_content.len() >= MIN_CHUNK {
+                if section_content.len() >= self.min_size {
                     let has_code = Self::has_code_blocks(&section_content);
                     let hierarchy = context.to_hierarchy();
 
@@ -316,7 +438,7 @@ impl Chunker for MarkdownChunker {
         }
 
         // If no chunks created (content too small), create one chunk with everything
-        if chunks.is_empty() && content.len() >= MIN_CHUNK {
+        if chunks.is_empty() && content.len() >= self.min_size {
             let meta = ChunkMetadata::new(metadata)
                 .with_lines(1, content.lines().count() as u32)
                 .with_code(Self::has_code_blocks(content));
@@ -460,4 +582,100 @@ def long_function():
             );
         }
     }
+
+    #[test]
+    fn test_frontmatter_stripped_from_chunks() {
+        let chunker = MarkdownChunker::new();
+        // Frontmatter long enough to form its own chunk if it were kept
+        let content = "---\nauthor: Jane Doe\ntags: [deployment, kubernetes]\nsummary: How the team ships releases to the production cluster every week\n---\n# Deploying\n\nRoll out the new release with a rolling update so that pods are replaced gradually without downtime.\n";
+
+        let chunks = chunker.chunk(content, &test_doc());
+        assert!(!chunks.is_empty());
+        for chunk in &chunks {
+            assert!(!chunk.content.contains("author"));
+            assert!(!chunk.content.contains("tags:"));
+            assert!(!chunk.content.starts_with("---"));
+        }
+        // Line numbers still point into the original file
+        assert!(chunks[0].metadata.line_start >= 6);
+
+        let kept = MarkdownChunker::new().with_frontmatter_stripping(false).chunk(content, &test_doc());
+        assert!(kept.iter().any(|c| c.content.contains("author: Jane Doe")));
+    }
+
+    #[test]
+    fn test_nested_headers_set_hierarchy() {
+        let chunker = MarkdownChunker::new();
+        let body = "This paragraph is long enough to stand as its own chunk, so every heading below starts a new one.";
+        let content = format!(
+            "# Guide\n\n{b}\n\n## Setup\n\n{b}\n\n### Linux\n\n{b}\n\n#### Debian\n\n{b}\n\n##### Bookworm\n\n{b}\n\n#### Fedora\n\n{b}\n\n## Usage\n\n{b}\n",
+            b = body
+        );
+
+        let chunks = chunker.chunk(&content, &test_doc());
+        let hierarchies: Vec<Vec<&str>> = chunks
+            .iter()
+            .map(|c| c.metadata.hierarchy.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(
+            hierarchies,
+            vec![
+                vec!["Guide"],
+                vec!["Guide", "Setup"],
+                vec!["Guide", "Setup", "Linux"],
+                vec!["Guide", "Setup", "Linux", "Debian"],
+                vec!["Guide", "Setup", "Linux", "Debian", "Bookworm"],
+                vec!["Guide", "Setup", "Linux", "Fedora"],
+                vec!["Guide", "Usage"],
+            ]
+        );
+        assert_eq!(chunks[3].metadata.section.as_deref(), Some("Setup"));
+        assert_eq!(chunks[3].metadata.subsection.as_deref(), Some("Linux"));
+        // A new H2 closes the H3
+        assert_eq!(chunks[6].metadata.subsection, None);
+    }
+
+    #[test]
+    fn test_large_section_split_within_target_size() {
+        let chunker = MarkdownChunker::new();
+        let paragraph = "Each line of this section adds a little more text so the whole section ends up well past the target size.\n";
+        let content = format!("# Big\n\n## Reference\n\n{}", paragraph.repeat(40));
+        assert!(content.len() > TARGET_SIZE * 2);
+
+        let chunks = chunker.chunk(&content, &test_doc());
+        assert!(chunks.len() >= 3, "got {} chunks", chunks.len());
+        for chunk in &chunks {
+            assert!(chunk.content.len() <= TARGET_SIZE + paragraph.len());
+            assert!(chunk.content.len() >= MIN_CHUNK);
+            assert_eq!(chunk.metadata.section.as_deref(), Some("Reference"));
+        }
+    }
+
+    #[test]
+    fn test_small_sections_are_merged_not_dropped() {
+        let chunker = MarkdownChunker::new();
+        let content = "# Notes\n\n## Short\n\nTiny.\n\n## Longer\n\nThis section has enough text on its own to be kept as a chunk by the Markdown chunker.\n\n## Tail\n\nAlso tiny.\n";
+
+        let chunks = chunker.chunk(content, &test_doc());
+        assert_eq!(chunks.len(), 1);
+        let chunk = &chunks[0];
+        assert!(chunk.content.contains("Tiny.") && chunk.content.contains("Also tiny."));
+        assert_eq!(chunk.metadata.section.as_deref(), Some("Longer"));
+        assert_eq!((chunk.metadata.line_start, chunk.metadata.line_end), (1, content.lines().count() as u32));
+    }
+
+    #[test]
+    fn test_split_sections_by_level() {
+        let content = "Intro text.\n# Install\nSteps.\n## Linux\napt install\n```sh\n# not a heading\n```\n# Usage\nRun it.\n";
+
+        let parts = split_sections(content, 1);
+        let titles: Vec<Option<&str>> = parts.iter().map(|p| p.title.as_deref()).collect();
+        assert_eq!(titles, vec![None, Some("Install"), Some("Usage")]);
+        assert!(parts[1].content.starts_with("# Install\n"));
+        assert!(parts[1].content.contains("## Linux") && parts[1].content.contains("# not a heading"));
+
+        let parts = split_sections(content, 2);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[2].title.as_deref(), Some("Linux"));
+    }
 }
@@ -4,19 +4,28 @@
 //! - Markdown: Header-aware with hierarchical metadata
 //! - Text: Paragraph-based splitting
 //! - PDF: Text extraction via pdf_oxide (converts to Markdown)
+//! - Code: Splits on top-level definitions (Rust, Python, JavaScript, Go)
 //! - Fallback: Recursive char-based for unknown types
 
+pub mod code;
 pub mod fallback;
+pub mod frontmatter;
 pub mod markdown;
 pub mod pdf;
 pub mod text;
+pub mod tokens;
 
+pub use code::CodeChunker;
 pub use fallback::FallbackChunker;
-pub use markdown::MarkdownChunker;
-pub use pdf::{extract_text_from_base64_pdf, extract_text_from_pdf, PdfChunker};
+pub use frontmatter::{parse_frontmatter, Frontmatter};
+pub use markdown::{split_sections, MarkdownChunker, MarkdownSection};
+pub use pdf::{extract_text_from_base64_pdf, extract_text_from_pdf, is_pdf_path, PdfChunker, PAGE_BREAK};
 pub use text::TextChunker;
+pub use tokens::{split_to_token_limit, TokenCounter, CHARS_PER_TOKEN};
 
+use crate::config::OverlapStrategy;
 use std::path::Path;
+use std::sync::Arc;
 
 /// Chunk size parameters
 pub const TARGET_SIZE: usize = 1500; // ~400-512 tokens
@@ -24,6 +33,25 @@ pub const OVERLAP: usize = 200; // ~10-15%
 pub const MIN_CHUNK: usize = 100; // Skip tiny chunks
 pub const MAX_CHUNK: usize = 3000; // Hard limit
 
+/// Chunk size settings in characters (`chunk_target_size`, `chunk_overlap_size`
+/// and `chunk_min_size` in config; defaults are the constants above)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkSizes {
+    pub target: usize,
+    pub overlap: usize,
+    pub min: usize,
+}
+
+impl Default for ChunkSizes {
+    fn default() -> Self {
+        Self {
+            target: TARGET_SIZE,
+            overlap: OVERLAP,
+            min: MIN_CHUNK,
+        }
+    }
+}
+
 /// Document metadata for chunking context
 #[derive(Debug, Clone)]
 pub struct DocMetadata {
@@ -58,6 +86,8 @@ pub struct ChunkMetadata {
     pub has_code: bool,
     pub line_start: u32,
     pub line_end: u32,
+    /// First and last page, for chunks of extracted PDF text
+    pub pages: Option<(u32, u32)>,
     pub content_hash: String,
 }
 
@@ -75,6 +105,7 @@ impl ChunkMetadata {
             has_code: false,
             line_start: 1,
             line_end: 1,
+            pages: None,
             content_hash: String::new(),
         }
     }
@@ -157,17 +188,56 @@ pub struct ChunkerRegistry {
     markdown: MarkdownChunker,
     text: TextChunker,
     pdf: PdfChunker,
+    code: CodeChunker,
     fallback: FallbackChunker,
+    /// Split chunks over this many tokens (token-based sizing)
+    token_limit: Option<(Arc<dyn TokenCounter>, usize)>,
 }
 
 impl ChunkerRegistry {
     /// Create a new registry with default chunkers
     pub fn new() -> Self {
+        Self::with_overlap(OverlapStrategy::default())
+    }
+
+    /// Create a registry whose chunkers use the given overlap strategy
+    pub fn with_overlap(strategy: OverlapStrategy) -> Self {
+        Self::with_settings(strategy, ChunkSizes::default())
+    }
+
+    /// Create a registry with the given overlap strategy and chunk sizes
+    pub fn with_settings(strategy: OverlapStrategy, sizes: ChunkSizes) -> Self {
         Self {
-            markdown: MarkdownChunker::new(),
-            text: TextChunker::new(),
-            pdf: PdfChunker::new(),
-            fallback: FallbackChunker::new(),
+            markdown: MarkdownChunker::new().with_chunk_sizes(sizes),
+            text: TextChunker::with_strategy(strategy).with_chunk_sizes(sizes),
+            pdf: PdfChunker::new().with_chunk_sizes(sizes),
+            code: CodeChunker::with_strategy(strategy).with_chunk_sizes(sizes),
+            fallback: FallbackChunker::with_strategy(strategy).with_chunk_sizes(sizes),
+            token_limit: None,
+        }
+    }
+
+    /// Cap chunks at `max_tokens` tokens as counted by `counter`
+    ///
+    /// Pair with a character target of about `max_tokens * CHARS_PER_TOKEN`
+    /// (see `Config::chunk_sizes`) so chunks are sized by tokens, not characters.
+    pub fn with_token_limit(mut self, counter: Arc<dyn TokenCounter>, max_tokens: usize) -> Self {
+        self.token_limit = Some((counter, max_tokens));
+        self
+    }
+
+    /// Keep (false) or strip (true, default) Markdown frontmatter from chunks
+    pub fn with_frontmatter_stripping(mut self, strip: bool) -> Self {
+        self.markdown = self.markdown.with_frontmatter_stripping(strip);
+        self
+    }
+
+    /// Document tags from Markdown frontmatter (empty for other file types)
+    pub fn document_tags(&self, content: &str, file_path: Option<&str>) -> Vec<String> {
+        let ext = file_path.and_then(Self::get_extension).unwrap_or_default();
+        match ext.as_str() {
+            "md" | "markdown" => parse_frontmatter(content).map(|f| f.tags).unwrap_or_default(),
+            _ => Vec::new(),
         }
     }
 
@@ -184,13 +254,41 @@ impl ChunkerRegistry {
             .and_then(Self::get_extension)
             .unwrap_or_default();
 
-        match ext.as_str() {
+        let chunks = match ext.as_str() {
             "md" | "markdown" => self.markdown.chunk(content, metadata),
             "txt" => self.text.chunk(content, metadata),
             "pdf" => self.pdf.chunk(content, metadata),
+            ext if self.code.supported_extensions().contains(&ext) => self.code.chunk(content, metadata),
             _ => self.fallback.chunk(content, metadata),
+        };
+
+        match &self.token_limit {
+            Some((counter, max_tokens)) => chunks
+                .into_iter()
+                .flat_map(|chunk| Self::split_chunk(chunk, counter.as_ref(), *max_tokens))
+                .collect(),
+            None => chunks,
         }
     }
+
+    /// Split a chunk over the token limit into pieces that fit, keeping its metadata
+    fn split_chunk(chunk: Chunk, counter: &dyn TokenCounter, max_tokens: usize) -> Vec<Chunk> {
+        let pieces = split_to_token_limit(&chunk.content, max_tokens, counter);
+        if pieces.len() <= 1 {
+            return vec![chunk];
+        }
+
+        let mut line = chunk.metadata.line_start;
+        pieces
+            .into_iter()
+            .map(|piece| {
+                let lines = piece.trim_end_matches('\n').matches('\n').count() as u32;
+                let metadata = chunk.metadata.clone().with_lines(line, line + lines);
+                line += piece.matches('\n').count() as u32;
+                create_chunk(piece.to_string(), metadata)
+            })
+            .collect()
+    }
 }
 
 impl Default for ChunkerRegistry {
@@ -211,6 +309,18 @@ pub fn create_chunk(content: String, metadata: ChunkMetadata) -> Chunk {
     }
 }
 
+/// Line range to store for a chunk, or `None` when lines are meaningless
+///
+/// Web pages are stored as extracted text, so line numbers don't map back
+/// to anything an editor could open.
+pub fn stored_lines(file_path: Option<&str>, line_start: u32, line_end: u32) -> (Option<u32>, Option<u32>) {
+    if file_path.is_some_and(|p| p.contains("://")) {
+        (None, None)
+    } else {
+        (Some(line_start), Some(line_end))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,4 +358,26 @@ mod tests {
         let chunks = registry.chunk(&content, Some("test.md"), &doc);
         assert!(!chunks.is_empty());
     }
+
+    #[test]
+    fn test_registry_honors_chunk_sizes() {
+        let doc = DocMetadata {
+            document_id: "doc1".to_string(),
+            source_id: "src1".to_string(),
+            file_path: Some("note.txt".to_string()),
+        };
+        let content = "Short note.";
+        assert!(ChunkerRegistry::new().chunk(content, Some("note.txt"), &doc).is_empty());
+
+        let sizes = ChunkSizes { target: 200, overlap: 20, min: 10 };
+        let registry = ChunkerRegistry::with_settings(OverlapStrategy::default(), sizes);
+        assert_eq!(registry.chunk(content, Some("note.txt"), &doc).len(), 1);
+    }
+
+    #[test]
+    fn test_stored_lines_skip_web_pages() {
+        assert_eq!(stored_lines(Some("docs/guide.md"), 3, 9), (Some(3), Some(9)));
+        assert_eq!(stored_lines(None, 1, 4), (Some(1), Some(4)));
+        assert_eq!(stored_lines(Some("https://example.com/guide"), 3, 9), (None, None));
+    }
 }
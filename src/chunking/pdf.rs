@@ -2,12 +2,23 @@
 //!
 //! PDF text extraction and chunking using pdf_oxide.
 //! Converts PDF → Markdown, then delegates to MarkdownChunker.
+//! Pages are joined with a form feed so chunks can record their page range.
 
-use super::{Chunk, Chunker, DocMetadata, MarkdownChunker};
+use super::{Chunk, ChunkSizes, Chunker, DocMetadata, MarkdownChunker};
 use anyhow::Result;
 use pdf_oxide::converters::ConversionOptions;
 use std::path::Path;
 
+/// Separator between pages of extracted text (a form feed, as pdftotext uses)
+pub const PAGE_BREAK: &str = "\n\n\u{000C}\n\n";
+
+/// Whether a stored file path is a PDF
+pub fn is_pdf_path(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
 /// Extract text from base64-encoded PDF content
 /// Used for web uploads where PDF is sent as base64
 pub fn extract_text_from_base64_pdf(base64_content: &str) -> Result<String> {
@@ -44,13 +55,12 @@ pub fn extract_text_from_pdf(pdf_path: &Path) -> Result<String> {
     let options = ConversionOptions::default();
 
     for page_idx in 0..page_count {
+        // Unreadable pages stay as empty pages so later page numbers still line up
+        if page_idx > 0 {
+            all_text.push_str(PAGE_BREAK);
+        }
         match doc.to_markdown(page_idx, &options) {
-            Ok(markdown) => {
-                if !all_text.is_empty() {
-                    all_text.push_str("\n\n---\n\n"); // Page separator
-                }
-                all_text.push_str(&markdown);
-            }
+            Ok(markdown) => all_text.push_str(&markdown),
             Err(e) => {
                 eprintln!("Warning: Failed to extract page {}: {}", page_idx + 1, e);
             }
@@ -71,6 +81,12 @@ impl PdfChunker {
             md_chunker: MarkdownChunker::new(),
         }
     }
+
+    /// Use the given target, overlap and minimum chunk sizes
+    pub fn with_chunk_sizes(mut self, sizes: ChunkSizes) -> Self {
+        self.md_chunker = self.md_chunker.with_chunk_sizes(sizes);
+        self
+    }
 }
 
 impl Default for PdfChunker {
@@ -83,7 +99,14 @@ impl Chunker for PdfChunker {
     fn chunk(&self, content: &str, metadata: &DocMetadata) -> Vec<Chunk> {
         // Extracted PDF text is converted to Markdown by pdf_oxide
         // MarkdownChunker handles the hierarchical structure
-        self.md_chunker.chunk(content, metadata)
+        let mut chunks = self.md_chunker.chunk(content, metadata);
+        let page_starts = page_start_lines(content);
+        for chunk in &mut chunks {
+            let first = page_of_line(&page_starts, chunk.metadata.line_start);
+            let last = page_of_line(&page_starts, chunk.metadata.line_end);
+            chunk.metadata.pages = Some((first, last));
+        }
+        chunks
     }
 
     fn supported_extensions(&self) -> &[&str] {
@@ -91,6 +114,21 @@ impl Chunker for PdfChunker {
     }
 }
 
+/// 1-based line on which each page after the first begins
+fn page_start_lines(content: &str) -> Vec<u32> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains('\u{000C}'))
+        .map(|(i, _)| i as u32 + 2)
+        .collect()
+}
+
+/// 1-based page containing `line`
+fn page_of_line(page_starts: &[u32], line: u32) -> u32 {
+    page_starts.partition_point(|&start| start <= line) as u32 + 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +152,34 @@ mod tests {
         assert!(!chunks.is_empty() || content.len() < super::super::MIN_CHUNK);
     }
 
+    #[test]
+    fn test_chunks_record_their_pages() {
+        let chunker = PdfChunker::new().with_chunk_sizes(ChunkSizes { target: 400, overlap: 0, min: 50 });
+        let page = |n: u32| {
+            format!("## Part {}\n\n{}", n, format!("Body text that only appears on page {}. ", n).repeat(6))
+        };
+        let content = [page(1), page(2), page(3)].join(PAGE_BREAK);
+
+        let chunks = chunker.chunk(&content, &test_doc());
+
+        assert!(chunks.len() >= 3, "{:?}", chunks);
+        for chunk in &chunks {
+            let (first, last) = chunk.metadata.pages.expect("PDF chunks have pages");
+            for n in 1..=3 {
+                if chunk.content.contains(&format!("appears on page {}", n)) {
+                    assert!((first..=last).contains(&n), "page {} outside {}-{}", n, first, last);
+                }
+            }
+        }
+        assert_eq!(chunks.last().unwrap().metadata.pages.unwrap().1, 3);
+    }
+
+    #[test]
+    fn test_is_pdf_path() {
+        assert!(is_pdf_path("docs/Manual.PDF"));
+        assert!(!is_pdf_path("docs/manual.md"));
+    }
+
     #[test]
     fn test_supported_extensions() {
         let chunker = PdfChunker::new();
@@ -3,19 +3,32 @@
 //! Paragraph-based chunking for plain text files.
 //! Splits on double newlines (\n\n) to preserve paragraph boundaries.
 
-use super::{create_chunk, Chunk, ChunkMetadata, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use super::{create_chunk, Chunk, ChunkMetadata, ChunkSizes, Chunker, DocMetadata, MIN_CHUNK, OVERLAP, TARGET_SIZE};
+use crate::config::OverlapStrategy;
 
 /// Paragraph-based chunker for plain text files
 pub struct TextChunker {
     target_size: usize,
     overlap: usize,
+    min_size: usize,
+    strategy: OverlapStrategy,
 }
 
 impl TextChunker {
     pub fn new() -> Self {
+        Self::with_strategy(OverlapStrategy::default())
+    }
+
+    /// Create a chunker with the given overlap strategy
+    ///
+    /// Paragraph chunks only carry whole paragraphs over, so any strategy
+    /// other than `None` behaves the same here.
+    pub fn with_strategy(strategy: OverlapStrategy) -> Self {
         Self {
             target_size: TARGET_SIZE,
             overlap: OVERLAP,
+            min_size: MIN_CHUNK,
+            strategy,
         }
     }
 
@@ -23,9 +36,19 @@ impl TextChunker {
         Self {
             target_size,
             overlap,
+            min_size: MIN_CHUNK,
+            strategy: OverlapStrategy::default(),
         }
     }
 
+    /// Use the given target, overlap and minimum chunk sizes
+    pub fn with_chunk_sizes(mut self, sizes: ChunkSizes) -> Self {
+        self.target_size = sizes.target;
+        self.overlap = sizes.overlap;
+        self.min_size = sizes.min;
+        self
+    }
+
     /// Split content into paragraphs
     fn split_paragraphs(content: &str) -> Vec<&str> {
         content
@@ -92,7 +115,7 @@ impl Chunker for TextChunker {
                 && !current_chunk.is_empty()
             {
                 // Create chunk if it's big enough
-                if current_chunk.len() >= MIN_CHUNK {
+                if current_chunk.len() >= self.min_size {
                     let line_end = chunk_start_line + Self::count_lines(&current_chunk) - 1;
                     let meta = ChunkMetadata::new(metadata)
                         .with_title(title.clone())
@@ -102,7 +125,7 @@ impl Chunker for TextChunker {
                 }
 
                 // For overlap, keep the last paragraph if it fits
-                if para.len() < self.overlap {
+                if self.strategy != OverlapStrategy::None && para.len() < self.overlap {
                     current_chunk = para.to_string();
                     chunk_start_line = current_line;
                 } else {
@@ -122,7 +145,7 @@ impl Chunker for TextChunker {
         }
 
         // Last chunk
-        if current_chunk.len() >= MIN_CHUNK {
+        if current_chunk.len() >= self.min_size {
             let line_end = chunk_start_line + Self::count_lines(&current_chunk) - 1;
             let meta = ChunkMetadata::new(metadata)
                 .with_title(title)
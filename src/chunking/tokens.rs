@@ -0,0 +1,119 @@
+//! Token-based chunk sizing
+//!
+//! Character targets fit some scripts better than others: 1500 characters of
+//! English is ~350 tokens, while the same length of CJK text can be several
+//! times the model's limit. With `chunk_max_tokens` set, chunks are sized
+//! from the token budget and any chunk still over it is split at token
+//! boundaries, using the embedding model's own tokenizer.
+
+/// Rough characters per token for English text, used to turn a token budget
+/// into the character target the chunkers work with
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokenizes text the way the embedding model will
+pub trait TokenCounter: Send + Sync {
+    /// Byte range of each token in `text`, excluding special tokens
+    fn token_offsets(&self, text: &str) -> Vec<(usize, usize)>;
+
+    /// Number of tokens in `text`, excluding special tokens
+    fn count_tokens(&self, text: &str) -> usize {
+        self.token_offsets(text).len()
+    }
+}
+
+/// Split `text` into pieces of at most `max_tokens` tokens
+///
+/// Cuts fall on token boundaries, moved back to the last line break or
+/// space in the second half of the piece when there is one. Text already
+/// within the limit comes back as a single piece.
+pub fn split_to_token_limit<'a>(text: &'a str, max_tokens: usize, counter: &dyn TokenCounter) -> Vec<&'a str> {
+    let max_tokens = max_tokens.max(1);
+    let mut pieces = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let offsets = counter.token_offsets(rest);
+        if offsets.len() <= max_tokens {
+            pieces.push(rest);
+            break;
+        }
+
+        let mut cut = offsets[max_tokens].0;
+        let half = offsets[max_tokens / 2].0;
+        if let Some(pos) = rest[..cut].rfind('\n').or_else(|| rest[..cut].rfind(' ')) {
+            if pos > half {
+                cut = pos + 1;
+            }
+        }
+        // Always make progress, even on odd offsets
+        if cut == 0 {
+            cut = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+
+        let (piece, tail) = rest.split_at(cut);
+        // Re-tokenizing a piece on its own can add tokens at the cut, so check again
+        if counter.count_tokens(piece) > max_tokens && piece.len() < rest.len() {
+            pieces.extend(split_to_token_limit(piece, max_tokens, counter));
+        } else {
+            pieces.push(piece);
+        }
+        rest = tail;
+    }
+
+    pieces.retain(|p| !p.trim().is_empty());
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One token per ASCII word, one per CJK character
+    struct WordsAndCjk;
+
+    impl TokenCounter for WordsAndCjk {
+        fn token_offsets(&self, text: &str) -> Vec<(usize, usize)> {
+            let mut offsets = Vec::new();
+            let mut word_start = None;
+            for (i, c) in text.char_indices() {
+                if c.is_ascii_alphanumeric() {
+                    word_start.get_or_insert(i);
+                    continue;
+                }
+                if let Some(start) = word_start.take() {
+                    offsets.push((start, i));
+                }
+                if !c.is_whitespace() {
+                    offsets.push((i, i + c.len_utf8()));
+                }
+            }
+            if let Some(start) = word_start {
+                offsets.push((start, text.len()));
+            }
+            offsets
+        }
+    }
+
+    #[test]
+    fn test_split_respects_token_limit_for_ascii_and_cjk() {
+        let ascii = "the quick brown fox jumps over the lazy dog ".repeat(20);
+        let cjk = "知识库把所有文档连接在一起并保存集体记忆".repeat(10);
+
+        for text in [ascii.as_str(), cjk.as_str()] {
+            let pieces = split_to_token_limit(text, 25, &WordsAndCjk);
+            assert!(pieces.len() > 1);
+            assert!(pieces.iter().all(|p| WordsAndCjk.count_tokens(p) <= 25), "{:?}", pieces);
+            assert_eq!(pieces.concat().replace(' ', ""), text.replace(' ', ""));
+        }
+
+        // ASCII pieces end on word boundaries
+        let pieces = split_to_token_limit(&ascii, 25, &WordsAndCjk);
+        assert!(pieces[..pieces.len() - 1].iter().all(|p| p.ends_with(' ')));
+    }
+
+    #[test]
+    fn test_text_within_limit_is_one_piece() {
+        assert_eq!(split_to_token_limit("short note", 25, &WordsAndCjk), vec!["short note"]);
+        assert!(split_to_token_limit("", 25, &WordsAndCjk).is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+//! Bookmark command handler
+
+use anyhow::Result;
+use std::path::Path;
+use eywa::ContentStore;
+
+pub fn run_bookmark(data_dir: &str, doc_id: Option<&str>, note: Option<&str>, remove: bool) -> Result<()> {
+    let content_store = ContentStore::open(&Path::new(data_dir).join("content.db"))?;
+
+    match doc_id {
+        Some(doc_id) if remove => {
+            if content_store.remove_bookmark(doc_id)? {
+                println!("Removed bookmark for '{}'", doc_id);
+            } else {
+                println!("Document '{}' is not bookmarked.", doc_id);
+            }
+        }
+        Some(doc_id) => {
+            content_store.add_bookmark(doc_id, note)?;
+            println!("Bookmarked '{}'", doc_id);
+        }
+        None => {
+            let bookmarks = content_store.list_bookmarks()?;
+            if bookmarks.is_empty() {
+                println!("No bookmarks yet. Use 'eywa bookmark <doc_id>' to add one.");
+            } else {
+                println!("Bookmarks:\n");
+                for bookmark in bookmarks {
+                    println!("  {} - {} [{}]", bookmark.document_id, bookmark.title, bookmark.source_id);
+                    if let Some(note) = bookmark.note {
+                        println!("      {}", note);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -42,7 +42,7 @@ pub fn run_info(data_dir: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn run_storage(data_dir: &str) -> Result<()> {
+pub async fn run_storage(data_dir: &str) -> Result<()> {
     println!("Eywa Storage Usage\n");
 
     // Data storage
@@ -61,6 +61,29 @@ pub fn run_storage(data_dir: &str) -> Result<()> {
     println!("  \x1b[90m───────────────────────────────\x1b[0m");
     println!("  Subtotal               {:>12}", format_bytes(data_total));
 
+    // Vector DB breakdown: raw .lance size includes old versions
+    let db = super::open_db(data_dir).await?;
+    let tables = db.storage_report().await?;
+    if !tables.is_empty() {
+        println!("\n\x1b[1mVector DB\x1b[0m");
+        println!("  {:<14} {:>10} {:>10} {:>9} {:>12}", "Table", "Rows", "Fragments", "Versions", "Reclaimable");
+        for table in &tables {
+            println!(
+                "  {:<14} {:>10} {:>10} {:>9} {:>12}",
+                table.name,
+                table.rows,
+                table.fragments,
+                table.versions,
+                format_bytes(table.reclaimable_bytes)
+            );
+        }
+        let reclaimable: u64 = tables.iter().map(|t| t.reclaimable_bytes).sum();
+        let fragments: usize = tables.iter().map(|t| t.fragments).sum();
+        if reclaimable > 0 || fragments > tables.len() {
+            println!("  \x1b[90mRun 'eywa optimize' to compact fragments and free {}\x1b[0m", format_bytes(reclaimable));
+        }
+    }
+
     // Models storage (scan HuggingFace cache)
     let cached_models = scan_hf_cache();
     let models_total: u64 = cached_models.iter().map(|m| m.size_bytes).sum();
@@ -83,3 +106,20 @@ pub fn run_storage(data_dir: &str) -> Result<()> {
 
     Ok(())
 }
+
+pub async fn run_optimize(data_dir: &str) -> Result<()> {
+    let db = super::open_db(data_dir).await?;
+    let before = lance_db_size(Path::new(data_dir));
+
+    println!("Optimizing vector database...");
+    db.optimize().await?;
+
+    let after = lance_db_size(Path::new(data_dir));
+    println!(
+        "\x1b[32m✓\x1b[0m Vector DB {} → {} (freed {})",
+        format_bytes(before),
+        format_bytes(after),
+        format_bytes(before.saturating_sub(after))
+    );
+    Ok(())
+}
@@ -3,28 +3,170 @@
 use anyhow::Result;
 use std::path::Path;
 use std::sync::Arc;
-use eywa::{BM25Index, Embedder, IngestPipeline, VectorDB};
+use eywa::{
+    explain_decisions, BM25Index, Config, DedupScope, Embedder, FileWalk, IngestPipeline, ProgressStyle,
+    VectorDB, WalkOptions,
+};
 
-pub async fn run_ingest(data_dir: &str, source: &str, path: &Path) -> Result<()> {
-    println!("Initializing embedder...");
-    let embedder = Arc::new(Embedder::new()?);
+#[allow(clippy::too_many_arguments)]
+pub async fn run_ingest(
+    data_dir: &str,
+    config: &Config,
+    source: Option<String>,
+    path: &Path,
+    walk_options: WalkOptions,
+    link: bool,
+    passage_prefix: Option<String>,
+    dedup_scope: DedupScope,
+    split_sections: Option<usize>,
+    update: bool,
+    force: bool,
+    manifest_path: Option<&Path>,
+    progress_style: ProgressStyle,
+) -> Result<()> {
+    // Setup chatter; `--quiet` keeps only the results
+    let chatty = progress_style != ProgressStyle::Hidden;
+    if chatty {
+        println!("Initializing embedder...");
+    }
+    let mut embedder = Embedder::from_config(config)?;
+    if let Some(prefix) = passage_prefix {
+        embedder = embedder.with_passage_prefix(prefix);
+    }
+    let embedder = Arc::new(embedder);
 
-    println!("Connecting to database...");
-    let mut db = VectorDB::new(data_dir).await?;
+    if chatty {
+        println!("Connecting to database...");
+    }
+    let mut db = VectorDB::open(data_dir, config.per_source_tables).await?;
     let data_path = Path::new(data_dir);
     let bm25_index = Arc::new(BM25Index::open(data_path)?);
 
-    println!("Ingesting documents from: {}\n", path.display());
-    let pipeline = IngestPipeline::new(embedder, bm25_index);
+    let source = config.resolve_source(source);
+    if chatty {
+        println!("Ingesting documents from: {}\n", path.display());
+    }
+    let pipeline = IngestPipeline::new(embedder, bm25_index)
+        .with_settings(config)
+        .with_linked_files(link)
+        .with_dedup_scope(dedup_scope)
+        .with_split_sections(split_sections)
+        .with_update(update)
+        .with_force(force)
+        .with_progress_style(progress_style);
 
-    let path_str = path.to_string_lossy().to_string();
-    let result = pipeline.ingest_from_path(&mut db, data_path, source, &path_str).await?;
+    // Files are read as the walk finds them, so skipped paths are reported afterwards
+    let mut walk = FileWalk::new(path, &walk_options)?;
+    let (result, manifest) = pipeline
+        .ingest_file_stream(&mut db, data_path, &source, &mut walk)
+        .await?;
+
+    let walk = walk.into_result();
+    if walk_options.explain {
+        println!("\nIngest decisions:");
+        for decision in explain_decisions(&walk, &manifest) {
+            println!("  {}", decision);
+        }
+        println!();
+    }
+    for (pattern, count) in &walk.excluded {
+        println!("  Excluded {} file(s) matching '{}'", count, pattern);
+    }
+    if !walk.ignored.is_empty() {
+        println!("  Skipped {} ignored path(s) (use --no-ignore to ingest them)", walk.ignored.len());
+    }
+    if !walk.hidden.is_empty() {
+        println!("  Skipped {} hidden path(s) (use --include-hidden to ingest them)", walk.hidden.len());
+    }
+    for (link, target) in &walk.symlinks {
+        println!("  Skipped symlink {} -> {} (use --follow-symlinks)", link.display(), target.display());
+    }
+    for link in &walk.cycles {
+        println!("  Skipped symlink cycle at {}", link.display());
+    }
 
     println!("\nIngestion complete!");
     println!("  Source: {}", result.source_id);
     println!("  Documents created: {}", result.documents_created);
+    if result.documents_skipped > 0 {
+        println!("  Files skipped (unchanged): {}", result.documents_skipped);
+    }
+    if result.files_skipped_binary > 0 {
+        println!("  Files skipped (binary): {}", result.files_skipped_binary);
+    }
+    if !result.files_failed.is_empty() {
+        println!("  Files failed: {}", result.files_failed.len());
+        for failed in &result.files_failed {
+            println!("    {}: {}", failed.file_path, failed.error);
+        }
+    }
+    if !result.files_lossy_utf8.is_empty() {
+        println!("  Files with invalid UTF-8 (bytes replaced): {}", result.files_lossy_utf8.len());
+        for path in &result.files_lossy_utf8 {
+            println!("    {}", path);
+        }
+    }
     println!("  Chunks created: {}", result.chunks_created);
     println!("  Chunks skipped (duplicates): {}", result.chunks_skipped);
 
+    if let Some(manifest_path) = manifest_path {
+        manifest.write(manifest_path)?;
+        println!("  Manifest: {} ({} entries)", manifest_path.display(), manifest.entries.len());
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eywa::DevicePreference;
+
+    #[tokio::test]
+    async fn test_ingest_without_source_uses_default_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(
+            docs.join("notes.md"),
+            "# Notes\n\nThe staging cluster is rebuilt every Monday morning, so deploy to it after lunch.\n",
+        )
+        .unwrap();
+
+        let config = Config {
+            default_source: "work".to_string(),
+            device: DevicePreference::Cpu,
+            ..Config::default()
+        };
+        let data_dir = data_dir.to_string_lossy().to_string();
+        run_ingest(
+            &data_dir,
+            &config,
+            None,
+            &docs,
+            WalkOptions::default(),
+            false,
+            None,
+            config.dedup_scope,
+            None,
+            false,
+            false,
+            None,
+            ProgressStyle::Hidden,
+        )
+        .await
+        .unwrap();
+
+        let db = VectorDB::open(&data_dir, config.per_source_tables).await.unwrap();
+        let sources: Vec<(String, u64)> = db
+            .list_sources()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.doc_count))
+            .collect();
+        assert_eq!(sources, vec![("work".to_string(), 1)]);
+    }
+}
@@ -1,15 +1,14 @@
 //! Init command handler
 
 use anyhow::Result;
-use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
 use eywa::{
-    run_download_wizard, run_init, BM25Index, Config, ContentStore,
-    DocumentInput, Embedder, IngestPipeline, InitResult, Reranker, VectorDB,
+    run_download_wizard, run_init, Config, ContentStore, Embedder, InitResult, ProgressStyle, Reranker,
 };
+use super::reindex::{marker_path, reindex_all};
 
-pub async fn run_init_command(data_dir: &str, default: bool) -> Result<()> {
+pub async fn run_init_command(data_dir: &str, default: bool, progress_style: ProgressStyle) -> Result<()> {
     // Non-interactive mode for CI/scripting
     if default {
         let config = Config::default();
@@ -25,7 +24,7 @@ pub async fn run_init_command(data_dir: &str, default: bool) -> Result<()> {
     let existing = Config::load()?;
 
     // Check if previous re-indexing was interrupted
-    let marker_path = Path::new(data_dir).join(".reindex_in_progress");
+    let marker_path = marker_path(data_dir);
     let interrupted = marker_path.exists();
 
     match run_init(existing.as_ref())? {
@@ -47,8 +46,7 @@ pub async fn run_init_command(data_dir: &str, default: bool) -> Result<()> {
 
                 // 1. Get document count from SQLite
                 let content_path = Path::new(data_dir).join("content.db");
-                let content_store = ContentStore::open(&content_path)?;
-                let doc_count = content_store.document_count()?;
+                let doc_count = ContentStore::open(&content_path)?.document_count()?;
 
                 if doc_count == 0 {
                     println!("  No documents to re-index.\n");
@@ -57,58 +55,18 @@ pub async fn run_init_command(data_dir: &str, default: bool) -> Result<()> {
                     // Remove marker if it exists
                     std::fs::remove_file(&marker_path).ok();
                 } else {
-                    // 2. Get all documents with metadata from SQLite
-                    let documents = content_store.get_all_documents_with_metadata()?;
-                    println!("  Found {} documents to re-index\n", documents.len());
-
-                    // 3. Download new models
+                    // 2. Download new models
                     run_download_wizard(&config)?;
 
-                    // 4. Initialize new embedder
+                    // 3. Initialize new embedder
                     let embedder = Arc::new(Embedder::new()?);
                     let _reranker = Reranker::new()?;
 
-                    // 5. Create marker file before starting (survives interruption)
-                    std::fs::write(&marker_path, "")?;
-
-                    // 6. Reset LanceDB and BM25 index (SQLite stays intact with content)
-                    let mut db = VectorDB::new(data_dir).await?;
-                    db.reset_all().await?;
-                    let data_path = Path::new(data_dir);
-                    let bm25_index = Arc::new(BM25Index::open(data_path)?);
-                    bm25_index.reset()?;
-
-                    // 7. Re-ingest from SQLite
-                    println!("\n  Re-indexing documents...\n");
-                    let pipeline = IngestPipeline::new(embedder, bm25_index);
-                    let mut total_chunks = 0u32;
-
-                    for (i, doc) in documents.iter().enumerate() {
-                        // Show progress
-                        print!("\r  [{}/{}] {}                              ",
-                            i + 1, documents.len(),
-                            if doc.title.len() > 40 { &doc.title[..40] } else { &doc.title }
-                        );
-                        std::io::stdout().flush()?;
-
-                        let doc_input = DocumentInput {
-                            content: doc.content.clone(),
-                            title: Some(doc.title.clone()),
-                            file_path: doc.file_path.clone(),
-                            is_pdf: false,
-                        };
-
-                        let result = pipeline
-                            .ingest_documents(&mut db, data_path, &doc.source_id, vec![doc_input])
-                            .await?;
-                        total_chunks += result.chunks_created;
-                    }
-
-                    // 8. Remove marker on successful completion
-                    std::fs::remove_file(&marker_path).ok();
+                    // 4. Reset LanceDB/BM25 and re-ingest from SQLite
+                    let summary = reindex_all(data_dir, &config, embedder, progress_style).await?;
 
                     println!("\n\n\x1b[32m✓\x1b[0m Re-indexed {} documents ({} chunks)\n",
-                        documents.len(), total_chunks);
+                        summary.documents, summary.chunks);
                 }
             } else {
                 // No re-indexing needed, just download models
@@ -0,0 +1,149 @@
+//! Jobs command handler
+
+use anyhow::{Context, Result};
+use eywa::{JobKind, JobProgress, JobQueue, JobStatus};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Width of the progress bar in characters
+const BAR_WIDTH: usize = 20;
+/// How often `--watch` polls the server
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// List jobs, show one job, or watch one job on the running server
+pub async fn run_jobs(data_dir: &str, job_id: Option<&str>, watch: bool, port: u16) -> Result<()> {
+    match job_id {
+        Some(job_id) if watch => watch_job(job_id, port).await,
+        Some(job_id) => {
+            let queue = open_queue(data_dir)?;
+            match queue.get_job(job_id)? {
+                Some(job) => {
+                    println!("{}", format_progress(&job));
+                    if let Some(error) = &job.error {
+                        println!("  \x1b[31m✗\x1b[0m {}", error);
+                    }
+                    for doc in queue.get_job_docs(job_id)?.iter().filter(|d| d.error.is_some()) {
+                        let title = doc.title.as_deref().unwrap_or(&doc.id);
+                        println!("  \x1b[31m✗\x1b[0m {}: {}", title, doc.error.as_deref().unwrap_or_default());
+                    }
+                }
+                None => println!("Job '{}' not found.", job_id),
+            }
+            Ok(())
+        }
+        None => {
+            let jobs = open_queue(data_dir)?.list_jobs()?;
+            if jobs.is_empty() {
+                println!("No jobs yet. Jobs are created by async ingest on the server.");
+            } else {
+                for job in &jobs {
+                    println!("{}", format_progress(job));
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Open the job DB the server writes to (inspection works without the server)
+fn open_queue(data_dir: &str) -> Result<JobQueue> {
+    JobQueue::open(&Path::new(data_dir).join("jobs.db"))
+}
+
+/// Poll the server's job API and redraw one progress line until the job ends
+async fn watch_job(job_id: &str, port: u16) -> Result<()> {
+    let url = format!("http://localhost:{}/api/jobs/{}", port, job_id);
+    let client = reqwest::Client::new();
+
+    loop {
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Could not reach the server on port {} (is 'eywa serve' running?)", port))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Job '{}' not found", job_id);
+        }
+        let job: JobProgress = response.error_for_status()?.json().await?;
+
+        print!("\r\x1b[2K{}", format_progress(&job));
+        std::io::stdout().flush()?;
+
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            println!();
+            return Ok(());
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+/// One-line summary of a job: id, status, bar, counts and current document
+pub fn format_progress(job: &JobProgress) -> String {
+    let processed = job.completed + job.failed;
+    let filled = if job.total == 0 {
+        0
+    } else {
+        (processed as usize * BAR_WIDTH / job.total as usize).min(BAR_WIDTH)
+    };
+
+    let status = job.status.to_string();
+    // A reindex covers every source
+    let scope = match job.kind {
+        JobKind::Reindex => "reindex",
+        JobKind::Ingest => job.source_id.as_str(),
+    };
+    let mut line = format!(
+        "{} [{}] {:<10} [{}{}] {}/{}",
+        job.job_id,
+        scope,
+        status,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        processed,
+        job.total,
+    );
+    if job.failed > 0 {
+        line.push_str(&format!(" ({} failed)", job.failed));
+    }
+    if job.status == JobStatus::Processing {
+        if let Some(current) = &job.current_doc {
+            line.push_str(&format!(" - {}", current));
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(status: JobStatus, completed: u32, failed: u32) -> JobProgress {
+        JobProgress {
+            job_id: "job-1".to_string(),
+            kind: JobKind::Ingest,
+            source_id: "docs".to_string(),
+            status,
+            total: 10,
+            completed,
+            failed,
+            current_doc: Some("guide.md".to_string()),
+            created_at: "2024-01-01 00:00:00".to_string(),
+            completed_at: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_format_progress_line() {
+        let line = format_progress(&job(JobStatus::Processing, 4, 1));
+        assert_eq!(
+            line,
+            "job-1 [docs] processing [##########----------] 5/10 (1 failed) - guide.md"
+        );
+
+        // Finished jobs don't show a current document
+        let line = format_progress(&job(JobStatus::Done, 10, 0));
+        assert_eq!(line, "job-1 [docs] done       [####################] 10/10");
+    }
+}
@@ -6,10 +6,31 @@ pub mod sources;
 pub mod reset;
 pub mod info;
 pub mod init;
+pub mod bookmarks;
+pub mod verify;
+pub mod jobs;
+pub mod reindex;
+pub mod watch;
 
 pub use ingest::run_ingest;
 pub use search::run_search;
-pub use sources::{run_sources, run_docs, run_delete};
+pub use sources::{run_sources, run_docs, run_find, run_delete, run_merge, run_summary};
 pub use reset::{run_reset, run_hard_reset, run_uninstall};
-pub use info::{run_info, run_storage};
+pub use info::{run_info, run_optimize, run_storage};
 pub use init::run_init_command;
+pub use bookmarks::run_bookmark;
+pub use verify::{run_config_check, run_verify};
+pub use jobs::run_jobs;
+pub use reindex::run_reindex;
+pub use watch::run_watch;
+
+use anyhow::Result;
+use eywa::{Config, VectorDB};
+
+/// Open the vector database with the chunk table layout from the config
+pub async fn open_db(data_dir: &str) -> Result<VectorDB> {
+    let per_source_tables = Config::load()?
+        .map(|c| c.per_source_tables)
+        .unwrap_or(false);
+    VectorDB::open(data_dir, per_source_tables).await
+}
@@ -0,0 +1,174 @@
+//! Reindex command handler
+//!
+//! Rebuilds LanceDB and the BM25 index from the content stored in SQLite,
+//! without re-reading any source files.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use eywa::{
+    write_progress, BM25Index, Config, ContentStore, Embedder, EywaError,
+    IngestPipeline, ProgressStyle, VectorDB,
+};
+
+const BAR_WIDTH: usize = 20;
+
+/// Marker file that exists while a re-index is running (survives interruption)
+pub fn marker_path(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join(".reindex_in_progress")
+}
+
+/// Documents and chunks rebuilt by a re-index
+pub struct ReindexSummary {
+    pub documents: usize,
+    pub chunks: u32,
+}
+
+pub async fn run_reindex(data_dir: &str, progress_style: ProgressStyle) -> Result<()> {
+    let Some(config) = Config::load()? else {
+        return Err(EywaError::NotInitialized.into());
+    };
+
+    if marker_path(data_dir).exists() {
+        println!("\x1b[33m!\x1b[0m Previous re-indexing was interrupted. Resuming...\n");
+    }
+
+    let embedder = Arc::new(Embedder::new()?);
+    let summary = reindex_all(data_dir, &config, embedder, progress_style).await?;
+
+    if summary.documents == 0 {
+        println!("No documents to re-index.");
+    } else {
+        println!("\n\n\x1b[32m✓\x1b[0m Re-indexed {} documents ({} chunks)",
+            summary.documents, summary.chunks);
+    }
+
+    Ok(())
+}
+
+/// Reset LanceDB and BM25, then re-embed every document in SQLite with the given embedder.
+/// Documents keep their IDs; only their chunks are rebuilt.
+/// The marker file is written before anything is reset and removed only on success.
+pub async fn reindex_all(
+    data_dir: &str,
+    config: &Config,
+    embedder: Arc<Embedder>,
+    progress_style: ProgressStyle,
+) -> Result<ReindexSummary> {
+    let marker = marker_path(data_dir);
+    let data_path = Path::new(data_dir);
+    let content_store = ContentStore::open(&data_path.join("content.db"))?;
+    let documents = content_store.get_all_documents_with_metadata()?;
+
+    if documents.is_empty() {
+        std::fs::remove_file(&marker).ok();
+        return Ok(ReindexSummary { documents: 0, chunks: 0 });
+    }
+    let chatty = progress_style != ProgressStyle::Hidden;
+    if chatty {
+        println!("  Found {} documents to re-index\n", documents.len());
+    }
+
+    std::fs::write(&marker, "")?;
+
+    // SQLite stays intact - it's the source of truth for content
+    let mut db = VectorDB::open(data_dir, config.per_source_tables).await?;
+    db.reset_all().await?;
+    let bm25_index = Arc::new(BM25Index::open(data_path)?);
+    bm25_index.reset()?;
+    content_store.clear_index_model()?;
+    drop(content_store);
+
+    if chatty {
+        println!("  Re-indexing documents...\n");
+    }
+    let pipeline = IngestPipeline::new(embedder, bm25_index)
+        .with_settings(config)
+        .with_progress_style(progress_style);
+    let mut total_chunks = 0u32;
+
+    for (i, doc) in documents.iter().enumerate() {
+        let title: String = doc.title.chars().take(40).collect();
+        let message = format!("{} {}", format_bar(i + 1, documents.len()), title);
+        write_progress(&mut std::io::stdout(), progress_style, &message)?;
+
+        // Same document ID, so bookmarks, pins and tags stay attached
+        let result = pipeline.reindex_document(&mut db, data_path, doc).await?;
+        total_chunks += result.chunks_created;
+    }
+
+    std::fs::remove_file(&marker).ok();
+
+    Ok(ReindexSummary { documents: documents.len(), chunks: total_chunks })
+}
+
+/// Progress bar with counts, e.g. `[#####---------------] 5/20`
+pub fn format_bar(done: usize, total: usize) -> String {
+    let filled = if total == 0 {
+        0
+    } else {
+        (done * BAR_WIDTH / total).min(BAR_WIDTH)
+    };
+    format!("[{}{}] {}/{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled), done, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eywa::{DevicePreference, DocumentInput, EmbeddingModelConfig};
+
+    #[tokio::test]
+    async fn test_reindex_keeps_document_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let data_dir = data_path.to_string_lossy().to_string();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(&data_dir).await.unwrap();
+        let docs = [
+            "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+            "The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock.",
+        ];
+        let response = IngestPipeline::new(Arc::clone(&embedder), bm25_index)
+            .ingest_documents(&mut db, data_path, "notes", docs.iter().map(|d| DocumentInput {
+                content: d.to_string(),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            }).collect())
+            .await
+            .unwrap();
+        let chunks_before = db.list_sources().await.unwrap()[0].chunk_count;
+        drop(db);
+
+        let content_store = ContentStore::open(&data_path.join("content.db")).unwrap();
+        content_store.add_bookmark(&response.document_ids[0], None).unwrap();
+        let ids = |store: &ContentStore| {
+            let mut ids: Vec<String> = store.get_all_documents_with_metadata().unwrap().into_iter().map(|d| d.id).collect();
+            ids.sort();
+            ids
+        };
+        let ids_before = ids(&content_store);
+        drop(content_store);
+
+        let summary = reindex_all(&data_dir, &Config::default(), embedder, ProgressStyle::Hidden).await.unwrap();
+        assert_eq!(summary.documents, 2);
+
+        let content_store = ContentStore::open(&data_path.join("content.db")).unwrap();
+        assert_eq!(content_store.count_documents().unwrap(), 2);
+        assert_eq!(ids(&content_store), ids_before);
+        assert_eq!(content_store.list_bookmarks().unwrap().len(), 1);
+        let db = VectorDB::new(&data_dir).await.unwrap();
+        assert_eq!(db.list_sources().await.unwrap()[0].chunk_count, chunks_before);
+        assert!(!marker_path(&data_dir).exists());
+    }
+
+    #[test]
+    fn test_format_bar() {
+        assert_eq!(format_bar(5, 20), "[#####---------------] 5/20");
+        assert_eq!(format_bar(3, 3), "[####################] 3/3");
+        assert_eq!(format_bar(0, 0), "[--------------------] 0/0");
+    }
+}
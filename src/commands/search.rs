@@ -1,48 +1,114 @@
 //! Search command handler
 
-use anyhow::Result;
-use std::collections::HashMap;
-use std::path::Path;
-use eywa::{ContentStore, Embedder, SearchEngine, SearchResult, VectorDB};
-
-pub async fn run_search(data_dir: &str, query: &str, limit: usize) -> Result<()> {
-    let embedder = Embedder::new()?;
-    let db = VectorDB::new(data_dir).await?;
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use eywa::search::{preview, DEFAULT_PREVIEW_CHARS};
+use eywa::{BM25Index, Config, ContentStore, Embedder, EywaError, SearchEngine, SearchResult, VectorDB};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn run_search(
+    data_dir: &str,
+    query: &str,
+    limit: usize,
+    source: Option<&str>,
+    code_only: bool,
+    query_prefix: Option<String>,
+    count: bool,
+    open: Option<usize>,
+    json_lines: bool,
+    json: bool,
+    candidates_per_source: Option<usize>,
+    min_length: usize,
+    group_by_source: bool,
+    embedding_only: bool,
+    dedup: bool,
+    quiet: bool,
+) -> Result<()> {
+    eywa::require_models()?;
+    let config = Config::load()?.unwrap_or_default();
+    let mut embedder = Embedder::new()?;
+    if let Some(prefix) = query_prefix {
+        embedder = embedder.with_query_prefix(prefix);
+    }
+    let db = VectorDB::open(data_dir, config.per_source_tables).await?;
+
+    if let Some(source) = source {
+        require_documents(&db, source).await?;
+    }
+
+    // Count only: no content fetch, no reranker, no previews
+    if count {
+        let min_score = SearchEngine::new().with_settings(&config).min_score;
+        let stdout = std::io::stdout();
+        return write_count(&db, &embedder, query, source, code_only, min_score, &mut stdout.lock()).await;
+    }
+
     let content_store = ContentStore::open(&Path::new(data_dir).join("content.db"))?;
-    let search_engine = SearchEngine::with_reranker()?;
-
-    println!("Searching for: {}\n", query);
-
-    let query_embedding = embedder.embed(query)?;
-    let chunk_metas = db.search(&query_embedding, 50).await?;
-
-    // Fetch content from SQLite
-    let chunk_ids: Vec<&str> = chunk_metas.iter().map(|c| c.id.as_str()).collect();
-    let contents = content_store.get_chunks(&chunk_ids)?;
-    let content_map: HashMap<String, String> = contents.into_iter().collect();
-
-    // Combine metadata + content
-    let results: Vec<SearchResult> = chunk_metas
-        .into_iter()
-        .filter_map(|meta| {
-            let content = content_map.get(&meta.id)?.clone();
-            Some(SearchResult {
-                id: meta.id,
-                source_id: meta.source_id,
-                title: meta.title,
-                content,
-                file_path: meta.file_path,
-                line_start: meta.line_start,
-                score: meta.score,
-            })
-        })
-        .collect();
-
-    let results = search_engine.filter_results(results);
-    let results = search_engine.rerank(results, query, limit);
 
+    if !json_lines && !json && !quiet {
+        println!("Searching for: {}\n", query);
+    }
+
+    let results = if embedding_only {
+        // Nearest chunks by cosine only; the reranker is never loaded
+        SearchEngine::new()
+            .search_embedding_only(&embedder, &db, &content_store, query, limit, source, code_only)
+            .await?
+    } else {
+        let search_engine = SearchEngine::with_reranker()
+            .with_settings(&config)
+            .with_synonyms_file()
+            .with_dedup_by_document(dedup);
+        let per_source = candidates_per_source.unwrap_or(search_engine.candidates_per_source);
+        // Grouping summarizes every candidate, so only the number of groups is limited
+        let rerank_limit = if group_by_source { usize::MAX } else { limit };
+        let bm25_index = BM25Index::open(Path::new(data_dir))?;
+        reranked_search(
+            &search_engine,
+            &embedder,
+            &db,
+            &bm25_index,
+            &content_store,
+            query,
+            source,
+            code_only,
+            per_source,
+            min_length,
+            rerank_limit,
+        )
+        .await?
+    };
+
+    if json_lines {
+        // One object per line, flushed as written, for streaming consumers
+        let stdout = std::io::stdout();
+        write_json_lines(results.iter().take(limit), &mut stdout.lock())?;
+        return Ok(());
+    }
+
+    if json {
+        let results: Vec<&SearchResult> = results.iter().take(limit).collect();
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    let preview_chars = config.preview_chars.unwrap_or(DEFAULT_PREVIEW_CHARS);
     if results.is_empty() {
         println!("No results found.");
+    } else if group_by_source {
+        for (i, group) in group_by_source_id(&results).iter().take(limit).enumerate() {
+            let top = group.top;
+            let hits = if group.hits == 1 { "hit" } else { "hits" };
+            println!("{}. {} ({} {})", i + 1, group.source_id, group.hits, hits);
+            print!("   Top: [Score: {:.3}] {}", top.score, top.title.as_deref().unwrap_or("(untitled)"));
+            if let Some(ref file_path) = top.file_path {
+                print!(" - {}", file_path);
+            }
+            println!();
+            println!("   Preview: {}\n", preview(&top.content, preview_chars));
+        }
     } else {
         for (i, result) in results.iter().take(limit).enumerate() {
             println!("{}. [Score: {:.3}]", i + 1, result.score);
@@ -53,20 +119,444 @@ pub async fn run_search(data_dir: &str, query: &str, limit: usize) -> Result<()>
                 print!("   File: {}", file_path);
                 if let Some(line) = result.line_start {
                     print!(":{}", line);
+                    if let Some(end) = result.line_end.filter(|&end| end > line) {
+                        print!("-{}", end);
+                    }
+                }
+                if let Some(page) = result.page_start {
+                    match result.page_end.filter(|&end| end > page) {
+                        Some(end) => print!(" (pages {}-{})", page, end),
+                        None => print!(" (page {})", page),
+                    }
                 }
                 println!();
             }
+            if let Some(ref section) = result.section {
+                println!("   Section: {}", section);
+            }
             println!("   Source: {}", result.source_id);
 
-            // Show first 200 chars of content
-            let preview: String = result
-                .content
-                .chars()
-                .take(200)
-                .collect();
-            println!("   Preview: {}...\n", preview.replace('\n', " "));
+            println!("   Preview: {}\n", preview(&result.content, preview_chars));
+        }
+    }
+
+    if let Some(n) = open {
+        let shown = results.len().min(limit);
+        let Some(result) = n.checked_sub(1).and_then(|i| results.get(i)).filter(|_| n <= shown) else {
+            bail!("No result {} to open ({} shown)", n, shown);
+        };
+        open_in_editor(result)?;
+    }
+
+    Ok(())
+}
+
+/// Fail with "No documents in source" for an unknown or empty source,
+/// rather than searching it and printing "No results found."
+async fn require_documents(db: &VectorDB, source: &str) -> Result<()> {
+    let sources = db.list_sources().await?;
+    if !sources.iter().any(|s| s.id == source && s.doc_count > 0) {
+        return Err(anyhow::Error::from(EywaError::SourceNotFound(source.to_string()))
+            .context(format!("No documents in source '{}'", source)));
+    }
+    Ok(())
+}
+
+/// Hybrid vector + BM25 candidates, then reranking with pins: the default `eywa search` path
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn reranked_search(
+    search_engine: &SearchEngine,
+    embedder: &Embedder,
+    db: &VectorDB,
+    bm25_index: &BM25Index,
+    content_store: &ContentStore,
+    query: &str,
+    source: Option<&str>,
+    code_only: bool,
+    per_source: usize,
+    min_length: usize,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let query_embedding = embedder.embed_query(query)?;
+    let results = search_engine
+        .hybrid_candidates(
+            db,
+            bm25_index,
+            content_store,
+            query,
+            &query_embedding,
+            50,
+            source,
+            code_only,
+            per_source,
+            search_engine.min_score,
+        )
+        .await?;
+
+    let results = search_engine.filter_min_length(results, min_length);
+    let result_ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    let pinned = content_store.pinned_chunks(&result_ids)?;
+    Ok(search_engine.rerank_with_pins(results, query, limit, &pinned))
+}
+
+/// Hits for one source in a grouped search
+struct SourceGroup<'a> {
+    source_id: &'a str,
+    hits: usize,
+    top: &'a SearchResult,
+}
+
+/// Group results by source, keeping each source's highest-scoring result.
+/// Groups are ordered by their top score.
+fn group_by_source_id(results: &[SearchResult]) -> Vec<SourceGroup<'_>> {
+    let mut groups: Vec<SourceGroup> = Vec::new();
+    for result in results {
+        match groups.iter_mut().find(|g| g.source_id == result.source_id) {
+            Some(group) => {
+                group.hits += 1;
+                if result.score > group.top.score {
+                    group.top = result;
+                }
+            }
+            None => groups.push(SourceGroup { source_id: &result.source_id, hits: 1, top: result }),
+        }
+    }
+    groups.sort_by(|a, b| b.top.score.partial_cmp(&a.top.score).unwrap_or(std::cmp::Ordering::Equal));
+    groups
+}
+
+/// Write each result as a standalone JSON object on its own line
+fn write_json_lines<'a, W: Write>(results: impl IntoIterator<Item = &'a SearchResult>, out: &mut W) -> Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut *out, result)?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Write the number of documents scoring at least `min_score` for `query`
+/// (the `--count` output)
+async fn write_count<W: Write>(
+    db: &VectorDB,
+    embedder: &Embedder,
+    query: &str,
+    source: Option<&str>,
+    code_only: bool,
+    min_score: f32,
+    out: &mut W,
+) -> Result<()> {
+    let query_embedding = embedder.embed_query(query)?;
+    let matches = db.count_matches(&query_embedding, min_score, source, code_only).await?;
+    writeln!(out, "{}", matches)?;
+    Ok(())
+}
+
+/// Local file and line a result points at, if it can be opened
+fn open_target(result: &SearchResult) -> Result<(PathBuf, Option<u32>)> {
+    let Some(file_path) = result.file_path.as_deref() else {
+        bail!("Result has no file path to open");
+    };
+    if file_path.contains("://") {
+        bail!("'{}' is not a local file", file_path);
+    }
+    let path = PathBuf::from(file_path);
+    if !path.is_file() {
+        bail!("File not found: {}", file_path);
+    }
+    Ok((path, result.line_start))
+}
+
+/// Build the command that opens `file` at `line` in `editor`
+///
+/// `editor` is the raw `$VISUAL`/`$EDITOR` value and may carry its own
+/// arguments (e.g. `code --wait`). Editors we don't recognise just get the file.
+fn editor_command(editor: &str, file: &Path, line: Option<u32>) -> Option<(String, Vec<String>)> {
+    let mut parts = editor.split_whitespace().map(str::to_string);
+    let program = parts.next()?;
+    let mut args: Vec<String> = parts.collect();
+
+    let name = Path::new(&program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let file = file.to_string_lossy().to_string();
+
+    match (name.as_str(), line) {
+        ("code" | "code-insiders" | "codium" | "cursor", Some(line)) => {
+            args.push("-g".to_string());
+            args.push(format!("{}:{}", file, line));
+        }
+        ("subl" | "zed" | "hx" | "helix", Some(line)) => args.push(format!("{}:{}", file, line)),
+        ("vi" | "vim" | "nvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak", Some(line)) => {
+            args.push(format!("+{}", line));
+            args.push(file);
         }
+        _ => args.push(file),
     }
 
+    Some((program, args))
+}
+
+/// Open a search result in the user's editor
+fn open_in_editor(result: &SearchResult) -> Result<()> {
+    let (path, line) = open_target(result)?;
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let Some((program, args)) = editor_command(&editor, &path, line) else {
+        bail!("$EDITOR is empty");
+    };
+
+    let status = Command::new(&program).args(&args).status()?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eywa::{DevicePreference, DocumentInput, EmbeddingModelConfig, IngestPipeline};
+    use std::sync::Arc;
+
+    fn result_at(file_path: Option<&str>, line: Option<u32>) -> SearchResult {
+        SearchResult {
+            id: "c1".to_string(),
+            source_id: "docs".to_string(),
+            document_id: "d1".to_string(),
+            title: None,
+            content: String::new(),
+            file_path: file_path.map(str::to_string),
+            line_start: line,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
+            score: 1.0,
+            matched_by: Vec::new(),
+        }
+    }
+
+    fn command(editor: &str, line: Option<u32>) -> (String, Vec<String>) {
+        editor_command(editor, Path::new("src/main.rs"), line).unwrap()
+    }
+
+    #[test]
+    fn test_json_lines_are_independently_parseable() {
+        let results = vec![
+            result_at(Some("a.md"), Some(1)),
+            result_at(None, None),
+            SearchResult {
+                content: "multi\nline \"quoted\" content".to_string(),
+                ..result_at(Some("b.md"), Some(9))
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_json_lines(&results, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let parsed: Vec<SearchResult> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), results.len());
+        assert_eq!(parsed[2].content, results[2].content);
+        assert_eq!(parsed[2].line_start, Some(9));
+    }
+
+    #[test]
+    fn test_group_by_source_keeps_highest_scoring_result() {
+        let hit = |id: &str, source: &str, score: f32| SearchResult {
+            id: id.to_string(),
+            source_id: source.to_string(),
+            score,
+            ..result_at(None, None)
+        };
+        let results = vec![
+            hit("a1", "alpha", 0.4),
+            hit("b1", "beta", 0.9),
+            hit("a2", "alpha", 0.7),
+            hit("b2", "beta", 0.2),
+            hit("g1", "gamma", 0.5),
+            hit("a3", "alpha", 0.1),
+        ];
+
+        let groups = group_by_source_id(&results);
+        let summary: Vec<(&str, usize, &str)> = groups
+            .iter()
+            .map(|g| (g.source_id, g.hits, g.top.id.as_str()))
+            .collect();
+        assert_eq!(summary, vec![("beta", 2, "b1"), ("alpha", 3, "a2"), ("gamma", 1, "g1")]);
+    }
+
+    #[tokio::test]
+    async fn test_count_prints_matching_document_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(&data_path.to_string_lossy()).await.unwrap();
+        let docs = [
+            "Kubernetes schedules pods onto nodes and restarts containers that fail their health checks.",
+            "A Kubernetes deployment manages replica sets so rolling updates replace pods without downtime.",
+            "Watercolour painting layers transparent washes of pigment from light to dark on damp paper.",
+        ];
+        IngestPipeline::new(Arc::clone(&embedder), bm25_index)
+            .ingest_documents(&mut db, data_path, "notes", docs.iter().map(|d| DocumentInput {
+                content: d.to_string(),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            }).collect())
+            .await
+            .unwrap();
+
+        let min_score = SearchEngine::new().min_score;
+        let count = |query: &'static str, source: Option<&'static str>| {
+            let (db, embedder) = (&db, &embedder);
+            async move {
+                let mut out = Vec::new();
+                write_count(db, embedder, query, source, false, min_score, &mut out).await.unwrap();
+                String::from_utf8(out).unwrap()
+            }
+        };
+
+        // Just the number, matching what a full search above the threshold finds
+        let expected: std::collections::HashSet<String> = db
+            .search(&embedder.embed_query("kubernetes pods").unwrap(), 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.score >= min_score)
+            .map(|m| m.document_id)
+            .collect();
+        assert_eq!(count("kubernetes pods", None).await, format!("{}\n", expected.len()));
+        assert_eq!(count("kubernetes pods", Some("elsewhere")).await, "0\n");
+    }
+
+    #[tokio::test]
+    async fn test_search_fuses_vector_and_keyword_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(&data_path.to_string_lossy()).await.unwrap();
+        let docs = [
+            "Kubernetes schedules pods onto nodes and restarts containers that fail their health checks.",
+            "Watercolour painting layers transparent washes of pigment from light to dark on damp paper.",
+            "Release notes for the zorblat build: nothing else changed this week.",
+        ];
+        IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+            .ingest_documents(&mut db, data_path, "notes", docs.iter().map(|d| DocumentInput {
+                content: d.to_string(),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            }).collect())
+            .await
+            .unwrap();
+        let content_store = ContentStore::open(&data_path.join("content.db")).unwrap();
+
+        // Found by both signals: ranked first and labelled with both
+        let engine = SearchEngine::new();
+        let results = reranked_search(
+            &engine, &embedder, &db, &bm25_index, &content_store,
+            "kubernetes pods health checks", None, false, 0, 0, 5,
+        )
+        .await
+        .unwrap();
+        assert!(results[0].content.starts_with("Kubernetes"));
+        assert_eq!(results[0].matched_by, vec!["vector", "bm25"]);
+
+        // No vector hit clears the floor, but the exact keyword still matches
+        let engine = SearchEngine::with_min_score(0.99);
+        let results = reranked_search(
+            &engine, &embedder, &db, &bm25_index, &content_store,
+            "zorblat", None, false, 0, 0, 5,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].content.contains("zorblat"));
+        assert_eq!(results[0].matched_by, vec!["bm25"]);
+    }
+
+    #[tokio::test]
+    async fn test_source_filter_searches_only_that_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(&data_path.to_string_lossy()).await.unwrap();
+        let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+        for (source, content) in [
+            ("recipes", "Sourdough bread needs a lively starter and a long, slow rise before baking."),
+            ("baking", "Bake the sourdough loaf in a preheated dutch oven until the crust is deep brown."),
+        ] {
+            let doc = DocumentInput { content: content.to_string(), title: None, file_path: None, is_pdf: false };
+            pipeline.ingest_documents(&mut db, data_path, source, vec![doc]).await.unwrap();
+        }
+        let content_store = ContentStore::open(&data_path.join("content.db")).unwrap();
+        let engine = SearchEngine::with_min_score(0.0);
+        let search = |source: Option<&'static str>| {
+            let (engine, embedder, db, bm25_index, content_store) = (&engine, &embedder, &db, &bm25_index, &content_store);
+            async move {
+                reranked_search(engine, embedder, db, bm25_index, content_store, "sourdough bread", source, false, 0, 0, 10)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(search(None).await.len(), 2);
+        let results = search(Some("baking")).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].source_id, "baking");
+
+        // An unknown source is reported before searching (exit code 3)
+        require_documents(&db, "recipes").await.unwrap();
+        let err = require_documents(&db, "desserts").await.unwrap_err();
+        assert_eq!(err.to_string(), "No documents in source 'desserts'");
+        assert!(matches!(err.downcast_ref::<EywaError>(), Some(EywaError::SourceNotFound(_))));
+    }
+
+    #[test]
+    fn test_editor_command_for_known_editors() {
+        assert_eq!(command("code", Some(42)), ("code".to_string(), vec!["-g".to_string(), "src/main.rs:42".to_string()]));
+        assert_eq!(command("/usr/bin/vim", Some(7)), ("/usr/bin/vim".to_string(), vec!["+7".to_string(), "src/main.rs".to_string()]));
+        assert_eq!(command("hx", Some(3)).1, vec!["src/main.rs:3"]);
+    }
+
+    #[test]
+    fn test_editor_command_keeps_editor_args() {
+        assert_eq!(command("code --wait", Some(10)).1, vec!["--wait", "-g", "src/main.rs:10"]);
+    }
+
+    #[test]
+    fn test_editor_command_without_line_or_unknown_editor() {
+        assert_eq!(command("vim", None).1, vec!["src/main.rs"]);
+        assert_eq!(command("ed", Some(5)).1, vec!["src/main.rs"]);
+        assert!(editor_command("  ", Path::new("a.md"), Some(1)).is_none());
+    }
+
+    #[test]
+    fn test_open_target_rejects_urls_and_missing_files() {
+        assert!(open_target(&result_at(Some("https://example.com/page"), Some(1))).is_err());
+        assert!(open_target(&result_at(Some("/definitely/not/here.md"), Some(1))).is_err());
+        assert!(open_target(&result_at(None, None)).is_err());
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let (path, line) = open_target(&result_at(file.path().to_str(), Some(12))).unwrap();
+        assert_eq!(path, file.path());
+        assert_eq!(line, Some(12));
+    }
+}
@@ -2,13 +2,15 @@
 
 use anyhow::Result;
 use std::path::Path;
-use eywa::{db, BM25Index, ContentStore, VectorDB};
+use eywa::{db, merge_sources, summarize_source, BM25Index, ContentStore, EywaError, JobQueue};
 
-pub async fn run_sources(data_dir: &str) -> Result<()> {
-    let db = VectorDB::new(data_dir).await?;
+pub async fn run_sources(data_dir: &str, json: bool) -> Result<()> {
+    let db = super::open_db(data_dir).await?;
     let sources = db.list_sources().await?;
 
-    if sources.is_empty() {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&sources)?);
+    } else if sources.is_empty() {
         println!("No sources found. Use 'eywa ingest' to add documents.");
     } else {
         println!("Sources:\n");
@@ -20,12 +22,16 @@ pub async fn run_sources(data_dir: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn run_docs(data_dir: &str, source: &str) -> Result<()> {
-    let db = VectorDB::new(data_dir).await?;
-    let docs = db.list_documents(source, Some(db::MAX_QUERY_LIMIT)).await?;
+pub async fn run_docs(data_dir: &str, source: &str, json: bool) -> Result<()> {
+    let db = super::open_db(data_dir).await?;
+    let docs = db.list_documents(source, Some(db::MAX_QUERY_LIMIT), None).await?;
 
     if docs.is_empty() {
-        println!("No documents found in source '{}'.", source);
+        return Err(EywaError::SourceNotFound(source.to_string()).into());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&docs)?);
     } else {
         println!("Documents in '{}':\n", source);
         for doc in docs {
@@ -37,9 +43,24 @@ pub async fn run_docs(data_dir: &str, source: &str) -> Result<()> {
     Ok(())
 }
 
+pub fn run_find(data_dir: &str, text: &str, source: Option<&str>, limit: usize) -> Result<()> {
+    let content_store = ContentStore::open(&Path::new(data_dir).join("content.db"))?;
+    let docs = content_store.find_documents_by_title(text, source, limit)?;
+
+    if docs.is_empty() {
+        println!("No document titles match '{}'.", text);
+    } else {
+        for doc in docs {
+            println!("  {} - {} [{}]", doc.id, doc.title, doc.source_id);
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn run_delete(data_dir: &str, source: &str) -> Result<()> {
     let data_path = Path::new(data_dir);
-    let db = VectorDB::new(data_dir).await?;
+    let db = super::open_db(data_dir).await?;
     let bm25_index = BM25Index::open(data_path)?;
     let content_store = ContentStore::open(&data_path.join("content.db"))?;
 
@@ -52,7 +73,51 @@ pub async fn run_delete(data_dir: &str, source: &str) -> Result<()> {
     bm25_index.delete_source(source)?;
     content_store.delete_source(&doc_id_refs)?;
 
+    // Queued docs would recreate the source the next time the server runs
+    let jobs_db = data_path.join("jobs.db");
+    if jobs_db.exists() {
+        let cancelled = JobQueue::open(&jobs_db)?.cancel_source(source)?;
+        if cancelled > 0 {
+            println!("Cancelled {} queued document(s)", cancelled);
+        }
+    }
+
     println!("Deleted source: {}", source);
 
     Ok(())
 }
+
+pub async fn run_merge(data_dir: &str, from: &str, to: &str) -> Result<()> {
+    let data_path = Path::new(data_dir);
+    let db = super::open_db(data_dir).await?;
+    let bm25_index = BM25Index::open(data_path)?;
+    let content_store = ContentStore::open(&data_path.join("content.db"))?;
+
+    let stats = merge_sources(&db, &bm25_index, &content_store, from, to).await?;
+
+    println!("Merged source '{}' into '{}'", from, to);
+    println!("  Documents moved: {}", stats.documents_moved);
+    println!("  Duplicates skipped: {}", stats.documents_deduped);
+
+    Ok(())
+}
+
+pub async fn run_summary(data_dir: &str, source: &str, clusters: usize, per_cluster: usize) -> Result<()> {
+    let db = super::open_db(data_dir).await?;
+    let summary = summarize_source(&db, source, clusters, per_cluster).await?;
+
+    if summary.is_empty() {
+        return Err(EywaError::SourceNotFound(source.to_string()).into());
+    }
+
+    println!("Topics in '{}':", source);
+    for (i, cluster) in summary.iter().enumerate() {
+        println!("\n  Cluster {} ({} docs)", i + 1, cluster.documents);
+        for doc in &cluster.representatives {
+            let location = doc.file_path.as_deref().unwrap_or(&doc.document_id);
+            println!("    {:.2}  {} - {}", doc.score, doc.title, location);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! Verify command handler
+
+use anyhow::Result;
+use eywa::{verify_config_file, verify_index, verify_models, BM25Index, Config, ContentStore, EywaError, VerifyReport};
+use std::path::Path;
+
+/// Run all health checks and print them; returns the process exit code
+pub async fn run_verify(data_dir: &str) -> Result<u8> {
+    let Some(config) = Config::load()? else {
+        return Err(EywaError::NotInitialized.into());
+    };
+
+    let db = super::open_db(data_dir).await?;
+    let content = ContentStore::open(&Path::new(data_dir).join("content.db"))?;
+    let bm25_index = BM25Index::open(Path::new(data_dir))?;
+
+    let mut report = verify_index(&config, &db, &content, &bm25_index).await?;
+    report.checks.extend(verify_models(&config).checks);
+
+    print_report(&report);
+    Ok(report.exit_code())
+}
+
+/// Validate a config file without saving it; returns the process exit code
+pub fn run_config_check(path: &Path) -> u8 {
+    let report = verify_config_file(path);
+    print_report(&report);
+    report.exit_code()
+}
+
+fn print_report(report: &VerifyReport) {
+    for check in &report.checks {
+        let mark = if check.passed { "\x1b[32m✓\x1b[0m" } else { "\x1b[31m✗\x1b[0m" };
+        println!("{} {:<16} {}", mark, check.name, check.detail);
+    }
+
+    let failed = report.checks.iter().filter(|c| !c.passed).count();
+    if failed == 0 {
+        println!("\nAll checks passed");
+    } else {
+        println!("\n{} check(s) failed", failed);
+    }
+}
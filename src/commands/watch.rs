@@ -0,0 +1,309 @@
+//! Watch command handler
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use eywa::{
+    explain_decisions, walk_includes, BM25Index, Config, ContentStore, Decision, Embedder, FileWalk, IngestPipeline,
+    ProgressStyle, SkipReason, VectorDB, WalkOptions, WalkResult,
+};
+
+/// Quiet period before a burst of events is processed, so an editor's
+/// temp-file-and-rename save is handled as one change
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Longest a change waits while events keep arriving (e.g. a long checkout)
+const MAX_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Changed paths collected until events pause for `DEBOUNCE`, or until
+/// `MAX_DEBOUNCE` after the first of them
+#[derive(Default)]
+struct Debouncer {
+    pending: HashSet<PathBuf>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Debouncer {
+    fn add(&mut self, paths: impl IntoIterator<Item = PathBuf>, now: Instant) {
+        self.pending.extend(paths);
+        if !self.pending.is_empty() {
+            self.first.get_or_insert(now);
+            self.last = Some(now);
+        }
+    }
+
+    /// When the pending paths are due, or None if there are none
+    fn deadline(&self) -> Option<Instant> {
+        Some((self.last? + DEBOUNCE).min(self.first? + MAX_DEBOUNCE))
+    }
+
+    fn take(&mut self) -> HashSet<PathBuf> {
+        self.first = None;
+        self.last = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+pub async fn run_watch(
+    data_dir: &str,
+    config: &Config,
+    source: &str,
+    path: &Path,
+    walk_options: WalkOptions,
+    progress_style: ProgressStyle,
+) -> Result<()> {
+    // Events carry absolute paths; ingest the same form so updates match
+    let root = path
+        .canonicalize()
+        .with_context(|| format!("Cannot watch {}", path.display()))?;
+    anyhow::ensure!(root.is_dir(), "{} is not a directory", path.display());
+    // Rejects invalid exclude patterns before anything is loaded
+    FileWalk::new(&root, &walk_options)?;
+
+    let chatty = progress_style != ProgressStyle::Hidden;
+    if chatty {
+        println!("Initializing embedder...");
+    }
+    let embedder = Arc::new(Embedder::new()?);
+    let mut db = VectorDB::open(data_dir, config.per_source_tables).await?;
+    let data_path = Path::new(data_dir);
+    let bm25_index = Arc::new(BM25Index::open(data_path)?);
+    let pipeline = IngestPipeline::new(embedder, bm25_index)
+        .with_settings(config)
+        .with_update(true)
+        .with_progress_style(ProgressStyle::Hidden);
+
+    // Start watching before the initial sync so nothing changed during it is missed
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+
+    if chatty {
+        println!("Syncing {}...", root.display());
+    }
+    let files: Vec<PathBuf> = FileWalk::new(&root, &walk_options)?.collect();
+    sync(&pipeline, &mut db, data_path, source, files, std::slice::from_ref(&root)).await?;
+    if chatty {
+        println!("Watching {} (Ctrl-C to stop)", root.display());
+    }
+
+    let mut debouncer = Debouncer::default();
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let deadline = debouncer.deadline();
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            event = rx.recv() => match event {
+                // Our own reads show up as access events
+                Some(Ok(event)) if !matches!(event.kind, EventKind::Access(_)) => {
+                    debouncer.add(event.paths, Instant::now());
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => eprintln!("Warning: watch error: {}", e),
+                None => break,
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                let (changed, deleted) = split_changes(&root, &walk_options, debouncer.take().into_iter());
+                // A failed batch (e.g. a file that vanished mid-read) shouldn't stop the watcher
+                if let Err(e) = sync(&pipeline, &mut db, data_path, source, changed, &deleted).await {
+                    eprintln!("Warning: {:#}", e);
+                }
+            }
+        }
+    }
+
+    if chatty {
+        println!("\nStopped watching {}", root.display());
+    }
+    Ok(())
+}
+
+/// Sort changed paths into files to (re)ingest and paths that are gone
+///
+/// A directory moved into the tree raises a single event, so it's walked
+/// for the files inside it.
+fn split_changes(
+    root: &Path,
+    options: &WalkOptions,
+    paths: impl Iterator<Item = PathBuf>,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut changed = Vec::new();
+    let mut deleted = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            if let Ok(walk) = FileWalk::new(&path, options) {
+                changed.extend(walk.filter(|f| walk_includes(root, f, options)));
+            }
+        } else if !path.exists() {
+            deleted.push(path);
+        } else if walk_includes(root, &path, options) {
+            changed.push(path);
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    deleted.sort();
+    (changed, deleted)
+}
+
+/// Drop the documents of files gone from under `deleted`, then ingest `changed`
+async fn sync(
+    pipeline: &IngestPipeline,
+    db: &mut VectorDB,
+    data_dir: &Path,
+    source: &str,
+    changed: Vec<PathBuf>,
+    deleted: &[PathBuf],
+) -> Result<()> {
+    let removed = remove_missing(pipeline, db, data_dir, source, deleted).await?;
+    process(pipeline, db, data_dir, source, changed, removed).await
+}
+
+/// Remove documents for files at or under `paths` that no longer exist
+///
+/// A deleted or moved-out directory raises one event for the directory, so
+/// its files are found among the source's stored paths. Also catches files
+/// deleted while nothing was watching. Returns the files removed.
+async fn remove_missing(
+    pipeline: &IngestPipeline,
+    db: &mut VectorDB,
+    data_dir: &Path,
+    source: &str,
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Read in a block to drop ContentStore before await
+    let mut gone: Vec<PathBuf> = {
+        let source_id = pipeline.resolve_source_id(data_dir, source)?;
+        ContentStore::open(&data_dir.join("content.db"))?
+            .list_documents_by_source(&source_id, None, None)?
+            .0
+            .into_iter()
+            .filter_map(|d| d.file_path.map(PathBuf::from))
+            .filter(|f| paths.iter().any(|p| f.starts_with(p)) && !f.exists())
+            .collect()
+    };
+    gone.sort();
+    gone.dedup();
+
+    pipeline.remove_files(db, data_dir, source, &gone).await?;
+    Ok(gone)
+}
+
+/// Ingest `changed`, then print one line per file that was ingested,
+/// skipped for a reason other than being unchanged, or `removed`
+async fn process(
+    pipeline: &IngestPipeline,
+    db: &mut VectorDB,
+    data_dir: &Path,
+    source: &str,
+    changed: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+) -> Result<()> {
+    if !changed.is_empty() {
+        let (_, manifest) = pipeline.ingest_files_with_manifest(db, data_dir, source, &changed).await?;
+        for decision in explain_decisions(&WalkResult::default(), &manifest) {
+            if decision.decision != Decision::Skipped(SkipReason::Unchanged) {
+                println!("{}", decision);
+            }
+        }
+    }
+    for path in &removed {
+        println!("removed   {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eywa::{DevicePreference, EmbeddingModelConfig};
+
+    #[test]
+    fn test_debounce_waits_for_quiet_but_not_forever() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::default();
+        assert!(debouncer.deadline().is_none());
+
+        debouncer.add([PathBuf::from("a.md")], start);
+        assert_eq!(debouncer.deadline(), Some(start + DEBOUNCE));
+
+        // Each event pushes the deadline back...
+        let later = start + Duration::from_millis(300);
+        debouncer.add([PathBuf::from("b.md")], later);
+        assert_eq!(debouncer.deadline(), Some(later + DEBOUNCE));
+
+        // ...but no further than MAX_DEBOUNCE after the first
+        debouncer.add([PathBuf::from("a.md")], start + MAX_DEBOUNCE - Duration::from_millis(100));
+        assert_eq!(debouncer.deadline(), Some(start + MAX_DEBOUNCE));
+
+        assert_eq!(debouncer.take().len(), 2);
+        assert!(debouncer.deadline().is_none());
+        debouncer.add(Vec::new(), later);
+        assert!(debouncer.deadline().is_none());
+    }
+
+    #[test]
+    fn test_split_changes_finds_deletions_and_new_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("guides")).unwrap();
+        for file in ["notes.md", "guides/setup.md", "guides/deploy.md", "image.bin", "layout.xyz"] {
+            std::fs::write(root.join(file), "text").unwrap();
+        }
+        let paths = ["notes.md", "guides", "image.bin", "layout.xyz", "old.md"].map(|p| root.join(p));
+
+        let (changed, deleted) = split_changes(&root, &WalkOptions::default(), paths.into_iter());
+
+        // Binaries go on to ingest, which counts them as skipped
+        assert_eq!(
+            changed,
+            ["guides/deploy.md", "guides/setup.md", "image.bin", "notes.md"].map(|p| root.join(p))
+        );
+        assert_eq!(deleted, vec![root.join("old.md")]);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_files_leave_all_three_stores() {
+        let data = tempfile::tempdir().unwrap();
+        let watched = tempfile::tempdir().unwrap();
+        let root = watched.path().canonicalize().unwrap();
+        let kept = root.join("kept.md");
+        let gone = root.join("gone.md");
+        std::fs::write(&kept, "# Kept\n\nThe staging cluster is rebuilt from scratch every Sunday night, so never keep data on it. Ask in the platform channel before scaling it up for load tests.\n").unwrap();
+        std::fs::write(&gone, "# Gone\n\nSourdough starter needs feeding twice a day and stays lively in a warm kitchen. Discard half before each feed so the jar does not overflow.\n").unwrap();
+
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data.path()).unwrap());
+        let mut db = VectorDB::new(data.path().to_str().unwrap()).await.unwrap();
+        let pipeline = IngestPipeline::new(embedder, Arc::clone(&bm25_index)).with_update(true);
+        sync(&pipeline, &mut db, data.path(), "notes", vec![kept.clone(), gone.clone()], &[]).await.unwrap();
+        assert_eq!(db.get_document_ids_for_source("notes").await.unwrap().len(), 2);
+
+        std::fs::remove_file(&gone).unwrap();
+        let removed = remove_missing(&pipeline, &mut db, data.path(), "notes", &[root]).await.unwrap();
+        assert_eq!(removed, vec![gone]);
+
+        assert_eq!(db.get_document_ids_for_source("notes").await.unwrap().len(), 1);
+        let (docs, _) = ContentStore::open(&data.path().join("content.db"))
+            .unwrap()
+            .list_documents_by_source("notes", None, None)
+            .unwrap();
+        let paths: Vec<Option<PathBuf>> = docs.into_iter().map(|d| d.file_path.map(PathBuf::from)).collect();
+        assert_eq!(paths, vec![Some(kept)]);
+        assert!(bm25_index.search("sourdough", 10).unwrap().is_empty());
+        assert!(!bm25_index.search("staging", 10).unwrap().is_empty());
+    }
+}
@@ -3,6 +3,7 @@
 //! Handles model selection and persistence of user preferences.
 //! Supports both curated models and custom HuggingFace models.
 
+use crate::chunking::{ChunkSizes, CHARS_PER_TOKEN, MIN_CHUNK, OVERLAP, TARGET_SIZE};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -38,10 +39,119 @@ impl DevicePreference {
     }
 }
 
+/// How consecutive chunks overlap
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum OverlapStrategy {
+    /// No overlap (smallest index)
+    None,
+    /// Exactly the last N characters
+    Chars,
+    /// The last N characters, snapped forward to a line boundary
+    #[default]
+    Lines,
+    /// The last N characters, snapped forward to a sentence boundary
+    Sentence,
+}
+
+impl OverlapStrategy {
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Chars => "chars",
+            Self::Lines => "lines",
+            Self::Sentence => "sentence",
+        }
+    }
+}
+
+/// Which chunks an ingested chunk is deduplicated against
+///
+/// Global dedup stores and embeds identical content once across all sources,
+/// which is smaller and faster, but the shared vector belongs to whichever
+/// source ingested it first, so a search filtered to another source can miss
+/// it. Per-source dedup embeds the chunk again for each source that has it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupScope {
+    /// Skip chunks whose content exists in any source
+    #[default]
+    Global,
+    /// Skip chunks whose content exists in the same source only
+    Source,
+}
+
+/// How ingest treats source names that differ only by case
+///
+/// `Docs` and `docs` are separate sources by default, which splits search
+/// filtering in a way users rarely intend.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceNameCase {
+    /// Keep the name as given, but warn when it differs only by case
+    #[default]
+    Warn,
+    /// Ingest into the existing source whose name matches ignoring case
+    Insensitive,
+    /// Treat names as distinct without warning
+    Exact,
+}
+
+/// Weight precision for the embedding model
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingQuantization {
+    /// Full precision (reference quality)
+    #[default]
+    F32,
+    /// Half precision: roughly half the weight memory
+    F16,
+    /// 8-bit weights, if the model repo publishes them
+    Int8,
+}
+
+impl EmbeddingQuantization {
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::F16 => "f16",
+            Self::Int8 => "int8",
+        }
+    }
+}
+
+/// Which web UI the server serves at `/`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UiVersion {
+    /// Legacy single-page UI
+    V1,
+    #[default]
+    V2,
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Embedding Model Configuration
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// How token embeddings are pooled into one sentence vector
+///
+/// Must match how the model was trained; the wrong pooling still produces
+/// valid-looking vectors but retrieval quality drops. Changing it requires
+/// re-indexing.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolingStrategy {
+    /// Average over all tokens (sentence-transformers MiniLM, nomic)
+    #[default]
+    Mean,
+    /// The [CLS] token's embedding
+    Cls,
+    /// The last non-padding token's embedding (decoder-style models)
+    LastToken,
+}
+
 /// Embedding model configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct EmbeddingModelConfig {
@@ -59,6 +169,9 @@ pub struct EmbeddingModelConfig {
     /// Whether this is a curated (built-in) model
     #[serde(default)]
     pub curated: bool,
+    /// Pooling strategy (defaults to mean)
+    #[serde(default)]
+    pub pooling: PoolingStrategy,
 }
 
 impl EmbeddingModelConfig {
@@ -72,6 +185,7 @@ impl EmbeddingModelConfig {
             dimensions,
             size_mb: 0,
             curated: false,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
@@ -92,6 +206,7 @@ impl EmbeddingModelConfig {
             dimensions: 384,
             size_mb: 86,
             curated: true,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
@@ -103,9 +218,12 @@ impl EmbeddingModelConfig {
             dimensions: 384,
             size_mb: 134,
             curated: true,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
+    // BGE's model card pools with [CLS], but eywa has always mean-pooled BGE;
+    // keeping mean avoids silently invalidating existing indexes
     pub fn bge_small_en_v15() -> Self {
         Self {
             id: "bge-small-en-v1.5".to_string(),
@@ -114,6 +232,7 @@ impl EmbeddingModelConfig {
             dimensions: 384,
             size_mb: 134,
             curated: true,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
@@ -125,6 +244,7 @@ impl EmbeddingModelConfig {
             dimensions: 768,
             size_mb: 418,
             curated: true,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
@@ -136,6 +256,7 @@ impl EmbeddingModelConfig {
             dimensions: 768,
             size_mb: 548,
             curated: true,
+            pooling: PoolingStrategy::Mean,
         }
     }
 
@@ -438,6 +559,95 @@ pub struct Config {
     /// Device preference (auto, cpu, metal, cuda)
     #[serde(default)]
     pub device: DevicePreference,
+    /// Number of query-document pairs scored per reranker forward pass
+    /// (None = 8 on CPU, 16 on GPU)
+    #[serde(default)]
+    pub rerank_batch_size: Option<usize>,
+    /// Number of candidates fetched and scored by the reranker before
+    /// truncating to the requested limit (None = 20)
+    #[serde(default)]
+    pub rerank_candidates: Option<usize>,
+    /// Drop results the reranker scores below this (None = 0.05)
+    #[serde(default)]
+    pub rerank_min_score: Option<f32>,
+    /// Candidates guaranteed to each source before reranking, so a large
+    /// source can't crowd out small ones (None = 0, off)
+    #[serde(default)]
+    pub rerank_candidates_per_source: Option<usize>,
+    /// Rank fusion constant for combining vector and BM25 hits; larger
+    /// values flatten the advantage of top-ranked hits (None = 60)
+    #[serde(default)]
+    pub rrf_k: Option<f32>,
+    /// Length of result previews in the CLI and REPL (None = 200)
+    #[serde(default)]
+    pub preview_chars: Option<usize>,
+    /// Searches the server runs at once (None = 4)
+    #[serde(default)]
+    pub search_max_concurrent: Option<usize>,
+    /// Searches waiting for a slot before new ones get 503 (None = 32)
+    #[serde(default)]
+    pub search_max_queued: Option<usize>,
+    /// Largest `limit` a single search may request via the API or MCP;
+    /// larger requests are clamped (None = 100)
+    #[serde(default)]
+    pub max_search_limit: Option<usize>,
+    /// Score added to chunks of pinned documents that don't set their own
+    /// boost (None = 0.1)
+    #[serde(default)]
+    pub pin_boost: Option<f32>,
+    /// Seconds the server queue worker spends embedding one document
+    /// before marking it failed (None = 300)
+    #[serde(default)]
+    pub worker_timeout_secs: Option<u64>,
+    /// How chunks overlap during ingestion
+    #[serde(default)]
+    pub chunk_overlap: OverlapStrategy,
+    /// Target chunk length in characters
+    #[serde(default = "default_chunk_target_size")]
+    pub chunk_target_size: usize,
+    /// Characters repeated between consecutive chunks (must be below
+    /// `chunk_target_size`; `chunk_overlap` picks where the overlap starts)
+    #[serde(default = "default_chunk_overlap_size")]
+    pub chunk_overlap_size: usize,
+    /// Chunks shorter than this are merged into a neighbour or dropped
+    /// (must not exceed `chunk_target_size`)
+    #[serde(default = "default_chunk_min_size")]
+    pub chunk_min_size: usize,
+    /// Size chunks by embedding-model tokens instead of characters: chunks are
+    /// capped at this many tokens and `chunk_target_size` is ignored (None = off).
+    /// Never more than the model can embed after the passage prefix.
+    #[serde(default)]
+    pub chunk_max_tokens: Option<usize>,
+    /// Embedding weight precision (falls back to f32 when unavailable)
+    #[serde(default)]
+    pub embedding_quantization: EmbeddingQuantization,
+    /// Web UI served at `/` (both stay reachable at `/v1` and `/v2`)
+    #[serde(default)]
+    pub default_ui: UiVersion,
+    /// Instruction prefix prepended to search queries (None or "" = none)
+    #[serde(default)]
+    pub query_prefix: Option<String>,
+    /// Instruction prefix prepended to chunks at ingest (None or "" = none).
+    /// Changing this requires re-indexing existing sources.
+    #[serde(default)]
+    pub passage_prefix: Option<String>,
+    /// Strip YAML/TOML frontmatter from Markdown chunks (kept as document tags)
+    #[serde(default = "default_strip_frontmatter")]
+    pub strip_frontmatter: bool,
+    /// Chunk dedup scope for ingest (`global` or `source`)
+    #[serde(default)]
+    pub dedup_scope: DedupScope,
+    /// Source names differing only by case (`warn`, `insensitive` or `exact`)
+    #[serde(default)]
+    pub source_name_case: SourceNameCase,
+    /// Store each source's chunks in its own vector table (experimental).
+    /// Speeds up source-filtered search and source deletion; existing data
+    /// must be re-ingested after switching, and source merges are unsupported.
+    #[serde(default)]
+    pub per_source_tables: bool,
+    /// Source used by `eywa ingest` when `--source` is omitted
+    #[serde(default = "default_source")]
+    pub default_source: String,
     /// Version of config schema
     #[serde(default = "current_version")]
     pub version: u32,
@@ -451,12 +661,57 @@ fn current_version() -> u32 {
     2
 }
 
+fn default_chunk_target_size() -> usize {
+    TARGET_SIZE
+}
+
+fn default_chunk_overlap_size() -> usize {
+    OVERLAP
+}
+
+fn default_chunk_min_size() -> usize {
+    MIN_CHUNK
+}
+
+fn default_strip_frontmatter() -> bool {
+    true
+}
+
+fn default_source() -> String {
+    "default".to_string()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             embedding_model: EmbeddingModelConfig::default(),
             reranker_model: RerankerModelConfig::default(),
             device: DevicePreference::default(),
+            rerank_batch_size: None,
+            rerank_candidates: None,
+            rerank_min_score: None,
+            rerank_candidates_per_source: None,
+            rrf_k: None,
+            preview_chars: None,
+            search_max_concurrent: None,
+            search_max_queued: None,
+            max_search_limit: None,
+            pin_boost: None,
+            worker_timeout_secs: None,
+            chunk_overlap: OverlapStrategy::default(),
+            chunk_target_size: default_chunk_target_size(),
+            chunk_overlap_size: default_chunk_overlap_size(),
+            chunk_min_size: default_chunk_min_size(),
+            chunk_max_tokens: None,
+            embedding_quantization: EmbeddingQuantization::default(),
+            default_ui: UiVersion::default(),
+            query_prefix: None,
+            passage_prefix: None,
+            strip_frontmatter: default_strip_frontmatter(),
+            dedup_scope: DedupScope::default(),
+            source_name_case: SourceNameCase::default(),
+            per_source_tables: false,
+            default_source: default_source(),
             version: current_version(),
         }
     }
@@ -488,6 +743,7 @@ impl Config {
         // Try parsing as v2 config first
         if let Ok(config) = toml::from_str::<Config>(&content) {
             if config.version >= 2 {
+                config.validate()?;
                 return Ok(Some(config));
             }
         }
@@ -499,6 +755,7 @@ impl Config {
                 reranker_model: legacy.reranker_model.to_config(),
                 device: legacy.device,
                 version: current_version(),
+                ..Config::default()
             };
             // Save migrated config
             if let Err(e) = migrated.save() {
@@ -510,9 +767,56 @@ impl Config {
         // If both fail, return error
         let config: Config = toml::from_str(&content)
             .context("Failed to parse config file")?;
+        config.validate()?;
         Ok(Some(config))
     }
 
+    /// Check settings that are only valid together
+    pub fn validate(&self) -> Result<()> {
+        // The upper bound depends on the model; the ingest pipeline caps it
+        if self.chunk_max_tokens == Some(0) {
+            anyhow::bail!("Invalid config: chunk_max_tokens must be at least 1");
+        }
+        // With chunk_max_tokens the target comes from the token budget
+        let target = self.chunk_sizes().target;
+        let target_name = if self.chunk_max_tokens.is_some() {
+            "the token-based chunk target"
+        } else {
+            "chunk_target_size"
+        };
+        if self.chunk_overlap_size >= target {
+            anyhow::bail!(
+                "Invalid config: chunk_overlap_size ({}) must be smaller than {} ({})",
+                self.chunk_overlap_size,
+                target_name,
+                target
+            );
+        }
+        if self.chunk_min_size > target {
+            anyhow::bail!(
+                "Invalid config: chunk_min_size ({}) must not exceed {} ({})",
+                self.chunk_min_size,
+                target_name,
+                target
+            );
+        }
+        Ok(())
+    }
+
+    /// Chunk sizes for the ingest pipeline
+    ///
+    /// With `chunk_max_tokens` set, the character target is derived from the
+    /// token budget; the chunker registry then enforces the exact limit.
+    pub fn chunk_sizes(&self) -> ChunkSizes {
+        ChunkSizes {
+            target: self
+                .chunk_max_tokens
+                .map_or(self.chunk_target_size, |tokens| tokens * CHARS_PER_TOKEN),
+            overlap: self.chunk_overlap_size,
+            min: self.chunk_min_size,
+        }
+    }
+
     /// Save config to disk
     pub fn save(&self) -> Result<()> {
         let path = Self::path()?;
@@ -531,6 +835,11 @@ impl Config {
         Ok(())
     }
 
+    /// Use the given source, or the configured default if none was given
+    pub fn resolve_source(&self, source: Option<String>) -> String {
+        source.unwrap_or_else(|| self.default_source.clone())
+    }
+
     /// Get total download size for selected models
     pub fn total_download_size_mb(&self) -> u32 {
         self.embedding_model.size_mb + self.reranker_model.size_mb
@@ -607,6 +916,55 @@ mod tests {
         assert_eq!(config.reranker_model.id, parsed.reranker_model.id);
     }
 
+    #[test]
+    fn test_default_ui() {
+        assert_eq!(Config::default().default_ui, UiVersion::V2);
+        let parsed: Config = toml::from_str("version = 2\ndefault_ui = \"v1\"").unwrap();
+        assert_eq!(parsed.default_ui, UiVersion::V1);
+    }
+
+    #[test]
+    fn test_default_source() {
+        let config = Config::default();
+        assert_eq!(config.resolve_source(None), "default");
+        assert_eq!(config.resolve_source(Some("notes".to_string())), "notes");
+
+        // Older config files without the field still get the default
+        let parsed: Config = toml::from_str("version = 2").unwrap();
+        assert_eq!(parsed.default_source, "default");
+
+        let parsed: Config = toml::from_str("version = 2\ndefault_source = \"work\"").unwrap();
+        assert_eq!(parsed.resolve_source(None), "work");
+    }
+
+    #[test]
+    fn test_chunk_sizes_default_and_validate() {
+        let parsed: Config = toml::from_str("version = 2").unwrap();
+        assert_eq!(parsed.chunk_sizes(), ChunkSizes::default());
+        assert!(parsed.validate().is_ok());
+
+        let parsed: Config =
+            toml::from_str("version = 2\nchunk_target_size = 800\nchunk_overlap_size = 800").unwrap();
+        let err = parsed.validate().unwrap_err().to_string();
+        assert!(err.contains("chunk_overlap_size"), "{}", err);
+
+        let parsed: Config =
+            toml::from_str("version = 2\nchunk_target_size = 800\nchunk_min_size = 900").unwrap();
+        let err = parsed.validate().unwrap_err().to_string();
+        assert!(err.contains("chunk_min_size"), "{}", err);
+    }
+
+    #[test]
+    fn test_chunk_max_tokens_sets_target() {
+        let parsed: Config = toml::from_str("version = 2\nchunk_max_tokens = 256").unwrap();
+        assert_eq!(parsed.chunk_sizes().target, 256 * CHARS_PER_TOKEN);
+        assert!(parsed.validate().is_ok());
+
+        let parsed: Config = toml::from_str("version = 2\nchunk_max_tokens = 0").unwrap();
+        let err = parsed.validate().unwrap_err().to_string();
+        assert!(err.contains("chunk_max_tokens"), "{}", err);
+    }
+
     #[test]
     fn test_legacy_conversion() {
         let legacy = EmbeddingModel::BgeBaseEnV15;
@@ -3,14 +3,39 @@
 //! Stores document and chunk content separately from vector storage.
 //! This enables efficient storage (content stored once, compressed)
 //! while keeping vector search fast (LanceDB handles only embeddings).
+//!
+//! Linked documents (`eywa ingest --link`) keep only their file path, and
+//! their chunks only a byte range into that file; both are read from disk on
+//! retrieval. A linked document whose file is gone has no content.
 
 use anyhow::{Context, Result};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Compression level for zstd (1-22, higher = smaller but slower)
 const COMPRESSION_LEVEL: i32 = 3;
 
+/// Insert a chunk at the end of its document
+///
+/// Replacing a chunk already in the document keeps its position.
+const INSERT_CHUNK_SQL: &str = "INSERT OR REPLACE INTO chunks (id, document_id, content, position)
+     VALUES (?1, ?2, ?3, COALESCE(
+         (SELECT position FROM chunks WHERE id = ?1 AND document_id = ?2),
+         (SELECT COALESCE(MAX(position) + 1, 0) FROM chunks WHERE document_id = ?2)))";
+
+/// Insert a linked chunk (a byte range of its document's file) at the end of its document
+///
+/// Replacing a chunk already in the document keeps its position.
+const INSERT_LINKED_CHUNK_SQL: &str = "INSERT OR REPLACE INTO chunks (id, document_id, content, position, byte_start, byte_end)
+     VALUES (?1, ?2, X'', COALESCE(
+         (SELECT position FROM chunks WHERE id = ?1 AND document_id = ?2),
+         (SELECT COALESCE(MAX(position) + 1, 0) FROM chunks WHERE document_id = ?2)), ?3, ?4)";
+
+/// Chunk columns needed to resolve its text, joined with its document
+const CHUNK_TEXT_COLUMNS: &str = "c.id, c.content, c.byte_start, c.byte_end, d.file_path
+     FROM chunks c LEFT JOIN documents d ON d.id = c.document_id";
+
 /// Document row returned from streaming iteration.
 #[derive(Debug, Clone)]
 pub struct DocumentRow {
@@ -37,11 +62,33 @@ pub struct DocumentListItem {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SourceStats {
     pub id: String,
+    pub description: Option<String>,
     pub doc_count: u64,
     pub total_size: u64,
     pub last_updated: Option<String>,
 }
 
+/// A bookmarked document.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Bookmark {
+    pub document_id: String,
+    pub source_id: String,
+    pub title: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+/// Consecutive chunks of a document, from `ContentStore::chunk_window`.
+#[derive(Debug, Clone)]
+pub struct ChunkWindow {
+    /// (id, content) pairs in document order
+    pub chunks: Vec<(String, String)>,
+    /// Position of the first chunk in the document (0-based)
+    pub first: usize,
+    /// Position of the last chunk in the document (0-based)
+    pub last: usize,
+}
+
 /// Content store backed by SQLite with zstd compression.
 pub struct ContentStore {
     conn: Connection,
@@ -70,13 +117,21 @@ impl ContentStore {
                 title       TEXT NOT NULL DEFAULT 'Untitled',
                 file_path   TEXT,
                 content     BLOB NOT NULL,
-                created_at  TEXT NOT NULL
+                created_at  TEXT NOT NULL,
+                linked      INTEGER NOT NULL DEFAULT 0,
+                tags        TEXT,
+                file_hash   TEXT,
+                pinned      INTEGER NOT NULL DEFAULT 0,
+                pin_boost   REAL
             );
 
             CREATE TABLE IF NOT EXISTS chunks (
                 id          TEXT PRIMARY KEY,
                 document_id TEXT NOT NULL,
                 content     BLOB NOT NULL,
+                position    INTEGER,
+                byte_start  INTEGER,
+                byte_end    INTEGER,
                 FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
             );
 
@@ -86,6 +141,27 @@ impl ContentStore {
             CREATE INDEX IF NOT EXISTS idx_documents_source
                 ON documents(source_id);
 
+            CREATE INDEX IF NOT EXISTS idx_documents_source_nocase
+                ON documents(source_id COLLATE NOCASE);
+
+            CREATE TABLE IF NOT EXISTS sources (
+                id          TEXT PRIMARY KEY,
+                description TEXT,
+                created_at  TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS index_meta (
+                key         TEXT PRIMARY KEY,
+                value       TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS bookmarks (
+                document_id TEXT PRIMARY KEY,
+                note        TEXT,
+                created_at  TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+            );
+
             PRAGMA foreign_keys = ON;
             ",
         )?;
@@ -114,6 +190,92 @@ impl ContentStore {
             )?;
         }
 
+        // Linked documents (content re-read from disk)
+        let has_linked: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='linked'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_linked == 0 {
+            self.conn.execute_batch(
+                "ALTER TABLE documents ADD COLUMN linked INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+
+        // Document tags (JSON array, e.g. from Markdown frontmatter)
+        let has_tags: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='tags'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_tags == 0 {
+            self.conn.execute_batch("ALTER TABLE documents ADD COLUMN tags TEXT;")?;
+        }
+
+        // SHA-256 of the file a document was ingested from (skips unchanged files)
+        let has_file_hash: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='file_hash'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_file_hash == 0 {
+            self.conn.execute_batch("ALTER TABLE documents ADD COLUMN file_hash TEXT;")?;
+        }
+
+        // Pinned documents (boosted in search, optional per-document weight)
+        let has_pinned: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('documents') WHERE name='pinned'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_pinned == 0 {
+            self.conn.execute_batch(
+                "
+                ALTER TABLE documents ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE documents ADD COLUMN pin_boost REAL;
+                ",
+            )?;
+        }
+
+        // Chunk order within its document; existing chunks were inserted in
+        // order, so rowid recovers it
+        let has_position: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('chunks') WHERE name='position'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_position == 0 {
+            self.conn.execute_batch(
+                "
+                ALTER TABLE chunks ADD COLUMN position INTEGER;
+                UPDATE chunks SET position = (
+                    SELECT COUNT(*) FROM chunks AS earlier
+                    WHERE earlier.document_id = chunks.document_id AND earlier.rowid < chunks.rowid
+                );
+                ",
+            )?;
+        }
+
+        let has_byte_range: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('chunks') WHERE name='byte_start'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_byte_range == 0 {
+            self.conn.execute_batch(
+                "
+                ALTER TABLE chunks ADD COLUMN byte_start INTEGER;
+                ALTER TABLE chunks ADD COLUMN byte_end INTEGER;
+                ",
+            )?;
+        }
+
         Ok(())
     }
 
@@ -142,23 +304,161 @@ impl ContentStore {
         Ok(())
     }
 
+    /// Store a linked document: only the path is kept, content is read from disk.
+    pub fn insert_linked_document(
+        &self,
+        id: &str,
+        source_id: &str,
+        title: &str,
+        file_path: &str,
+        created_at: &str,
+    ) -> Result<()> {
+        let empty = compress("")?;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO documents (id, source_id, title, file_path, content, created_at, linked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)",
+            params![id, source_id, title, file_path, empty, created_at],
+        )?;
+
+        Ok(())
+    }
+
+    /// Replace a document's tags.
+    pub fn set_document_tags(&self, id: &str, tags: &[String]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE documents SET tags = ?2 WHERE id = ?1",
+            params![id, serde_json::to_string(tags)?],
+        )?;
+        Ok(())
+    }
+
+    /// Get a document's tags (empty if it has none).
+    pub fn get_document_tags(&self, id: &str) -> Result<Vec<String>> {
+        let tags: Option<String> = self
+            .conn
+            .query_row("SELECT tags FROM documents WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?
+            .flatten();
+        Ok(tags.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default())
+    }
+
+    /// Pin or unpin a document; `boost` overrides the configured pin boost.
+    /// Returns false if the document doesn't exist.
+    pub fn set_pinned(&self, id: &str, pinned: bool, boost: Option<f32>) -> Result<bool> {
+        let boost = boost.filter(|_| pinned).map(f64::from);
+        let updated = self.conn.execute(
+            "UPDATE documents SET pinned = ?2, pin_boost = ?3 WHERE id = ?1",
+            params![id, pinned, boost],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Chunks among `chunk_ids` whose document is pinned, with the
+    /// document's boost override (None = use the configured boost).
+    pub fn pinned_chunks(&self, chunk_ids: &[&str]) -> Result<HashMap<String, Option<f32>>> {
+        if chunk_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let placeholders: Vec<&str> = chunk_ids.iter().map(|_| "?").collect();
+        let query = format!(
+            "SELECT c.id, d.pin_boost FROM chunks c JOIN documents d ON d.id = c.document_id
+             WHERE d.pinned = 1 AND c.id IN ({})",
+            placeholders.join(",")
+        );
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(chunk_ids.iter()), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<f64>>(1)?))
+        })?;
+
+        let mut pinned = HashMap::new();
+        for row in rows {
+            let (id, boost) = row?;
+            pinned.insert(id, boost.map(|b| b as f32));
+        }
+
+        Ok(pinned)
+    }
+
     /// Get a document's content by ID.
+    ///
+    /// Returns None for a linked document whose file is gone.
     pub fn get_document(&self, id: &str) -> Result<Option<String>> {
-        let result: Option<Vec<u8>> = self
+        let result: Option<(Vec<u8>, bool, Option<String>)> = self
             .conn
             .query_row(
-                "SELECT content FROM documents WHERE id = ?1",
+                "SELECT content, linked, file_path FROM documents WHERE id = ?1",
                 params![id],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
             .optional()?;
 
         match result {
-            Some(compressed) => Ok(Some(decompress(&compressed)?)),
+            Some((compressed, linked, file_path)) => resolve_content(&compressed, linked, file_path.as_deref()),
             None => Ok(None),
         }
     }
 
+    /// Whether a document's content lives in a linked file on disk.
+    pub fn is_linked(&self, id: &str) -> Result<bool> {
+        let linked: Option<bool> = self
+            .conn
+            .query_row("SELECT linked FROM documents WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        Ok(linked.unwrap_or(false))
+    }
+
+    /// Record the SHA-256 of the file these documents were ingested from.
+    pub fn set_file_hash(&self, document_ids: &[String], file_path: &str, hash: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("UPDATE documents SET file_hash = ?3 WHERE id = ?1 AND file_path = ?2")?;
+        for id in document_ids {
+            stmt.execute(params![id, file_path, hash])?;
+        }
+        Ok(())
+    }
+
+    /// Whether a source still holds documents ingested from this exact file content.
+    pub fn has_file_hash(&self, source_id: &str, file_path: &str, hash: &str) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM documents WHERE source_id = ?1 AND file_path = ?2 AND file_hash = ?3 LIMIT 1",
+                params![source_id, file_path, hash],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Title, file path and chunk count of a document.
+    pub fn document_summary(&self, id: &str) -> Result<Option<(String, Option<String>, u32)>> {
+        self.conn
+            .query_row(
+                "SELECT title, file_path, (SELECT COUNT(*) FROM chunks WHERE document_id = ?1)
+                 FROM documents WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// ID, title and chunk count of each document in a source ingested from `file_path`.
+    pub fn documents_for_path(&self, source_id: &str, file_path: &str) -> Result<Vec<(String, String, u32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.title, (SELECT COUNT(*) FROM chunks c WHERE c.document_id = d.id)
+             FROM documents d WHERE d.source_id = ?1 AND d.file_path = ?2",
+        )?;
+        let rows = stmt.query_map(params![source_id, file_path], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect::<rusqlite::Result<_>>().map_err(Into::into)
+    }
+
     /// Delete a document and its chunks.
     pub fn delete_document(&self, id: &str) -> Result<()> {
         // Chunks are deleted via CASCADE
@@ -232,19 +532,76 @@ impl ContentStore {
         Ok((docs, total as usize))
     }
 
+    /// Documents whose title contains `text` (case-insensitive), title
+    /// prefix matches first.
+    pub fn find_documents_by_title(
+        &self,
+        text: &str,
+        source_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<DocumentListItem>> {
+        // `%` and `_` in the input are literal
+        let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let mut stmt = self.conn.prepare(
+            "SELECT id, source_id, title, file_path, LENGTH(content), created_at
+             FROM documents
+             WHERE title LIKE '%' || ?1 || '%' ESCAPE '\\' AND (?2 IS NULL OR source_id = ?2)
+             ORDER BY title LIKE ?1 || '%' ESCAPE '\\' DESC, title
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![escaped, source_id, limit as i64], |row| {
+            Ok(DocumentListItem {
+                id: row.get(0)?,
+                source_id: row.get(1)?,
+                title: row.get(2)?,
+                file_path: row.get(3)?,
+                content_length: row.get::<_, i64>(4)? as usize,
+                created_at: row.get(5)?,
+            })
+        })?;
+
+        let mut docs = Vec::new();
+        for row in rows {
+            docs.push(row?);
+        }
+
+        Ok(docs)
+    }
+
+    /// Names of sources equal to `name` ignoring ASCII case
+    ///
+    /// An index lookup, so ingest can check source name case without
+    /// summarizing every source.
+    pub fn sources_matching_ignore_case(&self, name: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_id FROM documents WHERE source_id = ?1 COLLATE NOCASE
+             UNION SELECT id FROM sources WHERE id = ?1 COLLATE NOCASE",
+        )?;
+        let names = stmt
+            .query_map(params![name], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(names)
+    }
+
     /// List all sources with stats (for web UI).
+    ///
+    /// Includes sources created with `create_source` that have no documents yet.
     pub fn list_sources(&self) -> Result<Vec<SourceStats>> {
         let mut stmt = self.conn.prepare(
-            "SELECT source_id, COUNT(*), SUM(LENGTH(content)), MAX(created_at)
-             FROM documents GROUP BY source_id ORDER BY source_id"
+            "SELECT s.id, m.description, COUNT(d.id), COALESCE(SUM(LENGTH(d.content)), 0), MAX(d.created_at)
+             FROM (SELECT source_id AS id FROM documents UNION SELECT id FROM sources) s
+             LEFT JOIN documents d ON d.source_id = s.id
+             LEFT JOIN sources m ON m.id = s.id
+             GROUP BY s.id ORDER BY s.id"
         )?;
 
         let rows = stmt.query_map([], |row| {
             Ok(SourceStats {
                 id: row.get(0)?,
-                doc_count: row.get::<_, i64>(1)? as u64,
-                total_size: row.get::<_, i64>(2)? as u64,
-                last_updated: row.get(3)?,
+                description: row.get(1)?,
+                doc_count: row.get::<_, i64>(2)? as u64,
+                total_size: row.get::<_, i64>(3)? as u64,
+                last_updated: row.get(4)?,
             })
         })?;
 
@@ -256,24 +613,52 @@ impl ContentStore {
         Ok(sources)
     }
 
+    /// Stats for one source, or `None` if it has no documents.
+    pub fn source_stats(&self, source_id: &str) -> Result<Option<SourceStats>> {
+        let stats = self.conn.query_row(
+            "SELECT m.description, COUNT(d.id), COALESCE(SUM(LENGTH(d.content)), 0), MAX(d.created_at)
+             FROM documents d
+             LEFT JOIN sources m ON m.id = d.source_id
+             WHERE d.source_id = ?1",
+            params![source_id],
+            |row| {
+                Ok(SourceStats {
+                    id: source_id.to_string(),
+                    description: row.get(0)?,
+                    doc_count: row.get::<_, i64>(1)? as u64,
+                    total_size: row.get::<_, i64>(2)? as u64,
+                    last_updated: row.get(3)?,
+                })
+            },
+        )?;
+
+        Ok((stats.doc_count > 0).then_some(stats))
+    }
+
     /// Get all documents (for export) - legacy format.
+    ///
+    /// Linked documents whose file is gone are skipped.
     pub fn get_all_documents(&self) -> Result<Vec<(String, String, String)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, content, created_at FROM documents")?;
+            .prepare("SELECT id, content, created_at, linked, file_path FROM documents")?;
 
         let rows = stmt.query_map([], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, Vec<u8>>(1)?,
                 row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<String>>(4)?,
             ))
         })?;
 
         let mut documents = Vec::new();
         for row in rows {
-            let (id, compressed, created_at) = row?;
-            let content = decompress(&compressed)?;
+            let (id, compressed, created_at, linked, file_path) = row?;
+            let Some(content) = resolve_content(&compressed, linked, file_path.as_deref())? else {
+                continue;
+            };
             documents.push((id, content, created_at));
         }
 
@@ -289,12 +674,29 @@ impl ContentStore {
 
     /// Get all documents with full metadata (for re-indexing).
     /// Returns documents in chunks to avoid loading everything into memory at once.
+    /// Linked documents whose file is gone are skipped.
     pub fn get_all_documents_with_metadata(&self) -> Result<Vec<DocumentRow>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, source_id, title, file_path, content, created_at FROM documents",
+        self.query_document_rows(
+            "SELECT id, source_id, title, file_path, content, created_at, linked FROM documents",
+            [],
+        )
+    }
+
+    /// Get one document with its metadata, if it exists and its content is readable
+    pub fn get_document_row(&self, id: &str) -> Result<Option<DocumentRow>> {
+        let rows = self.query_document_rows(
+            "SELECT id, source_id, title, file_path, content, created_at, linked FROM documents WHERE id = ?1",
+            params![id],
         )?;
+        Ok(rows.into_iter().next())
+    }
 
-        let rows = stmt.query_map([], |row| {
+    /// Run a document query and resolve each row's content, skipping linked
+    /// files that are gone
+    fn query_document_rows(&self, query: &str, params: impl rusqlite::Params) -> Result<Vec<DocumentRow>> {
+        let mut stmt = self.conn.prepare(query)?;
+
+        let rows = stmt.query_map(params, |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
@@ -302,13 +704,16 @@ impl ContentStore {
                 row.get::<_, Option<String>>(3)?,
                 row.get::<_, Vec<u8>>(4)?,
                 row.get::<_, String>(5)?,
+                row.get::<_, bool>(6)?,
             ))
         })?;
 
         let mut documents = Vec::new();
         for row in rows {
-            let (id, source_id, title, file_path, compressed, created_at) = row?;
-            let content = decompress(&compressed)?;
+            let (id, source_id, title, file_path, compressed, created_at, linked) = row?;
+            let Some(content) = resolve_content(&compressed, linked, file_path.as_deref())? else {
+                continue;
+            };
             documents.push(DocumentRow {
                 id,
                 source_id,
@@ -326,26 +731,24 @@ impl ContentStore {
     // Chunk Operations
     // ─────────────────────────────────────────────────────────────────────────
 
-    /// Store a chunk's content.
+    /// Store a chunk's content after the document's existing chunks.
     pub fn insert_chunk(&self, id: &str, document_id: &str, content: &str) -> Result<()> {
         let compressed = compress(content)?;
 
-        self.conn.execute(
-            "INSERT OR REPLACE INTO chunks (id, document_id, content) VALUES (?1, ?2, ?3)",
-            params![id, document_id, compressed],
-        )?;
+        self.conn.execute(INSERT_CHUNK_SQL, params![id, document_id, compressed])?;
 
         Ok(())
     }
 
     /// Store multiple chunks in a transaction (batch insert).
+    ///
+    /// Chunks must be given in document order; each is appended after the
+    /// document's existing chunks.
     pub fn insert_chunks(&self, chunks: &[(String, String, String)]) -> Result<()> {
         let tx = self.conn.unchecked_transaction()?;
 
         {
-            let mut stmt = tx.prepare(
-                "INSERT OR REPLACE INTO chunks (id, document_id, content) VALUES (?1, ?2, ?3)",
-            )?;
+            let mut stmt = tx.prepare(INSERT_CHUNK_SQL)?;
 
             for (id, document_id, content) in chunks {
                 let compressed = compress(content)?;
@@ -357,24 +760,62 @@ impl ContentStore {
         Ok(())
     }
 
+    /// Store the chunks of a linked document whose file holds `file_content`.
+    ///
+    /// Chunks that appear verbatim in the file are stored as a byte range
+    /// only; any other chunk (e.g. one the chunker rewrote) keeps its text.
+    /// Chunks must be given in document order.
+    pub fn insert_linked_chunks(&self, file_content: &str, chunks: &[(String, String, String)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        {
+            let mut linked = tx.prepare(INSERT_LINKED_CHUNK_SQL)?;
+            let mut stored = tx.prepare(INSERT_CHUNK_SQL)?;
+
+            // Overlapping chunks can start before the previous one ends
+            let mut search_from = 0;
+            for (id, document_id, content) in chunks {
+                match file_content[search_from..].find(content.as_str()) {
+                    Some(offset) if !content.is_empty() => {
+                        let start = search_from + offset;
+                        linked.execute(params![id, document_id, start as i64, (start + content.len()) as i64])?;
+                        search_from = start + file_content[start..].chars().next().map_or(0, char::len_utf8);
+                    }
+                    _ => {
+                        stored.execute(params![id, document_id, compress(content)?])?;
+                    }
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Get a chunk's content by ID.
+    ///
+    /// Returns None if a linked chunk's file is gone.
     pub fn get_chunk(&self, id: &str) -> Result<Option<String>> {
-        let result: Option<Vec<u8>> = self
+        Ok(self.get_chunks(&[id])?.pop().map(|(_, content)| content))
+    }
+
+    /// Position of a chunk within its document (0-based).
+    pub fn get_chunk_position(&self, id: &str) -> Result<Option<u32>> {
+        let position: Option<Option<u32>> = self
             .conn
             .query_row(
-                "SELECT content FROM chunks WHERE id = ?1",
+                "SELECT position FROM chunks WHERE id = ?1",
                 params![id],
                 |row| row.get(0),
             )
             .optional()?;
 
-        match result {
-            Some(compressed) => Ok(Some(decompress(&compressed)?)),
-            None => Ok(None),
-        }
+        Ok(position.flatten())
     }
 
     /// Get multiple chunks by IDs (batch fetch for search results).
+    ///
+    /// Linked chunks whose file is gone are left out.
     pub fn get_chunks(&self, ids: &[&str]) -> Result<Vec<(String, String)>> {
         if ids.is_empty() {
             return Ok(Vec::new());
@@ -383,27 +824,132 @@ impl ContentStore {
         // Build query with placeholders
         let placeholders: Vec<&str> = ids.iter().map(|_| "?").collect();
         let query = format!(
-            "SELECT id, content FROM chunks WHERE id IN ({})",
+            "SELECT {} WHERE c.id IN ({})",
+            CHUNK_TEXT_COLUMNS,
             placeholders.join(",")
         );
 
-        let mut stmt = self.conn.prepare(&query)?;
+        self.query_chunks(&query, rusqlite::params_from_iter(ids.iter()))
+    }
+
+    /// Get all chunks for a document as (id, content) pairs, in document order.
+    ///
+    /// Linked chunks whose file is gone are left out.
+    pub fn get_chunks_for_document(&self, document_id: &str) -> Result<Vec<(String, String)>> {
+        let query = format!(
+            "SELECT {} WHERE c.document_id = ?1 ORDER BY c.position, c.rowid",
+            CHUNK_TEXT_COLUMNS
+        );
+
+        self.query_chunks(&query, params![document_id])
+    }
 
-        // Bind all IDs
-        let rows = stmt.query_map(rusqlite::params_from_iter(ids.iter()), |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+    /// Run a query selecting `CHUNK_TEXT_COLUMNS` and resolve each chunk's text.
+    ///
+    /// Each linked file is read at most once.
+    fn query_chunks(&self, query: &str, params: impl rusqlite::Params) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(query)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
         })?;
 
+        let mut files: HashMap<String, Option<String>> = HashMap::new();
         let mut results = Vec::new();
         for row in rows {
-            let (id, compressed) = row?;
-            let content = decompress(&compressed)?;
+            let (id, compressed, byte_start, byte_end, file_path) = row?;
+            let content = match (byte_start, byte_end) {
+                (Some(start), Some(end)) => {
+                    let file = match file_path {
+                        Some(path) => files
+                            .entry(path)
+                            .or_insert_with_key(|path| std::fs::read_to_string(path).ok())
+                            .as_deref(),
+                        None => None,
+                    };
+                    match file.and_then(|f| f.get(start as usize..end as usize)) {
+                        Some(text) => text.to_string(),
+                        None => continue,
+                    }
+                }
+                _ => decompress(&compressed)?,
+            };
             results.push((id, content));
         }
 
         Ok(results)
     }
 
+    /// A chunk and up to `context` chunks either side of it, in document order
+    ///
+    /// Returns the window as (id, content) pairs with the positions of its
+    /// first and last chunk, or None if the chunk isn't in the document.
+    pub fn chunk_window(
+        &self,
+        document_id: &str,
+        chunk_id: &str,
+        context: usize,
+    ) -> Result<Option<ChunkWindow>> {
+        let chunks = self.get_chunks_for_document(document_id)?;
+        let Some(index) = chunks.iter().position(|(id, _)| id == chunk_id) else {
+            return Ok(None);
+        };
+
+        let first = index.saturating_sub(context);
+        let last = (index + context).min(chunks.len() - 1);
+        Ok(Some(ChunkWindow {
+            chunks: chunks.into_iter().skip(first).take(last - first + 1).collect(),
+            first,
+            last,
+        }))
+    }
+
+    /// Reassign all chunks of one document to another.
+    ///
+    /// The moved chunks keep their order and follow the target's own chunks.
+    pub fn move_chunks(&self, from_document_id: &str, to_document_id: &str) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let next: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM chunks WHERE document_id = ?1",
+            params![to_document_id],
+            |row| row.get(0),
+        )?;
+        let ids = tx
+            .prepare("SELECT id FROM chunks WHERE document_id = ?1 ORDER BY position, rowid")?
+            .query_map(params![from_document_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        {
+            let mut stmt = tx.prepare("UPDATE chunks SET document_id = ?2, position = ?3 WHERE id = ?1")?;
+            for (offset, id) in ids.iter().enumerate() {
+                stmt.execute(params![id, to_document_id, next + offset as i64])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(ids.len())
+    }
+
+    /// Delete chunks by ID.
+    pub fn delete_chunks(&self, ids: &[String]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        {
+            let mut stmt = tx.prepare("DELETE FROM chunks WHERE id = ?1")?;
+            for id in ids {
+                stmt.execute(params![id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Delete all chunks for a document.
     pub fn delete_chunks_for_document(&self, document_id: &str) -> Result<()> {
         self.conn.execute(
@@ -438,12 +984,33 @@ impl ContentStore {
         Ok(())
     }
 
+    /// Move documents to another source.
+    pub fn move_documents_to_source(&self, document_ids: &[&str], source_id: &str) -> Result<usize> {
+        if document_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut moved = 0;
+        {
+            let mut stmt = tx.prepare("UPDATE documents SET source_id = ?2 WHERE id = ?1")?;
+            for id in document_ids {
+                moved += stmt.execute(params![id, source_id])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(moved)
+    }
+
     /// Delete all content for a source directly by source_id.
     pub fn delete_source_by_source_id(&self, source_id: &str) -> Result<usize> {
         // Chunks deleted via CASCADE
         let deleted = self
             .conn
             .execute("DELETE FROM documents WHERE source_id = ?1", params![source_id])?;
+        self.conn
+            .execute("DELETE FROM sources WHERE id = ?1", params![source_id])?;
 
         Ok(deleted)
     }
@@ -452,8 +1019,11 @@ impl ContentStore {
     pub fn reset(&self) -> Result<()> {
         self.conn.execute_batch(
             "
+            DELETE FROM bookmarks;
             DELETE FROM chunks;
             DELETE FROM documents;
+            DELETE FROM sources;
+            DELETE FROM index_meta;
             VACUUM;
             ",
         )?;
@@ -461,6 +1031,142 @@ impl ContentStore {
         Ok(())
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Source Metadata
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Create an empty source. Returns false if a source with this ID
+    /// already exists, whether created here or by ingesting into it.
+    pub fn create_source(&self, id: &str, description: Option<&str>) -> Result<bool> {
+        let has_documents: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM documents WHERE source_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if has_documents > 0 {
+            return Ok(false);
+        }
+
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO sources (id, description, created_at) VALUES (?1, ?2, ?3)",
+            params![id, description, now],
+        )?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Descriptions of sources created with `create_source`.
+    pub fn source_descriptions(&self) -> Result<Vec<(String, Option<String>)>> {
+        let mut stmt = self.conn.prepare("SELECT id, description FROM sources ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut sources = Vec::new();
+        for row in rows {
+            sources.push(row?);
+        }
+
+        Ok(sources)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Index Metadata
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Record the embedding model that built the index. Keeps the first
+    /// model recorded, so vectors written by another model later don't
+    /// hide the mismatch.
+    pub fn record_index_model(&self, model_id: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO index_meta (key, value) VALUES ('embedding_model', ?1)",
+            params![model_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Embedding model that built the index, if anything has been indexed.
+    pub fn index_model(&self) -> Result<Option<String>> {
+        let model = self
+            .conn
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = 'embedding_model'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(model)
+    }
+
+    /// Forget the index model, before rebuilding vectors with a new one.
+    pub fn clear_index_model(&self) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM index_meta WHERE key = 'embedding_model'", [])?;
+
+        Ok(())
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Bookmark Operations
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Bookmark a document, replacing the note if it's already bookmarked.
+    pub fn add_bookmark(&self, document_id: &str, note: Option<&str>) -> Result<()> {
+        let exists: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM documents WHERE id = ?1",
+            params![document_id],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            anyhow::bail!("Document not found: {}", document_id);
+        }
+
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        self.conn.execute(
+            "INSERT INTO bookmarks (document_id, note, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(document_id) DO UPDATE SET note = excluded.note",
+            params![document_id, note, now],
+        )?;
+
+        Ok(())
+    }
+
+    /// List bookmarks, newest first.
+    pub fn list_bookmarks(&self) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT b.document_id, d.source_id, d.title, b.note, b.created_at
+             FROM bookmarks b JOIN documents d ON d.id = b.document_id
+             ORDER BY b.created_at DESC, b.rowid DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(Bookmark {
+                document_id: row.get(0)?,
+                source_id: row.get(1)?,
+                title: row.get(2)?,
+                note: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+
+        let mut bookmarks = Vec::new();
+        for row in rows {
+            bookmarks.push(row?);
+        }
+
+        Ok(bookmarks)
+    }
+
+    /// Remove a bookmark. Returns false if the document wasn't bookmarked.
+    pub fn remove_bookmark(&self, document_id: &str) -> Result<bool> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM bookmarks WHERE document_id = ?1", params![document_id])?;
+
+        Ok(removed > 0)
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Stats
     // ─────────────────────────────────────────────────────────────────────────
@@ -501,6 +1207,17 @@ pub struct ContentStats {
 // Compression Helpers
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// Decompress stored content, or read it from disk for linked documents.
+///
+/// Returns None if a linked document's file is gone.
+fn resolve_content(compressed: &[u8], linked: bool, file_path: Option<&str>) -> Result<Option<String>> {
+    if !linked {
+        return decompress(compressed).map(Some);
+    }
+
+    Ok(file_path.and_then(|p| std::fs::read_to_string(p).ok()))
+}
+
 /// Compress a string using zstd.
 fn compress(data: &str) -> Result<Vec<u8>> {
     zstd::encode_all(data.as_bytes(), COMPRESSION_LEVEL)
@@ -630,6 +1347,124 @@ mod tests {
         assert_eq!(doc2.file_path, None);
     }
 
+    #[test]
+    fn test_get_document_row() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store
+            .insert_document(
+                "doc1",
+                "source-a",
+                "Doc One",
+                Some("/path/one.md"),
+                "Content one",
+                "2024-01-01T00:00:00Z",
+            )
+            .unwrap();
+
+        let row = store.get_document_row("doc1").unwrap().unwrap();
+        assert_eq!(row.source_id, "source-a");
+        assert_eq!(row.content, "Content one");
+        assert!(store.get_document_row("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_move_documents_and_chunks() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store
+            .insert_document("doc1", "old", "One", None, "Content", "2024-01-01T00:00:00Z")
+            .unwrap();
+        store
+            .insert_document("doc2", "new", "Two", None, "Content", "2024-01-01T00:00:00Z")
+            .unwrap();
+        store.insert_chunk("c1", "doc1", "Chunk").unwrap();
+
+        assert_eq!(store.move_documents_to_source(&["doc1"], "new").unwrap(), 1);
+        let (docs, total) = store.list_documents_by_source("new", None, None).unwrap();
+        assert_eq!(total, 2);
+        assert!(docs.iter().any(|d| d.id == "doc1"));
+
+        store.insert_chunk("c2", "doc1", "Chunk").unwrap();
+        store.insert_chunk("t1", "doc2", "Chunk").unwrap();
+        store.insert_chunk("t2", "doc2", "Chunk").unwrap();
+
+        // Moved chunks follow the target's own instead of interleaving
+        assert_eq!(store.move_chunks("doc1", "doc2").unwrap(), 2);
+        assert!(store.get_chunks_for_document("doc1").unwrap().is_empty());
+        let order: Vec<String> = store.get_chunks_for_document("doc2").unwrap().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(order, vec!["t1", "t2", "c1", "c2"]);
+        assert_eq!(store.get_chunk_position("c2").unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_linked_document_reads_from_disk() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+        let file = dir.path().join("notes.md");
+        let on_disk = "Linked content on disk, linked again";
+        std::fs::write(&file, on_disk).unwrap();
+
+        store
+            .insert_linked_document("doc1", "docs", "Notes", file.to_str().unwrap(), "2024-01-01T00:00:00Z")
+            .unwrap();
+        let chunks = [
+            ("c1".to_string(), "doc1".to_string(), "Linked content".to_string()),
+            ("c2".to_string(), "doc1".to_string(), "content on disk".to_string()),
+            ("c3".to_string(), "doc1".to_string(), "linked again".to_string()),
+            ("c4".to_string(), "doc1".to_string(), "Rewritten by the chunker".to_string()),
+        ];
+        store.insert_linked_chunks(on_disk, &chunks).unwrap();
+
+        assert_eq!(store.get_document("doc1").unwrap().as_deref(), Some(on_disk));
+        assert_eq!(
+            store.get_chunks_for_document("doc1").unwrap(),
+            chunks.iter().map(|(id, _, text)| (id.clone(), text.clone())).collect::<Vec<_>>()
+        );
+
+        // Edits on disk are picked up
+        std::fs::write(&file, "Updated content").unwrap();
+        assert_eq!(store.get_document("doc1").unwrap().as_deref(), Some("Updated content"));
+
+        // Only chunks that weren't in the file kept their text
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(store.get_document("doc1").unwrap(), None);
+        assert_eq!(store.get_chunk("c1").unwrap(), None);
+        assert_eq!(
+            store.get_chunks_for_document("doc1").unwrap(),
+            vec![("c4".to_string(), "Rewritten by the chunker".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("content.db");
+        let store = ContentStore::open(&path).unwrap();
+
+        store
+            .insert_document("doc1", "docs", "Guide", None, "Content", "2024-01-01T00:00:00Z")
+            .unwrap();
+
+        assert!(store.add_bookmark("missing", None).is_err());
+        store.add_bookmark("doc1", Some("read later")).unwrap();
+        drop(store);
+
+        // Survives reopening
+        let store = ContentStore::open(&path).unwrap();
+        let bookmarks = store.list_bookmarks().unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].document_id, "doc1");
+        assert_eq!(bookmarks[0].title, "Guide");
+        assert_eq!(bookmarks[0].note.as_deref(), Some("read later"));
+
+        assert!(store.remove_bookmark("doc1").unwrap());
+        assert!(!store.remove_bookmark("doc1").unwrap());
+        assert!(store.list_bookmarks().unwrap().is_empty());
+    }
+
     #[test]
     fn test_compression() {
         let original = "Hello ".repeat(1000); // Repetitive content compresses well
@@ -639,4 +1474,230 @@ mod tests {
         assert_eq!(original, decompressed);
         assert!(compressed.len() < original.len()); // Should be smaller
     }
+
+    #[test]
+    fn test_create_empty_source() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        assert!(store.create_source("papers", Some("Research papers")).unwrap());
+        let sources = store.list_sources().unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].id, "papers");
+        assert_eq!(sources[0].description.as_deref(), Some("Research papers"));
+        assert_eq!(sources[0].doc_count, 0);
+        assert_eq!(sources[0].total_size, 0);
+
+        // Creating it again, or creating one that ingest already made, fails
+        assert!(!store.create_source("papers", None).unwrap());
+        store
+            .insert_document("doc1", "notes", "Note", None, "content", "2024-01-01T00:00:00Z")
+            .unwrap();
+        assert!(!store.create_source("notes", None).unwrap());
+
+        // Documents added later are counted against the created source
+        store
+            .insert_document("doc2", "papers", "Paper", None, "content", "2024-01-01T00:00:00Z")
+            .unwrap();
+        let sources = store.list_sources().unwrap();
+        let papers = sources.iter().find(|s| s.id == "papers").unwrap();
+        assert_eq!(papers.doc_count, 1);
+        assert_eq!(papers.description.as_deref(), Some("Research papers"));
+
+        store.delete_source_by_source_id("papers").unwrap();
+        assert!(store.list_sources().unwrap().iter().all(|s| s.id != "papers"));
+    }
+
+    #[test]
+    fn test_sources_matching_ignore_case() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+        store
+            .insert_document("doc1", "Docs", "Doc", None, "content", "2024-01-01T00:00:00Z")
+            .unwrap();
+        store.create_source("Papers", None).unwrap();
+
+        assert_eq!(store.sources_matching_ignore_case("docs").unwrap(), vec!["Docs"]);
+        assert_eq!(store.sources_matching_ignore_case("PAPERS").unwrap(), vec!["Papers"]);
+        assert!(store.sources_matching_ignore_case("notes").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_index_model_keeps_first_until_cleared() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store.record_index_model("all-minilm-l12-v2").unwrap();
+        store.record_index_model("bge-base-en-v1.5").unwrap();
+        assert_eq!(store.index_model().unwrap().as_deref(), Some("all-minilm-l12-v2"));
+
+        store.clear_index_model().unwrap();
+        store.record_index_model("bge-base-en-v1.5").unwrap();
+        assert_eq!(store.index_model().unwrap().as_deref(), Some("bge-base-en-v1.5"));
+    }
+
+    #[test]
+    fn test_file_hash_lives_with_its_documents() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store
+            .insert_document("doc1", "docs", "Guide", Some("guide.md"), "Guide text", "2024-01-01T00:00:00Z")
+            .unwrap();
+        store
+            .insert_document("doc2", "docs", "Other", Some("other.md"), "Other text", "2024-01-01T00:00:00Z")
+            .unwrap();
+        store.set_file_hash(&["doc1".to_string(), "doc2".to_string()], "guide.md", "abc").unwrap();
+
+        assert!(store.has_file_hash("docs", "guide.md", "abc").unwrap());
+        assert!(!store.has_file_hash("docs", "guide.md", "def").unwrap());
+        assert!(!store.has_file_hash("notes", "guide.md", "abc").unwrap());
+        // Only documents from that file are tagged
+        assert!(!store.has_file_hash("docs", "other.md", "abc").unwrap());
+
+        // Deleting the document forgets the hash
+        store.delete_document("doc1").unwrap();
+        assert!(!store.has_file_hash("docs", "guide.md", "abc").unwrap());
+    }
+
+    #[test]
+    fn test_pinned_chunks() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store.insert_document("doc1", "docs", "Guide", None, "Guide text", "2024-01-01T00:00:00Z").unwrap();
+        store.insert_document("doc2", "docs", "Other", None, "Other text", "2024-01-01T00:00:00Z").unwrap();
+        store.insert_chunk("c1", "doc1", "Guide text").unwrap();
+        store.insert_chunk("c2", "doc2", "Other text").unwrap();
+
+        assert!(store.pinned_chunks(&["c1", "c2"]).unwrap().is_empty());
+        assert!(!store.set_pinned("missing", true, None).unwrap());
+
+        assert!(store.set_pinned("doc1", true, None).unwrap());
+        assert!(store.set_pinned("doc2", true, Some(0.5)).unwrap());
+        let pinned = store.pinned_chunks(&["c1", "c2"]).unwrap();
+        assert_eq!(pinned.get("c1"), Some(&None));
+        assert_eq!(pinned.get("c2"), Some(&Some(0.5)));
+
+        store.set_pinned("doc2", false, Some(0.5)).unwrap();
+        assert_eq!(store.pinned_chunks(&["c1", "c2"]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_find_documents_by_title() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        for (id, source, title) in [
+            ("d1", "docs", "Deploy checklist"),
+            ("d2", "docs", "How we deploy"),
+            ("d3", "notes", "Deployment notes"),
+            ("d4", "docs", "100% uptime_plan"),
+        ] {
+            store.insert_document(id, source, title, None, "Text", "2024-01-01T00:00:00Z").unwrap();
+        }
+
+        let ids = |docs: Vec<DocumentListItem>| docs.into_iter().map(|d| d.id).collect::<Vec<_>>();
+        // Prefix matches first, then by title; case-insensitive
+        assert_eq!(ids(store.find_documents_by_title("DEPLOY", None, 10).unwrap()), vec!["d1", "d3", "d2"]);
+        assert_eq!(ids(store.find_documents_by_title("deploy", Some("docs"), 10).unwrap()), vec!["d1", "d2"]);
+        assert_eq!(ids(store.find_documents_by_title("deploy", None, 1).unwrap()), vec!["d1"]);
+        // Wildcards are matched literally
+        assert_eq!(ids(store.find_documents_by_title("% up", None, 10).unwrap()), vec!["d4"]);
+        assert!(store.find_documents_by_title("0%u", None, 10).unwrap().is_empty());
+        assert!(store.find_documents_by_title("t_m", None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chunk_order_roundtrip() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store.insert_document("doc1", "docs", "Guide", None, "Guide text", "2024-01-01T00:00:00Z").unwrap();
+        store.insert_document("doc2", "docs", "Other", None, "Other text", "2024-01-01T00:00:00Z").unwrap();
+        let chunks: Vec<(String, String, String)> = [("m", "doc1"), ("x", "doc2"), ("b", "doc1"), ("k", "doc1")]
+            .iter()
+            .map(|(id, doc)| (id.to_string(), doc.to_string(), format!("Chunk {}", id)))
+            .collect();
+        store.insert_chunks(&chunks).unwrap();
+
+        let stored = store.get_chunks_for_document("doc1").unwrap();
+        assert_eq!(
+            stored,
+            vec![
+                ("m".to_string(), "Chunk m".to_string()),
+                ("b".to_string(), "Chunk b".to_string()),
+                ("k".to_string(), "Chunk k".to_string()),
+            ]
+        );
+        // Positions count per document
+        assert_eq!(store.get_chunk_position("k").unwrap(), Some(2));
+        assert_eq!(store.get_chunk_position("x").unwrap(), Some(0));
+        assert_eq!(store.get_chunk_position("missing").unwrap(), None);
+
+        // Replacing a chunk keeps its place
+        store.insert_chunk("m", "doc1", "Chunk m, edited").unwrap();
+        store.insert_linked_chunks("Chunk b", &[("b".to_string(), "doc1".to_string(), "Chunk b".to_string())]).unwrap();
+        assert_eq!(store.get_chunk_position("m").unwrap(), Some(0));
+        assert_eq!(store.get_chunk_position("b").unwrap(), Some(1));
+        assert_eq!(store.get_chunk_position("k").unwrap(), Some(2));
+
+        // Re-chunking a document starts over from 0
+        store.delete_chunks_for_document("doc1").unwrap();
+        store.insert_chunk("k", "doc1", "Chunk k").unwrap();
+        assert_eq!(store.get_chunk_position("k").unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_chunk_window() {
+        let dir = tempdir().unwrap();
+        let store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+
+        store.insert_document("doc1", "docs", "Guide", None, "Guide text", "2024-01-01T00:00:00Z").unwrap();
+        let chunks: Vec<(String, String, String)> = ["e", "d", "c", "b"]
+            .iter()
+            .map(|id| (id.to_string(), "doc1".to_string(), format!("Chunk {}", id)))
+            .collect();
+        store.insert_chunks(&chunks).unwrap();
+        store.insert_chunk("a", "doc1", "Chunk a").unwrap();
+
+        let ids = |window: &ChunkWindow| window.chunks.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let window = store.chunk_window("doc1", "c", 1).unwrap().unwrap();
+        assert_eq!(ids(&window), vec!["d", "c", "b"]);
+        assert_eq!((window.first, window.last), (1, 3));
+
+        // Clipped at both ends of the document
+        let window = store.chunk_window("doc1", "d", 5).unwrap().unwrap();
+        assert_eq!(ids(&window), vec!["e", "d", "c", "b", "a"]);
+        assert_eq!((window.first, window.last), (0, 4));
+
+        assert!(store.chunk_window("doc1", "missing", 1).unwrap().is_none());
+        assert!(store.chunk_window("doc2", "c", 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chunk_positions_backfilled_on_migration() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("content.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE chunks (id TEXT PRIMARY KEY, document_id TEXT NOT NULL, content BLOB NOT NULL);",
+            )
+            .unwrap();
+            for (id, doc) in [("z", "doc1"), ("x", "doc2"), ("a", "doc1"), ("m", "doc1")] {
+                conn.execute(
+                    "INSERT INTO chunks (id, document_id, content) VALUES (?1, ?2, ?3)",
+                    params![id, doc, compress(id).unwrap()],
+                )
+                .unwrap();
+            }
+        }
+
+        let store = ContentStore::open(&path).unwrap();
+        let order: Vec<String> = store.get_chunks_for_document("doc1").unwrap().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(order, vec!["z", "a", "m"]);
+        let window = store.chunk_window("doc1", "m", 0).unwrap().unwrap();
+        assert_eq!((window.first, window.last), (2, 2));
+    }
 }
@@ -3,21 +3,26 @@
 //! Stores only metadata and vectors. Content lives in SQLite (see content.rs).
 //! This separation enables efficient storage while maintaining fast vector search.
 
-use crate::config::Config;
+use crate::config::DedupScope;
 use anyhow::{Context, Result};
 use arrow_array::{
     Array, BooleanArray, Float32Array, RecordBatch, RecordBatchIterator, StringArray, UInt32Array,
 };
 use arrow_schema::{DataType, Field, Schema};
 use futures::TryStreamExt;
-use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::{NewColumnTransform, OptimizeAction};
 use lancedb::{connect, Connection, DistanceType, Table};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::types::{ChunkMeta, DocumentMeta, DocumentRecord, Source};
 
 const CHUNKS_TABLE: &str = "chunks_v2";
 const DOCS_TABLE: &str = "documents_v2";
+/// Prefix of per-source chunk tables (followed by the hex-encoded source id)
+const SOURCE_TABLE_PREFIX: &str = "chunks_src_";
 
 /// Maximum limit for queries when all documents are needed.
 /// LanceDB v0.15 defaults to 10 if no limit is specified.
@@ -33,6 +38,9 @@ pub struct ChunkRecord {
     pub file_path: Option<String>,
     pub line_start: Option<u32>,
     pub line_end: Option<u32>,
+    /// PDF page range; line fields stay empty for PDF chunks
+    pub page_start: Option<u32>,
+    pub page_end: Option<u32>,
     pub content_hash: String,
     // Hierarchical metadata from smart chunking
     pub section: Option<String>,
@@ -41,26 +49,217 @@ pub struct ChunkRecord {
     pub has_code: bool,
 }
 
+/// On-disk breakdown of a single LanceDB table
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TableStorage {
+    /// Table name (e.g. `chunks_v2`)
+    pub name: String,
+    /// Logical row count in the current version
+    pub rows: usize,
+    /// Data fragments referenced by the current version
+    pub fragments: usize,
+    /// Table versions still on disk
+    pub versions: usize,
+    /// Total size of the table directory
+    pub total_bytes: u64,
+    /// Bytes held only by old versions (freed by `eywa optimize`)
+    pub reclaimable_bytes: u64,
+}
+
 /// Escape single quotes in strings to prevent SQL injection
 fn escape_sql(s: &str) -> String {
     s.replace('\'', "''")
 }
 
+/// Chunk metadata from query results; the score is cosine similarity for
+/// vector searches and 0 for plain queries
+fn chunk_metas_from_batches(batches: Vec<RecordBatch>) -> Vec<ChunkMeta> {
+    let mut search_results = Vec::new();
+
+    for batch in batches {
+        let ids = batch
+            .column_by_name("id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let document_ids = batch
+            .column_by_name("document_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let source_ids = batch
+            .column_by_name("source_id")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let titles = batch
+            .column_by_name("title")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let file_paths = batch
+            .column_by_name("file_path")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let line_starts = batch
+            .column_by_name("line_start")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+        let line_ends = batch
+            .column_by_name("line_end")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+        // Missing in tables written before page ranges were stored
+        let page_starts = batch
+            .column_by_name("page_start")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+        let page_ends = batch
+            .column_by_name("page_end")
+            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+        let sections = batch
+            .column_by_name("section")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+        let has_codes = batch
+            .column_by_name("has_code")
+            .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+        let distances = batch
+            .column_by_name("_distance")
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+
+        if let (Some(ids), Some(document_ids), Some(source_ids)) = (ids, document_ids, source_ids) {
+            for i in 0..batch.num_rows() {
+                let score = distances.map(|d| 1.0 - d.value(i)).unwrap_or(0.0);
+                search_results.push(ChunkMeta {
+                    id: ids.value(i).to_string(),
+                    document_id: document_ids.value(i).to_string(),
+                    source_id: source_ids.value(i).to_string(),
+                    title: titles.and_then(|t| {
+                        if t.is_null(i) {
+                            None
+                        } else {
+                            Some(t.value(i).to_string())
+                        }
+                    }),
+                    file_path: file_paths.and_then(|f| {
+                        if f.is_null(i) {
+                            None
+                        } else {
+                            Some(f.value(i).to_string())
+                        }
+                    }),
+                    line_start: line_starts.and_then(|l| {
+                        if l.is_null(i) {
+                            None
+                        } else {
+                            Some(l.value(i))
+                        }
+                    }),
+                    line_end: line_ends.and_then(|l| {
+                        if l.is_null(i) {
+                            None
+                        } else {
+                            Some(l.value(i))
+                        }
+                    }),
+                    page_start: page_starts.and_then(|p| {
+                        if p.is_null(i) {
+                            None
+                        } else {
+                            Some(p.value(i))
+                        }
+                    }),
+                    page_end: page_ends.and_then(|p| {
+                        if p.is_null(i) {
+                            None
+                        } else {
+                            Some(p.value(i))
+                        }
+                    }),
+                    section: sections.and_then(|s| {
+                        if s.is_null(i) {
+                            None
+                        } else {
+                            Some(s.value(i).to_string())
+                        }
+                    }),
+                    has_code: has_codes.map(|h| h.value(i)).unwrap_or(false),
+                    score,
+                });
+            }
+        }
+    }
+
+    search_results
+}
+
+/// Sort search hits best first
+fn sort_by_score(hits: &mut [ChunkMeta]) {
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// The top `limit` hits plus each source's top `per_source`, best first
+///
+/// Hits found more than once are kept once.
+fn with_source_quota(mut hits: Vec<ChunkMeta>, limit: usize, per_source: usize) -> Vec<ChunkMeta> {
+    sort_by_score(&mut hits);
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut per_source_kept: HashMap<String, usize> = HashMap::new();
+    let mut kept = Vec::new();
+    for hit in hits {
+        if !seen.insert(hit.id.clone()) {
+            continue;
+        }
+        let rank = seen.len();
+        let source_rank = per_source_kept.entry(hit.source_id.clone()).or_default();
+        *source_rank += 1;
+        if rank <= limit || *source_rank <= per_source {
+            kept.push(hit);
+        }
+    }
+    kept
+}
+
+/// Filter on chunk rows for a source and/or code-only search
+fn chunk_filter(source_id: Option<&str>, code_only: bool) -> Option<String> {
+    let mut filters = Vec::new();
+    if let Some(source) = source_id {
+        filters.push(format!("source_id = '{}'", escape_sql(source)));
+    }
+    if code_only {
+        filters.push("has_code = true".to_string());
+    }
+    (!filters.is_empty()).then(|| filters.join(" AND "))
+}
+
+/// Chunk table name for a source (hex keeps any source id a valid name)
+fn source_table_name(source_id: &str) -> String {
+    let hex: String = source_id.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", SOURCE_TABLE_PREFIX, hex)
+}
+
+/// Source id encoded in a per-source chunk table name
+fn source_from_table_name(name: &str) -> Option<String> {
+    let hex = name.strip_prefix(SOURCE_TABLE_PREFIX)?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
 pub struct VectorDB {
     conn: Connection,
+    data_dir: PathBuf,
     chunks_table: Option<Table>,
+    /// Chunk tables keyed by source id (None = single shared chunks table)
+    source_tables: Option<HashMap<String, Table>>,
     docs_table: Option<Table>,
-    embedding_dim: usize,
 }
 
 impl VectorDB {
-    /// Create a new VectorDB instance
+    /// Create a new VectorDB instance with a single shared chunks table
     pub async fn new(data_dir: &str) -> Result<Self> {
-        // Get embedding dimension from config
-        let embedding_dim = Config::load()?
-            .map(|c| c.embedding_model.dimensions)
-            .unwrap_or(768); // Default to BGE base dimensions
+        Self::open(data_dir, false).await
+    }
 
+    /// Create a VectorDB with an explicit chunk table layout
+    ///
+    /// With `per_source_tables` each source's chunks live in their own table,
+    /// so source-filtered search and source deletion only touch that table.
+    pub async fn open(data_dir: &str, per_source_tables: bool) -> Result<Self> {
         let conn = connect(data_dir)
             .execute()
             .await
@@ -69,21 +268,37 @@ impl VectorDB {
         let chunks_table = conn.open_table(CHUNKS_TABLE).execute().await.ok();
         let docs_table = conn.open_table(DOCS_TABLE).execute().await.ok();
 
+        let source_tables = if per_source_tables {
+            let mut tables = HashMap::new();
+            for name in conn.table_names().execute().await? {
+                let Some(source_id) = source_from_table_name(&name) else {
+                    continue;
+                };
+                if let Ok(table) = conn.open_table(&name).execute().await {
+                    tables.insert(source_id, table);
+                }
+            }
+            Some(tables)
+        } else {
+            None
+        };
+
         Ok(Self {
             conn,
+            data_dir: PathBuf::from(data_dir),
             chunks_table,
+            source_tables,
             docs_table,
-            embedding_dim,
         })
     }
 
     /// Get or create the chunks table
-    async fn get_or_create_chunks_table(&mut self) -> Result<Table> {
+    async fn get_or_create_chunks_table(&mut self, embedding_dim: usize) -> Result<Table> {
         if let Some(ref table) = self.chunks_table {
             return Ok(table.clone());
         }
 
-        let schema = self.chunks_schema();
+        let schema = Self::chunks_schema(embedding_dim);
         let table = self
             .conn
             .create_empty_table(CHUNKS_TABLE, schema)
@@ -95,6 +310,62 @@ impl VectorDB {
         Ok(table)
     }
 
+    /// Get or create the chunk table of one source (per-source layout only)
+    async fn get_or_create_source_table(&mut self, source_id: &str, embedding_dim: usize) -> Result<Table> {
+        if let Some(table) = self.source_tables.as_ref().and_then(|t| t.get(source_id)) {
+            return Ok(table.clone());
+        }
+
+        let schema = Self::chunks_schema(embedding_dim);
+        let table = self
+            .conn
+            .create_empty_table(source_table_name(source_id), schema)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to create chunks table for source '{}'", source_id))?;
+
+        self.source_tables
+            .get_or_insert_with(HashMap::new)
+            .insert(source_id.to_string(), table.clone());
+        Ok(table)
+    }
+
+    /// Chunk tables a query scoped to `source_id` has to look at
+    fn chunk_tables_for(&self, source_id: Option<&str>) -> Vec<&Table> {
+        match (&self.source_tables, source_id) {
+            (Some(tables), Some(source)) => tables.get(source).into_iter().collect(),
+            (Some(tables), None) => tables.values().collect(),
+            (None, _) => self.chunks_table.iter().collect(),
+        }
+    }
+
+    /// Every chunk table with its on-disk name
+    fn named_chunk_tables(&self) -> Vec<(String, &Table)> {
+        match &self.source_tables {
+            Some(tables) => {
+                let mut named: Vec<(String, &Table)> = tables
+                    .iter()
+                    .map(|(source, table)| (source_table_name(source), table))
+                    .collect();
+                named.sort_by(|a, b| a.0.cmp(&b.0));
+                named
+            }
+            None => self
+                .chunks_table
+                .iter()
+                .map(|table| (CHUNKS_TABLE.to_string(), table))
+                .collect(),
+        }
+    }
+
+    /// Refuse operations that would move chunk rows between per-source tables
+    fn ensure_shared_chunks_table(&self, operation: &str) -> Result<()> {
+        if self.source_tables.is_some() {
+            anyhow::bail!("{} is not supported with per_source_tables enabled", operation);
+        }
+        Ok(())
+    }
+
     /// Get or create the documents table
     async fn get_or_create_docs_table(&mut self) -> Result<Table> {
         if let Some(ref table) = self.docs_table {
@@ -131,7 +402,7 @@ impl VectorDB {
     }
 
     /// Schema for chunks table (metadata + vectors, no content)
-    fn chunks_schema(&self) -> Arc<Schema> {
+    fn chunks_schema(embedding_dim: usize) -> Arc<Schema> {
         Arc::new(Schema::new(vec![
             Field::new("id", DataType::Utf8, false),
             Field::new("document_id", DataType::Utf8, false),
@@ -146,11 +417,13 @@ impl VectorDB {
             Field::new("subsection", DataType::Utf8, true),
             Field::new("hierarchy", DataType::Utf8, true), // JSON serialized
             Field::new("has_code", DataType::Boolean, false),
+            Field::new("page_start", DataType::UInt32, true),
+            Field::new("page_end", DataType::UInt32, true),
             Field::new(
                 "vector",
                 DataType::FixedSizeList(
                     Arc::new(Field::new("item", DataType::Float32, true)),
-                    self.embedding_dim as i32,
+                    embedding_dim as i32,
                 ),
                 false,
             ),
@@ -179,12 +452,42 @@ impl VectorDB {
             ],
         )?;
 
+        // Tables written before page ranges were stored gain the columns at
+        // the end, so line the batch up with the table's column order
+        let table_schema = Self::ensure_page_columns(table).await?;
+        let indices = table_schema
+            .fields()
+            .iter()
+            .map(|f| schema.index_of(f.name()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let batch = batch.project(&indices)?;
+        let schema = batch.schema();
+
         let batches = RecordBatchIterator::new(vec![Ok(batch)], schema);
         table.add(batches).execute().await?;
 
         Ok(())
     }
 
+    /// Add the page range columns to a chunk table created without them
+    async fn ensure_page_columns(table: &Table) -> Result<Arc<Schema>> {
+        let schema = table.schema().await?;
+        if schema.field_with_name("page_start").is_ok() {
+            return Ok(schema);
+        }
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![
+                    ("page_start".to_string(), "CAST(NULL AS INT UNSIGNED)".to_string()),
+                    ("page_end".to_string(), "CAST(NULL AS INT UNSIGNED)".to_string()),
+                ]),
+                None,
+            )
+            .await
+            .context("Failed to add page columns to chunks table")?;
+        Ok(table.schema().await?)
+    }
+
     /// Get a document record by ID (metadata only)
     pub async fn get_document(&self, doc_id: &str) -> Result<Option<DocumentRecord>> {
         let table = match &self.docs_table {
@@ -212,21 +515,24 @@ impl VectorDB {
         Ok(None)
     }
 
-    /// List all documents in a source
+    /// List documents in a source, one page at a time
     /// Note: LanceDB v0.15 defaults to limit=10, so we explicitly set a limit.
-    /// Pass None for default (10), or Some(n) for custom limit.
-    pub async fn list_documents(&self, source_id: &str, limit: Option<usize>) -> Result<Vec<DocumentMeta>> {
+    /// Pass None for default (10), or Some(n) for custom limit. An offset past
+    /// the last document gives an empty page.
+    pub async fn list_documents(&self, source_id: &str, limit: Option<usize>, offset: Option<usize>) -> Result<Vec<DocumentMeta>> {
         let table = match &self.docs_table {
             Some(t) => t,
             None => return Ok(vec![]),
         };
 
-        let results = table
+        let mut query = table
             .query()
             .only_if(format!("source_id = '{}'", escape_sql(source_id)))
-            .limit(limit.unwrap_or(10))
-            .execute()
-            .await?;
+            .limit(limit.unwrap_or(10));
+        if let Some(offset) = offset {
+            query = query.offset(offset);
+        }
+        let results = query.execute().await?;
 
         let batches: Vec<RecordBatch> = results.try_collect().await?;
         let mut docs = Vec::new();
@@ -250,6 +556,16 @@ impl VectorDB {
         Ok(docs)
     }
 
+    /// Number of documents in a source
+    pub async fn count_documents(&self, source_id: &str) -> Result<usize> {
+        match &self.docs_table {
+            Some(table) => Ok(table
+                .count_rows(Some(format!("source_id = '{}'", escape_sql(source_id))))
+                .await?),
+            None => Ok(0),
+        }
+    }
+
     /// Get all document records (for export)
     /// Note: LanceDB v0.15 defaults to limit=10, so we explicitly set a limit.
     /// Pass None for default (10), or Some(n) for custom limit.
@@ -360,9 +676,44 @@ impl VectorDB {
         if chunks.is_empty() {
             return Ok(());
         }
+        // The vectors set the table's dimensions, so any embedding model works
+        let embedding_dim = embeddings
+            .first()
+            .map(|e| e.len())
+            .context("Chunks given without embeddings")?;
+
+        if self.source_tables.is_none() {
+            let table = self.get_or_create_chunks_table(embedding_dim).await?;
+            return self.add_chunks(&table, chunks, embeddings).await;
+        }
 
-        let table = self.get_or_create_chunks_table().await?;
+        // Per-source layout: split the batch by source
+        let mut by_source: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            by_source.entry(chunk.source_id.as_str()).or_default().push(i);
+        }
+        for (source_id, indices) in by_source {
+            let table = self.get_or_create_source_table(source_id, embedding_dim).await?;
+            if indices.len() == chunks.len() {
+                self.add_chunks(&table, chunks, embeddings).await?;
+                continue;
+            }
+            let part: Vec<ChunkRecord> = indices.iter().map(|&i| chunks[i].clone()).collect();
+            let part_embeddings: Vec<Vec<f32>> =
+                indices.iter().map(|&i| embeddings[i].clone()).collect();
+            self.add_chunks(&table, &part, &part_embeddings).await?;
+        }
+
+        Ok(())
+    }
 
+    /// Append chunk rows to one chunk table
+    async fn add_chunks(
+        &self,
+        table: &Table,
+        chunks: &[ChunkRecord],
+        embeddings: &[Vec<f32>],
+    ) -> Result<()> {
         let ids: Vec<&str> = chunks.iter().map(|c| c.id.as_str()).collect();
         let document_ids: Vec<&str> = chunks.iter().map(|c| c.document_id.as_str()).collect();
         let source_ids: Vec<&str> = chunks.iter().map(|c| c.source_id.as_str()).collect();
@@ -382,10 +733,13 @@ impl VectorDB {
             .collect();
         let hierarchy_refs: Vec<&str> = hierarchies.iter().map(|s| s.as_str()).collect();
         let has_codes: Vec<bool> = chunks.iter().map(|c| c.has_code).collect();
+        let page_starts: Vec<Option<u32>> = chunks.iter().map(|c| c.page_start).collect();
+        let page_ends: Vec<Option<u32>> = chunks.iter().map(|c| c.page_end).collect();
 
+        let embedding_dim = embeddings.first().map_or(0, |e| e.len());
         let flat_embeddings: Vec<f32> = embeddings.iter().flatten().copied().collect();
 
-        let schema = self.chunks_schema();
+        let schema = Self::chunks_schema(embedding_dim);
 
         let batch = RecordBatch::try_new(
             schema.clone(),
@@ -403,9 +757,11 @@ impl VectorDB {
                 Arc::new(StringArray::from(subsections)),
                 Arc::new(StringArray::from(hierarchy_refs)),
                 Arc::new(BooleanArray::from(has_codes)),
+                Arc::new(UInt32Array::from(page_starts)),
+                Arc::new(UInt32Array::from(page_ends)),
                 Arc::new(arrow_array::FixedSizeListArray::new(
                     Arc::new(Field::new("item", DataType::Float32, true)),
-                    self.embedding_dim as i32,
+                    embedding_dim as i32,
                     Arc::new(Float32Array::from(flat_embeddings)),
                     None,
                 )),
@@ -430,121 +786,283 @@ impl VectorDB {
         limit: usize,
         source_id: Option<&str>,
     ) -> Result<Vec<ChunkMeta>> {
-        let table = match &self.chunks_table {
-            Some(t) => t,
-            None => return Ok(vec![]),
-        };
+        self.search_with_filters(query_embedding, limit, source_id, false).await
+    }
 
-        let mut query = table
-            .vector_search(query_embedding.to_vec())
-            .context("Failed to create vector search")?
-            .distance_type(DistanceType::Cosine)
-            .limit(limit);
+    /// Search for similar chunks with optional source and code-only filters
+    pub async fn search_with_filters(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        source_id: Option<&str>,
+        code_only: bool,
+    ) -> Result<Vec<ChunkMeta>> {
+        let tables = self.chunk_tables_for(source_id);
+        let mut search_results = self
+            .search_tables(&tables, query_embedding, limit, chunk_filter(source_id, code_only))
+            .await?;
 
-        if let Some(source) = source_id {
-            query = query.only_if(format!("source_id = '{}'", escape_sql(source)));
+        // Each per-source table returned its own top `limit`
+        if tables.len() > 1 {
+            sort_by_score(&mut search_results);
+            search_results.truncate(limit);
         }
 
-        let results = query
-            .execute()
-            .await
-            .context("Failed to execute search")?;
+        Ok(search_results)
+    }
 
-        let batches: Vec<RecordBatch> = results
-            .try_collect()
-            .await
-            .context("Failed to collect results")?;
+    /// Top `limit` chunks of each table matching `filter`, unsorted across tables
+    async fn search_tables(
+        &self,
+        tables: &[&Table],
+        query_embedding: &[f32],
+        limit: usize,
+        filter: Option<String>,
+    ) -> Result<Vec<ChunkMeta>> {
+        let mut batches: Vec<RecordBatch> = Vec::new();
+        for table in tables {
+            let mut query = table
+                .vector_search(query_embedding.to_vec())
+                .context("Failed to create vector search")?
+                .distance_type(DistanceType::Cosine)
+                .limit(limit);
+            if let Some(filter) = &filter {
+                query = query.only_if(filter.clone());
+            }
 
-        let mut search_results = Vec::new();
+            let results = query
+                .execute()
+                .await
+                .context("Failed to execute search")?;
 
-        for batch in batches {
-            let ids = batch
-                .column_by_name("id")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-            let document_ids = batch
-                .column_by_name("document_id")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-            let source_ids = batch
-                .column_by_name("source_id")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-            let titles = batch
-                .column_by_name("title")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-            let file_paths = batch
-                .column_by_name("file_path")
-                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-            let line_starts = batch
-                .column_by_name("line_start")
-                .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
-            let line_ends = batch
-                .column_by_name("line_end")
-                .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
-            let distances = batch
-                .column_by_name("_distance")
-                .and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+            let table_batches: Vec<RecordBatch> = results
+                .try_collect()
+                .await
+                .context("Failed to collect results")?;
+            batches.extend(table_batches);
+        }
 
-            if let (Some(ids), Some(document_ids), Some(source_ids), Some(distances)) =
-                (ids, document_ids, source_ids, distances)
-            {
-                for i in 0..batch.num_rows() {
-                    let score = 1.0 - distances.value(i);
-                    search_results.push(ChunkMeta {
-                        id: ids.value(i).to_string(),
-                        document_id: document_ids.value(i).to_string(),
-                        source_id: source_ids.value(i).to_string(),
-                        title: titles.and_then(|t| {
-                            if t.is_null(i) {
-                                None
-                            } else {
-                                Some(t.value(i).to_string())
-                            }
-                        }),
-                        file_path: file_paths.and_then(|f| {
-                            if f.is_null(i) {
-                                None
-                            } else {
-                                Some(f.value(i).to_string())
-                            }
-                        }),
-                        line_start: line_starts.and_then(|l| {
-                            if l.is_null(i) {
-                                None
-                            } else {
-                                Some(l.value(i))
-                            }
-                        }),
-                        line_end: line_ends.and_then(|l| {
-                            if l.is_null(i) {
-                                None
-                            } else {
-                                Some(l.value(i))
-                            }
-                        }),
-                        score,
-                    });
+        Ok(chunk_metas_from_batches(batches))
+    }
+
+    /// Metadata for chunks by ID, with a score of 0
+    ///
+    /// Used to hydrate keyword-only hits. Chunks without a stored vector
+    /// (deduplicated content) are not returned.
+    pub async fn get_chunk_metas(&self, chunk_ids: &[&str]) -> Result<Vec<ChunkMeta>> {
+        if chunk_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let id_list = chunk_ids
+            .iter()
+            .map(|id| format!("'{}'", escape_sql(id)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut batches: Vec<RecordBatch> = Vec::new();
+        for table in self.chunk_tables_for(None) {
+            let results = table
+                .query()
+                .only_if(format!("id IN ({})", id_list))
+                .limit(chunk_ids.len())
+                .execute()
+                .await?;
+            let table_batches: Vec<RecordBatch> = results.try_collect().await?;
+            batches.extend(table_batches);
+        }
+
+        Ok(chunk_metas_from_batches(batches))
+    }
+
+    /// Search with every source guaranteed `per_source` candidates
+    ///
+    /// Takes the global top `limit`, then adds each source's own top hits so
+    /// one large source can't crowd small ones out of the rerank pool. Sources
+    /// short of their quota are topped up by a single query filtered to them
+    /// (per-source tables already return every source's top hits).
+    /// With `per_source = 0` or a source filter this is a plain search.
+    pub async fn search_with_source_quota(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        source_id: Option<&str>,
+        code_only: bool,
+        per_source: usize,
+    ) -> Result<Vec<ChunkMeta>> {
+        if per_source == 0 || source_id.is_some() {
+            return self
+                .search_with_filters(query_embedding, limit, source_id, code_only)
+                .await;
+        }
+
+        let tables = self.chunk_tables_for(None);
+        let filter = chunk_filter(None, code_only);
+        if tables.len() > 1 {
+            let hits = self
+                .search_tables(&tables, query_embedding, limit.max(per_source), filter)
+                .await?;
+            return Ok(with_source_quota(hits, limit, per_source));
+        }
+
+        let mut hits = self
+            .search_tables(&tables, query_embedding, limit, filter.clone())
+            .await?;
+        let mut found: HashMap<&str, usize> = HashMap::new();
+        for hit in &hits {
+            *found.entry(hit.source_id.as_str()).or_default() += 1;
+        }
+        let short: Vec<String> = self
+            .list_sources()
+            .await?
+            .into_iter()
+            .filter(|source| found.get(source.name.as_str()).copied().unwrap_or(0) < per_source)
+            .map(|source| format!("'{}'", escape_sql(&source.name)))
+            .collect();
+
+        if !short.is_empty() {
+            let short_filter = format!("source_id IN ({})", short.join(", "));
+            let filter = match filter {
+                Some(filter) => format!("{} AND {}", short_filter, filter),
+                None => short_filter,
+            };
+            let top_up = self
+                .search_tables(&tables, query_embedding, per_source * short.len(), Some(filter))
+                .await?;
+            hits.extend(top_up);
+        }
+
+        Ok(with_source_quota(hits, limit, per_source))
+    }
+
+    /// Count documents with at least one chunk scoring `min_score` or higher
+    ///
+    /// Only chunks within the matching cosine distance are returned, and only
+    /// their document IDs are read; no content fetch or reranking.
+    pub async fn count_matches(
+        &self,
+        query_embedding: &[f32],
+        min_score: f32,
+        source_id: Option<&str>,
+        code_only: bool,
+    ) -> Result<usize> {
+        let filter = chunk_filter(source_id, code_only);
+        let mut documents: HashSet<String> = HashSet::new();
+
+        for table in self.chunk_tables_for(source_id) {
+            let rows = table.count_rows(filter.clone()).await?;
+            if rows == 0 {
+                continue;
+            }
+
+            let mut query = table
+                .vector_search(query_embedding.to_vec())
+                .context("Failed to create vector search")?
+                .distance_type(DistanceType::Cosine)
+                .distance_range(None, Some(1.0 - min_score))
+                .select(Select::columns(&["document_id"]))
+                .limit(rows);
+            if let Some(filter) = &filter {
+                query = query.only_if(filter.clone());
+            }
+
+            let batches: Vec<RecordBatch> = query
+                .execute()
+                .await
+                .context("Failed to execute count")?
+                .try_collect()
+                .await
+                .context("Failed to collect count results")?;
+
+            for batch in &batches {
+                if let Some(ids) = batch
+                    .column_by_name("document_id")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                {
+                    documents.extend((0..batch.num_rows()).map(|i| ids.value(i).to_string()));
                 }
             }
         }
 
-        Ok(search_results)
+        Ok(documents.len())
     }
 
     /// Check if a chunk already exists by content hash
     pub async fn chunk_exists(&self, content_hash: &str) -> Result<bool> {
-        let table = match &self.chunks_table {
-            Some(t) => t,
-            None => return Ok(false),
-        };
+        self.chunk_exists_where(format!("content_hash = '{}'", escape_sql(content_hash)), None)
+            .await
+    }
 
-        let results = table
-            .query()
-            .only_if(format!("content_hash = '{}'", escape_sql(content_hash)))
-            .limit(1)
-            .execute()
-            .await?;
+    /// Check if a chunk already exists within a dedup scope
+    pub async fn chunk_exists_in_scope(
+        &self,
+        content_hash: &str,
+        scope: DedupScope,
+        source_id: &str,
+    ) -> Result<bool> {
+        match scope {
+            DedupScope::Global => self.chunk_exists(content_hash).await,
+            DedupScope::Source => {
+                self.chunk_exists_where(
+                    format!(
+                        "content_hash = '{}' AND source_id = '{}'",
+                        escape_sql(content_hash),
+                        escape_sql(source_id)
+                    ),
+                    Some(source_id),
+                )
+                .await
+            }
+        }
+    }
 
-        let batches: Vec<RecordBatch> = results.try_collect().await?;
-        Ok(batches.iter().any(|b| b.num_rows() > 0))
+    /// Document ID and vector of every chunk in a source
+    pub async fn chunk_vectors_for_source(&self, source_id: &str) -> Result<Vec<(String, Vec<f32>)>> {
+        let mut vectors = Vec::new();
+        for table in self.chunk_tables_for(Some(source_id)) {
+            let results = table
+                .query()
+                .only_if(format!("source_id = '{}'", escape_sql(source_id)))
+                .limit(MAX_QUERY_LIMIT)
+                .execute()
+                .await?;
+
+            let batches: Vec<RecordBatch> = results.try_collect().await?;
+            for batch in batches {
+                let doc_ids = batch
+                    .column_by_name("document_id")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let lists = batch
+                    .column_by_name("vector")
+                    .and_then(|c| c.as_any().downcast_ref::<arrow_array::FixedSizeListArray>());
+                let (Some(doc_ids), Some(lists)) = (doc_ids, lists) else {
+                    continue;
+                };
+                for i in 0..batch.num_rows() {
+                    let values = lists.value(i);
+                    if let Some(values) = values.as_any().downcast_ref::<Float32Array>() {
+                        vectors.push((doc_ids.value(i).to_string(), values.values().to_vec()));
+                    }
+                }
+            }
+        }
+        Ok(vectors)
+    }
+
+    async fn chunk_exists_where(&self, filter: String, source_id: Option<&str>) -> Result<bool> {
+        for table in self.chunk_tables_for(source_id) {
+            let results = table
+                .query()
+                .only_if(filter.clone())
+                .limit(1)
+                .execute()
+                .await?;
+
+            let batches: Vec<RecordBatch> = results.try_collect().await?;
+            if batches.iter().any(|b| b.num_rows() > 0) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -634,6 +1152,113 @@ impl VectorDB {
         Ok(sources)
     }
 
+    /// Move documents and their chunks to another source
+    pub async fn move_documents_to_source(&self, doc_ids: &[&str], source_id: &str) -> Result<()> {
+        if doc_ids.is_empty() {
+            return Ok(());
+        }
+        self.ensure_shared_chunks_table("Moving documents between sources")?;
+
+        let id_list = doc_ids
+            .iter()
+            .map(|id| format!("'{}'", escape_sql(id)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let new_source = format!("'{}'", escape_sql(source_id));
+
+        if let Some(ref table) = self.chunks_table {
+            table
+                .update()
+                .only_if(format!("document_id IN ({})", id_list))
+                .column("source_id", new_source.clone())
+                .execute()
+                .await?;
+        }
+        if let Some(ref table) = self.docs_table {
+            table
+                .update()
+                .only_if(format!("id IN ({})", id_list))
+                .column("source_id", new_source)
+                .execute()
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fold a duplicate document into an existing one
+    ///
+    /// Chunk vectors of `duplicate_id` are reassigned to `target_id` in
+    /// `source_id` (they may be the only vectors for that content, since chunk
+    /// dedup is global), then the duplicate's document record is removed.
+    pub async fn merge_document_into(
+        &self,
+        duplicate_id: &str,
+        target_id: &str,
+        source_id: &str,
+    ) -> Result<()> {
+        self.ensure_shared_chunks_table("Merging documents")?;
+
+        if let Some(ref table) = self.chunks_table {
+            table
+                .update()
+                .only_if(format!("document_id = '{}'", escape_sql(duplicate_id)))
+                .column("document_id", format!("'{}'", escape_sql(target_id)))
+                .column("source_id", format!("'{}'", escape_sql(source_id)))
+                .execute()
+                .await?;
+        }
+        if let Some(ref table) = self.docs_table {
+            table
+                .delete(&format!("id = '{}'", escape_sql(duplicate_id)))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// IDs of a document's chunks that have a stored vector
+    ///
+    /// With global dedup a chunk whose content was already indexed has no
+    /// vector of its own, so this can be a subset of the document's chunks.
+    pub async fn chunk_ids_with_vectors(&self, document_id: &str) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        for table in self.chunk_tables_for(None) {
+            let results = table
+                .query()
+                .only_if(format!("document_id = '{}'", escape_sql(document_id)))
+                .limit(MAX_QUERY_LIMIT)
+                .execute()
+                .await?;
+
+            let batches: Vec<RecordBatch> = results.try_collect().await?;
+            for batch in batches {
+                if let Some(id_col) = batch
+                    .column_by_name("id")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                {
+                    for i in 0..batch.num_rows() {
+                        ids.insert(id_col.value(i).to_string());
+                    }
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Set the stored chunk count of a document
+    pub async fn set_chunk_count(&self, doc_id: &str, chunk_count: u32) -> Result<()> {
+        if let Some(ref table) = self.docs_table {
+            table
+                .update()
+                .only_if(format!("id = '{}'", escape_sql(doc_id)))
+                .column("chunk_count", chunk_count.to_string())
+                .execute()
+                .await?;
+        }
+        Ok(())
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Deletion Operations
     // ─────────────────────────────────────────────────────────────────────────
@@ -646,7 +1271,7 @@ impl VectorDB {
             table.delete(&format!("id = '{}'", escaped_id)).await?;
         }
 
-        if let Some(ref table) = self.chunks_table {
+        for table in self.chunk_tables_for(None) {
             table
                 .delete(&format!("document_id = '{}'", escaped_id))
                 .await?;
@@ -655,11 +1280,31 @@ impl VectorDB {
         Ok(())
     }
 
+    /// Delete chunks by ID from LanceDB
+    pub async fn delete_chunks(&self, chunk_ids: &[String]) -> Result<()> {
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+        let id_list = chunk_ids
+            .iter()
+            .map(|id| format!("'{}'", escape_sql(id)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        for table in self.chunk_tables_for(None) {
+            table.delete(&format!("id IN ({})", id_list)).await?;
+        }
+
+        Ok(())
+    }
+
     /// Delete all documents and chunks for a source
+    ///
+    /// With per-source tables only that source's chunk table is touched.
     pub async fn delete_source(&self, source_id: &str) -> Result<()> {
         let escaped_id = escape_sql(source_id);
 
-        if let Some(ref table) = self.chunks_table {
+        for table in self.chunk_tables_for(Some(source_id)) {
             table
                 .delete(&format!("source_id = '{}'", escaped_id))
                 .await?;
@@ -673,12 +1318,109 @@ impl VectorDB {
         Ok(())
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Storage Maintenance
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Report logical rows, fragments and reclaimable space for each table
+    ///
+    /// Every write creates a new fragment and version, so the raw `.lance`
+    /// directory size overstates the live data until the table is optimized.
+    pub async fn storage_report(&self) -> Result<Vec<TableStorage>> {
+        let mut tables: Vec<(String, &Table)> = self
+            .docs_table
+            .iter()
+            .map(|table| (DOCS_TABLE.to_string(), table))
+            .collect();
+        tables.extend(self.named_chunk_tables());
+
+        let mut report = Vec::new();
+        for (name, table) in tables {
+            let table_dir = self.data_dir.join(format!("{}.lance", name));
+            let manifest = table
+                .as_native()
+                .context("Storage report needs a local table")?
+                .manifest()
+                .await
+                .context("Failed to read table manifest")?;
+            let live_files: HashSet<&str> = manifest
+                .fragments
+                .iter()
+                .flat_map(|fragment| fragment.files.iter())
+                .map(|file| file.path.rsplit('/').next().unwrap_or(&file.path))
+                .collect();
+            let (total_bytes, reclaimable_bytes) = scan_table_dir(&table_dir, &live_files, manifest.version);
+            report.push(TableStorage {
+                name,
+                rows: table.count_rows(None).await?,
+                fragments: manifest.fragments.len(),
+                versions: table.list_versions().await?.len(),
+                total_bytes,
+                reclaimable_bytes,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Vector dimensions of the stored chunks table (None if nothing is indexed)
+    pub async fn stored_embedding_dim(&self) -> Result<Option<usize>> {
+        let Some(table) = self.chunk_tables_for(None).into_iter().next() else {
+            return Ok(None);
+        };
+        let schema = table.schema().await?;
+        Ok(schema.field_with_name("vector").ok().and_then(|f| match f.data_type() {
+            DataType::FixedSizeList(_, size) => Some(*size as usize),
+            _ => None,
+        }))
+    }
+
+    /// Row counts of the (documents, chunks) tables
+    pub async fn row_counts(&self) -> Result<(usize, usize)> {
+        let docs = match &self.docs_table {
+            Some(table) => table.count_rows(None).await?,
+            None => 0,
+        };
+        let mut chunks = 0;
+        for table in self.chunk_tables_for(None) {
+            chunks += table.count_rows(None).await?;
+        }
+        Ok((docs, chunks))
+    }
+
+    /// Compact fragments and delete old versions of every table
+    pub async fn optimize(&self) -> Result<()> {
+        let tables = self.docs_table.iter().chain(self.chunk_tables_for(None));
+        for table in tables {
+            table
+                .optimize(OptimizeAction::Compact {
+                    options: Default::default(),
+                    remap_options: None,
+                })
+                .await
+                .context("Failed to compact table")?;
+            table
+                .optimize(OptimizeAction::Prune {
+                    older_than: Some(chrono::Duration::zero()),
+                    delete_unverified: Some(true),
+                    error_if_tagged_old_versions: None,
+                })
+                .await
+                .context("Failed to prune old versions")?;
+        }
+        Ok(())
+    }
+
     /// Reset everything - delete all data
     pub async fn reset_all(&mut self) -> Result<()> {
         if self.chunks_table.is_some() {
             self.conn.drop_table(CHUNKS_TABLE).await.ok();
             self.chunks_table = None;
         }
+        if let Some(tables) = self.source_tables.as_mut() {
+            for (source_id, _) in tables.drain() {
+                self.conn.drop_table(&source_table_name(&source_id)).await.ok();
+            }
+        }
         if self.docs_table.is_some() {
             self.conn.drop_table(DOCS_TABLE).await.ok();
             self.docs_table = None;
@@ -687,6 +1429,57 @@ impl VectorDB {
     }
 }
 
+/// Split a `.lance` table directory into live and reclaimable files
+///
+/// Returns (total bytes, reclaimable bytes). Data files not in `live_files`
+/// (those the current manifest references), plus manifests of other
+/// versions than `version`, are only kept around for old versions.
+fn scan_table_dir(table_dir: &Path, live_files: &HashSet<&str>, version: u64) -> (u64, u64) {
+    let file_sizes = |dir: PathBuf| -> Vec<(String, u64)> {
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| {
+                        let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+                        Some((e.file_name().to_string_lossy().into_owned(), size))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let current_manifest = format!("{}.manifest", version);
+    let reclaimable = file_sizes(table_dir.join("data"))
+        .into_iter()
+        .filter(|(name, _)| !live_files.contains(name.as_str()))
+        .chain(
+            file_sizes(table_dir.join("_versions"))
+                .into_iter()
+                .filter(|(name, _)| *name != current_manifest),
+        )
+        .map(|(_, size)| size)
+        .sum();
+
+    (dir_bytes(table_dir), reclaimable)
+}
+
+/// Total size of a directory tree, ignoring unreadable entries
+fn dir_bytes(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| match e.metadata() {
+                    Ok(m) if m.is_dir() => dir_bytes(&e.path()),
+                    Ok(m) => m.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
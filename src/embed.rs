@@ -7,14 +7,19 @@
 //! - `metal` - Apple Silicon GPU (macOS)
 //! - `cuda` - NVIDIA GPU
 
-use crate::config::{Config, DevicePreference, EmbeddingModelConfig};
+use crate::chunking::TokenCounter;
+use crate::config::{Config, DevicePreference, EmbeddingModelConfig, EmbeddingQuantization, PoolingStrategy};
+use crate::error::EywaError;
 use anyhow::{Context, Result};
-use candle_core::{Device, Tensor};
+use candle_core::{DType, Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
 use hf_hub::{api::sync::ApiBuilder, Repo, RepoType};
 use tokenizers::Tokenizer;
 
+/// BERT models have at most 512 position embeddings
+const MAX_SEQ_LEN: usize = 512;
+
 /// Resolve the compute device based on preference and available features
 pub fn resolve_device(preference: &DevicePreference) -> Result<Device> {
     match preference {
@@ -68,19 +73,70 @@ pub fn device_name(device: &Device) -> &'static str {
     }
 }
 
+/// Pool `[batch, seq, hidden]` token embeddings into `[batch, hidden]`
+///
+/// `lengths` holds the number of real (non-padding) tokens per row; inputs
+/// are right-padded, so the last real token is at `length - 1`.
+fn pool(embeddings: &Tensor, attention_mask: &Tensor, lengths: &[usize], strategy: PoolingStrategy) -> Result<Tensor> {
+    match strategy {
+        PoolingStrategy::Mean => {
+            let attention_mask_f = attention_mask.to_dtype(DTYPE)?;
+            let mask_expanded = attention_mask_f.unsqueeze(2)?.broadcast_as(embeddings.shape())?;
+
+            let sum_embeddings = (embeddings * mask_expanded)?.sum(1)?;
+            let sum_mask = attention_mask_f.sum(1)?.unsqueeze(1)?;
+            // Use recip + mul instead of broadcast_div (more stable on Metal GPU)
+            Ok(sum_embeddings.broadcast_mul(&sum_mask.recip()?)?)
+        }
+        PoolingStrategy::Cls => Ok(embeddings.narrow(1, 0, 1)?.squeeze(1)?),
+        PoolingStrategy::LastToken => {
+            let rows = lengths
+                .iter()
+                .enumerate()
+                .map(|(i, &len)| embeddings.get(i)?.get(len.saturating_sub(1)))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Ok(Tensor::stack(&rows, 0)?)
+        }
+    }
+}
+
 pub struct Embedder {
     model: BertModel,
     tokenizer: Tokenizer,
     device: Device,
     dimensions: usize,
+    /// Longest token sequence the model embeds, special tokens included
+    max_seq_len: usize,
+    /// Config ID of the loaded model, recorded with the index it builds
+    model_id: String,
+    /// Precision the weights were actually loaded in
+    quantization: EmbeddingQuantization,
+    /// How token embeddings become one vector
+    pooling: PoolingStrategy,
+    /// Prepended to search queries (empty = none)
+    query_prefix: String,
+    /// Prepended to indexed chunks (empty = none)
+    passage_prefix: String,
 }
 
 impl Embedder {
     /// Create a new embedder using the model from config
     pub fn new() -> Result<Self> {
         let config = Config::load()?
-            .ok_or_else(|| anyhow::anyhow!("Eywa not initialized. Run 'eywa' or 'eywa init' first."))?;
-        Self::new_with_model(&config.embedding_model, &config.device, true)
+            .ok_or(EywaError::NotInitialized)?;
+        Self::from_config(&config)
+    }
+
+    /// Create a new embedder with the model, device and prefixes from `config`
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new_with_options(
+            &config.embedding_model,
+            &config.device,
+            config.embedding_quantization,
+            true,
+        )?
+            .with_query_prefix(config.query_prefix.clone().unwrap_or_default())
+            .with_passage_prefix(config.passage_prefix.clone().unwrap_or_default()))
     }
 
     /// Create a new embedder with a specific model and device preference
@@ -88,6 +144,20 @@ impl Embedder {
         embedding_model: &EmbeddingModelConfig,
         device_pref: &DevicePreference,
         show_progress: bool,
+    ) -> Result<Self> {
+        Self::new_with_options(embedding_model, device_pref, EmbeddingQuantization::F32, show_progress)
+    }
+
+    /// Create a new embedder, loading weights at the requested precision
+    ///
+    /// F16 halves weight memory at a small cost in vector quality. BERT int8
+    /// weights aren't loadable with Candle, so Int8 falls back to F32 with a
+    /// warning; `quantization()` reports what was actually loaded.
+    pub fn new_with_options(
+        embedding_model: &EmbeddingModelConfig,
+        device_pref: &DevicePreference,
+        quantization: EmbeddingQuantization,
+        show_progress: bool,
     ) -> Result<Self> {
         let device = resolve_device(device_pref)?;
         let model_id = embedding_model.hf_id();
@@ -111,7 +181,29 @@ impl Embedder {
 
         let config_path = repo.get("config.json").context("Failed to get config.json")?;
         let tokenizer_path = repo.get("tokenizer.json").context("Failed to get tokenizer.json")?;
-        let weights_path = repo.get("model.safetensors").context("Failed to get model.safetensors")?;
+
+        // Prefer published fp16 weights; otherwise convert the f32 ones on load
+        let (weights_path, dtype, quantization) = match quantization {
+            EmbeddingQuantization::F16 => {
+                let path = match repo.get("model.fp16.safetensors") {
+                    Ok(path) => path,
+                    Err(_) => repo.get("model.safetensors").context("Failed to get model.safetensors")?,
+                };
+                (path, DType::F16, EmbeddingQuantization::F16)
+            }
+            EmbeddingQuantization::Int8 => {
+                eprintln!(
+                    "Warning: int8 weights are not supported for {}, using f32",
+                    embedding_model.name
+                );
+                let path = repo.get("model.safetensors").context("Failed to get model.safetensors")?;
+                (path, DTYPE, EmbeddingQuantization::F32)
+            }
+            EmbeddingQuantization::F32 => {
+                let path = repo.get("model.safetensors").context("Failed to get model.safetensors")?;
+                (path, DTYPE, EmbeddingQuantization::F32)
+            }
+        };
 
         // Load config
         let config_str = std::fs::read_to_string(&config_path)?;
@@ -123,7 +215,7 @@ impl Embedder {
 
         // Load model weights
         let vb = unsafe {
-            VarBuilder::from_mmaped_safetensors(&[weights_path], DTYPE, &device)?
+            VarBuilder::from_mmaped_safetensors(&[weights_path], dtype, &device)?
         };
         let model = BertModel::load(vb, &bert_config)?;
 
@@ -136,9 +228,56 @@ impl Embedder {
             tokenizer,
             device,
             dimensions,
+            max_seq_len: bert_config.max_position_embeddings.min(MAX_SEQ_LEN),
+            model_id: embedding_model.id.clone(),
+            quantization,
+            pooling: embedding_model.pooling,
+            query_prefix: String::new(),
+            passage_prefix: String::new(),
         })
     }
 
+    /// Set the instruction prefix for search queries (empty string disables)
+    pub fn with_query_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.query_prefix = prefix.into();
+        self
+    }
+
+    /// Set the instruction prefix for indexed passages (empty string disables)
+    ///
+    /// Changing this after ingestion requires re-indexing, since existing
+    /// vectors were embedded with the old prefix.
+    pub fn with_passage_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.passage_prefix = prefix.into();
+        self
+    }
+
+    /// Most tokens a passage can have and still be embedded whole
+    ///
+    /// The model's sequence length less [CLS], [SEP] and the passage prefix.
+    pub fn max_passage_tokens(&self) -> usize {
+        self.max_seq_len
+            .saturating_sub(2 + self.count_tokens(&self.passage_prefix))
+            .max(1)
+    }
+
+    /// Create embedding for a search query, applying the query prefix
+    pub fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        self.embed(&format!("{}{}", self.query_prefix, query))
+    }
+
+    /// Create embeddings for passages to index, applying the passage prefix
+    pub fn embed_passages(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if self.passage_prefix.is_empty() {
+            return self.embed_batch(texts);
+        }
+        let prefixed: Vec<String> = texts
+            .iter()
+            .map(|t| format!("{}{}", self.passage_prefix, t))
+            .collect();
+        self.embed_batch(&prefixed)
+    }
+
     /// Create embedding for a single text
     pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let embeddings = self.embed_batch(&[text.to_string()])?;
@@ -147,28 +286,27 @@ impl Embedder {
 
     /// Create embeddings for multiple texts
     pub fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        // BERT models have max 512 position embeddings - must truncate
-        const MAX_SEQ_LEN: usize = 512;
-
         let tokens = self.tokenizer
             .encode_batch(texts.to_vec(), true)
             .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
 
         // Cap at 512 tokens (model limit)
         let max_len = tokens.iter()
-            .map(|t| t.get_ids().len().min(MAX_SEQ_LEN))
+            .map(|t| t.get_ids().len().min(self.max_seq_len))
             .max()
             .unwrap_or(0);
 
         // Prepare input tensors
+        let mut lengths = Vec::with_capacity(tokens.len());
         let mut input_ids_vec = Vec::new();
         let mut attention_mask_vec = Vec::new();
         let mut token_type_ids_vec = Vec::new();
 
         for encoding in &tokens {
-            // Truncate to MAX_SEQ_LEN tokens
-            let ids: Vec<u32> = encoding.get_ids().iter().take(MAX_SEQ_LEN).copied().collect();
-            let mask: Vec<u32> = encoding.get_attention_mask().iter().take(MAX_SEQ_LEN).copied().collect();
+            // Truncate to the model's sequence length
+            let ids: Vec<u32> = encoding.get_ids().iter().take(self.max_seq_len).copied().collect();
+            let mask: Vec<u32> = encoding.get_attention_mask().iter().take(self.max_seq_len).copied().collect();
+            lengths.push(mask.iter().filter(|&&m| m != 0).count());
 
             let mut padded_ids = ids.clone();
             let mut padded_mask = mask.clone();
@@ -191,20 +329,17 @@ impl Embedder {
         let token_type_ids = Tensor::from_vec(token_type_ids_vec, (batch_size, max_len), &self.device)?;
 
         // Run model
-        let embeddings = self.model.forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+        // Pool in f32 whatever precision the weights are in
+        let embeddings = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?
+            .to_dtype(DTYPE)?;
 
-        // Mean pooling over sequence dimension
-        let attention_mask_f = attention_mask.to_dtype(DTYPE)?;
-        let mask_expanded = attention_mask_f.unsqueeze(2)?.broadcast_as(embeddings.shape())?;
-
-        let sum_embeddings = (embeddings * mask_expanded)?.sum(1)?;
-        let sum_mask = attention_mask_f.sum(1)?.unsqueeze(1)?;
-        // Use recip + mul instead of broadcast_div (more stable on Metal GPU)
-        let mean_embeddings = sum_embeddings.broadcast_mul(&sum_mask.recip()?)?;
+        let pooled = pool(&embeddings, &attention_mask, &lengths, self.pooling)?;
 
         // Normalize
-        let norms = mean_embeddings.sqr()?.sum(1)?.sqrt()?.unsqueeze(1)?;
-        let normalized = mean_embeddings.broadcast_mul(&norms.recip()?)?;
+        let norms = pooled.sqr()?.sum(1)?.sqrt()?.unsqueeze(1)?;
+        let normalized = pooled.broadcast_mul(&norms.recip()?)?;
 
         // Convert to Vec<Vec<f32>>
         let embeddings_vec: Vec<Vec<f32>> = normalized.to_vec2()?;
@@ -217,12 +352,36 @@ impl Embedder {
         self.dimensions
     }
 
+    /// Config ID of the loaded model (e.g. "bge-base-en-v1.5")
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Weight precision actually in use
+    pub fn quantization(&self) -> EmbeddingQuantization {
+        self.quantization
+    }
+
+    /// Pooling strategy in use
+    pub fn pooling(&self) -> PoolingStrategy {
+        self.pooling
+    }
+
     /// Get the name of the device being used
     pub fn device_name(&self) -> &'static str {
         device_name(&self.device)
     }
 }
 
+impl TokenCounter for Embedder {
+    fn token_offsets(&self, text: &str) -> Vec<(usize, usize)> {
+        self.tokenizer
+            .encode(text, false)
+            .map(|encoding| encoding.get_offsets().to_vec())
+            .unwrap_or_default()
+    }
+}
+
 /// Get info about compiled GPU support
 pub fn gpu_support_info() -> GpuSupportInfo {
     GpuSupportInfo {
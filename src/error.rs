@@ -0,0 +1,19 @@
+//! Errors callers may need to tell apart
+//!
+//! Everything else is a plain `anyhow::Error`. The CLI maps these to their
+//! own exit codes so scripts can react without parsing messages.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EywaError {
+    /// No config yet
+    #[error("Eywa not initialized. Run `eywa init` to download models.")]
+    NotInitialized,
+    /// Config exists but the models aren't in the local cache
+    #[error("Models not downloaded. Run `eywa init` to download models.")]
+    ModelsMissing,
+    /// No source with this ID, or it has no documents
+    #[error("Source '{0}' not found")]
+    SourceNotFound(String),
+}
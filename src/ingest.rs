@@ -8,6 +8,7 @@ use anyhow::Result;
 use std::path::Path;
 use walkdir::WalkDir;
 
+use crate::chunking::stored_lines;
 use crate::content::ContentStore;
 use crate::db::{ChunkRecord, VectorDB};
 use crate::embed::Embedder;
@@ -314,25 +315,31 @@ impl<'a> Ingester<'a> {
                 }
 
                 // Generate embeddings
-                let embeddings = self.embedder.embed_batch(&texts)?;
+                let embeddings = self.embedder.embed_passages(&texts)?;
 
                 // Store chunk metadata + vectors in LanceDB
                 let chunk_records: Vec<ChunkRecord> = new_chunks
                     .iter()
-                    .map(|c| ChunkRecord {
-                        id: c.id.clone(),
-                        document_id: c.document_id.clone(),
-                        source_id: c.source_id.clone(),
-                        title: c.title.clone(),
-                        file_path: c.file_path.clone(),
-                        line_start: Some(c.line_start),
-                        line_end: Some(c.line_end),
-                        content_hash: c.content_hash.clone(),
-                        // Hierarchical metadata from legacy chunker
-                        section: c.section.clone(),
-                        subsection: c.subsection.clone(),
-                        hierarchy: c.hierarchy.clone(),
-                        has_code: c.has_code,
+                    .map(|c| {
+                        let (line_start, line_end) =
+                            stored_lines(c.file_path.as_deref(), c.line_start, c.line_end);
+                        ChunkRecord {
+                            id: c.id.clone(),
+                            document_id: c.document_id.clone(),
+                            source_id: c.source_id.clone(),
+                            title: c.title.clone(),
+                            file_path: c.file_path.clone(),
+                            line_start,
+                            line_end,
+                            page_start: None,
+                            page_end: None,
+                            content_hash: c.content_hash.clone(),
+                            // Hierarchical metadata from legacy chunker
+                            section: c.section.clone(),
+                            subsection: c.subsection.clone(),
+                            hierarchy: c.hierarchy.clone(),
+                            has_code: c.has_code,
+                        }
                     })
                     .collect();
 
@@ -346,6 +353,10 @@ impl<'a> Ingester<'a> {
             documents_created,
             chunks_created,
             chunks_skipped,
+            documents_skipped: 0,
+            files_skipped_binary: 0,
+            files_failed: Vec::new(),
+            files_lossy_utf8: Vec::new(),
             document_ids,
         })
     }
@@ -383,11 +394,20 @@ impl<'a> Ingester<'a> {
         };
 
         let mut doc_inputs = Vec::new();
+        let mut lossy = Vec::new();
         for file in &files {
-            let content = match std::fs::read_to_string(file) {
-                Ok(c) if !c.trim().is_empty() => c,
-                _ => continue,
+            let content = match std::fs::read(file).map(String::from_utf8) {
+                Ok(Ok(c)) => c,
+                Ok(Err(e)) => {
+                    eprintln!("Warning: {} is not valid UTF-8; invalid bytes replaced with U+FFFD", file.display());
+                    lossy.push(file.to_string_lossy().to_string());
+                    String::from_utf8_lossy(e.as_bytes()).into_owned()
+                }
+                Err(_) => continue,
             };
+            if content.trim().is_empty() {
+                continue;
+            }
 
             doc_inputs.push(DocumentInput {
                 content,
@@ -397,8 +417,9 @@ impl<'a> Ingester<'a> {
             });
         }
 
-        self.ingest_documents(db, data_dir, source_id, doc_inputs)
-            .await
+        let mut response = self.ingest_documents(db, data_dir, source_id, doc_inputs).await?;
+        response.files_lossy_utf8 = lossy;
+        Ok(response)
     }
 }
 
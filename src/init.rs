@@ -88,12 +88,71 @@ fn run_custom_selection(existing_config: Option<&Config>) -> Result<Config> {
     let embedding_model = select_embedding_model(existing_config)?;
     let reranker_model = select_reranker_model(existing_config)?;
 
-    Ok(Config {
+    let mut config = Config {
         embedding_model,
         reranker_model,
         device: DevicePreference::default(),
         version: 2,
-    })
+        ..Config::default()
+    };
+    select_chunking(&mut config, existing_config)?;
+
+    Ok(config)
+}
+
+/// Advanced: chunk sizes (kept at current values unless the user opts in)
+fn select_chunking(config: &mut Config, existing_config: Option<&Config>) -> Result<()> {
+    if let Some(existing) = existing_config {
+        config.chunk_target_size = existing.chunk_target_size;
+        config.chunk_overlap_size = existing.chunk_overlap_size;
+        config.chunk_min_size = existing.chunk_min_size;
+    }
+
+    println!();
+    print!("Configure chunking (advanced)? [y/N]: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    if input != "y" && input != "yes" {
+        return Ok(());
+    }
+
+    println!();
+    println!("Chunking (sizes in characters):");
+    let mut chunked = config.clone();
+    chunked.chunk_target_size = prompt_size("  Target size", config.chunk_target_size)?;
+    chunked.chunk_overlap_size = prompt_size("  Overlap", config.chunk_overlap_size)?;
+    chunked.chunk_min_size = prompt_size("  Minimum size", config.chunk_min_size)?;
+
+    match chunked.validate() {
+        Ok(()) => *config = chunked,
+        Err(e) => println!("{}. Keeping current chunk sizes.", e),
+    }
+    Ok(())
+}
+
+/// Prompt for a size, keeping `current` on empty or invalid input
+fn prompt_size(label: &str, current: usize) -> Result<usize> {
+    print!("{} [{}]: ", label, current);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+
+    if input.is_empty() {
+        return Ok(current);
+    }
+
+    match input.parse::<usize>() {
+        Ok(n) => Ok(n),
+        Err(_) => {
+            println!("Invalid number, using {}.", current);
+            Ok(current)
+        }
+    }
 }
 
 /// Select embedding model interactively
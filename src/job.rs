@@ -8,7 +8,7 @@ use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use crate::types::{DocStatus, DocumentInput, JobProgress, JobStatus, PendingDoc};
+use crate::types::{DocStatus, DocumentInput, JobKind, JobProgress, JobStatus, PendingDoc};
 
 /// Document info for status API (without content)
 #[derive(Debug, Clone, serde::Serialize)]
@@ -20,6 +20,9 @@ pub struct PendingDocInfo {
     pub error: Option<String>,
 }
 
+/// `source_id` of jobs that cover every source (reindex)
+pub const ALL_SOURCES: &str = "*";
+
 /// Persistent job queue backed by SQLite
 pub struct JobQueue {
     conn: Connection,
@@ -33,6 +36,7 @@ impl JobQueue {
 
         let queue = Self { conn };
         queue.init_schema()?;
+        queue.migrate_schema()?;
         queue.recover_processing()?;
 
         Ok(queue)
@@ -80,6 +84,27 @@ impl JobQueue {
         Ok(())
     }
 
+    /// Add columns introduced after the first release
+    fn migrate_schema(&self) -> Result<()> {
+        // Job kind (ingest or reindex), and the error of a failed reindex
+        let has_kind: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('jobs') WHERE name='kind'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        if has_kind == 0 {
+            self.conn.execute_batch(
+                "
+                ALTER TABLE jobs ADD COLUMN kind TEXT NOT NULL DEFAULT 'ingest';
+                ALTER TABLE jobs ADD COLUMN error TEXT;
+                ",
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Reset any docs that were "processing" back to "pending" (server restart recovery)
     fn recover_processing(&self) -> Result<()> {
         self.conn.execute(
@@ -132,6 +157,79 @@ impl JobQueue {
         Ok(job_id)
     }
 
+    /// Queue a rebuild of the whole index, returns job_id
+    ///
+    /// The job has no pending docs; the worker reads documents from SQLite
+    /// and reports progress with `set_job_progress`.
+    pub fn queue_reindex(&mut self) -> Result<String> {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+        self.conn.execute(
+            "INSERT INTO jobs (id, kind, source_id, total_docs, status, created_at)
+             VALUES (?1, 'reindex', ?2, 0, 'pending', ?3)",
+            params![job_id, ALL_SOURCES, now],
+        )?;
+
+        Ok(job_id)
+    }
+
+    /// ID of a reindex job that's waiting or running, if any
+    pub fn active_reindex_job(&self) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id FROM jobs WHERE kind = 'reindex' AND status IN ('pending', 'processing') LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Take the next waiting reindex job, marking it processing
+    ///
+    /// A job left processing by a restart is back to pending (see
+    /// `recover_processing`), so an interrupted reindex runs again.
+    pub fn next_reindex_job(&mut self) -> Result<Option<String>> {
+        let job_id: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT id FROM jobs WHERE kind = 'reindex' AND status = 'pending'
+                 ORDER BY created_at ASC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(job_id) = &job_id {
+            self.conn.execute(
+                "UPDATE jobs SET status = 'processing' WHERE id = ?1",
+                params![job_id],
+            )?;
+        }
+        Ok(job_id)
+    }
+
+    /// Record progress of a job that runs as a whole
+    pub fn set_job_progress(&mut self, job_id: &str, total: u32, completed: u32, current_doc: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE jobs SET total_docs = ?2, completed_docs = ?3, current_doc = ?4 WHERE id = ?1",
+            params![job_id, total, completed, current_doc],
+        )?;
+        Ok(())
+    }
+
+    /// Finish a job that runs as a whole, failed if `error` is set
+    pub fn finish_job(&mut self, job_id: &str, error: Option<&str>) -> Result<()> {
+        let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let status = if error.is_some() { "failed" } else { "done" };
+        self.conn.execute(
+            "UPDATE jobs SET status = ?2, error = ?3, current_doc = NULL, completed_at = ?4 WHERE id = ?1",
+            params![job_id, status, error, now],
+        )?;
+        Ok(())
+    }
+
     /// Get the next pending document to process
     pub fn get_next_pending(&mut self) -> Result<Option<PendingDoc>> {
         let doc: Option<(String, String, String, Option<String>, String, Option<String>, String)> = self
@@ -289,11 +387,14 @@ impl JobQueue {
             return Ok(());
         };
 
-        // Update doc status
-        self.conn.execute(
-            "UPDATE pending_docs SET status = 'done' WHERE id = ?1",
+        // Update doc status (unless it was cancelled meanwhile)
+        let updated = self.conn.execute(
+            "UPDATE pending_docs SET status = 'done' WHERE id = ?1 AND status IN ('pending', 'processing')",
             params![doc_id],
         )?;
+        if updated == 0 {
+            return Ok(());
+        }
 
         // Update job counts
         self.conn.execute(
@@ -321,11 +422,14 @@ impl JobQueue {
             return Ok(());
         };
 
-        // Update doc status
-        self.conn.execute(
-            "UPDATE pending_docs SET status = 'failed', error = ?2 WHERE id = ?1",
+        // Update doc status (unless it was cancelled meanwhile)
+        let updated = self.conn.execute(
+            "UPDATE pending_docs SET status = 'failed', error = ?2 WHERE id = ?1 AND status IN ('pending', 'processing')",
             params![doc_id, error],
         )?;
+        if updated == 0 {
+            return Ok(());
+        }
 
         // Update job counts
         self.conn.execute(
@@ -337,6 +441,66 @@ impl JobQueue {
         Ok(())
     }
 
+    /// Whether a document is still waiting or being processed
+    ///
+    /// False once it's done, failed or cancelled, or if it doesn't exist.
+    pub fn is_active(&self, doc_id: &str) -> Result<bool> {
+        let status: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT status FROM pending_docs WHERE id = ?1",
+                params![doc_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(matches!(status.as_deref(), Some("pending" | "processing")))
+    }
+
+    /// Cancel waiting and in-flight documents for a deleted source
+    ///
+    /// They're marked failed so their jobs finish and the worker doesn't
+    /// recreate the source. Returns the number of documents cancelled.
+    pub fn cancel_source(&mut self, source_id: &str) -> Result<usize> {
+        self.cancel_matching(Some(source_id), &format!("Source '{}' was deleted", source_id))
+    }
+
+    /// Cancel every waiting and in-flight document (e.g. after a reset)
+    pub fn cancel_all(&mut self) -> Result<usize> {
+        self.cancel_matching(None, "All data was reset")
+    }
+
+    /// Mark active docs failed, for one source or all of them
+    fn cancel_matching(&mut self, source_id: Option<&str>, reason: &str) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let per_job: Vec<(String, u32)> = {
+            let mut stmt = tx.prepare(
+                "SELECT job_id, COUNT(*) FROM pending_docs
+                 WHERE status IN ('pending', 'processing') AND (?1 IS NULL OR source_id = ?1)
+                 GROUP BY job_id",
+            )?;
+            let rows = stmt.query_map(params![source_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        tx.execute(
+            "UPDATE pending_docs SET status = 'failed', error = ?2
+             WHERE status IN ('pending', 'processing') AND (?1 IS NULL OR source_id = ?1)",
+            params![source_id, reason],
+        )?;
+        for (job_id, count) in &per_job {
+            tx.execute(
+                "UPDATE jobs SET failed_docs = failed_docs + ?2, current_doc = NULL WHERE id = ?1",
+                params![job_id, count],
+            )?;
+        }
+        tx.commit()?;
+
+        for (job_id, _) in &per_job {
+            self.check_job_completion(job_id)?;
+        }
+        Ok(per_job.iter().map(|(_, count)| *count as usize).sum())
+    }
+
     /// Check if a job is complete and update its status
     fn check_job_completion(&self, job_id: &str) -> Result<()> {
         let job: Option<(u32, u32, u32)> = self
@@ -371,45 +535,16 @@ impl JobQueue {
 
     /// Get job progress
     pub fn get_job(&self, job_id: &str) -> Result<Option<JobProgress>> {
-        let job: Option<(String, String, String, u32, u32, u32, Option<String>, String, Option<String>)> = self
+        let job = self
             .conn
             .query_row(
-                "SELECT id, source_id, status, total_docs, completed_docs, failed_docs, current_doc, created_at, completed_at
-                 FROM jobs WHERE id = ?1",
+                &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
                 params![job_id],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                        row.get(6)?,
-                        row.get(7)?,
-                        row.get(8)?,
-                    ))
-                },
+                job_from_row,
             )
             .optional()?;
 
-        let Some((id, source_id, status_str, total, completed, failed, current_doc, created_at, completed_at)) = job else {
-            return Ok(None);
-        };
-
-        let status = status_str.parse().unwrap_or(JobStatus::Pending);
-
-        Ok(Some(JobProgress {
-            job_id: id,
-            source_id,
-            status,
-            total,
-            completed,
-            failed,
-            current_doc,
-            created_at,
-            completed_at,
-        }))
+        Ok(job)
     }
 
     /// Get all documents for a job (for per-doc status API)
@@ -448,41 +583,12 @@ impl JobQueue {
 
     /// List all jobs
     pub fn list_jobs(&self) -> Result<Vec<JobProgress>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, source_id, status, total_docs, completed_docs, failed_docs, current_doc, created_at, completed_at
-             FROM jobs ORDER BY created_at DESC",
-        )?;
-
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, u32>(3)?,
-                row.get::<_, u32>(4)?,
-                row.get::<_, u32>(5)?,
-                row.get::<_, Option<String>>(6)?,
-                row.get::<_, String>(7)?,
-                row.get::<_, Option<String>>(8)?,
-            ))
-        })?;
-
-        let mut jobs = Vec::new();
-        for row in rows {
-            let (id, source_id, status_str, total, completed, failed, current_doc, created_at, completed_at) = row?;
-            let status = status_str.parse().unwrap_or(JobStatus::Pending);
-            jobs.push(JobProgress {
-                job_id: id,
-                source_id,
-                status,
-                total,
-                completed,
-                failed,
-                current_doc,
-                created_at,
-                completed_at,
-            });
-        }
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {} FROM jobs ORDER BY created_at DESC", JOB_COLUMNS))?;
+        let jobs = stmt
+            .query_map([], job_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
 
         Ok(jobs)
     }
@@ -522,6 +628,27 @@ impl JobQueue {
     }
 }
 
+/// Columns read by `job_from_row`, in order
+const JOB_COLUMNS: &str =
+    "id, kind, source_id, status, total_docs, completed_docs, failed_docs, current_doc, created_at, completed_at, error";
+
+/// Job progress from a row selected with `JOB_COLUMNS`
+fn job_from_row(row: &rusqlite::Row) -> rusqlite::Result<JobProgress> {
+    Ok(JobProgress {
+        job_id: row.get(0)?,
+        kind: row.get::<_, String>(1)?.parse().unwrap_or_default(),
+        source_id: row.get(2)?,
+        status: row.get::<_, String>(3)?.parse().unwrap_or(JobStatus::Pending),
+        total: row.get(4)?,
+        completed: row.get(5)?,
+        failed: row.get(6)?,
+        current_doc: row.get(7)?,
+        created_at: row.get(8)?,
+        completed_at: row.get(9)?,
+        error: row.get(10)?,
+    })
+}
+
 /// Thread-safe job queue wrapper (uses std::sync::Mutex since SQLite isn't Sync)
 pub type SharedJobQueue = Arc<Mutex<JobQueue>>;
 
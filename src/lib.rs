@@ -11,6 +11,7 @@ pub mod config;
 pub mod content;
 pub mod db;
 pub mod embed;
+pub mod error;
 pub mod ingest;
 pub mod init;
 pub mod job;
@@ -19,20 +20,29 @@ pub mod repl;
 pub mod rerank;
 pub mod search;
 pub mod setup;
+pub mod similar;
+pub mod sources;
+pub mod summary;
 pub mod types;
+pub mod verify;
 
 pub use bm25::{BM25Index, BM25Result, ChunkInput};
-pub use config::{Config, DevicePreference, EmbeddingModel, EmbeddingModelConfig, RerankerModel, RerankerModelConfig};
-pub use content::{ContentStore, DocumentListItem, DocumentRow, SourceStats};
-pub use db::{ChunkRecord, VectorDB};
+pub use config::{Config, DedupScope, DevicePreference, EmbeddingModel, EmbeddingModelConfig, EmbeddingQuantization, OverlapStrategy, PoolingStrategy, RerankerModel, RerankerModelConfig, SourceNameCase, UiVersion};
+pub use content::{Bookmark, ChunkWindow, ContentStore, DocumentListItem, DocumentRow, SourceStats};
+pub use db::{ChunkRecord, TableStorage, VectorDB};
 pub use embed::{gpu_support_info, Embedder, GpuSupportInfo};
+pub use error::EywaError;
 pub use ingest::Ingester;
 pub use init::{run_init, show_status, show_welcome, InitResult};
 pub use job::{create_job_queue, JobQueue, PendingDocInfo, SharedJobQueue};
-pub use setup::{run_download_wizard, models_cached};
-pub use pipeline::{BatchConfig, EmbeddedBatch, IngestPipeline};
+pub use setup::{run_download_wizard, models_cached, require_models};
+pub use pipeline::{embed_in_batches, explain_decisions, walk_includes, write_progress, BatchConfig, Decision, EmbeddedBatch, FileDecision, FileWalk, IngestManifest, IngestPipeline, ManifestEntry, ManifestStatus, ProgressStyle, SkipReason, WalkOptions, WalkResult};
 pub use rerank::Reranker;
 pub use search::SearchEngine;
+pub use similar::find_similar_documents;
+pub use sources::{merge_sources, MergeStats};
+pub use summary::{summarize_source, RepresentativeDocument, SourceCluster};
+pub use verify::{model_mismatch_warning, verify_config_file, verify_index, verify_models, VerifyCheck, VerifyReport};
 pub use types::*;
 
 use std::collections::HashMap;
@@ -52,14 +62,15 @@ pub struct Eywa {
 impl Eywa {
     /// Create a new Eywa instance
     pub async fn new(data_dir: &str) -> anyhow::Result<Self> {
+        let config = Config::load()?.unwrap_or_default();
         let embedder = Embedder::new()?;
-        let db = VectorDB::new(data_dir).await?;
+        let db = VectorDB::open(data_dir, config.per_source_tables).await?;
         let bm25_index = Arc::new(BM25Index::open(Path::new(data_dir))?);
 
         let content_path = Path::new(data_dir).join("content.db");
         let content = ContentStore::open(&content_path)?;
 
-        let search = SearchEngine::new();
+        let search = SearchEngine::new().with_synonyms_file();
 
         Ok(Self {
             embedder: RwLock::new(embedder),
@@ -101,7 +112,7 @@ impl Eywa {
     /// using convex combination: 0.8 * vector + 0.2 * bm25
     pub async fn search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchResult>> {
         let embedder = self.embedder.read().await;
-        let query_embedding = embedder.embed(query)?;
+        let query_embedding = embedder.embed_query(query)?;
         let db = self.db.read().await;
 
         // Hybrid search: vector + BM25
@@ -109,22 +120,26 @@ impl Eywa {
         let bm25_limit = 50;
 
         // Step 1: Get vector search results
-        let chunk_metas = db.search(&query_embedding, vector_limit).await?;
+        let chunk_metas = db
+            .search_with_source_quota(&query_embedding, vector_limit, None, false, self.search.candidates_per_source)
+            .await?;
 
-        // Step 2: Get BM25 search results
-        let bm25_results = self.bm25_index.search(query, bm25_limit)?;
+        // Step 2: Get BM25 search results, with synonyms when configured
+        let bm25_results = self.bm25_index.search(&self.search.expand_query(query), bm25_limit)?;
 
         // Step 3: Normalize and fuse scores
         let fused_scores = Self::convex_fusion(&chunk_metas, &bm25_results, 0.8, 0.2);
+        let mut signals = Self::retrieval_signals(&chunk_metas, &bm25_results);
 
         if fused_scores.is_empty() {
             return Ok(vec![]);
         }
 
         // Take top candidates for content fetch
+        let candidates = self.search.candidate_count(limit);
         let top_ids: Vec<String> = fused_scores
             .iter()
-            .take(limit * 2)
+            .take(candidates)
             .map(|(id, _)| id.clone())
             .collect();
 
@@ -143,7 +158,7 @@ impl Eywa {
         // Step 5: Combine into SearchResult with fused scores
         let mut results: Vec<SearchResult> = fused_scores
             .iter()
-            .take(limit * 2)
+            .take(candidates)
             .filter_map(|(id, fused_score)| {
                 let content_text = content_map.get(id)?.clone();
                 // Try to get metadata from vector results, or create minimal metadata
@@ -151,11 +166,18 @@ impl Eywa {
                     Some(SearchResult {
                         id: meta.id.clone(),
                         source_id: meta.source_id.clone(),
+                        document_id: meta.document_id.clone(),
                         title: meta.title.clone(),
                         content: content_text,
                         file_path: meta.file_path.clone(),
                         line_start: meta.line_start,
+                        line_end: meta.line_end,
+                        page_start: meta.page_start,
+                        page_end: meta.page_end,
+                        section: meta.section.clone(),
+                        has_code: meta.has_code,
                         score: *fused_score,
+                        matched_by: signals.remove(id).unwrap_or_default(),
                     })
                 } else {
                     // BM25-only result - need to fetch metadata
@@ -230,6 +252,24 @@ impl Eywa {
         results
     }
 
+    /// Which retrieval signals returned each chunk, keyed by chunk ID
+    fn retrieval_signals(
+        vector_results: &[ChunkMeta],
+        bm25_results: &[BM25Result],
+    ) -> HashMap<String, Vec<String>> {
+        let mut signals: HashMap<String, Vec<String>> = HashMap::new();
+        for r in vector_results {
+            signals.entry(r.id.clone()).or_default().push("vector".to_string());
+        }
+        for r in bm25_results {
+            let matched = signals.entry(r.chunk_id.clone()).or_default();
+            if !matched.iter().any(|s| s == "bm25") {
+                matched.push("bm25".to_string());
+            }
+        }
+        signals
+    }
+
     /// List all sources
     pub async fn list_sources(&self) -> anyhow::Result<Vec<Source>> {
         let db = self.db.read().await;
@@ -240,7 +280,7 @@ impl Eywa {
     /// Pass None for default limit (10), or Some(n) for custom limit.
     pub async fn list_documents(&self, source_id: &str, limit: Option<usize>) -> anyhow::Result<Vec<DocumentMeta>> {
         let db = self.db.read().await;
-        db.list_documents(source_id, limit).await
+        db.list_documents(source_id, limit, None).await
     }
 
     /// Get a document by ID (metadata from LanceDB, content from SQLite)
@@ -453,6 +493,10 @@ mod tests {
             file_path: None,
             line_start: None,
             line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score,
         }
     }
@@ -515,6 +559,29 @@ mod tests {
         assert!((bm25_only_score - 0.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_retrieval_signals_list_each_matching_signal() {
+        let vector_results = vec![
+            make_chunk_meta("shared", 0.8),
+            make_chunk_meta("vec_only", 0.4),
+        ];
+        let bm25_results = vec![
+            BM25Result {
+                chunk_id: "shared".to_string(),
+                score: 0.9,
+            },
+            BM25Result {
+                chunk_id: "bm25_only".to_string(),
+                score: 0.5,
+            },
+        ];
+
+        let signals = Eywa::retrieval_signals(&vector_results, &bm25_results);
+        assert_eq!(signals["shared"], vec!["vector", "bm25"]);
+        assert_eq!(signals["vec_only"], vec!["vector"]);
+        assert_eq!(signals["bm25_only"], vec!["bm25"]);
+    }
+
     #[test]
     fn test_convex_fusion_disjoint_ids() {
         let vector_results = vec![make_chunk_meta("vec1", 0.9)];
@@ -4,10 +4,12 @@
 //!
 //! Commands:
 //!   ingest  - Ingest documents from a file or directory
+//!   watch   - Keep a directory ingested as its files change
 //!   search  - Search for similar documents
-//!   sources - List all sources
+//!   sources - List all sources (sources merge <from> <to>)
 //!   docs    - List documents in a source
 //!   delete  - Delete a source
+//!   bookmark - Bookmark a document (or list bookmarks)
 //!   reset   - Reset config and data (keeps models)
 //!   hard-reset - Delete everything including models
 //!   uninstall - Full uninstall with instructions
@@ -15,6 +17,8 @@
 //!   mcp     - Start MCP server (for Claude/Cursor)
 //!   info    - Show model info
 //!   storage - Show storage usage
+//!   optimize - Compact the vector DB and reclaim space
+//!   verify  - Health check for CI (non-zero exit on failure)
 //!   init    - Configure models
 
 mod commands;
@@ -25,10 +29,11 @@ mod utils;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use eywa::{
     db, run_download_wizard, run_init, show_status, show_welcome,
-    Config, Embedder, InitResult, Reranker, VectorDB,
+    Config, DedupScope, Embedder, EywaError, InitResult, ProgressStyle, Reranker, WalkOptions,
 };
 use utils::expand_path;
 
@@ -41,6 +46,15 @@ struct Cli {
     #[arg(short, long, default_value = "~/.eywa/data")]
     data_dir: String,
 
+    /// Print progress as plain lines instead of updating in place
+    /// (automatic when stdout isn't a terminal)
+    #[arg(long, global = true)]
+    no_progress: bool,
+
+    /// Only print results: no setup messages or progress
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -49,12 +63,91 @@ struct Cli {
 enum Commands {
     /// Ingest documents from a file or directory
     Ingest {
-        /// Source ID (name for this collection)
+        /// Source ID (name for this collection; defaults to `default_source` in config)
         #[arg(short, long)]
-        source: String,
+        source: Option<String>,
 
         /// Path to file or directory to ingest
         path: PathBuf,
+
+        /// Skip files matching a glob pattern (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Also ingest dotfiles and dot-directories
+        #[arg(long)]
+        include_hidden: bool,
+
+        /// Follow symlinks (cycles are detected and skipped)
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Don't skip paths matched by .gitignore/.eywaignore or the built-in
+        /// list (.git, target, node_modules, __pycache__)
+        #[arg(long)]
+        no_ignore: bool,
+
+        /// Don't store file content; re-read it from disk when needed
+        #[arg(long)]
+        link: bool,
+
+        /// Override the passage prefix for this ingest ("" disables).
+        /// Must match the prefix the rest of the index was built with.
+        #[arg(long, value_name = "PREFIX")]
+        passage_prefix: Option<String>,
+
+        /// Only skip chunks already stored in this source (embeds shared
+        /// content again, so source-filtered search always finds it)
+        #[arg(long, conflicts_with = "dedup_global")]
+        dedup_within_source: bool,
+
+        /// Skip chunks already stored in any source (smaller index)
+        #[arg(long)]
+        dedup_global: bool,
+
+        /// Ingest each Markdown file as one document per heading at this
+        /// level or above (1 = per H1, 2 = per H1/H2)
+        #[arg(long, value_name = "LEVEL", value_parser = clap::value_parser!(u8).range(1..=6))]
+        split_sections: Option<u8>,
+
+        /// Replace documents previously ingested from the same file paths
+        /// instead of adding new copies alongside them
+        #[arg(long)]
+        update: bool,
+
+        /// Re-ingest files even if they're unchanged since the last ingest
+        #[arg(long)]
+        force: bool,
+
+        /// Write a JSON report of each document's status (new/updated/skipped)
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+
+        /// List every file considered, with whether it was ingested and why not
+        #[arg(long)]
+        explain_ingest: bool,
+    },
+
+    /// Keep a directory ingested: pick up created, changed and deleted files until Ctrl-C
+    Watch {
+        /// Directory to watch
+        path: PathBuf,
+
+        /// Source ID (defaults to `default_source` in config)
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Skip files matching a glob pattern (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Also ingest dotfiles and dot-directories
+        #[arg(long)]
+        include_hidden: bool,
+
+        /// Don't skip paths matched by .gitignore/.eywaignore or the built-in list
+        #[arg(long)]
+        no_ignore: bool,
     },
 
     /// Search for documents
@@ -69,15 +162,87 @@ enum Commands {
         /// Filter by source ID
         #[arg(short, long)]
         source: Option<String>,
+
+        /// Only return chunks that contain code blocks
+        #[arg(long)]
+        code_only: bool,
+
+        /// Override the query prefix for this search ("" disables)
+        #[arg(long, value_name = "PREFIX")]
+        query_prefix: Option<String>,
+
+        /// Print only the number of matching documents
+        #[arg(long)]
+        count: bool,
+
+        /// Open result N (1-based) in $EDITOR at its line after printing results
+        #[arg(long, value_name = "N")]
+        open: Option<usize>,
+
+        /// Print one JSON result per line (for streaming into other tools)
+        #[arg(long, conflicts_with_all = ["count", "open"])]
+        json_lines: bool,
+
+        /// Print results as a pretty JSON array
+        #[arg(long, conflicts_with_all = ["count", "open", "json_lines", "group_by"])]
+        json: bool,
+
+        /// Candidates guaranteed to each source before reranking
+        /// (overrides rerank_candidates_per_source in config)
+        #[arg(long, value_name = "N")]
+        rerank_candidates_per_source: Option<usize>,
+
+        /// Drop results with fewer than this many characters (0 = off)
+        #[arg(long, value_name = "CHARS", default_value = "0")]
+        min_length: usize,
+
+        /// Summarize hits per source, showing each source's top result
+        #[arg(long, value_name = "FIELD", value_parser = ["source"], conflicts_with_all = ["count", "open", "json_lines"])]
+        group_by: Option<String>,
+
+        /// Fastest path: vector search only, ranked by cosine similarity
+        /// (no reranker, keyword boost, pins or score floor)
+        #[arg(long, conflicts_with_all = ["count", "group_by", "rerank_candidates_per_source", "min_length", "no_dedup"])]
+        embedding_only: bool,
+
+        /// Show every matching chunk instead of the best one per document
+        #[arg(long)]
+        no_dedup: bool,
+    },
+
+    /// Find documents by title (no embedding, instant)
+    Find {
+        /// Text to look for in titles (case-insensitive)
+        text: String,
+
+        /// Filter by source ID
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Maximum number of documents
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
     },
 
-    /// List all sources
-    Sources,
+    /// List all sources, or manage them
+    #[command(args_conflicts_with_subcommands = true)]
+    Sources {
+        #[command(subcommand)]
+        action: Option<SourcesAction>,
+
+        /// Print sources as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
 
     /// List documents in a source
     Docs {
         /// Source ID
         source: String,
+
+        /// Print documents as a JSON array
+        #[arg(long)]
+        json: bool,
     },
 
     /// Delete a source
@@ -86,6 +251,20 @@ enum Commands {
         source: String,
     },
 
+    /// Bookmark a document, or list bookmarks when no ID is given
+    Bookmark {
+        /// Document ID to bookmark
+        doc_id: Option<String>,
+
+        /// Note to attach to the bookmark
+        #[arg(short, long)]
+        note: Option<String>,
+
+        /// Remove the bookmark instead of adding it
+        #[arg(long, requires = "doc_id")]
+        remove: bool,
+    },
+
     /// Reset - delete ~/.eywa (config, data, sqlite). Keeps models.
     Reset,
 
@@ -111,6 +290,36 @@ enum Commands {
     /// Show storage usage (data, models, total)
     Storage,
 
+    /// Compact the vector DB and delete old versions
+    Optimize,
+
+    /// Rebuild vectors and the BM25 index from stored content (no source files needed)
+    Reindex,
+
+    /// Check index, stores and models for problems (exits non-zero on failure)
+    Verify,
+
+    /// Work with config files
+    Config {
+        /// Validate a config file without applying it (exits non-zero on problems)
+        #[arg(long, value_name = "PATH")]
+        check: PathBuf,
+    },
+
+    /// List async ingest jobs, or show one job's progress
+    Jobs {
+        /// Job ID to show
+        job_id: Option<String>,
+
+        /// Live-update the job's progress until it finishes (needs 'eywa serve')
+        #[arg(short, long, requires = "job_id")]
+        watch: bool,
+
+        /// Port of the running server (for --watch)
+        #[arg(short, long, default_value = "8005")]
+        port: u16,
+    },
+
     /// Run initialization flow (re-configure models)
     Init {
         /// Use default models without prompts (for CI/scripting)
@@ -119,10 +328,61 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum SourcesAction {
+    /// Move all documents from one source into another, skipping duplicates
+    Merge {
+        /// Source to merge from (removed afterwards)
+        from: String,
+
+        /// Source to merge into
+        to: String,
+    },
+
+    /// Show the most representative documents for each topic in a source
+    Summary {
+        /// Source ID
+        source: String,
+
+        /// Number of topic clusters
+        #[arg(long, default_value = "5")]
+        clusters: usize,
+
+        /// Representative documents shown per cluster
+        #[arg(long, default_value = "3")]
+        per_cluster: usize,
+    },
+}
+
+/// Exit code for a failed command: 2 when eywa isn't set up, 3 for an
+/// unknown source, 1 for anything else
+fn exit_code(error: &anyhow::Error) -> u8 {
+    match error.downcast_ref::<EywaError>() {
+        Some(EywaError::NotInitialized | EywaError::ModelsMissing) => 2,
+        Some(EywaError::SourceNotFound(_)) => 3,
+        None => 1,
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+/// Run the command; checks like `verify` report failures through the exit code
+async fn run(cli: Cli) -> Result<ExitCode> {
     let data_dir = expand_path(&cli.data_dir);
+    let progress_style = if cli.quiet {
+        ProgressStyle::Hidden
+    } else {
+        ProgressStyle::detect(cli.no_progress)
+    };
 
     // Ensure data directory exists
     std::fs::create_dir_all(&data_dir)?;
@@ -133,13 +393,13 @@ async fn main() -> Result<()> {
             match Config::load()? {
                 Some(config) => {
                     // Show status
-                    let db = VectorDB::new(&data_dir).await?;
+                    let db = crate::commands::open_db(&data_dir).await?;
                     let sources = db.list_sources().await?;
                     let total_chunks: usize = sources.iter().map(|s| s.chunk_count as usize).sum();
                     let total_docs: usize = {
                         let mut count = 0;
                         for source in &sources {
-                            count += db.list_documents(&source.name, Some(db::MAX_QUERY_LIMIT)).await?.len();
+                            count += db.list_documents(&source.name, Some(db::MAX_QUERY_LIMIT), None).await?.len();
                         }
                         count
                     };
@@ -169,26 +429,98 @@ async fn main() -> Result<()> {
             }
         }
 
-        Some(Commands::Ingest { source, path }) => {
-            commands::run_ingest(&data_dir, &source, &path).await?;
+        Some(Commands::Ingest {
+            source,
+            path,
+            exclude,
+            include_hidden,
+            follow_symlinks,
+            no_ignore,
+            link,
+            passage_prefix,
+            dedup_within_source,
+            dedup_global,
+            split_sections,
+            update,
+            force,
+            manifest,
+            explain_ingest,
+        }) => {
+            let config = Config::load()?.unwrap_or_default();
+            let dedup_scope = match (dedup_within_source, dedup_global) {
+                (true, _) => DedupScope::Source,
+                (_, true) => DedupScope::Global,
+                _ => config.dedup_scope,
+            };
+            let walk_options = WalkOptions {
+                exclude,
+                include_hidden,
+                follow_symlinks,
+                no_ignore,
+                explain: explain_ingest,
+            };
+            commands::run_ingest(
+                &data_dir,
+                &config,
+                source,
+                &path,
+                walk_options,
+                link,
+                passage_prefix,
+                dedup_scope,
+                split_sections.map(usize::from),
+                update,
+                force,
+                manifest.as_deref(),
+                progress_style,
+            )
+            .await?;
+        }
+
+        Some(Commands::Watch { path, source, exclude, include_hidden, no_ignore }) => {
+            let config = Config::load()?.unwrap_or_default();
+            let source = config.resolve_source(source);
+            let walk_options = WalkOptions {
+                exclude,
+                include_hidden,
+                no_ignore,
+                ..Default::default()
+            };
+            commands::run_watch(&data_dir, &config, &source, &path, walk_options, progress_style).await?;
         }
 
-        Some(Commands::Search { query, limit, source: _ }) => {
-            commands::run_search(&data_dir, &query, limit).await?;
+        Some(Commands::Search { query, limit, source, code_only, query_prefix, count, open, json_lines, json, rerank_candidates_per_source, min_length, group_by, embedding_only, no_dedup }) => {
+            commands::run_search(&data_dir, &query, limit, source.as_deref(), code_only, query_prefix, count, open, json_lines, json, rerank_candidates_per_source, min_length, group_by.is_some(), embedding_only, !no_dedup, cli.quiet).await?;
         }
 
-        Some(Commands::Sources) => {
-            commands::run_sources(&data_dir).await?;
+        Some(Commands::Find { text, source, limit }) => {
+            commands::run_find(&data_dir, &text, source.as_deref(), limit)?;
         }
 
-        Some(Commands::Docs { source }) => {
-            commands::run_docs(&data_dir, &source).await?;
+        Some(Commands::Sources { action: None, json }) => {
+            commands::run_sources(&data_dir, json).await?;
+        }
+
+        Some(Commands::Sources { action: Some(SourcesAction::Merge { from, to }), .. }) => {
+            commands::run_merge(&data_dir, &from, &to).await?;
+        }
+
+        Some(Commands::Sources { action: Some(SourcesAction::Summary { source, clusters, per_cluster }), .. }) => {
+            commands::run_summary(&data_dir, &source, clusters, per_cluster).await?;
+        }
+
+        Some(Commands::Docs { source, json }) => {
+            commands::run_docs(&data_dir, &source, json).await?;
         }
 
         Some(Commands::Delete { source }) => {
             commands::run_delete(&data_dir, &source).await?;
         }
 
+        Some(Commands::Bookmark { doc_id, note, remove }) => {
+            commands::run_bookmark(&data_dir, doc_id.as_deref(), note.as_deref(), remove)?;
+        }
+
         Some(Commands::Reset) => {
             commands::run_reset()?;
         }
@@ -215,13 +547,49 @@ async fn main() -> Result<()> {
         }
 
         Some(Commands::Storage) => {
-            commands::run_storage(&data_dir)?;
+            commands::run_storage(&data_dir).await?;
+        }
+
+        Some(Commands::Optimize) => {
+            commands::run_optimize(&data_dir).await?;
+        }
+
+        Some(Commands::Reindex) => {
+            commands::run_reindex(&data_dir, progress_style).await?;
+        }
+
+        Some(Commands::Config { check }) => {
+            return Ok(ExitCode::from(commands::run_config_check(&check)));
+        }
+
+        Some(Commands::Verify) => {
+            return Ok(ExitCode::from(commands::run_verify(&data_dir).await?));
+        }
+
+        Some(Commands::Jobs { job_id, watch, port }) => {
+            commands::run_jobs(&data_dir, job_id.as_deref(), watch, port).await?;
         }
 
         Some(Commands::Init { default }) => {
-            commands::run_init_command(&data_dir, default).await?;
+            commands::run_init_command(&data_dir, default, progress_style).await?;
         }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code(&EywaError::NotInitialized.into()), 2);
+        assert_eq!(exit_code(&EywaError::ModelsMissing.into()), 2);
+        assert_eq!(exit_code(&EywaError::SourceNotFound("docs".to_string()).into()), 3);
+        // Context added on the way up doesn't hide the cause
+        let wrapped = anyhow::Error::from(EywaError::SourceNotFound("docs".to_string())).context("Merging");
+        assert_eq!(exit_code(&wrapped), 3);
+        assert_eq!(exit_code(&anyhow::anyhow!("disk full")), 1);
+    }
 }
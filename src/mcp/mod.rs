@@ -1,21 +1,24 @@
 //! MCP (Model Context Protocol) server module
 //! Provides JSON-RPC interface for Claude/Cursor integration
 
+mod prompts;
 mod tools;
 
 use anyhow::Result;
 use serde_json::{json, Value};
 use std::io::{BufRead, BufReader, Write};
 
-use eywa::{ContentStore, Embedder, SearchEngine, VectorDB};
+use eywa::{Config, ContentStore, Embedder, SearchEngine, VectorDB};
+use prompts::{get_prompt, get_prompt_definitions};
 use tools::{get_tool_definitions, handle_tool_call};
 
 /// Run the MCP server (JSON-RPC over stdio)
 pub async fn run_mcp_server(data_dir: &str) -> Result<()> {
+    let config = Config::load()?.unwrap_or_default();
     let embedder = Embedder::new()?;
-    let db = VectorDB::new(data_dir).await?;
+    let db = VectorDB::open(data_dir, config.per_source_tables).await?;
     let content_store = ContentStore::open(&std::path::Path::new(data_dir).join("content.db"))?;
-    let search_engine = SearchEngine::with_reranker()?;
+    let search_engine = SearchEngine::with_reranker().with_settings(&config);
 
     let stdin = std::io::stdin();
     let reader = BufReader::new(stdin.lock());
@@ -52,7 +55,8 @@ pub async fn run_mcp_server(data_dir: &str) -> Result<()> {
                     "result": {
                         "protocolVersion": "2024-11-05",
                         "capabilities": {
-                            "tools": {}
+                            "tools": {},
+                            "prompts": {}
                         },
                         "serverInfo": {
                             "name": "eywa",
@@ -96,6 +100,31 @@ pub async fn run_mcp_server(data_dir: &str) -> Result<()> {
                 }
             }
 
+            "prompts/list" => {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": {
+                        "prompts": get_prompt_definitions()
+                    }
+                })
+            }
+
+            "prompts/get" => {
+                let params = request.get("params").cloned().unwrap_or(json!({}));
+                let prompt_name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+                match get_prompt(prompt_name, &arguments) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(message) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32602, "message": message }
+                    }),
+                }
+            }
+
             _ => {
                 json!({
                     "jsonrpc": "2.0",
@@ -0,0 +1,124 @@
+//! MCP prompt templates (prompts/list and prompts/get)
+//!
+//! Templates expand into instructions that drive Eywa's own tools, so
+//! clients can offer common workflows without knowing the tool set.
+
+use serde_json::{json, Value};
+
+/// Get prompt definitions for MCP prompts/list response
+pub fn get_prompt_definitions() -> Value {
+    json!([
+        {
+            "name": "summarize_source",
+            "description": "Summarize what a source in the knowledge base covers",
+            "arguments": [
+                {
+                    "name": "source",
+                    "description": "The source ID to summarize",
+                    "required": true
+                }
+            ]
+        },
+        {
+            "name": "find_code",
+            "description": "Find code examples related to a topic",
+            "arguments": [
+                {
+                    "name": "topic",
+                    "description": "What the code should do or relate to",
+                    "required": true
+                },
+                {
+                    "name": "source",
+                    "description": "Optional: only look in this source",
+                    "required": false
+                }
+            ]
+        }
+    ])
+}
+
+/// Expand a prompt template with its arguments
+///
+/// Returns the prompts/get result, or an error message for unknown prompts
+/// and missing required arguments.
+pub fn get_prompt(name: &str, arguments: &Value) -> Result<Value, String> {
+    let arg = |key: &str| {
+        arguments
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|v| !v.trim().is_empty())
+    };
+    let required = |key: &str| arg(key).ok_or_else(|| format!("Missing required argument: {}", key));
+
+    let (description, text) = match name {
+        "summarize_source" => {
+            let source = required("source")?;
+            (
+                format!("Summarize source '{}'", source),
+                format!(
+                    "Call the list_documents tool with source_id \"{source}\" to see what it holds, \
+                     then call get_document on the most representative documents. \
+                     Summarize source \"{source}\": its main topics, what each document covers, \
+                     and anything that appears to be missing or out of date."
+                ),
+            )
+        }
+        "find_code" => {
+            let topic = required("topic")?;
+            let search_args = match arg("source") {
+                Some(source) => format!("query \"{}\", code_only true and source \"{}\"", topic, source),
+                None => format!("query \"{}\" and code_only true", topic),
+            };
+            (
+                format!("Find code related to '{}'", topic),
+                format!(
+                    "Call the search tool with {}. \
+                     Show the most relevant code snippets with their file paths and line numbers, \
+                     and explain how each relates to: {}.",
+                    search_args, topic
+                ),
+            )
+        }
+        _ => return Err(format!("Unknown prompt: {}", name)),
+    };
+
+    Ok(json!({
+        "description": description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompts_list_and_get() {
+        let definitions = get_prompt_definitions();
+        let names: Vec<&str> = definitions
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["summarize_source", "find_code"]);
+
+        let prompt = get_prompt("find_code", &json!({ "topic": "config parsing", "source": "docs" })).unwrap();
+        let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+        assert_eq!(prompt["messages"][0]["role"], "user");
+        assert!(text.contains("query \"config parsing\", code_only true and source \"docs\""));
+
+        let prompt = get_prompt("summarize_source", &json!({ "source": "notes" })).unwrap();
+        assert_eq!(prompt["description"], "Summarize source 'notes'");
+    }
+
+    #[test]
+    fn test_get_prompt_rejects_bad_requests() {
+        assert!(get_prompt("summarize_source", &json!({})).unwrap_err().contains("source"));
+        assert!(get_prompt("nope", &json!({})).unwrap_err().contains("Unknown prompt"));
+    }
+}
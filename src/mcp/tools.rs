@@ -4,7 +4,7 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::io::Write;
 
-use eywa::{db, ContentStore, Embedder, SearchEngine, SearchResult, VectorDB};
+use eywa::{db, Citation, ContentStore, Embedder, SearchEngine, SearchResult, VectorDB};
 
 /// Get tool definitions for MCP tools/list response
 pub fn get_tool_definitions() -> Value {
@@ -27,6 +27,17 @@ pub fn get_tool_definitions() -> Value {
                     "source": {
                         "type": "string",
                         "description": "Optional: filter results to a specific source"
+                    },
+                    "code_only": {
+                        "type": "boolean",
+                        "description": "Optional: only return chunks that contain code blocks",
+                        "default": false
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "citations"],
+                        "description": "Optional: 'citations' also returns a JSON list of the chunks used (default: text)",
+                        "default": "text"
                     }
                 },
                 "required": ["query"]
@@ -126,12 +137,15 @@ async fn handle_search(
     id: &Option<Value>,
 ) -> Option<Value> {
     let query = arguments.get("query").and_then(|q| q.as_str()).unwrap_or("");
-    let limit = arguments.get("limit").and_then(|l| l.as_u64()).unwrap_or(5) as usize;
+    let requested = arguments.get("limit").and_then(|l| l.as_u64()).unwrap_or(5) as usize;
+    let (limit, limit_note) = search_engine.clamp_limit(requested);
     let source = arguments.get("source").and_then(|s| s.as_str());
+    let code_only = arguments.get("code_only").and_then(|c| c.as_bool()).unwrap_or(false);
+    let with_citations = arguments.get("format").and_then(|f| f.as_str()) == Some("citations");
 
-    match embedder.embed(query) {
+    match embedder.embed_query(query) {
         Ok(embedding) => {
-            match db.search_filtered(&embedding, limit * 2, source).await {
+            match db.search_with_filters(&embedding, search_engine.candidate_count(limit), source, code_only).await {
                 Ok(chunk_metas) => {
                     let chunk_ids: Vec<&str> = chunk_metas.iter().map(|c| c.id.as_str()).collect();
                     let contents = match content_store.get_chunks(&chunk_ids) {
@@ -156,40 +170,32 @@ async fn handle_search(
                             Some(SearchResult {
                                 id: meta.id,
                                 source_id: meta.source_id,
+                                document_id: meta.document_id,
                                 title: meta.title,
                                 content,
                                 file_path: meta.file_path,
                                 line_start: meta.line_start,
+                                line_end: meta.line_end,
+                                page_start: meta.page_start,
+                                page_end: meta.page_end,
+                                section: meta.section,
+                                has_code: meta.has_code,
                                 score: meta.score,
+                                matched_by: vec!["vector".to_string()],
                             })
                         })
                         .collect();
 
                     let results = search_engine.filter_results(results);
-                    let results = search_engine.rerank(results, query, limit);
-
-                    let text = results.iter().map(|r| {
-                        format!(
-                            "## {} (Score: {:.3})\nSource: {}\n\n{}",
-                            r.title.as_deref().unwrap_or("Untitled"),
-                            r.score,
-                            r.source_id,
-                            r.content
-                        )
-                    }).collect::<Vec<_>>().join("\n\n---\n\n");
+                    let result_ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+                    let pinned = content_store.pinned_chunks(&result_ids).unwrap_or_default();
+                    let results = search_engine.rerank_with_pins(results, query, limit, &pinned);
 
                     Some(json!({
                         "jsonrpc": "2.0",
                         "id": id,
                         "result": {
-                            "content": [{
-                                "type": "text",
-                                "text": if results.is_empty() {
-                                    "No results found.".to_string()
-                                } else {
-                                    format!("Found {} results:\n\n{}", results.len(), text)
-                                }
-                            }]
+                            "content": search_content(&results, limit_note.as_deref(), with_citations)
                         }
                     }))
                 }
@@ -208,6 +214,69 @@ async fn handle_search(
     }
 }
 
+/// MCP content for search results: a text block, plus a JSON block of
+/// citations when asked for
+fn search_content(results: &[SearchResult], note: Option<&str>, with_citations: bool) -> Vec<Value> {
+    let text = results.iter().map(|r| {
+        format!(
+            "## {} (Score: {:.3})\nSource: {}\n\n{}",
+            r.title.as_deref().unwrap_or("Untitled"),
+            r.score,
+            r.source_id,
+            r.content
+        )
+    }).collect::<Vec<_>>().join("\n\n---\n\n");
+
+    let mut text = if results.is_empty() {
+        "No results found.".to_string()
+    } else {
+        format!("Found {} results:\n\n{}", results.len(), text)
+    };
+    if let Some(note) = note {
+        text = format!("Note: {}\n\n{}", note, text);
+    }
+    let mut content = vec![json!({
+        "type": "text",
+        "text": text
+    })];
+
+    // Citations cover exactly the results in the text, in the same order
+    if with_citations {
+        let citations: Vec<Citation> = results.iter().map(Citation::from_result).collect();
+        content.push(json!({
+            "type": "text",
+            "text": json!({ "citations": citations }).to_string()
+        }));
+    }
+    content
+}
+
+/// MCP content for similar documents: a single text block
+fn similar_content(results: &[SearchResult], note: Option<&str>) -> Vec<Value> {
+    let text = results.iter().map(|r| {
+        format!(
+            "## {} (Score: {:.3})\nSource: {}\n\n{}",
+            r.title.as_deref().unwrap_or("Untitled"),
+            r.score,
+            r.source_id,
+            r.content
+        )
+    }).collect::<Vec<_>>().join("\n\n---\n\n");
+
+    let mut text = if results.is_empty() {
+        "No similar documents found.".to_string()
+    } else {
+        format!("Found {} similar documents:\n\n{}", results.len(), text)
+    };
+    if let Some(note) = note {
+        text = format!("Note: {}\n\n{}", note, text);
+    }
+    vec![json!({
+        "type": "text",
+        "text": text
+    })]
+}
+
 async fn handle_list_sources(db: &VectorDB, id: &Option<Value>) -> Option<Value> {
     match db.list_sources().await {
         Ok(sources) => {
@@ -249,7 +318,7 @@ async fn handle_list_documents(arguments: &Value, db: &VectorDB, id: &Option<Val
         }));
     }
 
-    match db.list_documents(source_id, Some(db::MAX_QUERY_LIMIT)).await {
+    match db.list_documents(source_id, Some(db::MAX_QUERY_LIMIT), None).await {
         Ok(docs) => {
             let text = if docs.is_empty() {
                 format!("No documents found in source '{}'.", source_id)
@@ -366,7 +435,8 @@ async fn handle_similar_docs(
     id: &Option<Value>,
 ) -> Option<Value> {
     let doc_id = arguments.get("document_id").and_then(|s| s.as_str()).unwrap_or("");
-    let limit = arguments.get("limit").and_then(|l| l.as_u64()).unwrap_or(5) as usize;
+    let requested = arguments.get("limit").and_then(|l| l.as_u64()).unwrap_or(5) as usize;
+    let (limit, limit_note) = search_engine.clamp_limit(requested);
 
     if doc_id.is_empty() {
         return Some(json!({
@@ -435,39 +505,29 @@ async fn handle_similar_docs(
                             Some(SearchResult {
                                 id: meta.id,
                                 source_id: meta.source_id,
+                                document_id: meta.document_id,
                                 title: meta.title,
                                 content,
                                 file_path: meta.file_path,
                                 line_start: meta.line_start,
+                                line_end: meta.line_end,
+                                page_start: meta.page_start,
+                                page_end: meta.page_end,
+                                section: meta.section,
+                                has_code: meta.has_code,
                                 score: meta.score,
+                                matched_by: vec!["vector".to_string()],
                             })
                         })
                         .collect();
 
                     let results = search_engine.rerank(results, &source_content, limit);
 
-                    let text = results.iter().map(|r| {
-                        format!(
-                            "## {} (Score: {:.3})\nSource: {}\n\n{}",
-                            r.title.as_deref().unwrap_or("Untitled"),
-                            r.score,
-                            r.source_id,
-                            r.content
-                        )
-                    }).collect::<Vec<_>>().join("\n\n---\n\n");
-
                     Some(json!({
                         "jsonrpc": "2.0",
                         "id": id,
                         "result": {
-                            "content": [{
-                                "type": "text",
-                                "text": if results.is_empty() {
-                                    "No similar documents found.".to_string()
-                                } else {
-                                    format!("Found {} similar documents:\n\n{}", results.len(), text)
-                                }
-                            }]
+                            "content": similar_content(&results, limit_note.as_deref())
                         }
                     }))
                 }
@@ -485,3 +545,61 @@ async fn handle_similar_docs(
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, document_id: &str, score: f32) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            source_id: "docs".to_string(),
+            document_id: document_id.to_string(),
+            title: Some(format!("Title {}", id)),
+            content: format!("Content of {}", id),
+            file_path: None,
+            line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
+            score,
+            matched_by: vec!["vector".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_search_content_citations_match_results() {
+        let results = vec![result("c2", "d1", 0.9), result("c7", "d3", 0.4)];
+
+        let content = search_content(&results, None, true);
+
+        assert_eq!(content.len(), 2);
+        let text = content[0]["text"].as_str().unwrap();
+        assert!(text.starts_with("Found 2 results:"));
+        let citations: Value = serde_json::from_str(content[1]["text"].as_str().unwrap()).unwrap();
+        let citations: Vec<Citation> = serde_json::from_value(citations["citations"].clone()).unwrap();
+        assert_eq!(citations, results.iter().map(Citation::from_result).collect::<Vec<_>>());
+        assert_eq!(citations[0].chunk_id, "c2");
+        assert_eq!(citations[1].document_id, "d3");
+        assert!(text.find("Title c2").unwrap() < text.find("Title c7").unwrap());
+
+        // Plain text by default
+        assert_eq!(search_content(&results, None, false).len(), 1);
+    }
+
+    #[test]
+    fn test_clamped_limit_note_leads_the_text() {
+        let results = vec![result("c2", "d1", 0.9)];
+        let (_, note) = SearchEngine::new().with_max_limit(1).clamp_limit(50);
+        let note = note.unwrap();
+
+        for content in [search_content(&results, Some(&note), false), similar_content(&results, Some(&note))] {
+            let text = content[0]["text"].as_str().unwrap();
+            assert!(text.starts_with(&format!("Note: {}\n\n", note)), "{}", text);
+            assert!(text.contains("Title c2"));
+        }
+        assert!(!similar_content(&results, None)[0]["text"].as_str().unwrap().contains("Note:"));
+    }
+}
@@ -13,6 +13,8 @@ pub struct BatchAccumulator {
     total_chunks: usize,
     /// Estimated memory usage in bytes
     memory_bytes: usize,
+    /// Highest estimated memory usage since creation
+    peak_memory_bytes: usize,
     /// Configuration thresholds
     config: BatchConfig,
 }
@@ -24,6 +26,7 @@ impl BatchAccumulator {
             documents: Vec::new(),
             total_chunks: 0,
             memory_bytes: 0,
+            peak_memory_bytes: 0,
             config,
         }
     }
@@ -37,6 +40,7 @@ impl BatchAccumulator {
 
         self.total_chunks += doc.chunks.len();
         self.memory_bytes += doc_memory;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(self.memory_bytes);
         self.documents.push(doc);
 
         self.should_flush()
@@ -47,7 +51,22 @@ impl BatchAccumulator {
         // Flush if any threshold is exceeded
         self.documents.len() >= self.config.max_docs
             || self.total_chunks >= self.config.max_chunks
-            || self.memory_bytes >= self.config.max_memory_mb * 1024 * 1024
+            || self.memory_bytes >= self.memory_budget()
+    }
+
+    /// Whether `doc` can be added without going past any threshold
+    ///
+    /// Callers flush first when it can't, so the batch stays within budget
+    /// (a single document larger than the budget still gets a batch of its own).
+    pub fn has_room_for(&self, doc: &PreparedDoc) -> bool {
+        self.documents.len() < self.config.max_docs
+            && self.total_chunks + doc.chunks.len() <= self.config.max_chunks
+            && self.memory_bytes + Self::estimate_doc_memory(doc) <= self.memory_budget()
+    }
+
+    /// Memory threshold in bytes
+    pub fn memory_budget(&self) -> usize {
+        self.config.max_memory_mb * 1024 * 1024
     }
 
     /// Get the number of accumulated documents
@@ -65,6 +84,11 @@ impl BatchAccumulator {
         self.memory_bytes
     }
 
+    /// Highest estimated memory usage since creation, across flushes
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory_bytes
+    }
+
     /// Get all chunks from accumulated documents
     pub fn all_chunks(&self) -> Vec<&ChunkData> {
         self.documents
@@ -126,6 +150,7 @@ mod tests {
                 file_path: None,
                 line_start: 1,
                 line_end: 10,
+                pages: None,
                 content_hash: format!("hash-{}", i),
                 section: None,
                 subsection: None,
@@ -142,6 +167,8 @@ mod tests {
             created_at: "2024-01-01T00:00:00Z".to_string(),
             content_length: 12,
             chunks,
+            linked: false,
+            tags: Vec::new(),
         }
     }
 
@@ -152,6 +179,7 @@ mod tests {
             max_chunks: 100,
             max_memory_mb: 10,
             flush_timeout_secs: 5,
+            max_write_rows: 1000,
         };
         let mut acc = BatchAccumulator::new(config);
 
@@ -175,6 +203,7 @@ mod tests {
             max_chunks: 1000,
             max_memory_mb: 100,
             flush_timeout_secs: 5,
+            max_write_rows: 1000,
         };
         let mut acc = BatchAccumulator::new(config);
 
@@ -191,6 +220,7 @@ mod tests {
             max_chunks: 10,
             max_memory_mb: 100,
             flush_timeout_secs: 5,
+            max_write_rows: 1000,
         };
         let mut acc = BatchAccumulator::new(config);
 
@@ -199,6 +229,26 @@ mod tests {
         assert!(should_flush);
     }
 
+    #[test]
+    fn test_has_room_for_respects_thresholds() {
+        let config = BatchConfig {
+            max_docs: 100,
+            max_chunks: 10,
+            max_memory_mb: 100,
+            flush_timeout_secs: 5,
+            max_write_rows: 1000,
+        };
+        let mut acc = BatchAccumulator::new(config);
+
+        acc.add_document(make_test_doc(6));
+        assert!(acc.has_room_for(&make_test_doc(4)));
+        assert!(!acc.has_room_for(&make_test_doc(5)));
+
+        acc.take_documents();
+        assert_eq!(acc.memory_usage(), 0);
+        assert!(acc.peak_memory() > 0);
+    }
+
     #[test]
     fn test_take_documents() {
         let config = BatchConfig::default();
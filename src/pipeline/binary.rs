@@ -0,0 +1,80 @@
+//! Binary file detection
+//!
+//! Files are skipped when their extension marks them as an image, archive,
+//! executable or other non-text format, or when a null byte shows up in the
+//! first few KB. PDFs are binary too but go to the PDF extractor instead.
+
+use std::path::Path;
+
+/// How much of a file is checked for null bytes
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Extensions that are never text
+const BINARY_EXTENSIONS: &[&str] = &[
+    // Images
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tif", "tiff", "psd",
+    // Archives
+    "zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "zst", "jar",
+    // Executables and libraries
+    "exe", "dll", "so", "dylib", "bin", "o", "a", "lib", "class", "wasm", "pyc",
+    // Media and fonts
+    "mp3", "mp4", "mov", "avi", "wav", "flac", "ogg", "woff", "woff2", "ttf", "otf",
+    // Databases
+    "db", "sqlite", "sqlite3",
+];
+
+/// Whether the file extension is on the binary blocklist
+pub fn has_binary_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| BINARY_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Whether the content looks binary (a null byte in the first `SNIFF_BYTES`)
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_BYTES)].contains(&0)
+}
+
+/// Whether a file should be skipped as binary, given its path and contents
+///
+/// PDFs are never treated as binary here; they go to the PDF extractor.
+pub fn is_binary_file(path: &Path, bytes: &[u8]) -> bool {
+    let is_pdf = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    !is_pdf && (has_binary_extension(path) || looks_binary(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+    #[test]
+    fn test_png_is_binary() {
+        assert!(is_binary_file(Path::new("logo.png"), PNG_HEADER));
+        // Caught by sniffing even when the extension says text
+        assert!(is_binary_file(Path::new("logo.txt"), PNG_HEADER));
+        assert!(has_binary_extension(Path::new("ARCHIVE.ZIP")));
+    }
+
+    #[test]
+    fn test_utf8_text_is_not_binary() {
+        let text = "# Notes\n\nCafé, naïve, 知识库 — all valid UTF-8.\n".as_bytes();
+        assert!(!is_binary_file(Path::new("notes.md"), text));
+        assert!(!is_binary_file(Path::new("Makefile"), text));
+    }
+
+    #[test]
+    fn test_pdf_is_not_skipped() {
+        assert!(!is_binary_file(Path::new("paper.pdf"), b"%PDF-1.7\n\x00\x01binary"));
+    }
+
+    #[test]
+    fn test_null_past_sniff_window_is_ignored() {
+        let mut bytes = vec![b'a'; SNIFF_BYTES];
+        bytes.push(0);
+        assert!(!looks_binary(&bytes));
+    }
+}
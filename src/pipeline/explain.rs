@@ -0,0 +1,111 @@
+//! Per-file ingest decisions
+//!
+//! Backs `ingest --explain-ingest`: every file the walk or the pipeline
+//! considered, with whether it was ingested and, if not, why.
+
+use super::manifest::{IngestManifest, ManifestStatus};
+use super::WalkResult;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Why a file was not ingested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Dotfile or dot-directory
+    Hidden,
+    /// Matched `.gitignore`, `.eywaignore` or the built-in ignore list
+    Ignored,
+    /// Matched an `--exclude` pattern
+    Excluded(String),
+    /// Symlink, and symlinks aren't followed
+    Symlink,
+    /// Following the symlink would loop
+    SymlinkCycle,
+    /// Already reached through another symlink
+    Duplicate,
+    /// Not a file type eywa ingests
+    UnsupportedExtension,
+    /// Content hash matches the last ingest
+    Unchanged,
+    /// Nothing but whitespace
+    Empty,
+    /// Looks binary (blocklisted extension or null bytes)
+    Binary,
+    /// Couldn't be read, or PDF text extraction failed
+    Unreadable,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Hidden => write!(f, "hidden (use --include-hidden)"),
+            SkipReason::Ignored => write!(f, "matched an ignore rule (use --no-ignore)"),
+            SkipReason::Excluded(pattern) => write!(f, "matched --exclude '{}'", pattern),
+            SkipReason::Symlink => write!(f, "symlink not followed (use --follow-symlinks)"),
+            SkipReason::SymlinkCycle => write!(f, "symlink cycle"),
+            SkipReason::Duplicate => write!(f, "already reached through another link"),
+            SkipReason::UnsupportedExtension => write!(f, "unsupported file type"),
+            SkipReason::Unchanged => write!(f, "unchanged since last ingest (use --force)"),
+            SkipReason::Empty => write!(f, "empty"),
+            SkipReason::Binary => write!(f, "binary file"),
+            SkipReason::Unreadable => write!(f, "could not be read"),
+        }
+    }
+}
+
+/// What happened to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Ingested(ManifestStatus),
+    Skipped(SkipReason),
+}
+
+/// One line of `--explain-ingest` output
+#[derive(Debug, Clone)]
+pub struct FileDecision {
+    pub path: PathBuf,
+    pub decision: Decision,
+}
+
+impl fmt::Display for FileDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.decision {
+            Decision::Ingested(ManifestStatus::Updated) => write!(f, "ingested  {} (updated)", self.path.display()),
+            Decision::Ingested(_) => write!(f, "ingested  {}", self.path.display()),
+            Decision::Skipped(reason) => write!(f, "skipped   {}: {}", self.path.display(), reason),
+        }
+    }
+}
+
+/// Combine walk skips (needs `WalkOptions::explain`) and the ingest manifest
+/// into one decision per file, sorted by path
+pub fn explain_decisions(walk: &WalkResult, manifest: &IngestManifest) -> Vec<FileDecision> {
+    let mut decisions: Vec<FileDecision> = walk
+        .skipped
+        .iter()
+        .map(|(path, reason)| FileDecision {
+            path: path.clone(),
+            decision: Decision::Skipped(reason.clone()),
+        })
+        .collect();
+
+    // A file can produce several documents; report it once
+    let mut last_path: Option<&str> = None;
+    for entry in &manifest.entries {
+        if last_path == Some(entry.file_path.as_str()) {
+            continue;
+        }
+        last_path = Some(entry.file_path.as_str());
+        let decision = match (&entry.status, &entry.reason) {
+            (ManifestStatus::Skipped, Some(reason)) => Decision::Skipped(reason.clone()),
+            (ManifestStatus::Skipped, None) => Decision::Skipped(SkipReason::Unchanged),
+            (status, _) => Decision::Ingested(*status),
+        };
+        decisions.push(FileDecision { path: PathBuf::from(&entry.file_path), decision });
+    }
+
+    decisions.sort_by(|a, b| a.path.cmp(&b.path));
+    decisions
+}
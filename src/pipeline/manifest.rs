@@ -0,0 +1,65 @@
+//! Ingest manifest
+//!
+//! A per-document record of what an ingest run did, written as JSON so runs
+//! can be audited and diffed.
+
+use super::explain::SkipReason;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What happened to a file during ingest
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestStatus {
+    /// Added for the first time
+    New,
+    /// Replaced documents previously ingested from the same file (`--update`)
+    Updated,
+    /// Left alone: unchanged since the last ingest, empty, binary, or unreadable
+    Skipped,
+}
+
+/// One document (or, for skipped files with no document, one file) in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub document_id: Option<String>,
+    pub source_id: String,
+    pub title: Option<String>,
+    pub file_path: String,
+    pub chunk_count: u32,
+    pub status: ManifestStatus,
+    /// Why the file was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<SkipReason>,
+}
+
+/// Report of an ingest run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestManifest {
+    pub source_id: String,
+    pub created_at: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl IngestManifest {
+    pub fn new(source_id: &str) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            created_at: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Number of entries with the given status
+    pub fn count(&self, status: ManifestStatus) -> usize {
+        self.entries.iter().filter(|e| e.status == status).count()
+    }
+
+    /// Write the manifest as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write manifest to {}", path.display()))
+    }
+}
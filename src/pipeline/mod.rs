@@ -6,25 +6,39 @@
 //! Architecture:
 //! - IngestPipeline: Coordinates the ingestion flow
 //! - BatchAccumulator: Holds documents until threshold reached
+//! - binary: Detects binary files so they're skipped rather than indexed as text
 //! - BatchWriter: Writes batches atomically to LanceDB + SQLite
 //! - ProgressTracker: Tracks and displays ingestion progress
 
 pub mod accumulator;
+pub mod binary;
+pub mod explain;
+pub mod manifest;
 pub mod progress;
 pub mod writer;
 
 pub use accumulator::BatchAccumulator;
-pub use progress::ProgressTracker;
+pub use binary::is_binary_file;
+pub use explain::{explain_decisions, Decision, FileDecision, SkipReason};
+pub use manifest::{IngestManifest, ManifestEntry, ManifestStatus};
+pub use progress::{write_progress, ProgressStyle, ProgressTracker};
 pub use writer::{BatchWriter, WriteStats};
 
 use crate::bm25::BM25Index;
-use crate::chunking::{ChunkerRegistry, DocMetadata};
+use crate::chunking::{ChunkerRegistry, DocMetadata, TokenCounter};
+use crate::config::{Config, DedupScope, SourceNameCase};
+use crate::content::{ContentStore, DocumentRow};
 use crate::db::VectorDB;
 use crate::embed::Embedder;
-use crate::types::{DocumentInput, IngestResponse};
+use crate::types::{DocumentInput, DocumentRecord, FailedFile, IngestResponse};
 use anyhow::Result;
-use std::path::Path;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use sha2::{Digest, Sha256};
 use walkdir::WalkDir;
 
 /// Configuration for batch ingestion thresholds
@@ -32,9 +46,15 @@ use walkdir::WalkDir;
 pub struct BatchConfig {
     /// Maximum documents per batch before flush
     pub max_docs: usize,
-    /// Maximum chunks per LanceDB write
+    /// Maximum chunks per batch before flush
     pub max_chunks: usize,
+    /// Maximum rows per LanceDB append; larger batches are split so the
+    /// Arrow record batch built for each append stays bounded
+    pub max_write_rows: usize,
     /// Maximum memory in MB before flush
+    ///
+    /// Counts the batch's documents and chunks, not the embedding model's
+    /// working memory, which grows with the number of embedding workers.
     pub max_memory_mb: usize,
     /// Flush timeout in seconds for partial batches
     pub flush_timeout_secs: u64,
@@ -45,12 +65,330 @@ impl Default for BatchConfig {
         Self {
             max_docs: 50,
             max_chunks: 5000,
+            max_write_rows: 1000,
             max_memory_mb: 100,
             flush_timeout_secs: 5,
         }
     }
 }
 
+/// Options controlling which files a directory walk picks up
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Glob patterns to skip, matched against the path relative to the walk
+    /// root, any of its parent directories, or the file name
+    pub exclude: Vec<String>,
+    /// Walk into dotfiles and dot-directories (skipped by default)
+    pub include_hidden: bool,
+    /// Follow symlinks; cycles are detected and skipped (off by default)
+    pub follow_symlinks: bool,
+    /// Don't skip paths matched by `.gitignore`/`.eywaignore` files or the
+    /// built-in ignore list
+    pub no_ignore: bool,
+    /// Record every skipped path with its reason in `WalkResult::skipped`
+    pub explain: bool,
+}
+
+/// Files found by a directory walk
+#[derive(Debug, Default)]
+pub struct WalkResult {
+    /// Files to ingest, in walk order
+    pub files: Vec<PathBuf>,
+    /// Number of files skipped by each exclude pattern, in the order given
+    pub excluded: Vec<(String, usize)>,
+    /// Hidden files and directories skipped (directories count once)
+    pub hidden: Vec<PathBuf>,
+    /// Symlinks not followed, with their targets
+    pub symlinks: Vec<(PathBuf, PathBuf)>,
+    /// Symlinks skipped because following them would loop
+    pub cycles: Vec<PathBuf>,
+    /// Paths skipped by ignore rules (directories count once)
+    pub ignored: Vec<PathBuf>,
+    /// Every skipped path with its reason, filled with `WalkOptions::explain`
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+}
+
+/// Directories skipped in every walk unless ignoring is turned off
+const DEFAULT_IGNORES: &[&str] = &[".git/", "target/", "node_modules/", "__pycache__/"];
+
+/// Ignore files read from each walked directory; later files take precedence
+const IGNORE_FILES: &[&str] = &[".gitignore", ".eywaignore"];
+
+/// Matcher for the built-in ignore list, rooted at the walk root
+fn default_ignores(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in DEFAULT_IGNORES {
+        let _ = builder.add_line(None, pattern);
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Matcher for the ignore files in `dir`, if it has any
+fn dir_ignores(dir: &Path) -> Option<Gitignore> {
+    let files: Vec<PathBuf> = IGNORE_FILES.iter().map(|name| dir.join(name)).filter(|p| p.is_file()).collect();
+    if files.is_empty() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    for file in &files {
+        if let Some(e) = builder.add(file) {
+            eprintln!("Warning: Ignoring bad pattern in {}: {}", file.display(), e);
+        }
+    }
+    match builder.build() {
+        Ok(matcher) => Some(matcher),
+        Err(e) => {
+            eprintln!("Warning: Failed to read ignore files in {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Whether ignore rules skip `path`; the deepest matching directory wins
+fn is_ignored(matchers: &[(usize, Gitignore)], defaults: &Gitignore, path: &Path, is_dir: bool) -> bool {
+    for (_, matcher) in matchers.iter().rev() {
+        match matcher.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+    }
+    defaults.matched(path, is_dir).is_ignore()
+}
+
+/// Lazy walk over the ingestible files under a path
+///
+/// Yields the same files as `IngestPipeline::collect_files`, one at a time,
+/// so a large directory never has to be listed up front. Skipped paths are
+/// recorded as the walk goes and returned by `into_result`.
+pub struct FileWalk {
+    root: PathBuf,
+    /// Set for a single-file path, taken on the first call to `next`
+    single: Option<PathBuf>,
+    entries: Option<walkdir::IntoIter>,
+    patterns: Vec<glob::Pattern>,
+    include_hidden: bool,
+    follow_symlinks: bool,
+    explain: bool,
+    /// Built-in ignore list, or `None` with `no_ignore`
+    default_ignores: Option<Gitignore>,
+    /// Ignore-file matchers of the directories above the current entry, with their depth
+    dir_ignores: Vec<(usize, Gitignore)>,
+    /// Linked files can be reached by more than one path
+    seen: HashSet<PathBuf>,
+    result: WalkResult,
+}
+
+impl FileWalk {
+    /// Start walking `path`; fails on an invalid exclude pattern
+    pub fn new(path: &Path, options: &WalkOptions) -> Result<Self> {
+        let patterns = options
+            .exclude
+            .iter()
+            .map(|p| {
+                glob::Pattern::new(p)
+                    .map_err(|e| anyhow::anyhow!("Invalid exclude pattern '{}': {}", p, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let is_dir = path.is_dir();
+
+        Ok(Self {
+            root: path.to_path_buf(),
+            single: (!is_dir).then(|| path.to_path_buf()),
+            entries: is_dir.then(|| WalkDir::new(path).follow_links(options.follow_symlinks).into_iter()),
+            patterns,
+            include_hidden: options.include_hidden,
+            follow_symlinks: options.follow_symlinks,
+            explain: options.explain,
+            default_ignores: (!options.no_ignore).then(|| default_ignores(path)),
+            dir_ignores: Vec::new(),
+            seen: HashSet::new(),
+            result: WalkResult {
+                excluded: options.exclude.iter().map(|p| (p.clone(), 0)).collect(),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Paths skipped so far (`files` is left empty)
+    pub fn into_result(self) -> WalkResult {
+        self.result
+    }
+}
+
+impl Iterator for FileWalk {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        if let Some(file) = self.single.take() {
+            return Some(file);
+        }
+        let entries = self.entries.as_mut()?;
+
+        loop {
+            let entry = match entries.next()? {
+                Ok(entry) => entry,
+                // WalkDir reports a loop instead of descending into it
+                Err(e) if e.loop_ancestor().is_some() => {
+                    if let Some(p) = e.path() {
+                        self.result.cycles.push(p.to_path_buf());
+                        note_skip(self.explain, &mut self.result, p, SkipReason::SymlinkCycle);
+                    }
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            if !self.include_hidden && is_hidden(&entry) {
+                self.result.hidden.push(entry.path().to_path_buf());
+                note_skip(self.explain, &mut self.result, entry.path(), SkipReason::Hidden);
+                if entry.file_type().is_dir() {
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+            if let Some(defaults) = &self.default_ignores {
+                // Matchers of directories the walk has left no longer apply
+                self.dir_ignores.retain(|(depth, _)| *depth < entry.depth());
+                let is_dir = entry.file_type().is_dir();
+                if entry.depth() > 0 && is_ignored(&self.dir_ignores, defaults, entry.path(), is_dir) {
+                    self.result.ignored.push(entry.path().to_path_buf());
+                    note_skip(self.explain, &mut self.result, entry.path(), SkipReason::Ignored);
+                    if is_dir {
+                        entries.skip_current_dir();
+                    }
+                    continue;
+                }
+                if is_dir {
+                    if let Some(matcher) = dir_ignores(entry.path()) {
+                        self.dir_ignores.push((entry.depth(), matcher));
+                    }
+                }
+            }
+            let p = entry.path();
+            if entry.path_is_symlink() && !self.follow_symlinks {
+                let target = std::fs::read_link(p).unwrap_or_default();
+                self.result.symlinks.push((p.to_path_buf(), target));
+                note_skip(self.explain, &mut self.result, p, SkipReason::Symlink);
+                continue;
+            }
+            if !p.is_file() {
+                continue;
+            }
+            let ext = p
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            // Blocklisted binaries go on to `read_file`, which reports them as binary
+            if !IngestPipeline::is_supported_extension(&ext) && !binary::has_binary_extension(p) {
+                note_skip(self.explain, &mut self.result, p, SkipReason::UnsupportedExtension);
+                continue;
+            }
+
+            let relative = p.strip_prefix(&self.root).unwrap_or(p);
+            if let Some(idx) = self.patterns.iter().position(|pat| matches_exclude(pat, relative)) {
+                self.result.excluded[idx].1 += 1;
+                let pattern = self.result.excluded[idx].0.clone();
+                note_skip(self.explain, &mut self.result, p, SkipReason::Excluded(pattern));
+                continue;
+            }
+
+            if self.follow_symlinks {
+                let canonical = p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+                if !self.seen.insert(canonical) {
+                    note_skip(self.explain, &mut self.result, p, SkipReason::Duplicate);
+                    continue;
+                }
+            }
+
+            return Some(p.to_path_buf());
+        }
+    }
+}
+
+/// Record a skipped path for `--explain-ingest`
+fn note_skip(explain: bool, result: &mut WalkResult, path: &Path, reason: SkipReason) {
+    if explain {
+        result.skipped.push((path.to_path_buf(), reason));
+    }
+}
+
+/// Dotfile or dot-directory below the walk root
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Check whether an exclude pattern matches a path relative to the walk root
+///
+/// `target` matches `target/debug/main.rs`, `*.log` matches `logs/app.log`,
+/// and `docs/**/draft-*` matches against the full relative path.
+fn matches_exclude(pattern: &glob::Pattern, relative: &Path) -> bool {
+    if pattern.matches_path(relative) {
+        return true;
+    }
+    if relative
+        .file_name()
+        .map(|n| pattern.matches(&n.to_string_lossy()))
+        .unwrap_or(false)
+    {
+        return true;
+    }
+    relative.ancestors().skip(1).any(|dir| {
+        !dir.as_os_str().is_empty() && pattern.matches_path(dir)
+    })
+}
+
+/// Whether a walk of `root` with these options would pick up `path`
+///
+/// Checks a single path without walking, for callers that learn about files
+/// one at a time. Paths outside `root` and directories are never included;
+/// an invalid exclude pattern matches nothing, as `FileWalk::new` rejects it.
+pub fn walk_includes(root: &Path, path: &Path, options: &WalkOptions) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    if relative.as_os_str().is_empty() || path.is_dir() {
+        return false;
+    }
+    if !options.include_hidden && relative.iter().any(|c| c.to_string_lossy().starts_with('.')) {
+        return false;
+    }
+    if !options.no_ignore {
+        // Same matchers FileWalk would hold on its way down to `path`
+        let defaults = default_ignores(root);
+        let mut matchers: Vec<(usize, Gitignore)> = dir_ignores(root).map(|m| (0, m)).into_iter().collect();
+        let depth = relative.iter().count();
+        let mut current = root.to_path_buf();
+        for (i, component) in relative.iter().enumerate() {
+            current.push(component);
+            let is_dir = i + 1 < depth;
+            if is_ignored(&matchers, &defaults, &current, is_dir) {
+                return false;
+            }
+            if is_dir {
+                if let Some(matcher) = dir_ignores(&current) {
+                    matchers.push((i + 1, matcher));
+                }
+            }
+        }
+    }
+    if !options.follow_symlinks && path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+        return false;
+    }
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    // Blocklisted binaries pass, as in `FileWalk`, so ingest can report them
+    if !IngestPipeline::is_supported_extension(&ext) && !binary::has_binary_extension(path) {
+        return false;
+    }
+    !options
+        .exclude
+        .iter()
+        .any(|p| glob::Pattern::new(p).is_ok_and(|pattern| matches_exclude(&pattern, relative)))
+}
+
 /// Get optimal batch size based on device type
 /// GPU can saturate with larger batches, CPU works better with smaller
 fn get_embedding_batch_size(device_name: &str) -> usize {
@@ -61,6 +399,109 @@ fn get_embedding_batch_size(device_name: &str) -> usize {
     }
 }
 
+/// Overrides the number of embedding workers
+const EMBED_THREADS_ENV: &str = "EYWA_EMBED_THREADS";
+
+/// Embedding workers on CPU unless overridden
+///
+/// Kept small: candle already spreads each batch's matrix work across the
+/// cores, and every worker holds its own batch of activations.
+const DEFAULT_CPU_EMBED_THREADS: usize = 2;
+
+/// Number of batches to embed at once
+///
+/// `EYWA_EMBED_THREADS` if set to a positive number, otherwise
+/// `DEFAULT_CPU_EMBED_THREADS` on CPU (fewer on a single core). A GPU already
+/// runs a whole batch in parallel, so it gets one.
+fn get_embedding_threads(device_name: &str) -> usize {
+    if let Some(threads) = std::env::var(EMBED_THREADS_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+    {
+        return threads;
+    }
+    if device_name.contains("CPU") {
+        std::thread::available_parallelism().map_or(1, |n| n.get().min(DEFAULT_CPU_EMBED_THREADS))
+    } else {
+        1
+    }
+}
+
+/// Embed passages in batches of `batch_size`, up to `threads` batches at a time
+///
+/// Texts are split into the same batches whatever the thread count, so the
+/// vectors are identical to a sequential run and come back in input order.
+pub fn embed_in_batches(
+    embedder: &Embedder,
+    texts: &[String],
+    batch_size: usize,
+    threads: usize,
+) -> Result<Vec<Vec<f32>>> {
+    let batches: Vec<&[String]> = texts.chunks(batch_size.max(1)).collect();
+    let embed = |batch_idx: usize, batch: &[String]| {
+        embedder.embed_passages(batch).map_err(|e| {
+            eprintln!(
+                "Embedding batch {} failed ({} texts, lengths: {:?}): {}",
+                batch_idx,
+                batch.len(),
+                batch.iter().map(|t| t.len()).collect::<Vec<_>>(),
+                e
+            );
+            e
+        })
+    };
+
+    let workers = threads.clamp(1, batches.len().max(1));
+    if workers == 1 {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for (batch_idx, &batch) in batches.iter().enumerate() {
+            embeddings.extend(embed(batch_idx, batch)?);
+        }
+        return Ok(embeddings);
+    }
+
+    // Workers take the next unclaimed batch until none are left or one fails
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut slots: Vec<Option<Vec<Vec<f32>>>> = vec![None; batches.len()];
+    std::thread::scope(|scope| -> Result<()> {
+        let mut handles = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            handles.push(scope.spawn(|| -> Result<Vec<(usize, Vec<Vec<f32>>)>> {
+                let mut done = Vec::new();
+                loop {
+                    let batch_idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&batch) = batches.get(batch_idx) else {
+                        return Ok(done);
+                    };
+                    if failed.load(Ordering::Relaxed) {
+                        return Ok(done);
+                    }
+                    match embed(batch_idx, batch) {
+                        Ok(embeddings) => done.push((batch_idx, embeddings)),
+                        Err(e) => {
+                            failed.store(true, Ordering::Relaxed);
+                            return Err(e);
+                        }
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            let done = handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Embedding worker panicked"))??;
+            for (batch_idx, embeddings) in done {
+                slots[batch_idx] = Some(embeddings);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(slots.into_iter().flatten().flatten().collect())
+}
+
 /// Prepared document with its chunks ready for processing
 #[derive(Debug, Clone)]
 pub struct PreparedDoc {
@@ -71,6 +512,10 @@ pub struct PreparedDoc {
     pub created_at: String,
     pub content_length: u32,
     pub chunks: Vec<ChunkData>,
+    /// Store only the file path; content is re-read from disk
+    pub linked: bool,
+    /// Tags from Markdown frontmatter
+    pub tags: Vec<String>,
 }
 
 /// Intermediate chunk structure during ingestion
@@ -84,6 +529,8 @@ pub struct ChunkData {
     pub file_path: Option<String>,
     pub line_start: u32,
     pub line_end: u32,
+    /// Page range, for PDF chunks
+    pub pages: Option<(u32, u32)>,
     pub content_hash: String,
     // Hierarchical metadata from smart chunking
     pub section: Option<String>,
@@ -108,6 +555,29 @@ pub struct IngestPipeline {
     embedder: Arc<Embedder>,
     bm25_index: Arc<BM25Index>,
     chunker: ChunkerRegistry,
+    link_files: bool,
+    dedup_scope: DedupScope,
+    source_name_case: SourceNameCase,
+    split_sections: Option<usize>,
+    update_existing: bool,
+    force: bool,
+    progress_style: ProgressStyle,
+    /// Largest batch held by any ingest so far, in estimated bytes
+    peak_batch_bytes: AtomicUsize,
+}
+
+/// What a file stream has recorded so far, besides the batch itself
+#[derive(Default)]
+struct FileStreamState {
+    manifest: IngestManifest,
+    /// (file path, content hash) of every file read into the pipeline
+    file_hashes: Vec<(String, String)>,
+    /// Files whose earlier documents were replaced (`with_update`)
+    replaced_paths: HashSet<String>,
+    documents_skipped: u32,
+    files_skipped_binary: u32,
+    files_failed: Vec<FailedFile>,
+    files_lossy_utf8: Vec<String>,
 }
 
 impl IngestPipeline {
@@ -123,7 +593,118 @@ impl IngestPipeline {
             embedder,
             bm25_index,
             chunker: ChunkerRegistry::new(),
+            link_files: false,
+            dedup_scope: DedupScope::default(),
+            source_name_case: SourceNameCase::default(),
+            split_sections: None,
+            update_existing: false,
+            force: false,
+            progress_style: ProgressStyle::default(),
+            peak_batch_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Apply the chunking and dedup settings from a loaded config
+    pub fn with_settings(self, user_config: &Config) -> Self {
+        let mut chunker = ChunkerRegistry::with_settings(user_config.chunk_overlap, user_config.chunk_sizes())
+            .with_frontmatter_stripping(user_config.strip_frontmatter);
+        if let Some(max_tokens) = user_config.chunk_max_tokens {
+            // Chunks must fit the model's window together with the passage prefix
+            let limit = self.embedder.max_passage_tokens();
+            if max_tokens > limit {
+                eprintln!(
+                    "Warning: chunk_max_tokens ({}) is more than {} can embed; using {}",
+                    max_tokens,
+                    self.embedder.model_id(),
+                    limit
+                );
+            }
+            chunker = chunker.with_token_limit(
+                Arc::clone(&self.embedder) as Arc<dyn TokenCounter>,
+                max_tokens.min(limit),
+            );
         }
+        self.with_chunker(chunker)
+            .with_dedup_scope(user_config.dedup_scope)
+            .with_source_name_case(user_config.source_name_case)
+    }
+
+    /// Use a specific chunker registry instead of the default one
+    pub fn with_chunker(mut self, chunker: ChunkerRegistry) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    /// Link documents read from local files instead of storing their content
+    ///
+    /// Only applies to files ingested whole as UTF-8 text (so extracted PDFs,
+    /// split sections and pasted text are always stored).
+    pub fn with_linked_files(mut self, link_files: bool) -> Self {
+        self.link_files = link_files;
+        self
+    }
+
+    /// Set which existing chunks count as duplicates (see `DedupScope`)
+    pub fn with_dedup_scope(mut self, scope: DedupScope) -> Self {
+        self.dedup_scope = scope;
+        self
+    }
+
+    /// Ingest Markdown files as one document per heading at `level` or above
+    ///
+    /// Each document is titled by its heading. Line numbers count from the
+    /// start of the section, not the file.
+    pub fn with_split_sections(mut self, level: Option<usize>) -> Self {
+        self.split_sections = level;
+        self
+    }
+
+    /// Replace documents already ingested from the same file path
+    ///
+    /// Existing documents in the source whose `file_path` matches an incoming
+    /// one are removed from all three stores before the new version is
+    /// written, so a changed file doesn't leave stale chunks behind.
+    pub fn with_update(mut self, update_existing: bool) -> Self {
+        self.update_existing = update_existing;
+        self
+    }
+
+    /// Re-ingest files even if their content is unchanged since the last ingest
+    ///
+    /// Use after changing chunking or embedding settings.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Set how source names differing only by case are handled
+    pub fn with_source_name_case(mut self, mode: SourceNameCase) -> Self {
+        self.source_name_case = mode;
+        self
+    }
+
+    /// Resolve the source to ingest into, applying `source_name_case`
+    ///
+    /// Prints a warning when the name differs only by case from an existing
+    /// source and the mode is `Warn`.
+    pub fn resolve_source_id(&self, data_dir: &Path, source_id: &str) -> Result<String> {
+        if self.source_name_case == SourceNameCase::Exact {
+            return Ok(source_id.to_string());
+        }
+        let content_store = ContentStore::open(&data_dir.join("content.db"))?;
+        let existing = content_store.sources_matching_ignore_case(source_id)?;
+
+        let (resolved, warning) = resolve_source_name(&existing, source_id, self.source_name_case);
+        if let Some(warning) = warning {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(resolved)
+    }
+
+    /// Set how ingest progress is printed
+    pub fn with_progress_style(mut self, style: ProgressStyle) -> Self {
+        self.progress_style = style;
+        self
     }
 
     /// Check if file extension is supported for ingestion
@@ -177,12 +758,21 @@ impl IngestPipeline {
         &self,
         doc_input: &DocumentInput,
         source_id: &str,
+    ) -> Option<PreparedDoc> {
+        self.prepare_document_with_id(uuid::Uuid::new_v4().to_string(), doc_input, source_id)
+    }
+
+    /// Prepare a document under an existing ID (used when re-embedding)
+    fn prepare_document_with_id(
+        &self,
+        doc_id: String,
+        doc_input: &DocumentInput,
+        source_id: &str,
     ) -> Option<PreparedDoc> {
         if doc_input.content.trim().is_empty() {
             return None;
         }
 
-        let doc_id = uuid::Uuid::new_v4().to_string();
         let title = doc_input
             .title
             .clone()
@@ -215,6 +805,7 @@ impl IngestPipeline {
                 file_path: c.metadata.file_path,
                 line_start: c.metadata.line_start,
                 line_end: c.metadata.line_end,
+                pages: c.metadata.pages,
                 content_hash: c.metadata.content_hash,
                 // Preserve hierarchical metadata from smart chunking
                 section: c.metadata.section,
@@ -228,6 +819,10 @@ impl IngestPipeline {
             id: doc_id,
             content: doc_input.content.clone(),
             title,
+            linked: false,
+            tags: self
+                .chunker
+                .document_tags(&doc_input.content, doc_input.file_path.as_deref()),
             file_path: doc_input.file_path.clone(),
             created_at,
             content_length,
@@ -247,15 +842,30 @@ impl IngestPipeline {
         data_dir: &Path,
         source_id: &str,
         documents: Vec<DocumentInput>,
+    ) -> Result<IngestResponse> {
+        let source_id = self.resolve_source_id(data_dir, source_id)?;
+        self.ingest_resolved(db, data_dir, &source_id, documents).await
+    }
+
+    /// `ingest_documents` into an already resolved source
+    async fn ingest_resolved(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        documents: Vec<DocumentInput>,
     ) -> Result<IngestResponse> {
         let mut accumulator = BatchAccumulator::new(self.config.clone());
-        let mut writer = BatchWriter::new(data_dir, Arc::clone(&self.bm25_index))?;
+        let mut writer = BatchWriter::new(data_dir, Arc::clone(&self.bm25_index))?
+            .with_max_write_rows(self.config.max_write_rows)
+            .with_dedup_scope(self.dedup_scope)
+            .with_index_model(self.embedder.model_id());
         let mut total_stats = WriteStats::default();
         let mut total_skipped = 0u32;
         let mut batch_num = 0usize;
 
         // Use ProgressTracker for consistent progress reporting
-        let mut progress = ProgressTracker::new(documents.len());
+        let mut progress = ProgressTracker::new(documents.len()).with_style(self.progress_style);
 
         // Phase 1: Prepare all documents (cheap - just parsing and chunking)
         progress.start_phase(&format!("Preparing {} documents", documents.len()));
@@ -271,10 +881,20 @@ impl IngestPipeline {
                 documents_created: 0,
                 chunks_created: 0,
                 chunks_skipped: 0,
+                documents_skipped: 0,
+                files_skipped_binary: 0,
+                files_failed: Vec::new(),
+                files_lossy_utf8: Vec::new(),
                 document_ids: vec![],
             });
         }
 
+        // Old versions go first so their chunks aren't skipped as duplicates
+        if self.update_existing {
+            let paths: HashSet<&str> = prepared_docs.iter().filter_map(|d| d.file_path.as_deref()).collect();
+            self.remove_documents_for_paths(db, data_dir, source_id, &paths).await?;
+        }
+
         // Phase 2: Process with batch flushing
         for doc in prepared_docs {
             let should_flush = accumulator.add_document(doc);
@@ -299,6 +919,8 @@ impl IngestPipeline {
             total_skipped += skipped;
         }
 
+        self.peak_batch_bytes.fetch_max(accumulator.peak_memory(), Ordering::Relaxed);
+
         // Update progress with final counts
         progress.update_docs(total_stats.documents_written as usize);
         progress.update_chunks(total_stats.chunks_written as usize);
@@ -309,10 +931,77 @@ impl IngestPipeline {
             documents_created: total_stats.documents_written,
             chunks_created: total_stats.chunks_written,
             chunks_skipped: total_skipped,
+            documents_skipped: 0,
+            files_skipped_binary: 0,
+            files_failed: Vec::new(),
+            files_lossy_utf8: Vec::new(),
             document_ids: total_stats.document_ids,
         })
     }
 
+    /// Delete documents in a source that were ingested from any of `paths`
+    ///
+    /// Returns the number of documents removed.
+    async fn remove_documents_for_paths(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        paths: &HashSet<&str>,
+    ) -> Result<usize> {
+        if paths.is_empty() {
+            return Ok(0);
+        }
+        let stale: Vec<String> = ContentStore::open(&data_dir.join("content.db"))?
+            .list_documents_by_source(source_id, None, None)?
+            .0
+            .into_iter()
+            .filter(|d| d.file_path.as_deref().is_some_and(|p| paths.contains(p)))
+            .map(|d| d.id)
+            .collect();
+
+        self.remove_documents(db, data_dir, &stale).await?;
+        Ok(stale.len())
+    }
+
+    /// Delete a source's documents that were ingested from `files`
+    ///
+    /// Paths are matched exactly as they were stored at ingest time. Returns
+    /// the number of documents removed.
+    pub async fn remove_files(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        files: &[PathBuf],
+    ) -> Result<usize> {
+        let source_id = self.resolve_source_id(data_dir, source_id)?;
+        let paths: Vec<String> = files.iter().map(|f| f.to_string_lossy().into_owned()).collect();
+        let paths: HashSet<&str> = paths.iter().map(String::as_str).collect();
+        self.remove_documents_for_paths(db, data_dir, &source_id, &paths).await
+    }
+
+    /// Delete documents from all three stores
+    async fn remove_documents(&self, db: &mut VectorDB, data_dir: &Path, doc_ids: &[String]) -> Result<()> {
+        let content_db = data_dir.join("content.db");
+        for doc_id in doc_ids {
+            // Read in a block to drop ContentStore before await
+            let chunk_ids: Vec<String> = {
+                let content_store = ContentStore::open(&content_db)?;
+                let ids = content_store
+                    .get_chunks_for_document(doc_id)?
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .collect();
+                content_store.delete_document(doc_id)?;
+                ids
+            };
+            self.bm25_index.delete_chunks(&chunk_ids)?;
+            db.delete_document(doc_id).await?;
+        }
+        Ok(())
+    }
+
     /// Flush a batch: deduplicate, embed, and write to storage
     async fn flush_batch(
         &self,
@@ -335,7 +1024,10 @@ impl IngestPipeline {
         let mut chunks_skipped = 0u32;
 
         for chunk in accumulator.all_chunks() {
-            if db.chunk_exists(&chunk.content_hash).await? {
+            if db
+                .chunk_exists_in_scope(&chunk.content_hash, self.dedup_scope, source_id)
+                .await?
+            {
                 chunks_skipped += 1;
             } else {
                 chunks_to_embed.push(chunk.clone());
@@ -343,23 +1035,7 @@ impl IngestPipeline {
         }
 
         // Step 2: Generate embeddings
-        let batch_size = get_embedding_batch_size(self.embedder.device_name());
-        let mut all_embeddings: Vec<Vec<f32>> = Vec::with_capacity(chunks_to_embed.len());
-
-        for (batch_idx, batch) in chunks_to_embed.chunks(batch_size).enumerate() {
-            let texts: Vec<String> = batch.iter().map(|c| c.content.clone()).collect();
-            let embeddings = self.embedder.embed_batch(&texts).map_err(|e| {
-                eprintln!(
-                    "Embedding batch {} failed ({} texts, lengths: {:?}): {}",
-                    batch_idx,
-                    texts.len(),
-                    texts.iter().map(|t| t.len()).collect::<Vec<_>>(),
-                    e
-                );
-                e
-            })?;
-            all_embeddings.extend(embeddings);
-        }
+        let all_embeddings = self.embed_chunks(&chunks_to_embed)?;
 
         // Step 3: Write to storage
         let documents = accumulator.take_documents();
@@ -371,6 +1047,13 @@ impl IngestPipeline {
         Ok((stats, chunks_skipped))
     }
 
+    /// Embed chunk contents, spreading batches over the embedding workers
+    fn embed_chunks(&self, chunks: &[ChunkData]) -> Result<Vec<Vec<f32>>> {
+        let device = self.embedder.device_name();
+        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        embed_in_batches(&self.embedder, &texts, get_embedding_batch_size(device), get_embedding_threads(device))
+    }
+
     /// Ingest from file path (CLI)
     pub async fn ingest_from_path(
         &self,
@@ -379,65 +1062,384 @@ impl IngestPipeline {
         source_id: &str,
         file_path: &str,
     ) -> Result<IngestResponse> {
-        let path = Path::new(file_path);
+        let walk = FileWalk::new(Path::new(file_path), &WalkOptions::default())?;
+        let (response, _) = self.ingest_file_stream(db, data_dir, source_id, walk).await?;
+        Ok(response)
+    }
 
-        let files: Vec<_> = if path.is_dir() {
-            WalkDir::new(file_path)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    let p = e.path();
-                    if !p.is_file() {
-                        return false;
-                    }
-                    let ext = p
-                        .extension()
-                        .map(|e| e.to_string_lossy().to_lowercase())
-                        .unwrap_or_default();
-                    Self::is_supported_extension(&ext)
-                })
-                .map(|e| e.path().to_path_buf())
-                .collect()
-        } else {
-            vec![path.to_path_buf()]
+    /// Collect ingestible files under a path
+    ///
+    /// A single file is returned as-is. Directories are walked recursively,
+    /// keeping files with a supported extension that no exclude pattern matches.
+    pub fn collect_files(path: &Path, options: &WalkOptions) -> Result<WalkResult> {
+        let mut walk = FileWalk::new(path, options)?;
+        let files: Vec<PathBuf> = walk.by_ref().collect();
+        Ok(WalkResult { files, ..walk.into_result() })
+    }
+
+    /// Read and ingest a list of files
+    ///
+    /// Files whose content hash matches what the source already holds for
+    /// that path are skipped unless `with_force` is set.
+    pub async fn ingest_files(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        files: &[PathBuf],
+    ) -> Result<IngestResponse> {
+        let (response, _) = self.ingest_files_with_manifest(db, data_dir, source_id, files).await?;
+        Ok(response)
+    }
+
+    /// `ingest_files`, also reporting what happened to each file
+    pub async fn ingest_files_with_manifest(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        files: &[PathBuf],
+    ) -> Result<(IngestResponse, IngestManifest)> {
+        self.ingest_file_stream(db, data_dir, source_id, files.iter().cloned()).await
+    }
+
+    /// Read and ingest files one at a time as `files` yields them
+    ///
+    /// Only the current batch is held in memory: a document that would push
+    /// the batch past a `BatchConfig` threshold flushes the batch first, so
+    /// peak memory stays near `max_memory_mb` however many files there are.
+    pub async fn ingest_file_stream(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        source_id: &str,
+        mut files: impl Iterator<Item = PathBuf>,
+    ) -> Result<(IngestResponse, IngestManifest)> {
+        let source_id = self.resolve_source_id(data_dir, source_id)?;
+        let content_db = data_dir.join("content.db");
+        let mut state = FileStreamState {
+            manifest: IngestManifest::new(&source_id),
+            ..Default::default()
         };
+        let mut pending: VecDeque<PreparedDoc> = VecDeque::new();
 
-        let mut doc_inputs = Vec::new();
-        for file in &files {
-            let ext = file
-                .extension()
-                .map(|e| e.to_string_lossy().to_lowercase())
-                .unwrap_or_default();
+        let mut accumulator = BatchAccumulator::new(self.config.clone());
+        let mut writer = BatchWriter::new(data_dir, Arc::clone(&self.bm25_index))?
+            .with_max_write_rows(self.config.max_write_rows)
+            .with_dedup_scope(self.dedup_scope)
+            .with_index_model(self.embedder.model_id());
+        let mut total_stats = WriteStats::default();
+        let mut total_skipped = 0u32;
+        let mut batch_num = 0usize;
+        let mut progress = ProgressTracker::new(files.size_hint().0).with_style(self.progress_style);
 
-            let content = if ext == "pdf" {
-                // Extract text from PDF via pdf_oxide
-                match crate::chunking::extract_text_from_pdf(file) {
-                    Ok(text) if !text.trim().is_empty() => text,
-                    Ok(_) => continue, // Empty content
-                    Err(e) => {
-                        eprintln!("Warning: Failed to extract PDF {}: {}", file.display(), e);
+        loop {
+            // Fill the batch in a block to drop ContentStore before await
+            let stale = {
+                let content_store = ContentStore::open(&content_db)?;
+                let mut stale = Vec::new();
+                loop {
+                    let Some(doc) = pending.pop_front() else {
+                        let Some(file) = files.next() else {
+                            break;
+                        };
+                        pending.extend(self.read_file(&content_store, &source_id, &file, &mut state, &mut stale)?);
                         continue;
+                    };
+                    if !accumulator.is_empty() && !accumulator.has_room_for(&doc) {
+                        pending.push_front(doc);
+                        break;
+                    }
+                    if accumulator.add_document(doc) {
+                        break;
                     }
                 }
+                stale
+            };
+
+            // Old versions go first so their chunks aren't skipped as duplicates
+            self.remove_documents(db, data_dir, &stale).await?;
+            if accumulator.is_empty() {
+                break;
+            }
+
+            batch_num += 1;
+            let (stats, skipped) = self
+                .flush_batch(&mut accumulator, &mut writer, db, &source_id, batch_num, &mut progress)
+                .await?;
+            total_stats.merge(stats);
+            total_skipped += skipped;
+        }
+        self.peak_batch_bytes.fetch_max(accumulator.peak_memory(), Ordering::Relaxed);
+
+        progress.update_docs(total_stats.documents_written as usize);
+        progress.update_chunks(total_stats.chunks_written as usize);
+        if batch_num > 0 {
+            progress.complete();
+        }
+
+        let content_store = ContentStore::open(&content_db)?;
+        for (file_path, hash) in &state.file_hashes {
+            content_store.set_file_hash(&total_stats.document_ids, file_path, hash)?;
+        }
+
+        let mut manifest = state.manifest;
+        for doc_id in &total_stats.document_ids {
+            let Some((title, file_path, chunk_count)) = content_store.document_summary(doc_id)? else {
+                continue;
+            };
+            let file_path = file_path.unwrap_or_default();
+            let status = if state.replaced_paths.contains(&file_path) {
+                ManifestStatus::Updated
             } else {
-                // Read as text (existing behavior)
-                match std::fs::read_to_string(file) {
-                    Ok(c) if !c.trim().is_empty() => c,
-                    _ => continue,
-                }
+                ManifestStatus::New
             };
+            manifest.entries.push(ManifestEntry {
+                document_id: Some(doc_id.clone()),
+                source_id: source_id.clone(),
+                title: Some(title),
+                file_path,
+                chunk_count,
+                status,
+                reason: None,
+            });
+        }
+        // Stable order so manifests from different runs diff cleanly
+        manifest.entries.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let response = IngestResponse {
+            source_id: source_id.clone(),
+            documents_created: total_stats.documents_written,
+            chunks_created: total_stats.chunks_written,
+            chunks_skipped: total_skipped,
+            documents_skipped: state.documents_skipped,
+            files_skipped_binary: state.files_skipped_binary,
+            files_failed: state.files_failed,
+            files_lossy_utf8: state.files_lossy_utf8,
+            document_ids: total_stats.document_ids,
+        };
+        Ok((response, manifest))
+    }
+
+    /// Read one file into prepared documents
+    ///
+    /// Unchanged, empty, binary and unreadable files yield nothing and are
+    /// recorded as skipped. Invalid UTF-8 is decoded lossily and the file
+    /// noted. With `with_update`, IDs of the documents the file replaces are
+    /// pushed to `stale`.
+    fn read_file(
+        &self,
+        content_store: &ContentStore,
+        source_id: &str,
+        file: &Path,
+        state: &mut FileStreamState,
+        stale: &mut Vec<String>,
+    ) -> Result<Vec<PreparedDoc>> {
+        let ext = file
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let file_path = file.to_string_lossy().to_string();
+        let skipped_entry = |file_path: &str, reason: SkipReason| ManifestEntry {
+            document_id: None,
+            source_id: source_id.to_string(),
+            title: None,
+            file_path: file_path.to_string(),
+            chunk_count: 0,
+            status: ManifestStatus::Skipped,
+            reason: Some(reason),
+        };
+
+        // Blocklisted extensions are skipped without reading the file
+        if ext != "pdf" && binary::has_binary_extension(file) {
+            state.files_skipped_binary += 1;
+            state.manifest.entries.push(skipped_entry(&file_path, SkipReason::Binary));
+            return Ok(Vec::new());
+        }
+        let bytes = match std::fs::read(file) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                state.files_failed.push(FailedFile { file_path: file_path.clone(), error: e.to_string() });
+                state.manifest.entries.push(skipped_entry(&file_path, SkipReason::Unreadable));
+                return Ok(Vec::new());
+            }
+        };
+        if ext != "pdf" && binary::looks_binary(&bytes) {
+            state.files_skipped_binary += 1;
+            state.manifest.entries.push(skipped_entry(&file_path, SkipReason::Binary));
+            return Ok(Vec::new());
+        }
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        if !self.force && content_store.has_file_hash(source_id, &file_path, &hash)? {
+            state.documents_skipped += 1;
+            for (id, title, chunk_count) in content_store.documents_for_path(source_id, &file_path)? {
+                state.manifest.entries.push(ManifestEntry {
+                    document_id: Some(id),
+                    title: Some(title),
+                    chunk_count,
+                    ..skipped_entry(&file_path, SkipReason::Unchanged)
+                });
+            }
+            return Ok(Vec::new());
+        }
+
+        // Whether the document text is byte-for-byte the file
+        let mut verbatim = ext != "pdf";
+        let content = if ext == "pdf" {
+            // Extract text from PDF via pdf_oxide
+            crate::chunking::extract_text_from_pdf(file).map_err(|e| {
+                eprintln!("Warning: Failed to extract PDF {}: {}", file.display(), e);
+                state.files_failed.push(FailedFile { file_path: file_path.clone(), error: e.to_string() });
+                SkipReason::Unreadable
+            })
+        } else {
+            // Invalid UTF-8 (e.g. latin-1 logs) is decoded lossily rather than dropped
+            Ok(match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("Warning: {} is not valid UTF-8; invalid bytes replaced with U+FFFD", file.display());
+                    state.files_lossy_utf8.push(file_path.clone());
+                    verbatim = false;
+                    String::from_utf8_lossy(e.as_bytes()).into_owned()
+                }
+            })
+        };
+        let content = match content {
+            Ok(content) if !content.trim().is_empty() => content,
+            other => {
+                let reason = other.err().unwrap_or(SkipReason::Empty);
+                state.manifest.entries.push(skipped_entry(&file_path, reason));
+                return Ok(Vec::new());
+            }
+        };
+
+        if self.update_existing {
+            let old = content_store.documents_for_path(source_id, &file_path)?;
+            if !old.is_empty() {
+                state.replaced_paths.insert(file_path.clone());
+                stale.extend(old.into_iter().map(|(id, _, _)| id));
+            }
+        }
+        state.file_hashes.push((file_path.clone(), hash));
+        let file_name = file.file_name().map(|n| n.to_string_lossy().to_string());
+        let file_path = Some(file_path);
 
-            doc_inputs.push(DocumentInput {
+        // Link mode: a whole-file document only needs the file's absolute path,
+        // since it was just read from there
+        let split = self.split_sections.is_some() && matches!(ext.as_str(), "md" | "markdown");
+        let linked_path = (self.link_files && verbatim && !split)
+            .then(|| std::fs::canonicalize(file).ok())
+            .flatten()
+            .map(|path| path.to_string_lossy().to_string());
+
+        let inputs = match (self.split_sections, ext.as_str()) {
+            (Some(level), "md" | "markdown") => crate::chunking::split_sections(&content, level)
+                .into_iter()
+                .map(|section| DocumentInput {
+                    content: section.content,
+                    title: section.title.or_else(|| file_name.clone()),
+                    file_path: file_path.clone(),
+                    is_pdf: false,
+                })
+                .collect(),
+            _ => vec![DocumentInput {
                 content,
-                title: file.file_name().map(|n| n.to_string_lossy().to_string()),
-                file_path: Some(file.to_string_lossy().to_string()),
+                title: file_name,
+                file_path,
                 is_pdf: false, // Already extracted if it was a PDF
-            });
+            }],
+        };
+
+        Ok(inputs
+            .iter()
+            .filter_map(|input| self.prepare_document(input, source_id))
+            .map(|mut doc| {
+                if let Some(path) = &linked_path {
+                    doc.linked = true;
+                    doc.file_path = Some(path.clone());
+                }
+                doc
+            })
+            .collect())
+    }
+
+    /// Largest batch held in memory by this pipeline so far (estimated bytes)
+    pub fn peak_batch_memory(&self) -> usize {
+        self.peak_batch_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Re-chunk and re-embed a single document from its stored content
+    ///
+    /// Replaces the document's chunks in all three stores while keeping its
+    /// ID, source, title and bookmarks. Returns `None` if it doesn't exist.
+    pub async fn reembed_document(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        doc_id: &str,
+    ) -> Result<Option<IngestResponse>> {
+        let Some(record) = db.get_document(doc_id).await? else {
+            return Ok(None);
+        };
+
+        // Read in a block to drop ContentStore before await
+        let (content, linked, old_chunk_ids) = {
+            let content_store = ContentStore::open(&data_dir.join("content.db"))?;
+            let Some(content) = content_store.get_document(doc_id)? else {
+                return Ok(None);
+            };
+            let ids: Vec<String> = content_store
+                .get_chunks_for_document(doc_id)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            (content, content_store.is_linked(doc_id)?, ids)
+        };
+
+        let input = DocumentInput {
+            content,
+            title: Some(record.title.clone()),
+            file_path: record.file_path.clone(),
+            is_pdf: false,
+        };
+        let mut doc = self
+            .prepare_document_with_id(doc_id.to_string(), &input, &record.source_id)
+            .ok_or_else(|| anyhow::anyhow!("Document '{}' has no content to re-embed", doc_id))?;
+        doc.linked = linked;
+
+        // Embed every chunk up front so a failure leaves the old chunks intact
+        let batch_size = get_embedding_batch_size(self.embedder.device_name());
+        let mut embeddings: Vec<Vec<f32>> = Vec::with_capacity(doc.chunks.len());
+        for batch in doc.chunks.chunks(batch_size) {
+            let texts: Vec<String> = batch.iter().map(|c| c.content.clone()).collect();
+            embeddings.extend(self.embedder.embed_passages(&texts)?);
         }
 
-        self.ingest_documents(db, data_dir, source_id, doc_inputs)
-            .await
+        let updated = DocumentRecord {
+            chunk_count: doc.chunks.len() as u32,
+            content_length: doc.content_length,
+            ..record
+        };
+
+        let mut writer = BatchWriter::new(data_dir, Arc::clone(&self.bm25_index))?
+            .with_max_write_rows(self.config.max_write_rows)
+            .with_dedup_scope(self.dedup_scope)
+            .with_index_model(self.embedder.model_id());
+        let stats = writer
+            .replace_document_chunks(db, &updated, &doc, &old_chunk_ids, &embeddings)
+            .await?;
+
+        Ok(Some(IngestResponse {
+            source_id: updated.source_id,
+            documents_created: 0,
+            chunks_created: stats.chunks_written,
+            chunks_skipped: doc.chunks.len() as u32 - stats.chunks_written,
+            documents_skipped: 0,
+            files_skipped_binary: 0,
+            files_failed: Vec::new(),
+            files_lossy_utf8: Vec::new(),
+            document_ids: stats.document_ids,
+        }))
     }
 
     /// Prepare documents and generate embeddings WITHOUT needing DB access
@@ -471,22 +1473,7 @@ impl IngestPipeline {
             .collect();
 
         // Step 3: Generate embeddings (the slow part - no lock needed!)
-        let batch_size = get_embedding_batch_size(self.embedder.device_name());
-        let mut all_embeddings: Vec<Vec<f32>> = Vec::with_capacity(all_chunks.len());
-        for (batch_idx, batch) in all_chunks.chunks(batch_size).enumerate() {
-            let texts: Vec<String> = batch.iter().map(|c| c.content.clone()).collect();
-            let embeddings = self.embedder.embed_batch(&texts).map_err(|e| {
-                eprintln!(
-                    "Embedding batch {} failed ({} texts, lengths: {:?}): {}",
-                    batch_idx,
-                    texts.len(),
-                    texts.iter().map(|t| t.len()).collect::<Vec<_>>(),
-                    e
-                );
-                e
-            })?;
-            all_embeddings.extend(embeddings);
-        }
+        let all_embeddings = self.embed_chunks(&all_chunks)?;
 
         Ok(EmbeddedBatch {
             source_id: source_id.to_string(),
@@ -503,7 +1490,10 @@ impl IngestPipeline {
         db: &mut VectorDB,
         batch: EmbeddedBatch,
     ) -> Result<IngestResponse> {
-        let mut writer = BatchWriter::new(&batch.data_dir, Arc::clone(&self.bm25_index))?;
+        let mut writer = BatchWriter::new(&batch.data_dir, Arc::clone(&self.bm25_index))?
+            .with_max_write_rows(self.config.max_write_rows)
+            .with_dedup_scope(self.dedup_scope)
+            .with_index_model(self.embedder.model_id());
 
         // Filter out duplicate chunks
         let mut chunks_to_write: Vec<&ChunkData> = Vec::new();
@@ -511,7 +1501,10 @@ impl IngestPipeline {
         let mut chunks_skipped = 0u32;
 
         for (chunk, embedding) in batch.chunks.iter().zip(batch.embeddings.iter()) {
-            if db.chunk_exists(&chunk.content_hash).await? {
+            if db
+                .chunk_exists_in_scope(&chunk.content_hash, self.dedup_scope, &batch.source_id)
+                .await?
+            {
                 chunks_skipped += 1;
             } else {
                 chunks_to_write.push(chunk);
@@ -538,7 +1531,407 @@ impl IngestPipeline {
             documents_created: stats.documents_written,
             chunks_created: stats.chunks_written,
             chunks_skipped,
+            documents_skipped: 0,
+            files_skipped_binary: 0,
+            files_failed: Vec::new(),
+            files_lossy_utf8: Vec::new(),
             document_ids: stats.document_ids,
         })
     }
+
+    /// Chunk and embed a stored document under its existing ID (no lock needed)
+    ///
+    /// The counterpart of `prepare_and_embed` for re-indexing: write the
+    /// result with `replace_embedded_batch` so the SQLite document row, and
+    /// with it bookmarks, pins, tags and the file hash, is kept.
+    pub fn prepare_and_embed_existing(&self, data_dir: &Path, row: &DocumentRow) -> Result<EmbeddedBatch> {
+        let input = DocumentInput {
+            content: row.content.clone(),
+            title: Some(row.title.clone()),
+            file_path: row.file_path.clone(),
+            is_pdf: false,
+        };
+        let documents: Vec<PreparedDoc> = self
+            .prepare_document_with_id(row.id.clone(), &input, &row.source_id)
+            .map(|doc| PreparedDoc { created_at: row.created_at.clone(), ..doc })
+            .into_iter()
+            .collect();
+        let chunks: Vec<ChunkData> = documents.iter().flat_map(|d| d.chunks.clone()).collect();
+        let embeddings = self.embed_chunks(&chunks)?;
+
+        Ok(EmbeddedBatch {
+            source_id: row.source_id.clone(),
+            data_dir: data_dir.to_path_buf(),
+            documents,
+            chunks,
+            embeddings,
+        })
+    }
+
+    /// Write a batch from `prepare_and_embed_existing`, replacing each
+    /// document's chunks in all three stores
+    pub async fn replace_embedded_batch(&self, db: &mut VectorDB, batch: EmbeddedBatch) -> Result<IngestResponse> {
+        let mut writer = BatchWriter::new(&batch.data_dir, Arc::clone(&self.bm25_index))?
+            .with_max_write_rows(self.config.max_write_rows)
+            .with_dedup_scope(self.dedup_scope)
+            .with_index_model(self.embedder.model_id());
+        let content_db = batch.data_dir.join("content.db");
+
+        let mut total_stats = WriteStats::default();
+        let mut chunks_skipped = 0u32;
+        let mut offset = 0;
+        for doc in &batch.documents {
+            let embeddings = &batch.embeddings[offset..offset + doc.chunks.len()];
+            offset += doc.chunks.len();
+
+            // Read in a block to drop ContentStore before await
+            let old_chunk_ids: Vec<String> = {
+                let content_store = ContentStore::open(&content_db)?;
+                content_store
+                    .get_chunks_for_document(&doc.id)?
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .collect()
+            };
+            let record = DocumentRecord {
+                id: doc.id.clone(),
+                source_id: batch.source_id.clone(),
+                title: doc.title.clone(),
+                file_path: doc.file_path.clone(),
+                created_at: doc.created_at.clone(),
+                chunk_count: doc.chunks.len() as u32,
+                content_length: doc.content_length,
+            };
+            let stats = writer
+                .replace_document_chunks(db, &record, doc, &old_chunk_ids, embeddings)
+                .await?;
+            chunks_skipped += doc.chunks.len() as u32 - stats.chunks_written;
+            total_stats.merge(stats);
+        }
+
+        Ok(IngestResponse {
+            source_id: batch.source_id,
+            documents_created: 0,
+            chunks_created: total_stats.chunks_written,
+            chunks_skipped,
+            documents_skipped: 0,
+            files_skipped_binary: 0,
+            files_failed: Vec::new(),
+            files_lossy_utf8: Vec::new(),
+            document_ids: total_stats.document_ids,
+        })
+    }
+
+    /// Rebuild a stored document's vectors and BM25 entries under its existing ID
+    pub async fn reindex_document(&self, db: &mut VectorDB, data_dir: &Path, row: &DocumentRow) -> Result<IngestResponse> {
+        let batch = self.prepare_and_embed_existing(data_dir, row)?;
+        self.replace_embedded_batch(db, batch).await
+    }
+
+    /// Confirm that written documents reached every store, repairing any
+    /// that stopped part way
+    ///
+    /// Checks in write order: the SQLite document row, then the LanceDB
+    /// document row and a vector for each chunk (unless deduplicated against
+    /// identical content already stored), then a BM25 entry for each chunk
+    /// with a vector. An incomplete document is rebuilt from SQLite, the
+    /// source of truth, and checked again. Call this before reporting a
+    /// document as done (e.g. marking a queued job completed); it fails if a
+    /// document never reached SQLite or can't be repaired.
+    pub async fn commit_barrier(
+        &self,
+        db: &mut VectorDB,
+        data_dir: &Path,
+        document_ids: &[String],
+    ) -> Result<()> {
+        // Tantivy buffers writes until a commit
+        self.bm25_index.commit()?;
+
+        for doc_id in document_ids {
+            let Some(gap) = self.find_write_gap(db, data_dir, doc_id).await? else {
+                continue;
+            };
+            eprintln!("Warning: document {} is {}; rebuilding it from the content store", doc_id, gap);
+
+            let row = ContentStore::open(&data_dir.join("content.db"))?
+                .get_document_row(doc_id)?
+                .ok_or_else(|| anyhow::anyhow!("Document {} content is unreadable, cannot repair", doc_id))?;
+            self.reindex_document(db, data_dir, &row).await?;
+            self.bm25_index.commit()?;
+
+            if let Some(gap) = self.find_write_gap(db, data_dir, doc_id).await? {
+                anyhow::bail!("Document {} is still {} after repair", doc_id, gap);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describe what a document lacks in LanceDB or BM25, or `None` if complete
+    async fn find_write_gap(&self, db: &VectorDB, data_dir: &Path, doc_id: &str) -> Result<Option<String>> {
+        // Step 1: SQLite (source of truth), nothing to repair from if missing
+        let chunks = {
+            let content_store = ContentStore::open(&data_dir.join("content.db"))?;
+            if content_store.document_summary(doc_id)?.is_none() {
+                anyhow::bail!("Document {} not committed to content store", doc_id);
+            }
+            content_store.get_chunks_for_document(doc_id)?
+        };
+
+        // Step 2: LanceDB (document row and chunk vectors)
+        let Some(record) = db.get_document(doc_id).await? else {
+            return Ok(Some("missing from the vector store".to_string()));
+        };
+        let with_vectors = db.chunk_ids_with_vectors(doc_id).await?;
+        let mut missing_vectors = 0;
+        for (chunk_id, content) in &chunks {
+            if with_vectors.contains(chunk_id) {
+                continue;
+            }
+            let hash = format!("{:x}", md5::compute(content.as_bytes()));
+            if !db.chunk_exists_in_scope(&hash, self.dedup_scope, &record.source_id).await? {
+                missing_vectors += 1;
+            }
+        }
+        if missing_vectors > 0 {
+            return Ok(Some(format!("missing {} of {} chunk vectors", missing_vectors, chunks.len())));
+        }
+
+        // Step 3: Tantivy (a BM25 entry for every chunk with a vector)
+        let with_vectors: Vec<String> = with_vectors.into_iter().collect();
+        let indexed = self.bm25_index.indexed_chunk_ids(&with_vectors)?;
+        if indexed.len() < with_vectors.len() {
+            return Ok(Some(format!(
+                "missing {} of {} BM25 entries",
+                with_vectors.len() - indexed.len(),
+                with_vectors.len()
+            )));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Name a requested source should be stored under, plus any warning
+///
+/// Exact matches always win. Otherwise an existing source matching ignoring
+/// case is reused (`Insensitive`) or reported (`Warn`).
+pub fn resolve_source_name(
+    existing: &[String],
+    requested: &str,
+    mode: SourceNameCase,
+) -> (String, Option<String>) {
+    if mode == SourceNameCase::Exact || existing.iter().any(|s| s == requested) {
+        return (requested.to_string(), None);
+    }
+    let Some(canonical) = existing.iter().find(|s| s.eq_ignore_ascii_case(requested)) else {
+        return (requested.to_string(), None);
+    };
+
+    match mode {
+        SourceNameCase::Insensitive => (canonical.clone(), None),
+        _ => (
+            requested.to_string(),
+            Some(format!(
+                "source '{}' differs only by case from existing source '{}' (set source_name_case = \"insensitive\" to merge them)",
+                requested, canonical
+            )),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_source_name_by_mode() {
+        let existing = vec!["Docs".to_string(), "notes".to_string()];
+
+        let (name, warning) = resolve_source_name(&existing, "docs", SourceNameCase::Insensitive);
+        assert_eq!((name.as_str(), warning), ("Docs", None));
+
+        let (name, warning) = resolve_source_name(&existing, "docs", SourceNameCase::Warn);
+        assert_eq!(name, "docs");
+        assert!(warning.unwrap().contains("'Docs'"));
+
+        assert_eq!(resolve_source_name(&existing, "docs", SourceNameCase::Exact), ("docs".to_string(), None));
+        assert_eq!(resolve_source_name(&existing, "notes", SourceNameCase::Warn), ("notes".to_string(), None));
+        assert_eq!(resolve_source_name(&existing, "papers", SourceNameCase::Insensitive), ("papers".to_string(), None));
+    }
+
+    fn touch(root: &Path, rel: &str) {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "content").unwrap();
+    }
+
+    #[test]
+    fn test_collect_files_excludes_patterns() {
+        let dir = tempdir().unwrap();
+        touch(dir.path(), "notes.md");
+        touch(dir.path(), "src/main.rs");
+        touch(dir.path(), "target/debug/build.rs");
+        touch(dir.path(), "drafts/a.md");
+        touch(dir.path(), "drafts/b.md");
+
+        // `target` is also on the built-in ignore list
+        let options = WalkOptions {
+            exclude: vec!["target".to_string(), "drafts/*.md".to_string(), "*.json".to_string()],
+            no_ignore: true,
+            ..Default::default()
+        };
+        let walk = IngestPipeline::collect_files(dir.path(), &options).unwrap();
+
+        let mut names: Vec<String> = walk
+            .files
+            .iter()
+            .map(|f| f.strip_prefix(dir.path()).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["notes.md", "src/main.rs"]);
+
+        assert_eq!(
+            walk.excluded,
+            vec![
+                ("target".to_string(), 1),
+                ("drafts/*.md".to_string(), 2),
+                ("*.json".to_string(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_files_rejects_invalid_pattern() {
+        let dir = tempdir().unwrap();
+        let options = WalkOptions {
+            exclude: vec!["[unclosed".to_string()],
+            ..Default::default()
+        };
+        assert!(IngestPipeline::collect_files(dir.path(), &options).is_err());
+    }
+
+    fn relative_names(root: &Path, files: &[PathBuf]) -> Vec<String> {
+        let mut names: Vec<String> = files
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_skips_hidden_and_symlinks_by_default() {
+        let dir = tempdir().unwrap();
+        touch(dir.path(), "notes.md");
+        touch(dir.path(), ".secret.md");
+        touch(dir.path(), ".github/workflow.md");
+        let outside = tempdir().unwrap();
+        touch(outside.path(), "linked.md");
+        std::os::unix::fs::symlink(outside.path().join("linked.md"), dir.path().join("link.md")).unwrap();
+
+        let walk = IngestPipeline::collect_files(dir.path(), &WalkOptions::default()).unwrap();
+        assert_eq!(relative_names(dir.path(), &walk.files), vec!["notes.md"]);
+        assert_eq!(relative_names(dir.path(), &walk.hidden), vec![".github", ".secret.md"]);
+        assert_eq!(walk.symlinks, vec![(dir.path().join("link.md"), outside.path().join("linked.md"))]);
+
+        let options = WalkOptions {
+            include_hidden: true,
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let walk = IngestPipeline::collect_files(dir.path(), &options).unwrap();
+        assert_eq!(
+            relative_names(dir.path(), &walk.files),
+            vec![".github/workflow.md", ".secret.md", "link.md", "notes.md"]
+        );
+        assert!(walk.hidden.is_empty() && walk.symlinks.is_empty());
+    }
+
+    #[test]
+    fn test_collect_files_respects_ignore_files() {
+        let dir = tempdir().unwrap();
+        touch(dir.path(), "notes.md");
+        touch(dir.path(), "build.log.md");
+        touch(dir.path(), "target/debug/build.rs");
+        touch(dir.path(), "web/node_modules/pkg/readme.md");
+        touch(dir.path(), "web/app.js");
+        touch(dir.path(), "web/dist/bundle.js");
+        touch(dir.path(), "web/dist/keep.js");
+        touch(dir.path(), "scripts/__pycache__/mod.py");
+        std::fs::write(dir.path().join(".gitignore"), "*.log.md\n").unwrap();
+        std::fs::write(dir.path().join("web/.gitignore"), "dist/*\n").unwrap();
+        std::fs::write(dir.path().join("web/.eywaignore"), "!dist/keep.js\n").unwrap();
+
+        let walk = IngestPipeline::collect_files(dir.path(), &WalkOptions::default()).unwrap();
+        assert_eq!(relative_names(dir.path(), &walk.files), vec!["notes.md", "web/app.js", "web/dist/keep.js"]);
+        assert_eq!(
+            relative_names(dir.path(), &walk.ignored),
+            vec!["build.log.md", "scripts/__pycache__", "target", "web/dist/bundle.js", "web/node_modules"]
+        );
+
+        let options = WalkOptions {
+            no_ignore: true,
+            ..Default::default()
+        };
+        let walk = IngestPipeline::collect_files(dir.path(), &options).unwrap();
+        assert_eq!(walk.files.len(), 8);
+        assert!(walk.ignored.is_empty());
+    }
+
+    #[test]
+    fn test_walk_includes_agrees_with_walk() {
+        let dir = tempdir().unwrap();
+        for rel in [
+            "notes.md",
+            "build.log.md",
+            ".secret.md",
+            "data.bin",
+            "drafts/todo.md",
+            "target/debug/build.rs",
+            "web/app.js",
+            "web/dist/bundle.js",
+            "web/dist/keep.js",
+        ] {
+            touch(dir.path(), rel);
+        }
+        std::fs::write(dir.path().join(".gitignore"), "*.log.md\n").unwrap();
+        std::fs::write(dir.path().join("web/.gitignore"), "dist/*\n").unwrap();
+        std::fs::write(dir.path().join("web/.eywaignore"), "!dist/keep.js\n").unwrap();
+        let options = WalkOptions {
+            exclude: vec!["drafts".to_string()],
+            ..Default::default()
+        };
+
+        let walk = IngestPipeline::collect_files(dir.path(), &options).unwrap();
+        let included: Vec<PathBuf> = WalkDir::new(dir.path())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| walk_includes(dir.path(), p, &options))
+            .collect();
+        assert_eq!(relative_names(dir.path(), &included), relative_names(dir.path(), &walk.files));
+        assert!(!walk_includes(dir.path(), &dir.path().join("web"), &options));
+        assert!(!walk_includes(dir.path(), Path::new("/elsewhere/notes.md"), &options));
+        // Deleted files are judged by their path alone
+        assert!(walk_includes(dir.path(), &dir.path().join("gone.md"), &options));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_detects_symlink_cycles() {
+        let dir = tempdir().unwrap();
+        touch(dir.path(), "docs/guide.md");
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("docs/loop")).unwrap();
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let walk = IngestPipeline::collect_files(dir.path(), &options).unwrap();
+        assert_eq!(relative_names(dir.path(), &walk.files), vec!["docs/guide.md"]);
+        assert_eq!(walk.cycles, vec![dir.path().join("docs/loop")]);
+    }
 }
@@ -2,7 +2,40 @@
 //!
 //! Provides real-time progress updates during document ingestion.
 
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+
+/// How progress updates are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressStyle {
+    /// Redraw a single line in place with `\r` (interactive terminals)
+    #[default]
+    InPlace,
+    /// One plain line per update (CI logs, pipes)
+    Lines,
+    /// No progress output (`--quiet`)
+    Hidden,
+}
+
+impl ProgressStyle {
+    /// In-place on a terminal, plain lines when disabled or stdout isn't a TTY
+    pub fn detect(no_progress: bool) -> Self {
+        if no_progress || !io::stdout().is_terminal() {
+            Self::Lines
+        } else {
+            Self::InPlace
+        }
+    }
+}
+
+/// Write one progress update in the given style
+pub fn write_progress<W: Write>(out: &mut W, style: ProgressStyle, message: &str) -> io::Result<()> {
+    match style {
+        ProgressStyle::InPlace => write!(out, "\r  {}\x1b[K", message)?,
+        ProgressStyle::Lines => writeln!(out, "  {}", message)?,
+        ProgressStyle::Hidden => return Ok(()),
+    }
+    out.flush()
+}
 
 /// Tracks and displays progress during ingestion
 pub struct ProgressTracker {
@@ -16,6 +49,8 @@ pub struct ProgressTracker {
     current_phase: Option<String>,
     /// Whether to show output (false for tests/quiet mode)
     show_output: bool,
+    /// How phases are printed
+    style: ProgressStyle,
 }
 
 impl ProgressTracker {
@@ -27,6 +62,7 @@ impl ProgressTracker {
             processed_chunks: 0,
             current_phase: None,
             show_output: true,
+            style: ProgressStyle::default(),
         }
     }
 
@@ -38,22 +74,39 @@ impl ProgressTracker {
             processed_chunks: 0,
             current_phase: None,
             show_output: false,
+            style: ProgressStyle::default(),
         }
     }
 
+    /// Set how phases are printed
+    pub fn with_style(mut self, style: ProgressStyle) -> Self {
+        self.style = style;
+        self
+    }
+
     /// Start a new phase of processing
     pub fn start_phase(&mut self, phase: &str) {
         self.current_phase = Some(phase.to_string());
         if self.show_output {
-            eprint!("  {}... ", phase);
+            match self.style {
+                ProgressStyle::InPlace => eprint!("  {}... ", phase),
+                ProgressStyle::Lines => eprintln!("  {}...", phase),
+                ProgressStyle::Hidden => {}
+            }
             let _ = io::stderr().flush();
         }
     }
 
     /// Finish the current phase
     pub fn finish_phase(&mut self) {
-        if self.show_output && self.current_phase.is_some() {
-            eprintln!("done");
+        if self.show_output {
+            if let Some(phase) = &self.current_phase {
+                match self.style {
+                    ProgressStyle::InPlace => eprintln!("done"),
+                    ProgressStyle::Lines => eprintln!("  {} done", phase),
+                    ProgressStyle::Hidden => {}
+                }
+            }
         }
         self.current_phase = None;
     }
@@ -80,10 +133,10 @@ impl ProgressTracker {
 
     /// Display final completion message
     pub fn complete(&self) {
-        if self.show_output {
+        if self.show_output && self.style != ProgressStyle::Hidden {
             eprintln!(
                 "  Completed: {} docs, {} chunks",
-                self.total_docs, self.processed_chunks
+                self.processed_docs, self.processed_chunks
             );
         }
     }
@@ -104,6 +157,25 @@ mod tests {
         assert_eq!(tracker.chunks_processed(), 50);
     }
 
+    #[test]
+    fn test_line_progress_has_no_carriage_returns() {
+        let mut out = Vec::new();
+        for i in 1..=3 {
+            write_progress(&mut out, ProgressStyle::Lines, &format!("[{}/3] doc {}", i, i)).unwrap();
+        }
+        let text = String::from_utf8(out).unwrap();
+        assert!(!text.contains('\r'));
+        assert_eq!(text.lines().count(), 3);
+
+        let mut out = Vec::new();
+        write_progress(&mut out, ProgressStyle::InPlace, "[1/3] doc 1").unwrap();
+        assert!(out.starts_with(b"\r"));
+
+        let mut out = Vec::new();
+        write_progress(&mut out, ProgressStyle::Hidden, "[1/3] doc 1").unwrap();
+        assert!(out.is_empty());
+    }
+
     #[test]
     fn test_phases() {
         let mut tracker = ProgressTracker::quiet(10);
@@ -3,9 +3,24 @@
 //! Writes documents and chunks in large batches to avoid LanceDB fragmentation.
 //! Ensures atomicity by writing to SQLite first (content), then LanceDB (vectors),
 //! then Tantivy (BM25 index).
+//!
+//! Recovery semantics:
+//! - SQLite is the source of truth; LanceDB and BM25 can be rebuilt from it.
+//! - A crash after phase 1 leaves content that no search finds.
+//! - A crash after LanceDB but before Tantivy leaves documents that are found
+//!   by vector search only.
+//! - Job queue entries are only marked completed after
+//!   `IngestPipeline::commit_barrier` passes. It checks each document's
+//!   LanceDB row, chunk vectors and BM25 entries, and rebuilds any that are
+//!   missing from SQLite. A crash before that leaves the entry in progress,
+//!   so it is retried on restart; a document missing from SQLite, or still
+//!   incomplete after the rebuild, marks it failed.
+//! - `eywa reindex` rebuilds every document the same way.
 
 use super::{ChunkData, PreparedDoc};
 use crate::bm25::{BM25Index, ChunkInput};
+use crate::chunking::stored_lines;
+use crate::config::DedupScope;
 use crate::content::ContentStore;
 use crate::db::{ChunkRecord, VectorDB};
 use crate::types::DocumentRecord;
@@ -39,6 +54,12 @@ pub struct BatchWriter {
     content_db_path: PathBuf,
     /// BM25 index for keyword search
     bm25_index: Arc<BM25Index>,
+    /// Maximum rows per LanceDB append
+    max_write_rows: usize,
+    /// Which existing chunks count as duplicates
+    dedup_scope: DedupScope,
+    /// Embedding model recorded as having built the index
+    index_model: Option<String>,
 }
 
 impl BatchWriter {
@@ -47,9 +68,30 @@ impl BatchWriter {
         Ok(Self {
             content_db_path: data_dir.join("content.db"),
             bm25_index,
+            max_write_rows: usize::MAX,
+            dedup_scope: DedupScope::default(),
+            index_model: None,
         })
     }
 
+    /// Split chunk writes into LanceDB appends of at most `rows` rows
+    pub fn with_max_write_rows(mut self, rows: usize) -> Self {
+        self.max_write_rows = rows.max(1);
+        self
+    }
+
+    /// Set the dedup scope used when replacing a document's chunks
+    pub fn with_dedup_scope(mut self, scope: DedupScope) -> Self {
+        self.dedup_scope = scope;
+        self
+    }
+
+    /// Record which embedding model produced the vectors being written
+    pub fn with_index_model(mut self, model_id: impl Into<String>) -> Self {
+        self.index_model = Some(model_id.into());
+        self
+    }
+
     /// Write a batch of documents and chunks to storage
     ///
     /// Order of operations:
@@ -78,15 +120,28 @@ impl BatchWriter {
             let content_store = ContentStore::open(&self.content_db_path)?;
 
             for doc in &documents {
-                // Insert document content with full metadata
-                content_store.insert_document(
-                    &doc.id,
-                    source_id,
-                    &doc.title,
-                    doc.file_path.as_deref(),
-                    &doc.content,
-                    &doc.created_at,
-                )?;
+                // Insert document content with full metadata (or just the path if linked)
+                match doc.file_path.as_deref() {
+                    Some(file_path) if doc.linked => content_store.insert_linked_document(
+                        &doc.id,
+                        source_id,
+                        &doc.title,
+                        file_path,
+                        &doc.created_at,
+                    )?,
+                    _ => content_store.insert_document(
+                        &doc.id,
+                        source_id,
+                        &doc.title,
+                        doc.file_path.as_deref(),
+                        &doc.content,
+                        &doc.created_at,
+                    )?,
+                }
+
+                if !doc.tags.is_empty() {
+                    content_store.set_document_tags(&doc.id, &doc.tags)?;
+                }
 
                 // Collect chunk contents for this document
                 let chunk_contents: Vec<(String, String, String)> = doc
@@ -95,10 +150,15 @@ impl BatchWriter {
                     .map(|c| (c.id.clone(), c.document_id.clone(), c.content.clone()))
                     .collect();
 
-                if !chunk_contents.is_empty() {
+                if doc.linked {
+                    content_store.insert_linked_chunks(&doc.content, &chunk_contents)?;
+                } else if !chunk_contents.is_empty() {
                     content_store.insert_chunks(&chunk_contents)?;
                 }
             }
+            if let Some(model_id) = &self.index_model {
+                content_store.record_index_model(model_id)?;
+            }
             // content_store is dropped here
         }
 
@@ -120,43 +180,167 @@ impl BatchWriter {
 
         // Phase 3: Write chunk vectors to LanceDB in large batches
         if !chunks.is_empty() && !embeddings.is_empty() {
-            let chunk_records: Vec<ChunkRecord> = chunks
-                .iter()
-                .map(|c| ChunkRecord {
-                    id: c.id.clone(),
-                    document_id: c.document_id.clone(),
-                    source_id: c.source_id.clone(),
-                    title: c.title.clone(),
-                    file_path: c.file_path.clone(),
-                    line_start: Some(c.line_start),
-                    line_end: Some(c.line_end),
-                    content_hash: c.content_hash.clone(),
-                    // Preserve hierarchical metadata from smart chunking
-                    section: c.section.clone(),
-                    subsection: c.subsection.clone(),
-                    hierarchy: c.hierarchy.clone(),
-                    has_code: c.has_code,
-                })
-                .collect();
+            let chunk_records: Vec<ChunkRecord> = chunks.iter().map(chunk_record).collect();
 
-            // Write all chunks in one batch to avoid fragmentation
-            db.insert_chunks(&chunk_records, embeddings).await?;
+            // Write in as few appends as possible to avoid fragmentation, but
+            // split very large batches to bound peak memory
+            for (records, vectors) in chunk_records
+                .chunks(self.max_write_rows)
+                .zip(embeddings.chunks(self.max_write_rows))
+            {
+                db.insert_chunks(records, vectors).await?;
+            }
             stats.chunks_written = chunks.len() as u32;
 
             // Phase 4: Index chunks in Tantivy for BM25 search
-            let chunk_inputs: Vec<ChunkInput> = chunks
+            let chunk_inputs: Vec<ChunkInput> = chunks.iter().map(bm25_input).collect();
+
+            self.bm25_index.index_chunks(&chunk_inputs)?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Replace an existing document's chunks in all three stores
+    ///
+    /// Unlike `write_batch`, the SQLite document row (and with it bookmarks)
+    /// is kept; only its chunks and tags change. `chunks` must hold every
+    /// chunk of `doc` with matching `embeddings`: deduplication against other
+    /// documents happens here, after this document's old vectors are gone.
+    pub async fn replace_document_chunks(
+        &mut self,
+        db: &mut VectorDB,
+        record: &DocumentRecord,
+        doc: &PreparedDoc,
+        old_chunk_ids: &[String],
+        embeddings: &[Vec<f32>],
+    ) -> Result<WriteStats> {
+        // Phase 1: SQLite chunks and tags
+        {
+            let content_store = ContentStore::open(&self.content_db_path)?;
+            content_store.delete_chunks_for_document(&doc.id)?;
+            let chunk_contents: Vec<(String, String, String)> = doc
+                .chunks
                 .iter()
-                .map(|c| ChunkInput {
-                    id: c.id.clone(),
-                    source_id: c.source_id.clone(),
-                    content: c.content.clone(),
-                    title: c.title.clone(),
-                })
+                .map(|c| (c.id.clone(), c.document_id.clone(), c.content.clone()))
                 .collect();
+            if doc.linked {
+                content_store.insert_linked_chunks(&doc.content, &chunk_contents)?;
+            } else if !chunk_contents.is_empty() {
+                content_store.insert_chunks(&chunk_contents)?;
+            }
+            content_store.set_document_tags(&doc.id, &doc.tags)?;
+            if let Some(model_id) = &self.index_model {
+                content_store.record_index_model(model_id)?;
+            }
+        }
 
+        // Phase 2: LanceDB metadata and vectors
+        db.delete_document(&doc.id).await?;
+        db.insert_document(record).await?;
+
+        let mut records = Vec::new();
+        let mut vectors = Vec::new();
+        let mut new_chunks = Vec::new();
+        for (chunk, embedding) in doc.chunks.iter().zip(embeddings) {
+            if !db
+                .chunk_exists_in_scope(&chunk.content_hash, self.dedup_scope, &chunk.source_id)
+                .await?
+            {
+                records.push(chunk_record(chunk));
+                vectors.push(embedding.clone());
+                new_chunks.push(chunk);
+            }
+        }
+        for (records, vectors) in records
+            .chunks(self.max_write_rows)
+            .zip(vectors.chunks(self.max_write_rows))
+        {
+            db.insert_chunks(records, vectors).await?;
+        }
+
+        // Phase 3: BM25
+        self.bm25_index.delete_chunks(old_chunk_ids)?;
+        let chunk_inputs: Vec<ChunkInput> = new_chunks.into_iter().map(bm25_input).collect();
+        if !chunk_inputs.is_empty() {
             self.bm25_index.index_chunks(&chunk_inputs)?;
         }
 
-        Ok(stats)
+        Ok(WriteStats {
+            documents_written: 1,
+            chunks_written: records.len() as u32,
+            document_ids: vec![doc.id.clone()],
+        })
+    }
+}
+
+/// LanceDB row for a chunk
+fn chunk_record(c: &ChunkData) -> ChunkRecord {
+    // PDF chunks carry a page range; their line numbers mean nothing
+    let (line_start, line_end) = match c.pages {
+        Some(_) => (None, None),
+        None => stored_lines(c.file_path.as_deref(), c.line_start, c.line_end),
+    };
+    ChunkRecord {
+        id: c.id.clone(),
+        document_id: c.document_id.clone(),
+        source_id: c.source_id.clone(),
+        title: c.title.clone(),
+        file_path: c.file_path.clone(),
+        line_start,
+        line_end,
+        page_start: c.pages.map(|(first, _)| first),
+        page_end: c.pages.map(|(_, last)| last),
+        content_hash: c.content_hash.clone(),
+        // Preserve hierarchical metadata from smart chunking
+        section: c.section.clone(),
+        subsection: c.subsection.clone(),
+        hierarchy: c.hierarchy.clone(),
+        has_code: c.has_code,
+    }
+}
+
+/// Tantivy input for a chunk
+fn bm25_input(c: &ChunkData) -> ChunkInput {
+    ChunkInput {
+        id: c.id.clone(),
+        source_id: c.source_id.clone(),
+        content: c.content.clone(),
+        title: c.title.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(file_path: &str, pages: Option<(u32, u32)>) -> ChunkData {
+        ChunkData {
+            id: "c1".to_string(),
+            document_id: "d1".to_string(),
+            source_id: "docs".to_string(),
+            title: None,
+            content: "text".to_string(),
+            file_path: Some(file_path.to_string()),
+            line_start: 12,
+            line_end: 30,
+            pages,
+            content_hash: "hash".to_string(),
+            section: None,
+            subsection: None,
+            hierarchy: Vec::new(),
+            has_code: false,
+        }
+    }
+
+    #[test]
+    fn test_pdf_chunks_store_pages_not_lines() {
+        let pdf = chunk_record(&chunk("manual.pdf", Some((2, 3))));
+        assert_eq!((pdf.page_start, pdf.page_end), (Some(2), Some(3)));
+        assert_eq!((pdf.line_start, pdf.line_end), (None, None));
+
+        let md = chunk_record(&chunk("notes.md", None));
+        assert_eq!((md.line_start, md.line_end), (Some(12), Some(30)));
+        assert_eq!((md.page_start, md.page_end), (None, None));
     }
 }
@@ -15,7 +15,8 @@ use std::io::{self, Write};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::db;
-use crate::{ContentStore, Embedder, Ingester, SearchEngine, SearchResult, VectorDB};
+use crate::search::{self, DEFAULT_PREVIEW_CHARS};
+use crate::{Config, ContentStore, Embedder, Ingester, SearchEngine, SearchResult, VectorDB};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -240,11 +241,14 @@ fn save_position(stdout: &mut io::Stdout) -> Result<()> {
 
 /// Run the interactive REPL
 pub async fn run_repl(data_dir: &str) -> Result<()> {
-    // Initialize components (downloads models on first run)
+    // Initialize components
+    crate::setup::require_models()?;
+    let config = Config::load()?.unwrap_or_default();
     let embedder = Embedder::new()?;
-    let mut db = VectorDB::new(data_dir).await?;
+    let mut db = VectorDB::open(data_dir, config.per_source_tables).await?;
     let content_store = ContentStore::open(&std::path::Path::new(data_dir).join("content.db"))?;
-    let search_engine = SearchEngine::with_reranker()?;
+    let search_engine = SearchEngine::with_reranker().with_settings(&config);
+    let preview_chars = config.preview_chars.unwrap_or(DEFAULT_PREVIEW_CHARS);
 
     // Get stats for banner
     let sources = db.list_sources().await?;
@@ -272,7 +276,7 @@ pub async fn run_repl(data_dir: &str) -> Result<()> {
             }
         } else {
             // Search
-            do_search(&input, &embedder, &db, &content_store, &search_engine).await?;
+            do_search(&input, &embedder, &db, &content_store, &search_engine, preview_chars).await?;
         }
 
         println!(); // Empty line after output
@@ -918,7 +922,7 @@ async fn handle_command(
             if args.is_empty() {
                 println!("{}", "Usage: /docs <source>".yellow());
             } else {
-                let docs = db.list_documents(args, Some(db::MAX_QUERY_LIMIT)).await?;
+                let docs = db.list_documents(args, Some(db::MAX_QUERY_LIMIT), None).await?;
                 if docs.is_empty() {
                     println!("{}", format!("No documents in source '{}'.", args).yellow());
                 } else {
@@ -961,7 +965,11 @@ async fn handle_command(
             } else {
                 let parts: Vec<&str> = args.split("--source").collect();
                 let path = parts[0].trim();
-                let source = parts.get(1).map(|s| s.trim()).unwrap_or("default");
+                let source = parts
+                    .get(1)
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_else(|| Config::load().ok().flatten().unwrap_or_default().default_source);
+                let source = source.as_str();
 
                 println!("{} from {}...", "Ingesting".green().bold(), path);
                 let ingester = Ingester::new(embedder);
@@ -998,8 +1006,9 @@ async fn do_search(
     db: &VectorDB,
     content_store: &ContentStore,
     search_engine: &SearchEngine,
+    preview_chars: usize,
 ) -> Result<()> {
-    let query_embedding = embedder.embed(query)?;
+    let query_embedding = embedder.embed_query(query)?;
     // Get chunk metadata from LanceDB
     let chunk_metas = db.search(&query_embedding, 50).await?;
 
@@ -1021,11 +1030,18 @@ async fn do_search(
             Some(SearchResult {
                 id: meta.id,
                 source_id: meta.source_id,
+                document_id: meta.document_id,
                 title: meta.title,
                 content,
                 file_path: meta.file_path,
                 line_start: meta.line_start,
+                line_end: meta.line_end,
+                page_start: meta.page_start,
+                page_end: meta.page_end,
+                section: meta.section,
+                has_code: meta.has_code,
                 score: meta.score,
+                matched_by: vec!["vector".to_string()],
             })
         })
         .collect();
@@ -1053,13 +1069,7 @@ async fn do_search(
             println!("     {}", file_path.dimmed());
         }
 
-        // Show preview (first 150 chars)
-        let preview: String = result
-            .content
-            .chars()
-            .take(150)
-            .collect::<String>()
-            .replace('\n', " ");
+        let preview = search::preview(&result.content, preview_chars);
         println!("     {}", preview.dimmed());
         println!();
     }
@@ -4,6 +4,7 @@
 
 use crate::config::{Config, DevicePreference, RerankerModelConfig};
 use crate::embed::{device_name, resolve_device};
+use crate::error::EywaError;
 use anyhow::{Context, Result};
 use candle_core::{Device, Tensor, DType, IndexOp};
 use candle_nn::VarBuilder;
@@ -23,14 +24,19 @@ pub struct Reranker {
     model: BertModel,
     tokenizer: Tokenizer,
     device: Device,
+    batch_size: usize,
 }
 
 impl Reranker {
     /// Create a new reranker using the model from config
     pub fn new() -> Result<Self> {
         let config = Config::load()?
-            .ok_or_else(|| anyhow::anyhow!("Eywa not initialized. Run 'eywa' or 'eywa init' first."))?;
-        Self::new_with_model(&config.reranker_model, &config.device, true)
+            .ok_or(EywaError::NotInitialized)?;
+        let reranker = Self::new_with_model(&config.reranker_model, &config.device, true)?;
+        Ok(match config.rerank_batch_size {
+            Some(size) => reranker.with_batch_size(size),
+            None => reranker,
+        })
     }
 
     /// Create a new reranker with a specific model and device preference
@@ -80,25 +86,40 @@ impl Reranker {
             eprintln!("done");
         }
 
+        let batch_size = get_rerank_batch_size(&device);
+
         Ok(Self {
             model,
             tokenizer,
             device,
+            batch_size,
         })
     }
 
+    /// Override the number of pairs scored per forward pass
+    ///
+    /// Scores are independent of batch size; this only trades memory for throughput.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Number of pairs scored per forward pass
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
     /// Score query-document pairs
-    /// Returns relevance scores (higher = more relevant)
+    /// Returns relevance scores (higher = more relevant), in the same order as `documents`
     pub fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
         if documents.is_empty() {
             return Ok(vec![]);
         }
 
-        let batch_size = get_rerank_batch_size(&self.device);
         let mut all_scores = Vec::with_capacity(documents.len());
 
-        // Process in batches for better GPU utilization
-        for batch in documents.chunks(batch_size) {
+        // Process in fixed-size batches for bounded memory and better GPU utilization
+        for batch in documents.chunks(self.batch_size) {
             let batch_scores = self.score_batch(query, batch)?;
             all_scores.extend(batch_scores);
         }
@@ -2,15 +2,75 @@
 //!
 //! Provides semantic search with configurable result filtering and neural reranking.
 
+use crate::config::{Config, DevicePreference, RerankerModelConfig};
+use crate::bm25::{BM25Index, BM25Result};
+use crate::content::ContentStore;
+use crate::db::VectorDB;
+use crate::embed::Embedder;
 use crate::rerank::Reranker;
-use crate::types::SearchResult;
+use crate::types::{ChunkMeta, Highlight, SearchResult};
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of candidates handed to the reranker
+pub const DEFAULT_CANDIDATE_POOL: usize = 20;
+
+/// Default length of result previews in characters (`preview_chars`)
+pub const DEFAULT_PREVIEW_CHARS: usize = 200;
+
+/// Synonym map for query expansion, in `~/.eywa`
+pub const SYNONYMS_FILE: &str = "synonyms.toml";
+
+/// Length of highlight snippets in characters
+pub const HIGHLIGHT_CHARS: usize = 200;
+
+/// Default Reciprocal Rank Fusion constant (`rrf_k`)
+///
+/// 60 is the value from the original RRF paper; larger values flatten the
+/// advantage of top-ranked hits.
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Default cap on results a single search may request (`max_search_limit`)
+pub const DEFAULT_MAX_SEARCH_LIMIT: usize = 100;
+
+/// Default score boost for chunks of pinned documents (`pin_boost`)
+pub const DEFAULT_PIN_BOOST: f32 = 0.1;
+
+/// Default floor on reranker scores (sigmoid, 0.0 - 1.0)
+///
+/// The default cross-encoder scores unrelated passages well below this and
+/// relevant ones well above, so it only drops results judged irrelevant.
+pub const DEFAULT_RERANK_MIN_SCORE: f32 = 0.05;
 
 /// Search engine configuration
 pub struct SearchEngine {
     /// Minimum similarity score threshold (0.0 - 1.0)
     pub min_score: f32,
+    /// Minimum reranker score, applied after neural reranking (0.0 - 1.0)
+    pub rerank_min_score: f32,
     /// Optional neural reranker for better accuracy
     pub reranker: Option<Reranker>,
+    /// Minimum number of candidates to fetch and rerank, independent of the
+    /// number of results returned
+    pub candidate_pool: usize,
+    /// Candidates fetched from each source on top of the global pool
+    /// (0 = off)
+    pub candidates_per_source: usize,
+    /// Rank fusion constant for `hybrid_search`
+    pub rrf_k: f32,
+    /// Largest result count a single search may request
+    pub max_limit: usize,
+    /// Score added to results from pinned documents without their own boost
+    pub pin_boost: f32,
+    /// Related terms appended to BM25 queries, keyed by lowercase term
+    /// (empty = query expansion off)
+    pub synonyms: HashMap<String, Vec<String>>,
+    /// Keep only the best-scoring chunk of each document after reranking
+    pub dedup_by_document: bool,
+    /// Number of rerank passes run, neural or keyword
+    rerank_calls: AtomicUsize,
 }
 
 impl SearchEngine {
@@ -18,24 +78,324 @@ impl SearchEngine {
     pub fn new() -> Self {
         Self {
             min_score: 0.3,
+            rerank_min_score: DEFAULT_RERANK_MIN_SCORE,
             reranker: None,
+            candidate_pool: DEFAULT_CANDIDATE_POOL,
+            candidates_per_source: 0,
+            rrf_k: DEFAULT_RRF_K,
+            max_limit: DEFAULT_MAX_SEARCH_LIMIT,
+            pin_boost: DEFAULT_PIN_BOOST,
+            synonyms: HashMap::new(),
+            dedup_by_document: false,
+            rerank_calls: AtomicUsize::new(0),
         }
     }
 
-    /// Create a new search engine with neural reranker
-    pub fn with_reranker() -> anyhow::Result<Self> {
-        Ok(Self {
-            min_score: 0.3,
-            reranker: Some(Reranker::new()?),
-        })
+    /// Create a new search engine with the configured neural reranker
+    ///
+    /// If the reranker fails to load, logs a warning and returns a degraded
+    /// engine that falls back to keyword reranking (see `has_reranker`).
+    pub fn with_reranker() -> Self {
+        Self::from_reranker(Reranker::new())
+    }
+
+    /// Create a new search engine with a specific reranker model
+    ///
+    /// Degrades to keyword reranking if the model can't be loaded.
+    pub fn with_reranker_model(model: &RerankerModelConfig, device: &DevicePreference) -> Self {
+        Self::from_reranker(Reranker::new_with_model(model, device, false))
+    }
+
+    fn from_reranker(reranker: anyhow::Result<Reranker>) -> Self {
+        match reranker {
+            Ok(reranker) => Self {
+                reranker: Some(reranker),
+                ..Self::new()
+            },
+            Err(e) => {
+                eprintln!(
+                    "Warning: Reranker unavailable ({}). Falling back to keyword reranking.",
+                    e
+                );
+                Self::new()
+            }
+        }
     }
 
     /// Create a new search engine with custom minimum score
     pub fn with_min_score(min_score: f32) -> Self {
         Self {
             min_score,
+            rerank_min_score: DEFAULT_RERANK_MIN_SCORE,
             reranker: None,
+            candidate_pool: DEFAULT_CANDIDATE_POOL,
+            candidates_per_source: 0,
+            rrf_k: DEFAULT_RRF_K,
+            max_limit: DEFAULT_MAX_SEARCH_LIMIT,
+            pin_boost: DEFAULT_PIN_BOOST,
+            synonyms: HashMap::new(),
+            dedup_by_document: false,
+            rerank_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Apply the search settings from a loaded config
+    ///
+    /// Options the config leaves unset keep their current values.
+    pub fn with_settings(mut self, config: &Config) -> Self {
+        if let Some(candidate_pool) = config.rerank_candidates {
+            self = self.with_candidate_pool(candidate_pool);
+        }
+        if let Some(rerank_min_score) = config.rerank_min_score {
+            self = self.with_rerank_min_score(rerank_min_score);
+        }
+        if let Some(candidates_per_source) = config.rerank_candidates_per_source {
+            self = self.with_candidates_per_source(candidates_per_source);
+        }
+        if let Some(rrf_k) = config.rrf_k {
+            self = self.with_rrf_k(rrf_k);
+        }
+        if let Some(max_limit) = config.max_search_limit {
+            self = self.with_max_limit(max_limit);
+        }
+        if let Some(pin_boost) = config.pin_boost {
+            self = self.with_pin_boost(pin_boost);
+        }
+        self
+    }
+
+    /// Set the floor on reranker scores
+    pub fn with_rerank_min_score(mut self, rerank_min_score: f32) -> Self {
+        self.rerank_min_score = rerank_min_score;
+        self
+    }
+
+    /// Set the minimum candidate pool size (at least 1)
+    pub fn with_candidate_pool(mut self, candidate_pool: usize) -> Self {
+        self.candidate_pool = candidate_pool.max(1);
+        self
+    }
+
+    /// Set how many candidates each source contributes (0 = off)
+    pub fn with_candidates_per_source(mut self, candidates_per_source: usize) -> Self {
+        self.candidates_per_source = candidates_per_source;
+        self
+    }
+
+    /// Set the rank fusion constant (at least 1)
+    pub fn with_rrf_k(mut self, rrf_k: f32) -> Self {
+        self.rrf_k = rrf_k.max(1.0);
+        self
+    }
+
+    /// Set the largest result count a search may request (at least 1)
+    pub fn with_max_limit(mut self, max_limit: usize) -> Self {
+        self.max_limit = max_limit.max(1);
+        self
+    }
+
+    /// Set the score boost for pinned documents
+    pub fn with_pin_boost(mut self, pin_boost: f32) -> Self {
+        self.pin_boost = pin_boost;
+        self
+    }
+
+    /// Return at most one result per document
+    pub fn with_dedup_by_document(mut self, dedup_by_document: bool) -> Self {
+        self.dedup_by_document = dedup_by_document;
+        self
+    }
+
+    /// Expand BM25 queries with related terms (keys match case-insensitively)
+    pub fn with_query_expansion(mut self, synonyms: HashMap<String, Vec<String>>) -> Self {
+        self.synonyms = synonyms
+            .into_iter()
+            .map(|(term, related)| (term.to_lowercase(), related))
+            .collect();
+        self
+    }
+
+    /// Turn on query expansion if `~/.eywa/synonyms.toml` exists
+    ///
+    /// A file that can't be read or parsed is reported and ignored.
+    pub fn with_synonyms_file(self) -> Self {
+        match load_synonyms() {
+            Ok(Some(synonyms)) => self.with_query_expansion(synonyms),
+            Ok(None) => self,
+            Err(e) => {
+                eprintln!("Warning: {:#}. Query expansion disabled.", e);
+                self
+            }
+        }
+    }
+
+    /// The query with related terms of its words appended, for BM25
+    ///
+    /// Only for keyword search: the embedding already captures meaning, and
+    /// extra words would pull the query vector off topic. Returns the query
+    /// unchanged when expansion is off or no term has synonyms.
+    pub fn expand_query(&self, query: &str) -> String {
+        if self.synonyms.is_empty() {
+            return query.to_string();
+        }
+
+        let terms = keyword_terms(query);
+        let mut extra: Vec<&str> = Vec::new();
+        for related in terms.iter().filter_map(|t| self.synonyms.get(t)).flatten() {
+            let lower = related.to_lowercase();
+            if !terms.contains(&lower) && !extra.iter().any(|e| e.to_lowercase() == lower) {
+                extra.push(related);
+            }
+        }
+
+        if extra.is_empty() {
+            query.to_string()
+        } else {
+            format!("{} {}", query, extra.join(" "))
+        }
+    }
+
+    /// Cap a requested result count at `max_limit`
+    ///
+    /// Returns the limit to use and, when it was lowered, a note for the
+    /// response so clients know they didn't get what they asked for.
+    pub fn clamp_limit(&self, limit: usize) -> (usize, Option<String>) {
+        if limit <= self.max_limit {
+            return (limit, None);
+        }
+        let note = format!(
+            "limit {} exceeds max_search_limit; returning at most {} results",
+            limit, self.max_limit
+        );
+        (self.max_limit, Some(note))
+    }
+
+    /// Number of candidates to fetch for a query returning `limit` results
+    ///
+    /// Never smaller than the configured pool, so small limits still give
+    /// the reranker something meaningful to reorder.
+    pub fn candidate_count(&self, limit: usize) -> usize {
+        self.candidate_pool.max(limit * 2)
+    }
+
+    /// Fuse vector and BM25 rankings with Reciprocal Rank Fusion
+    ///
+    /// Each list contributes `1 / (rrf_k + rank)` per chunk (rank from 1), so
+    /// chunks found by both signals rise above those found by one. Scores on
+    /// different scales never mix; only positions matter. Returns
+    /// `(chunk_id, fused_score)` best first, ready to hydrate and rerank.
+    pub fn hybrid_search(&self, vector: &[ChunkMeta], bm25: &[BM25Result]) -> Vec<(String, f32)> {
+        let mut fused: HashMap<&str, f32> = HashMap::new();
+        let ranked = vector
+            .iter()
+            .map(|m| m.id.as_str())
+            .enumerate()
+            .chain(bm25.iter().map(|r| r.chunk_id.as_str()).enumerate());
+        for (rank, id) in ranked {
+            *fused.entry(id).or_default() += 1.0 / (self.rrf_k + rank as f32 + 1.0);
+        }
+
+        let mut results: Vec<(String, f32)> = fused
+            .into_iter()
+            .map(|(id, score)| (id.to_string(), score))
+            .collect();
+        results.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        results
+    }
+
+    /// Vector and BM25 candidates fused with `hybrid_search`, with content
+    ///
+    /// Vector hits below `min_score` are dropped before fusion; keyword hits
+    /// have no floor, so exact terms the embedding misses still surface. BM25
+    /// runs on the expanded query. Scores are the fused score scaled so a
+    /// chunk ranked first by both signals gets 1.0, and `matched_by` lists the
+    /// signals that found each chunk.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn hybrid_candidates(
+        &self,
+        db: &VectorDB,
+        bm25_index: &BM25Index,
+        content_store: &ContentStore,
+        query: &str,
+        query_embedding: &[f32],
+        candidates: usize,
+        source_id: Option<&str>,
+        code_only: bool,
+        per_source: usize,
+        min_score: f32,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        let vector_hits: Vec<ChunkMeta> = db
+            .search_with_source_quota(query_embedding, candidates, source_id, code_only, per_source)
+            .await?
+            .into_iter()
+            .filter(|m| m.score >= min_score)
+            .collect();
+
+        let keyword_query = self.expand_query(query);
+        let keyword_hits = match source_id {
+            Some(source_id) => bm25_index.search_source(&keyword_query, source_id, candidates)?,
+            None => bm25_index.search(&keyword_query, candidates)?,
+        };
+
+        // Per-source quotas may have grown the vector pool past `candidates`
+        let mut fused = self.hybrid_search(&vector_hits, &keyword_hits);
+        fused.truncate(candidates.max(vector_hits.len()));
+
+        let keyword_ids: HashSet<&str> = keyword_hits.iter().map(|r| r.chunk_id.as_str()).collect();
+        let vector_ids: HashSet<String> = vector_hits.iter().map(|m| m.id.clone()).collect();
+        let mut metas: HashMap<String, ChunkMeta> =
+            vector_hits.into_iter().map(|m| (m.id.clone(), m)).collect();
+
+        let keyword_only: Vec<&str> = fused
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .filter(|id| !vector_ids.contains(*id))
+            .collect();
+        for meta in db.get_chunk_metas(&keyword_only).await? {
+            if !code_only || meta.has_code {
+                metas.insert(meta.id.clone(), meta);
+            }
         }
+
+        let chunk_ids: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+        let mut contents: HashMap<String, String> = content_store.get_chunks(&chunk_ids)?.into_iter().collect();
+
+        let scale = (self.rrf_k + 1.0) / 2.0;
+        Ok(fused
+            .into_iter()
+            .filter_map(|(id, fused_score)| {
+                let meta = metas.remove(&id)?;
+                let content = contents.remove(&id)?;
+                let matched_by = [
+                    ("vector", vector_ids.contains(&id)),
+                    ("bm25", keyword_ids.contains(id.as_str())),
+                ]
+                .into_iter()
+                .filter(|(_, matched)| *matched)
+                .map(|(signal, _)| signal.to_string())
+                .collect();
+                Some(SearchResult {
+                    id,
+                    source_id: meta.source_id,
+                    document_id: meta.document_id,
+                    title: meta.title,
+                    content,
+                    file_path: meta.file_path,
+                    line_start: meta.line_start,
+                    line_end: meta.line_end,
+                    page_start: meta.page_start,
+                    page_end: meta.page_end,
+                    section: meta.section,
+                    has_code: meta.has_code,
+                    score: fused_score * scale,
+                    matched_by,
+                })
+            })
+            .collect())
     }
 
     /// Filter results by minimum score
@@ -46,20 +406,85 @@ impl SearchEngine {
             .collect()
     }
 
+    /// Drop results whose content is shorter than `min_chars` (0 = keep all)
+    ///
+    /// Headings and one-liners often score well on similarity but say little.
+    pub fn filter_min_length(&self, results: Vec<SearchResult>, min_chars: usize) -> Vec<SearchResult> {
+        if min_chars == 0 {
+            return results;
+        }
+        results
+            .into_iter()
+            .filter(|r| r.content.trim().chars().count() >= min_chars)
+            .collect()
+    }
+
+    /// Number of rerank passes this engine has run
+    pub fn rerank_calls(&self) -> usize {
+        self.rerank_calls.load(Ordering::Relaxed)
+    }
+
+    /// Embedding-only search: the `limit` nearest chunks by cosine similarity
+    ///
+    /// The lowest-latency path. Skips BM25, reranking, keyword boosts, pins and
+    /// the score floor, and fetches content only for the chunks returned. On a
+    /// warm model a query typically takes well under 50ms.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_embedding_only(
+        &self,
+        embedder: &Embedder,
+        db: &VectorDB,
+        content_store: &ContentStore,
+        query: &str,
+        limit: usize,
+        source_id: Option<&str>,
+        code_only: bool,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        let (limit, _) = self.clamp_limit(limit);
+        let query_embedding = embedder.embed_query(query)?;
+        let chunk_metas = db
+            .search_with_filters(&query_embedding, limit, source_id, code_only)
+            .await?;
+
+        let chunk_ids: Vec<&str> = chunk_metas.iter().map(|c| c.id.as_str()).collect();
+        let mut contents: HashMap<String, String> = content_store.get_chunks(&chunk_ids)?.into_iter().collect();
+
+        Ok(chunk_metas
+            .into_iter()
+            .filter_map(|meta| {
+                let content = contents.remove(&meta.id)?;
+                Some(SearchResult {
+                    id: meta.id,
+                    source_id: meta.source_id,
+                    document_id: meta.document_id,
+                    title: meta.title,
+                    content,
+                    file_path: meta.file_path,
+                    line_start: meta.line_start,
+                    line_end: meta.line_end,
+                    page_start: meta.page_start,
+                    page_end: meta.page_end,
+                    section: meta.section,
+                    has_code: meta.has_code,
+                    score: meta.score,
+                    matched_by: vec!["vector".to_string()],
+                })
+            })
+            .collect())
+    }
+
     /// Rerank results using neural reranker if available, otherwise use keyword boost
+    ///
+    /// With the neural reranker, results scoring below `rerank_min_score` are
+    /// dropped, so an irrelevant query can come back empty.
     pub fn rerank(&self, mut results: Vec<SearchResult>, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.rerank_calls.fetch_add(1, Ordering::Relaxed);
         if let Some(ref reranker) = self.reranker {
             // Use neural reranker
             let documents: Vec<String> = results.iter().map(|r| r.content.clone()).collect();
 
             if let Ok(scores) = reranker.rerank(query, &documents) {
-                // Update scores with reranker scores
-                for (result, score) in results.iter_mut().zip(scores.iter()) {
-                    result.score = *score;
-                }
-
-                // Re-sort by reranker score
-                results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+                results = self.apply_rerank_scores(results, &scores);
             }
         } else {
             // Fall back to keyword reranking
@@ -69,18 +494,81 @@ impl SearchEngine {
         results.into_iter().take(limit).collect()
     }
 
+    /// Rerank with a reranker other than the engine's own (e.g. a per-request override)
+    ///
+    /// Unlike `rerank`, a scoring failure is returned instead of falling back to keywords.
+    pub fn rerank_using(
+        &self,
+        reranker: &Reranker,
+        results: Vec<SearchResult>,
+        query: &str,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        self.rerank_calls.fetch_add(1, Ordering::Relaxed);
+        let documents: Vec<String> = results.iter().map(|r| r.content.clone()).collect();
+        let scores = reranker.rerank(query, &documents)?;
+        Ok(self.apply_rerank_scores(results, &scores))
+    }
+
+    /// Rerank, then lift results from pinned documents before truncating
+    ///
+    /// `pinned` maps chunk ids to their document's boost override (see
+    /// `ContentStore::pinned_chunks`).
+    pub fn rerank_with_pins(
+        &self,
+        results: Vec<SearchResult>,
+        query: &str,
+        limit: usize,
+        pinned: &HashMap<String, Option<f32>>,
+    ) -> Vec<SearchResult> {
+        let count = results.len();
+        let results = self.rerank(results, query, count);
+        let results = self.boost_pinned(results, pinned);
+        let results = if self.dedup_by_document { best_per_document(results) } else { results };
+        results.into_iter().take(limit).collect()
+    }
+
+    /// Add the pin boost to results from pinned documents and re-sort
+    pub fn boost_pinned(
+        &self,
+        mut results: Vec<SearchResult>,
+        pinned: &HashMap<String, Option<f32>>,
+    ) -> Vec<SearchResult> {
+        if pinned.is_empty() {
+            return results;
+        }
+
+        for result in &mut results {
+            if let Some(boost) = pinned.get(&result.id) {
+                result.score += boost.unwrap_or(self.pin_boost);
+            }
+        }
+
+        results.sort_by(by_score_then_id);
+        results
+    }
+
+    /// Replace scores with reranker scores, re-sort and drop those under the floor
+    fn apply_rerank_scores(&self, mut results: Vec<SearchResult>, scores: &[f32]) -> Vec<SearchResult> {
+        for (result, score) in results.iter_mut().zip(scores.iter()) {
+            result.score = *score;
+        }
+
+        results.retain(|r| r.score >= self.rerank_min_score);
+        results.sort_by(by_score_then_id);
+        results
+    }
+
     /// Rerank results using a simple BM25-like scoring boost
     /// This gives a small boost to exact keyword matches
     pub fn rerank_with_keywords(&self, mut results: Vec<SearchResult>, query: &str) -> Vec<SearchResult> {
-        let query_lower = query.to_lowercase();
-        let query_terms: Vec<&str> = query_lower.split_whitespace().collect();
+        let query_terms = keyword_terms(query);
 
         for result in &mut results {
             let content_lower = result.content.to_lowercase();
             let mut keyword_boost = 0.0f32;
 
             for term in &query_terms {
-                if content_lower.contains(term) {
+                if content_lower.contains(term.as_str()) {
                     keyword_boost += 0.05; // Small boost per matching term
                 }
             }
@@ -90,7 +578,7 @@ impl SearchEngine {
         }
 
         // Re-sort by score
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.sort_by(by_score_then_id);
         results
     }
 
@@ -100,6 +588,169 @@ impl SearchEngine {
     }
 }
 
+/// Best score first; equal scores fall back to chunk ID so the order is stable
+fn by_score_then_id(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    b.score
+        .partial_cmp(&a.score)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Keep the first (best-ranked) result of each document, in order
+///
+/// Results without a document ID are all kept.
+pub fn best_per_document(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut seen: HashSet<String> = HashSet::new();
+    results
+        .into_iter()
+        .filter(|r| r.document_id.is_empty() || seen.insert(r.document_id.clone()))
+        .collect()
+}
+
+/// Load the synonym map from `~/.eywa/synonyms.toml`, if there is one
+///
+/// Each key lists the terms to add to queries containing it:
+///
+/// ```toml
+/// auth = ["authentication", "authorization", "login"]
+/// k8s = ["kubernetes"]
+/// ```
+pub fn load_synonyms() -> anyhow::Result<Option<HashMap<String, Vec<String>>>> {
+    load_synonyms_from(&crate::config::eywa_dir()?.join(SYNONYMS_FILE))
+}
+
+/// Load a synonym map from `path`; `None` if the file doesn't exist
+pub fn load_synonyms_from(path: &Path) -> anyhow::Result<Option<HashMap<String, Vec<String>>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let synonyms = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(synonyms))
+}
+
+/// Lowercased query terms, as matched by keyword reranking and highlighting
+///
+/// Leading and trailing punctuation is dropped so "borrow?" matches
+/// "borrow"; repeated terms count once.
+pub fn keyword_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query.to_lowercase().split_whitespace() {
+        let term = term.trim_matches(|c: char| !c.is_alphanumeric());
+        if !term.is_empty() && !terms.iter().any(|t| t == term) {
+            terms.push(term.to_string());
+        }
+    }
+    terms
+}
+
+/// Find the window of `content` where the query terms match best
+///
+/// The window of at most `max_chars` characters covering the most distinct
+/// terms (then the most matches) is returned with the matched byte ranges,
+/// centred on the matches. Matching is ASCII case-insensitive so offsets
+/// stay valid in the original text. Without any match the snippet is the
+/// start of the content.
+pub fn highlight(content: &str, query: &str, max_chars: usize) -> Highlight {
+    let lowered = content.to_ascii_lowercase();
+    let mut found: Vec<(usize, usize, usize)> = Vec::new();
+    for (t, term) in keyword_terms(query).iter().enumerate() {
+        found.extend(lowered.match_indices(term.as_str()).map(|(i, m)| (i, i + m.len(), t)));
+    }
+    found.sort_unstable();
+
+    // Overlapping matches ("rust" in "rustc") merge into one range
+    let mut matches: Vec<(usize, usize, usize)> = Vec::new();
+    for m in found {
+        match matches.last_mut() {
+            Some(last) if m.0 < last.1 => last.1 = last.1.max(m.1),
+            _ => matches.push(m),
+        }
+    }
+
+    // Byte offset of every char, plus the end, to convert between the two
+    let offsets: Vec<usize> = content
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(content.len()))
+        .collect();
+    let total_chars = offsets.len() - 1;
+    let char_at = |byte: usize| offsets.partition_point(|&o| o < byte);
+
+    let mut best: Option<(usize, usize)> = None;
+    let mut best_score = (0, 0);
+    for i in 0..matches.len() {
+        let first = char_at(matches[i].0);
+        let j = i + matches[i..]
+            .iter()
+            .take_while(|m| char_at(m.1) - first <= max_chars)
+            .count();
+        let distinct = matches[i..j].iter().map(|m| m.2).collect::<HashSet<_>>().len();
+        if (distinct, j - i) > best_score {
+            best_score = (distinct, j - i);
+            best = Some((i, j));
+        }
+    }
+
+    let start_char = match best {
+        Some((i, j)) => {
+            let first = char_at(matches[i].0);
+            let slack = max_chars - (char_at(matches[j - 1].1) - first);
+            first
+                .saturating_sub(slack / 2)
+                .min(total_chars.saturating_sub(max_chars))
+        }
+        None => 0,
+    };
+    let start = offsets[start_char];
+    let end = offsets[(start_char + max_chars).min(total_chars)];
+
+    Highlight {
+        snippet: content[start..end].to_string(),
+        snippet_start: start,
+        matches: matches
+            .iter()
+            .filter(|m| m.0 >= start && m.1 <= end)
+            .map(|m| (m.0 - start, m.1 - start))
+            .collect(),
+    }
+}
+
+/// Shorten text for display, ending on a sentence or word boundary
+///
+/// Whitespace (including newlines) is collapsed to single spaces. If the text
+/// is longer than `max_chars`, it is cut at the last sentence end in the
+/// second half of the budget, else the last space, and "…" is appended; the
+/// result never exceeds `max_chars` characters.
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    // Leave room for the ellipsis; byte offset of the first char that doesn't fit
+    let budget = max_chars - 1;
+    let end = text.char_indices().nth(budget).map_or(text.len(), |(i, _)| i);
+    let window = &text[..end];
+
+    let sentence_end = window
+        .match_indices(['.', '!', '?'])
+        .map(|(i, _)| i + 1)
+        .filter(|&i| text[i..].starts_with(' ') && window[..i].chars().count() >= budget / 2)
+        .last();
+    let cut = sentence_end
+        .or_else(|| (text[end..].starts_with(' ')).then_some(end))
+        .or_else(|| window.rfind(' '))
+        .unwrap_or(end);
+
+    format!("{}…", text[..cut].trim_end())
+}
+
 impl Default for SearchEngine {
     fn default() -> Self {
         Self::new()
@@ -114,14 +765,102 @@ mod tests {
         SearchResult {
             id: id.to_string(),
             source_id: "test".to_string(),
+            document_id: id.to_string(),
             title: None,
             content: content.to_string(),
             file_path: None,
             line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score,
+            matched_by: Vec::new(),
+        }
+    }
+
+    fn make_meta(id: &str) -> ChunkMeta {
+        ChunkMeta {
+            id: id.to_string(),
+            document_id: "doc".to_string(),
+            source_id: "test".to_string(),
+            title: None,
+            file_path: None,
+            line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
+            score: 0.0,
         }
     }
 
+    fn make_bm25(id: &str) -> BM25Result {
+        BM25Result {
+            chunk_id: id.to_string(),
+            score: 0.0,
+        }
+    }
+
+    fn ids(fused: &[(String, f32)]) -> Vec<&str> {
+        fused.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_hybrid_search_disjoint_lists_interleave() {
+        let engine = SearchEngine::new();
+        let vector = vec![make_meta("v1"), make_meta("v2")];
+        let bm25 = vec![make_bm25("b1"), make_bm25("b2")];
+
+        let fused = engine.hybrid_search(&vector, &bm25);
+        // Equal ranks tie, so each rank level comes before the next
+        assert_eq!(ids(&fused), vec!["b1", "v1", "b2", "v2"]);
+        assert!((fused[0].1 - 1.0 / 61.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hybrid_search_overlap_ranks_first() {
+        let engine = SearchEngine::new();
+        let vector = vec![make_meta("a"), make_meta("shared"), make_meta("c")];
+        let bm25 = vec![make_bm25("b"), make_bm25("shared"), make_bm25("c")];
+
+        let fused = engine.hybrid_search(&vector, &bm25);
+        // Found by both signals beats the top hit of either one
+        assert_eq!(ids(&fused), vec!["shared", "c", "a", "b"]);
+        assert!((fused[0].1 - 2.0 / 62.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hybrid_search_small_k_favours_top_ranks() {
+        let mut engine = SearchEngine::new();
+        let vector = vec![make_meta("top"), make_meta("x"), make_meta("y"), make_meta("both")];
+        let bm25 = vec![make_bm25("z"), make_bm25("w"), make_bm25("v"), make_bm25("both")];
+
+        assert_eq!(engine.hybrid_search(&vector, &bm25)[0].0, "both");
+
+        // With a tiny k a single first place outweighs two fourth places
+        engine.rrf_k = 1.0;
+        assert_eq!(engine.hybrid_search(&vector, &bm25)[0].0, "top");
+    }
+
+    #[test]
+    fn test_filter_min_length_drops_short_chunks() {
+        let engine = SearchEngine::new();
+        let results = vec![
+            make_result("heading", "## Install", 0.9),
+            make_result("body", "Run the installer, then restart the shell.", 0.7),
+        ];
+
+        let kept = engine.filter_min_length(results.clone(), 20);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].id, "body");
+
+        // Off by default
+        assert_eq!(engine.filter_min_length(results, 0).len(), 2);
+    }
+
     #[test]
     fn test_filter_empty_results() {
         let engine = SearchEngine::new();
@@ -196,9 +935,270 @@ mod tests {
         assert_eq!(filtered[0].id, "1");
     }
 
+    #[test]
+    fn test_weak_reranker_scores_return_no_results() {
+        let engine = SearchEngine::new();
+        // Vector scores pass min_score, but the cross-encoder finds nothing relevant
+        let results = vec![
+            make_result("1", "unrelated", 0.7),
+            make_result("2", "also unrelated", 0.6),
+        ];
+        let results = engine.filter_results(results);
+        assert_eq!(results.len(), 2);
+
+        let reranked = engine.apply_rerank_scores(results, &[0.002, 0.01]);
+        assert!(reranked.is_empty());
+    }
+
+    #[test]
+    fn test_rerank_min_score_keeps_relevant_results() {
+        let engine = SearchEngine::new().with_rerank_min_score(0.5);
+        let results = vec![
+            make_result("1", "weak", 0.9),
+            make_result("2", "strong", 0.4),
+            make_result("3", "medium", 0.5),
+        ];
+        let reranked = engine.apply_rerank_scores(results, &[0.3, 0.95, 0.6]);
+        let ids: Vec<&str> = reranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_preview_short_text_is_unchanged() {
+        assert_eq!(preview("Short  text\nwith lines", 50), "Short text with lines");
+    }
+
+    #[test]
+    fn test_preview_never_ends_mid_word() {
+        let text = "The quick brown fox jumps over the lazy dog and keeps running far away";
+        for max in 5..text.len() {
+            let shown = preview(text, max);
+            assert!(shown.chars().count() <= max, "{:?} exceeds {}", shown, max);
+            let kept = shown.trim_end_matches('…');
+            assert!(text.starts_with(kept));
+            let rest = &text[kept.len()..];
+            assert!(rest.is_empty() || rest.starts_with(' '), "{:?} cut mid-word", shown);
+        }
+    }
+
+    #[test]
+    fn test_preview_prefers_sentence_boundary() {
+        let text = "First sentence is here. Second sentence runs on and on past the limit.";
+        assert_eq!(preview(text, 40), "First sentence is here.…");
+    }
+
+    #[test]
+    fn test_preview_handles_multibyte_chars() {
+        let text = "Café naïve résumé — 日本語のテキスト 🚀 emoji après";
+        for max in 0..text.chars().count() + 2 {
+            let shown = preview(text, max);
+            assert!(shown.chars().count() <= max);
+        }
+        // A single long word can only be cut inside it
+        assert_eq!(preview("日本語のテキスト", 4), "日本語…");
+    }
+
     #[test]
     fn test_has_reranker() {
         let engine = SearchEngine::new();
         assert!(!engine.has_reranker());
     }
+
+    #[test]
+    fn test_with_reranker_model_degrades_when_model_missing() {
+        let missing = RerankerModelConfig::custom("eywa-test/nonexistent-reranker");
+        let engine = SearchEngine::with_reranker_model(&missing, &DevicePreference::Cpu);
+        assert!(!engine.has_reranker());
+
+        // Degraded engine still reranks with keywords
+        let results = vec![
+            make_result("1", "python is nice", 0.5),
+            make_result("2", "rust is great", 0.5),
+        ];
+        let reranked = engine.rerank(results, "rust", 2);
+        assert_eq!(reranked[0].id, "2");
+    }
+
+    #[test]
+    fn test_candidate_count_uses_pool_for_small_limits() {
+        let engine = SearchEngine::new();
+        assert_eq!(engine.candidate_count(1), DEFAULT_CANDIDATE_POOL);
+        assert_eq!(engine.candidate_count(50), 100);
+
+        let engine = SearchEngine::new().with_candidate_pool(0);
+        assert_eq!(engine.candidate_count(1), 2);
+    }
+
+    #[test]
+    fn test_settings_apply_only_configured_values() {
+        let config = Config {
+            rerank_candidates: Some(40),
+            rerank_candidates_per_source: Some(3),
+            rrf_k: Some(10.0),
+            max_search_limit: Some(25),
+            pin_boost: Some(0.4),
+            ..Config::default()
+        };
+        let engine = SearchEngine::new().with_rerank_min_score(0.2).with_settings(&config);
+        assert_eq!(engine.candidate_pool, 40);
+        assert_eq!(engine.candidates_per_source, 3);
+        assert_eq!(engine.rrf_k, 10.0);
+        assert_eq!(engine.clamp_limit(100).0, 25);
+        assert_eq!(engine.pin_boost, 0.4);
+        assert_eq!(engine.rerank_min_score, 0.2);
+    }
+
+    #[test]
+    fn test_pinned_document_ranks_above_equal_match() {
+        let engine = SearchEngine::new();
+        let results = vec![
+            make_result("unpinned", "rust ownership rules", 0.6),
+            make_result("pinned", "rust ownership rules", 0.6),
+        ];
+        let pinned: HashMap<String, Option<f32>> = [("pinned".to_string(), None)].into();
+
+        let ranked = engine.rerank_with_pins(results.clone(), "rust ownership", 2, &pinned);
+        assert_eq!(ranked[0].id, "pinned");
+        assert!((ranked[0].score - ranked[1].score - DEFAULT_PIN_BOOST).abs() < 1e-6);
+
+        // A per-document boost overrides the configured one
+        let pinned: HashMap<String, Option<f32>> = [("pinned".to_string(), Some(0.5))].into();
+        let ranked = engine.rerank_with_pins(results, "rust ownership", 1, &pinned);
+        assert_eq!(ranked.len(), 1);
+        assert!((ranked[0].score - (0.6 + 0.5 + 0.1)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clamp_limit_caps_oversized_requests() {
+        let engine = SearchEngine::new();
+        assert_eq!(engine.clamp_limit(5), (5, None));
+        assert_eq!(engine.clamp_limit(DEFAULT_MAX_SEARCH_LIMIT), (DEFAULT_MAX_SEARCH_LIMIT, None));
+
+        let (limit, note) = engine.clamp_limit(100_000);
+        assert_eq!(limit, DEFAULT_MAX_SEARCH_LIMIT);
+        assert!(note.unwrap().contains("max_search_limit"));
+
+        let engine = SearchEngine::new().with_max_limit(10);
+        assert_eq!(engine.clamp_limit(11).0, 10);
+    }
+
+    #[test]
+    fn test_limit_one_reranks_full_candidate_pool() {
+        let engine = SearchEngine::new().with_candidate_pool(10);
+        let pool = engine.candidate_count(1);
+        assert_eq!(pool, 10);
+
+        // Best keyword match sits at the bottom of the vector ranking
+        let mut results: Vec<SearchResult> = (0..pool - 1)
+            .map(|i| make_result(&i.to_string(), "unrelated text", 0.6 - i as f32 * 0.01))
+            .collect();
+        results.push(make_result("match", "rust borrow checker", 0.5));
+
+        let reranked = engine.rerank(results, "rust borrow checker", 1);
+        assert_eq!(reranked.len(), 1);
+        assert_eq!(reranked[0].id, "match");
+    }
+
+    #[test]
+    fn test_keyword_terms_drop_punctuation_and_repeats() {
+        assert_eq!(keyword_terms("What is the Borrow checker? borrow"), vec!["what", "is", "the", "borrow", "checker"]);
+        assert!(keyword_terms("  ?! ").is_empty());
+    }
+
+    #[test]
+    fn test_highlight_picks_window_with_most_terms() {
+        let filler = "Unrelated text about the weather and lunch plans. ".repeat(6);
+        let content = format!("Rust appears early. {}The Rust borrow checker rejects the program.", filler);
+
+        let h = highlight(&content, "rust borrow checker", HIGHLIGHT_CHARS);
+
+        assert!(h.snippet.chars().count() <= HIGHLIGHT_CHARS);
+        assert!(h.snippet.contains("The Rust borrow checker"));
+        assert_eq!(&content[h.snippet_start..h.snippet_start + h.snippet.len()], h.snippet);
+        let matched: Vec<&str> = h.matches.iter().map(|&(s, e)| &h.snippet[s..e]).collect();
+        assert!(matched.ends_with(&["Rust", "borrow", "checker"]), "{:?}", matched);
+    }
+
+    #[test]
+    fn test_highlight_without_match_starts_at_beginning() {
+        let content = "Ünïcode text with no matching terms at all, long enough to be cut short by the snippet size limit.";
+        let h = highlight(content, "kubernetes", 20);
+        assert_eq!(h.snippet_start, 0);
+        assert_eq!(h.snippet.chars().count(), 20);
+        assert!(h.matches.is_empty());
+    }
+
+    #[test]
+    fn test_expand_query_appends_synonyms() {
+        let engine = SearchEngine::new();
+        assert_eq!(engine.expand_query("auth setup"), "auth setup");
+
+        let synonyms = HashMap::from([
+            ("Auth".to_string(), vec!["authentication".to_string(), "login".to_string()]),
+            ("setup".to_string(), vec!["install".to_string(), "login".to_string()]),
+        ]);
+        let engine = SearchEngine::new().with_query_expansion(synonyms);
+        assert_eq!(engine.expand_query("auth setup"), "auth setup authentication login install");
+        // Terms already in the query aren't repeated
+        assert_eq!(engine.expand_query("auth authentication"), "auth authentication login");
+        assert_eq!(engine.expand_query("unrelated"), "unrelated");
+    }
+
+    #[test]
+    fn test_load_synonyms_from_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SYNONYMS_FILE);
+        assert!(load_synonyms_from(&path).unwrap().is_none());
+
+        std::fs::write(&path, "auth = [\"authentication\", \"login\"]\nk8s = [\"kubernetes\"]\n").unwrap();
+        let synonyms = load_synonyms_from(&path).unwrap().unwrap();
+        assert_eq!(synonyms["k8s"], vec!["kubernetes"]);
+        assert_eq!(synonyms["auth"].len(), 2);
+
+        std::fs::write(&path, "auth = \"not a list\"").unwrap();
+        assert!(load_synonyms_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_equal_scores_sort_by_id() {
+        let engine = SearchEngine::new();
+        let expected = vec!["a", "b", "c", "d"];
+        for input in [["c", "a", "d", "b"], ["d", "c", "b", "a"], ["b", "d", "a", "c"]] {
+            let results: Vec<SearchResult> = input.iter().map(|id| make_result(id, "same text", 0.5)).collect();
+            let ranked = engine.rerank_with_keywords(results, "unrelated query");
+            let ids: Vec<&str> = ranked.iter().map(|r| r.id.as_str()).collect();
+            assert_eq!(ids, expected);
+        }
+
+        // Score still comes first
+        let results = vec![make_result("a", "x", 0.6), make_result("z", "x", 0.4)];
+        let pinned = HashMap::from([("z".to_string(), Some(0.3))]);
+        let ranked = engine.boost_pinned(results, &pinned);
+        assert_eq!(ranked[0].id, "z");
+    }
+
+    #[test]
+    fn test_dedup_keeps_best_chunk_per_document_before_limit() {
+        let chunk = |id: &str, doc: &str, score: f32| SearchResult {
+            document_id: doc.to_string(),
+            ..make_result(id, "text", score)
+        };
+        let results = || vec![
+            chunk("a1", "a", 0.9),
+            chunk("a2", "a", 0.8),
+            chunk("b1", "b", 0.7),
+            chunk("a3", "a", 0.6),
+            chunk("c1", "c", 0.5),
+        ];
+        let no_pins = HashMap::new();
+
+        let engine = SearchEngine::new().with_dedup_by_document(true);
+        let ranked = engine.rerank_with_pins(results(), "unrelated query", 3, &no_pins);
+        let ids: Vec<&str> = ranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["a1", "b1", "c1"]);
+
+        let ranked = SearchEngine::new().rerank_with_pins(results(), "unrelated query", 3, &no_pins);
+        assert_eq!(ranked.len(), 3);
+        assert!(ranked.iter().all(|r| r.document_id == "a" || r.id == "b1"));
+    }
 }
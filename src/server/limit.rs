@@ -0,0 +1,114 @@
+//! Concurrency limit for expensive routes
+//!
+//! Each search embeds the query and may rerank, so a burst of requests can
+//! oversubscribe the CPU/GPU and slow every one of them down. At most
+//! `max_concurrent` requests run at once and up to `max_queued` more wait
+//! their turn; anything beyond that is shed with 503 and `Retry-After`.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use eywa::Config;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Default number of searches running at once (`search_max_concurrent`)
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+/// Default number of searches waiting for a slot (`search_max_queued`)
+pub const DEFAULT_MAX_QUEUED: usize = 32;
+/// Seconds clients are asked to wait after being shed
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// Shared limit state for one group of routes
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    /// Slots for requests currently running
+    running: Arc<Semaphore>,
+    /// Slots for requests running or queued
+    admitted: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            running: Arc::new(Semaphore::new(max_concurrent)),
+            admitted: Arc::new(Semaphore::new(max_concurrent + max_queued)),
+        }
+    }
+
+    /// Build the search limit from config, falling back to the defaults
+    pub fn for_search(config: &Config) -> Self {
+        Self::new(
+            config.search_max_concurrent.unwrap_or(DEFAULT_MAX_CONCURRENT),
+            config.search_max_queued.unwrap_or(DEFAULT_MAX_QUEUED),
+        )
+    }
+}
+
+/// Middleware: queue requests over the limit, shed them once the queue is full
+pub async fn limit_concurrency(
+    State(limit): State<ConcurrencyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Ok(_admitted) = limit.admitted.clone().try_acquire_owned() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+            Json(json!({ "error": "Server busy, too many concurrent requests. Retry shortly." })),
+        )
+            .into_response();
+    };
+
+    // The semaphore is never closed, so acquiring only waits
+    let _running = limit.running.acquire().await;
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request as HttpRequest, middleware, routing::get, Router};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_excess_requests_are_queued_or_shed() {
+        let limit = ConcurrencyLimit::new(1, 1);
+        let router = Router::new().route(
+            "/search",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "OK"
+            })
+            .layer(middleware::from_fn_with_state(limit, limit_concurrency)),
+        );
+
+        let requests = (0..4).map(|_| {
+            router
+                .clone()
+                .oneshot(HttpRequest::get("/search").body(Body::empty()).unwrap())
+        });
+        let responses: Vec<Response> = futures::future::join_all(requests)
+            .await
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+
+        // One runs, one waits its turn, the rest are shed
+        let ok = responses.iter().filter(|r| r.status() == StatusCode::OK).count();
+        let shed: Vec<&Response> = responses
+            .iter()
+            .filter(|r| r.status() == StatusCode::SERVICE_UNAVAILABLE)
+            .collect();
+        assert_eq!(ok, 2);
+        assert_eq!(shed.len(), 2);
+        assert!(shed.iter().all(|r| r.headers().get(header::RETRY_AFTER).unwrap() == "1"));
+    }
+}
@@ -1,46 +1,55 @@
 //! HTTP server module
 
 mod state;
+mod limit;
+mod pretty;
+mod request_id;
 mod routes;
 mod worker;
 
-pub use state::{AppState, DownloadJob, DownloadStatus, DownloadTracker, FileProgress, create_download_tracker};
+pub use state::{AppState, DownloadJob, DownloadStatus, DownloadTracker, FileProgress, ModelScanCache, RerankerCache, MODEL_SCAN_TTL, create_download_tracker};
 use routes::create_router;
-pub use worker::run_queue_worker;
+pub use worker::{run_queue_worker, DEFAULT_DOC_TIMEOUT_SECS};
 
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use eywa::{create_job_queue, BM25Index, Embedder, SearchEngine, VectorDB};
+use eywa::{create_job_queue, BM25Index, Config, Embedder, SearchEngine, VectorDB};
 
 /// Run the HTTP server
 pub async fn run_server(data_dir: &str, port: u16) -> Result<()> {
+    // Request logs are off unless RUST_LOG is set (e.g. RUST_LOG=tower_http=info)
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+
     // Shared components
+    eywa::require_models()?;
+    let config = Config::load()?.unwrap_or_default();
     let embedder = Arc::new(Embedder::new()?);
-    let db = Arc::new(RwLock::new(VectorDB::new(data_dir).await?));
-    let bm25_index = Arc::new(BM25Index::open(std::path::Path::new(data_dir))?);
-    let search_engine = SearchEngine::new();
-    let job_db_path = std::path::Path::new(data_dir).join("jobs.db");
-    let job_queue = create_job_queue(&job_db_path)?;
-
-    let state = Arc::new(AppState {
-        embedder: Arc::clone(&embedder),
-        db: Arc::clone(&db),
-        bm25_index: Arc::clone(&bm25_index),
-        search_engine,
-        job_queue: Arc::clone(&job_queue),
-        data_dir: data_dir.to_string(),
-        downloads: create_download_tracker(),
-    });
+    let state = build_state(data_dir, config, embedder).await?;
 
     // Spawn background worker for processing queue
-    let worker_queue = Arc::clone(&job_queue);
-    let worker_embedder = Arc::clone(&embedder);
-    let worker_db = Arc::clone(&db);
-    let worker_bm25 = Arc::clone(&bm25_index);
+    let worker_queue = Arc::clone(&state.job_queue);
+    let worker_embedder = Arc::clone(&state.embedder);
+    let worker_db = Arc::clone(&state.db);
+    let worker_bm25 = Arc::clone(&state.bm25_index);
     let worker_data_dir = data_dir.to_string();
+    let worker_config = state.config.clone();
+    let worker_timeout = std::time::Duration::from_secs(
+        state.config.worker_timeout_secs.unwrap_or(DEFAULT_DOC_TIMEOUT_SECS),
+    );
     tokio::spawn(async move {
-        run_queue_worker(worker_queue, worker_embedder, worker_db, worker_bm25, worker_data_dir).await;
+        run_queue_worker(
+            worker_queue,
+            worker_embedder,
+            worker_db,
+            worker_bm25,
+            worker_data_dir,
+            worker_config,
+            worker_timeout,
+        )
+        .await;
     });
 
     // Create router
@@ -61,6 +70,7 @@ pub async fn run_server(data_dir: &str, port: u16) -> Result<()> {
 
     println!("Server running on http://localhost:{}", port);
     println!("Web UI v1:       http://localhost:{}/v1", port);
+    println!("Web UI v2:       http://localhost:{}/v2", port);
     println!("\nAPI Endpoints:");
     println!("  GET    /health                  - Health check");
     println!("  GET    /api/info                - System info (models, storage, stats)");
@@ -71,13 +81,22 @@ pub async fn run_server(data_dir: &str, port: u16) -> Result<()> {
     println!("  GET    /api/jobs/:id            - Get job progress");
     println!("  GET    /api/jobs/:id/docs       - Get per-document status");
     println!("  GET    /api/sources             - List all sources");
+    println!("  POST   /api/sources             - Create an empty source");
     println!("  DELETE /api/sources/:id         - Delete a source");
     println!("  GET    /api/sources/:id/docs    - List documents in source");
+    println!("  GET    /api/sources/:id/stats   - Document, chunk and size stats for a source");
     println!("  GET    /api/sources/:id/export  - Export source as zip");
-    println!("  GET    /api/docs/:id            - Get document content");
+    println!("  GET    /api/docs/:id            - Get document content (?chunk=&context= for a window)");
     println!("  DELETE /api/docs/:id            - Delete a document");
+    println!("  GET    /api/docs/:id/similar    - Find related documents");
+    println!("  POST   /api/docs/:id/reembed    - Re-chunk and re-embed a document");
+    println!("  POST   /api/docs/:id/pin        - Pin a document to boost it in search");
+    println!("  GET    /api/bookmarks           - List bookmarked documents");
+    println!("  POST   /api/bookmarks           - Bookmark a document");
+    println!("  DELETE /api/bookmarks/:id       - Remove a bookmark");
     println!("  GET    /api/export              - Export all docs as zip");
     println!("  DELETE /api/reset               - Reset all data");
+    println!("  POST   /api/reindex             - Rebuild the index from stored documents (job)");
     println!("  GET    /api/settings            - Get current settings");
     println!("  PATCH  /api/settings            - Update settings");
     println!("  GET    /api/models/embedders    - List embedding models");
@@ -90,3 +109,28 @@ pub async fn run_server(data_dir: &str, port: u16) -> Result<()> {
     axum::serve(listener, app).await?;
     Ok(())
 }
+
+/// Open the stores and search engine the server shares across requests
+pub(crate) async fn build_state(data_dir: &str, config: Config, embedder: Arc<Embedder>) -> Result<Arc<AppState>> {
+    let db = Arc::new(RwLock::new(VectorDB::open(data_dir, config.per_source_tables).await?));
+    let bm25_index = Arc::new(BM25Index::open(std::path::Path::new(data_dir))?);
+    // Loaded once and shared; `Reranker::rerank` takes `&self`, so concurrent requests can score at once
+    let search_engine = SearchEngine::with_reranker()
+        .with_settings(&config)
+        .with_synonyms_file();
+    let job_db_path = std::path::Path::new(data_dir).join("jobs.db");
+    let job_queue = create_job_queue(&job_db_path)?;
+
+    Ok(Arc::new(AppState {
+        embedder,
+        db,
+        bm25_index,
+        search_engine,
+        job_queue,
+        data_dir: data_dir.to_string(),
+        downloads: create_download_tracker(),
+        model_scan: Arc::new(ModelScanCache::new(MODEL_SCAN_TTL)),
+        rerankers: RerankerCache::new(config.device.clone()),
+        config,
+    }))
+}
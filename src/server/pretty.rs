@@ -0,0 +1,99 @@
+//! Pretty-printed JSON for interactive use
+//!
+//! Responses stay compact by default. Adding `?pretty=1` (or `?pretty`,
+//! `?pretty=true`) to any request re-indents a JSON response body, which
+//! makes exploring the API with curl or a browser bearable.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::Value;
+
+/// Wrap a router so JSON responses can be pretty-printed on request
+pub fn with_pretty_json(router: Router) -> Router {
+    router.layer(middleware::from_fn(pretty_json))
+}
+
+/// Whether the query string asks for pretty output
+fn wants_pretty(query: Option<&str>) -> bool {
+    query.is_some_and(|q| {
+        q.split('&')
+            .any(|pair| matches!(pair, "pretty" | "pretty=1" | "pretty=true"))
+    })
+}
+
+/// Re-indent JSON bodies when `pretty` is set
+async fn pretty_json(request: Request, next: Next) -> Response {
+    let pretty = wants_pretty(request.uri().query());
+    let response = next.run(request).await;
+    if !pretty {
+        return response;
+    }
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.as_bytes().starts_with(b"application/json"))
+        .unwrap_or(false);
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => serde_json::to_vec_pretty(&value).unwrap_or_else(|_| bytes.to_vec()),
+        Err(_) => bytes.to_vec(),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::Request as HttpRequest, routing::get, Json};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn body_of(uri: &str) -> String {
+        let router = with_pretty_json(
+            Router::new().route("/stats", get(|| async { Json(json!({ "sources": 2, "chunks": 40 })) })),
+        );
+        let response = router
+            .oneshot(HttpRequest::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pretty_query_indents_json() {
+        let compact = body_of("/stats").await;
+        assert!(!compact.contains('\n'));
+
+        let pretty = body_of("/stats?pretty=1").await;
+        assert!(pretty.contains("\n  \"sources\": 2"));
+        let parsed: Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(parsed, serde_json::from_str::<Value>(&compact).unwrap());
+    }
+
+    #[test]
+    fn test_wants_pretty() {
+        assert!(wants_pretty(Some("pretty")));
+        assert!(wants_pretty(Some("limit=5&pretty=true")));
+        assert!(!wants_pretty(Some("pretty=0")));
+        assert!(!wants_pretty(None));
+    }
+}
@@ -0,0 +1,137 @@
+//! Request IDs for correlating responses with server logs
+//!
+//! Every request gets an `x-request-id` (a client-supplied one is kept),
+//! which is recorded on the request's tracing span, echoed back as a response
+//! header, and added to JSON error bodies as `request_id`.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderName, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::{json, Value};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Wrap a router so every request carries a request ID
+pub fn with_request_id(router: Router) -> Router {
+    let header_name = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    // Layers run outermost-last: assign ID, propagate it, trace, then echo in errors
+    router
+        .layer(middleware::from_fn(echo_request_id_in_errors))
+        .layer(TraceLayer::new_for_http().make_span_with(|request: &Request| {
+            let request_id = request
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-");
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                request_id = %request_id,
+            )
+        }))
+        .layer(PropagateRequestIdLayer::new(header_name.clone()))
+        .layer(SetRequestIdLayer::new(header_name, MakeRequestUuid))
+}
+
+/// Add `request_id` to JSON error bodies
+async fn echo_request_id_in_errors(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+
+    let Some(request_id) = request_id else {
+        return response;
+    };
+    if !(response.status().is_client_error() || response.status().is_server_error()) {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.as_bytes().starts_with(b"application/json"))
+        .unwrap_or(false);
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(b) => b,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut map)) => {
+            tracing::warn!(request_id = %request_id, status = %parts.status, "request failed");
+            map.insert("request_id".to_string(), json!(request_id));
+            serde_json::to_vec(&map).unwrap_or_else(|_| bytes.to_vec())
+        }
+        _ => bytes.to_vec(),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::Request as HttpRequest, routing::get, Json};
+    use tower::ServiceExt;
+
+    fn test_router() -> Router {
+        with_request_id(
+            Router::new()
+                .route("/ok", get(|| async { "OK" }))
+                .route("/fail", get(|| async {
+                    (StatusCode::NOT_FOUND, Json(json!({ "error": "Document not found" })))
+                })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_response_carries_request_id() {
+        let response = test_router()
+            .oneshot(HttpRequest::get("/ok").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let id = response.headers().get(REQUEST_ID_HEADER).expect("Missing request ID header");
+        assert!(!id.to_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_is_honored_and_echoed_in_errors() {
+        let response = test_router()
+            .oneshot(
+                HttpRequest::get("/fail")
+                    .header(REQUEST_ID_HEADER, "client-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get(REQUEST_ID_HEADER).unwrap(), "client-123");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], "client-123");
+        assert_eq!(body["error"], "Document not found");
+    }
+}
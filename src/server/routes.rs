@@ -4,6 +4,7 @@ use axum::{
     body::Body,
     extract::{DefaultBodyLimit, Path, Query, State},
     http::{header, StatusCode},
+    middleware,
     response::{Html, IntoResponse, Json, Response},
     routing::{delete, get, patch, post},
     Router,
@@ -14,9 +15,13 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 
-use eywa::{db, chunking, Config, ContentStore, DevicePreference, DocumentInput, EmbeddingModelConfig, FetchUrlRequest, gpu_support_info, IngestPipeline, IngestRequest, RerankerModelConfig, SearchRequest, SearchResult};
+use eywa::{db, chunking, find_similar_documents, model_mismatch_warning, Config, ContentStore, DevicePreference, DocumentInput, EmbeddingModelConfig, FetchUrlRequest, gpu_support_info, IngestPipeline, IngestRequest, RerankerModelConfig, SearchRequest, Source, UiVersion, VectorDB};
+use eywa::search::{best_per_document, highlight, HIGHLIGHT_CHARS};
 use eywa::setup::{DownloadProgress, ModelDownloader, ModelInfo};
 use crate::server::{AppState, DownloadJob, DownloadStatus, DownloadTracker, FileProgress};
+use crate::server::limit::{limit_concurrency, ConcurrencyLimit};
+use crate::server::pretty::with_pretty_json;
+use crate::server::request_id::with_request_id;
 use crate::utils::{create_zip, dir_size, extract_text_from_html, extract_title_from_html, lance_db_size, scan_hf_cache};
 
 /// Capitalize device name to match available_devices format (Auto, Cpu, Metal, Cuda)
@@ -52,15 +57,35 @@ fn preprocess_documents(documents: Vec<DocumentInput>) -> Vec<DocumentInput> {
     }).collect()
 }
 
+const UI_V1_INDEX: &str = include_str!("../../web/index.html");
+const UI_V2_INDEX: &str = include_str!("../../web/v2/index.html");
+
 /// Create the main application router
 pub fn create_router(state: Arc<AppState>) -> Router {
+    let default_ui = state.config.default_ui;
     let api = create_api_routes(state);
 
+    let router = create_ui_routes(default_ui)
+        .route("/health", get(|| async { "OK" }))
+        .nest("/api", api)
+        .layer(CorsLayer::permissive())
+        .layer(DefaultBodyLimit::max(100 * 1024 * 1024)); // 100MB limit
+
+    // Outermost, so error bodies are pretty-printed after the request ID is added
+    with_pretty_json(with_request_id(router))
+}
+
+/// Web UI routes; `/` serves `default_ui`, `/v1` and `/v2` are always available
+fn create_ui_routes(default_ui: UiVersion) -> Router {
+    let index = match default_ui {
+        UiVersion::V1 => UI_V1_INDEX,
+        UiVersion::V2 => UI_V2_INDEX,
+    };
+
     Router::new()
-        // Web UI v2 (default)
-        .route("/", get(|| async {
-            Html(include_str!("../../web/v2/index.html"))
-        }))
+        .route("/", get(move || async move { Html(index) }))
+        // Web UI v2
+        .route("/v2", get(|| async { Html(UI_V2_INDEX) }))
         .route("/style.css", get(|| async {
             (
                 [(header::CONTENT_TYPE, "text/css")],
@@ -122,20 +147,20 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             )
         }))
         // Web UI v1 (legacy)
-        .route("/v1", get(|| async {
-            Html(include_str!("../../web/index.html"))
-        }))
-        .route("/health", get(|| async { "OK" }))
-        .nest("/api", api)
-        .layer(CorsLayer::permissive())
-        .layer(DefaultBodyLimit::max(100 * 1024 * 1024)) // 100MB limit
+        .route("/v1", get(|| async { Html(UI_V1_INDEX) }))
 }
 
 /// Create API routes
 fn create_api_routes(state: Arc<AppState>) -> Router {
+    let search_limit = ConcurrencyLimit::for_search(&state.config);
+
     Router::new()
         .route("/info", get(handle_info))
-        .route("/search", post(handle_search))
+        .route("/stats", get(handle_stats))
+        .route(
+            "/search",
+            post(handle_search).layer(middleware::from_fn_with_state(search_limit, limit_concurrency)),
+        )
         .route("/ingest", post(handle_ingest))
         .route("/queue", post(handle_queue))
         .route("/ingest/async", post(handle_ingest_async))
@@ -143,14 +168,23 @@ fn create_api_routes(state: Arc<AppState>) -> Router {
         .route("/jobs/:job_id", get(handle_get_job))
         .route("/jobs/:job_id/docs", get(handle_get_job_docs))
         .route("/sources", get(handle_list_sources))
+        .route("/sources", post(handle_create_source))
         .route("/sources/:source_id", delete(handle_delete_source))
         .route("/sources/:source_id/docs", get(handle_list_source_docs))
+        .route("/sources/:source_id/stats", get(handle_source_stats))
         .route("/sources/:source_id/export", get(handle_export_source))
         .route("/docs/:doc_id", get(handle_get_doc))
         .route("/docs/:doc_id", delete(handle_delete_doc))
+        .route("/docs/:doc_id/similar", get(handle_similar_docs))
+        .route("/docs/:doc_id/reembed", post(handle_reembed_doc))
+        .route("/docs/:doc_id/pin", post(handle_pin_doc))
+        .route("/bookmarks", get(handle_list_bookmarks))
+        .route("/bookmarks", post(handle_add_bookmark))
+        .route("/bookmarks/:doc_id", delete(handle_remove_bookmark))
         .route("/sql/sources", get(handle_sql_sources))
         .route("/sql/sources/:source_id/docs", get(handle_sql_source_docs))
         .route("/reset", delete(handle_reset))
+        .route("/reindex", post(handle_reindex))
         .route("/export", get(handle_export))
         .route("/fetch-preview", post(handle_fetch_preview))
         .route("/fetch-url", post(handle_fetch_url))
@@ -171,19 +205,34 @@ fn create_api_routes(state: Arc<AppState>) -> Router {
 // Route Handlers
 // ─────────────────────────────────────────────────────────────────────────────
 
-async fn handle_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let config = Config::load().ok().flatten();
-    let db = state.db.read().await;
+/// Source, document and chunk counts (no filesystem walks)
+async fn index_stats(db: &VectorDB, data_dir: &std::path::Path) -> serde_json::Value {
     let sources = db.list_sources().await.unwrap_or_default();
-
-    let source_count = sources.len();
     let chunk_count: u64 = sources.iter().map(|s| s.chunk_count).sum();
-
-    let document_count = ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db"))
+    let document_count = ContentStore::open(&data_dir.join("content.db"))
         .ok()
         .and_then(|cs| cs.count_documents().ok())
         .unwrap_or(0);
 
+    json!({
+        "source_count": sources.len(),
+        "document_count": document_count,
+        "chunk_count": chunk_count
+    })
+}
+
+/// Just the counts from `/info`, for widgets that poll
+async fn handle_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let db = state.db.read().await;
+    let stats = index_stats(&db, std::path::Path::new(&state.data_dir)).await;
+    (StatusCode::OK, Json(stats))
+}
+
+async fn handle_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = Config::load().ok().flatten();
+    let db = state.db.read().await;
+    let stats = index_stats(&db, std::path::Path::new(&state.data_dir)).await;
+
     let data_path = std::path::Path::new(&state.data_dir);
     let content_db_bytes = std::fs::metadata(data_path.join("content.db"))
         .map(|m| m.len())
@@ -192,11 +241,7 @@ async fn handle_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let bm25_index_bytes = dir_size(&data_path.join("tantivy")).unwrap_or(0);
 
     let mut response = json!({
-        "stats": {
-            "source_count": source_count,
-            "document_count": document_count,
-            "chunk_count": chunk_count
-        },
+        "stats": stats,
         "storage": {
             "content_db_bytes": content_db_bytes,
             "vector_db_bytes": vector_db_bytes,
@@ -216,7 +261,7 @@ async fn handle_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
         });
     }
 
-    let cached_models = scan_hf_cache();
+    let cached_models = state.model_scan.get_or_scan(scan_hf_cache);
     let cached_models_json: Vec<_> = cached_models.iter().map(|m| {
         json!({
             "name": m.name,
@@ -239,55 +284,144 @@ async fn handle_search(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<SearchRequest>,
 ) -> impl IntoResponse {
-    let query_embedding = match state.embedder.embed(&payload.query) {
+    let query_embedding = match state.embedder.embed_query(&payload.query) {
         Ok(e) => e,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     };
 
+    let min_score = payload.min_score.unwrap_or(state.search_engine.min_score);
+    if !(0.0..=1.0).contains(&min_score) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "min_score must be between 0.0 and 1.0" })));
+    }
+
     let db = state.db.read().await;
-    let chunk_metas = match db.search(&query_embedding, payload.limit * 2).await {
-        Ok(r) => r,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+
+    if payload.count_only {
+        let source_id = payload.source_id.as_deref();
+        return match db.count_matches(&query_embedding, min_score, source_id, payload.code_only).await {
+            Ok(count) => (StatusCode::OK, Json(json!({ "query": payload.query, "count": count }))),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        };
+    }
+
+    // `rerank: true` without a model uses the engine's reranker, or loads the configured one
+    let rerank_model = match (payload.rerank, payload.rerank_model.as_deref()) {
+        (Some(false), Some(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "rerank_model can't be combined with rerank: false" })));
+        }
+        (_, Some(model_id)) => Some(model_id.to_string()),
+        (Some(true), None) if !state.search_engine.has_reranker() => Some(state.config.reranker_model.id.clone()),
+        _ => None,
+    };
+
+    // Unknown models are rejected before any search work is done
+    let override_reranker = match rerank_model.as_deref() {
+        None => None,
+        Some(model_id) => match state.rerankers.get_or_load(model_id).await {
+            Ok(Some(reranker)) => Some(reranker),
+            Ok(None) => {
+                let known: Vec<String> = RerankerModelConfig::curated_models().into_iter().map(|m| m.id).collect();
+                return (StatusCode::BAD_REQUEST, Json(json!({
+                    "error": format!("Unknown rerank_model '{}'. Known models: {}", model_id, known.join(", "))
+                })));
+            }
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        },
     };
 
+    let (limit, limit_note) = state.search_engine.clamp_limit(payload.limit);
+    let candidates = state.search_engine.candidate_count(limit);
+    let per_source = payload
+        .candidates_per_source
+        .map(|n| state.search_engine.clamp_limit(n).0)
+        .unwrap_or(state.search_engine.candidates_per_source);
     let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
         Ok(cs) => cs,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     };
 
-    let chunk_ids: Vec<&str> = chunk_metas.iter().map(|c| c.id.as_str()).collect();
-    let contents = match content_store.get_chunks(&chunk_ids) {
-        Ok(c) => c,
+    let results = match state
+        .search_engine
+        .hybrid_candidates(
+            &db,
+            &state.bm25_index,
+            &content_store,
+            &payload.query,
+            &query_embedding,
+            candidates,
+            payload.source_id.as_deref(),
+            payload.code_only,
+            per_source,
+            min_score,
+        )
+        .await
+    {
+        Ok(r) => r,
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     };
-    let content_map: HashMap<String, String> = contents.into_iter().collect();
 
-    let results: Vec<SearchResult> = chunk_metas
-        .into_iter()
-        .filter_map(|meta| {
-            let content = content_map.get(&meta.id)?.clone();
-            Some(SearchResult {
-                id: meta.id,
-                source_id: meta.source_id,
-                title: meta.title,
-                content,
-                file_path: meta.file_path,
-                line_start: meta.line_start,
-                score: meta.score,
-            })
+    let results = state.search_engine.filter_min_length(results, payload.min_content_length);
+    let results = if override_reranker.is_none() && payload.rerank == Some(false) {
+        // Fused vector + keyword order as is
+        results
+    } else {
+        // Cross-encoder scoring is CPU-bound, so keep it off the async runtime
+        let rerank_state = Arc::clone(&state);
+        let reranker = override_reranker.clone();
+        let query = payload.query.clone();
+        let reranked = tokio::task::spawn_blocking(move || match reranker {
+            Some(reranker) => rerank_state.search_engine.rerank_using(&reranker, results, &query),
+            // Neural reranker when loaded, keyword boost otherwise
+            None => {
+                let count = results.len();
+                Ok(rerank_state.search_engine.rerank(results, &query, count))
+            }
         })
-        .collect();
-
-    let results = state.search_engine.filter_results(results);
-    let results = state.search_engine.rerank_with_keywords(results, &payload.query);
-    let results: Vec<_> = results.into_iter().take(payload.limit).collect();
+        .await;
+        match reranked {
+            Ok(Ok(r)) => r,
+            Ok(Err(e)) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        }
+    };
+    let result_ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    let pinned = match content_store.pinned_chunks(&result_ids) {
+        Ok(p) => p,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    let results = state.search_engine.boost_pinned(results, &pinned);
+    let results = if payload.dedup { best_per_document(results) } else { results };
+    let results: Vec<_> = results.into_iter().take(limit).collect();
     let count = results.len();
 
-    (StatusCode::OK, Json(json!({
+    let index_model = content_store.index_model().ok().flatten();
+    let query_model = state.embedder.model_id();
+    let mut response = json!({
         "query": payload.query,
         "results": results,
-        "count": count
-    })))
+        "count": count,
+        "reranked": override_reranker.is_some() || (payload.rerank != Some(false) && state.search_engine.has_reranker()),
+        "index_model": index_model,
+        "query_model": query_model
+    });
+    if let Some(model_id) = override_reranker.as_ref().and(rerank_model.as_deref()) {
+        response["rerank_model"] = json!(model_id);
+    }
+    if let Some(warning) = model_mismatch_warning(index_model.as_deref(), query_model) {
+        response["warning"] = json!(warning);
+    }
+    if let Some(note) = limit_note {
+        response["note"] = json!(note);
+    }
+    if payload.highlight {
+        let highlights: Vec<_> = results
+            .iter()
+            .map(|r| highlight(&r.content, &payload.query, HIGHLIGHT_CHARS))
+            .collect();
+        response["highlights"] = json!(highlights);
+    }
+
+    (StatusCode::OK, Json(response))
 }
 
 async fn handle_ingest(
@@ -296,7 +430,8 @@ async fn handle_ingest(
 ) -> impl IntoResponse {
     let data_dir = std::path::Path::new(&state.data_dir);
     let mut db = state.db.write().await;
-    let pipeline = IngestPipeline::new(Arc::clone(&state.embedder), Arc::clone(&state.bm25_index));
+    let pipeline = IngestPipeline::new(Arc::clone(&state.embedder), Arc::clone(&state.bm25_index))
+        .with_settings(&state.config);
 
     match pipeline.ingest_documents(&mut db, data_dir, &payload.source_id, payload.documents).await {
         Ok(result) => (StatusCode::OK, Json(json!(result))),
@@ -394,8 +529,66 @@ async fn handle_get_job_docs(
 
 async fn handle_list_sources(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let db = state.db.read().await;
-    match db.list_sources().await {
-        Ok(sources) => (StatusCode::OK, Json(json!({ "sources": sources }))),
+    let mut sources = match db.list_sources().await {
+        Ok(sources) => sources,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    // Sources created via POST /api/sources carry a description and may be empty
+    let descriptions = ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db"))
+        .and_then(|cs| cs.source_descriptions());
+    match descriptions {
+        Ok(descriptions) => {
+            for (id, description) in descriptions {
+                match sources.iter_mut().find(|s| s.id == id) {
+                    Some(source) => source.description = description,
+                    None => sources.push(Source {
+                        id: id.clone(),
+                        name: id,
+                        description,
+                        doc_count: 0,
+                        chunk_count: 0,
+                        last_indexed: None,
+                    }),
+                }
+            }
+            (StatusCode::OK, Json(json!({ "sources": sources })))
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateSourceRequest {
+    id: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// POST /api/sources - Create an empty source ahead of ingesting into it
+async fn handle_create_source(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateSourceRequest>,
+) -> impl IntoResponse {
+    let id = payload.id.trim();
+    if id.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Source id is required" })));
+    }
+
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match content_store.create_source(id, payload.description.as_deref()) {
+        Ok(true) => (
+            StatusCode::CREATED,
+            Json(json!({ "id": id, "description": payload.description, "doc_count": 0 })),
+        ),
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": format!("Source '{}' already exists", id) })),
+        ),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     }
 }
@@ -406,6 +599,12 @@ async fn handle_delete_source(
 ) -> impl IntoResponse {
     let db = state.db.read().await;
 
+    // Cancel queued docs first; the worker can't write while we hold the lock
+    let cancelled = state.job_queue.lock().unwrap().cancel_source(&source_id);
+    if let Err(e) = cancelled {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
+    }
+
     if let Err(e) = db.delete_source(&source_id).await {
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
     }
@@ -434,18 +633,83 @@ async fn handle_list_source_docs(
     let limit = params.get("limit").and_then(|v| {
         if v == "all" { Some(db::MAX_QUERY_LIMIT) } else { v.parse().ok() }
     });
+    let offset = params.get("offset").and_then(|v| v.parse().ok());
 
     let db = state.db.read().await;
-    match db.list_documents(&source_id, limit).await {
-        Ok(docs) => (StatusCode::OK, Json(json!({ "documents": docs }))),
+    let total = match db.count_documents(&source_id).await {
+        Ok(total) => total,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match db.list_documents(&source_id, limit, offset).await {
+        Ok(docs) => (StatusCode::OK, Json(json!({
+            "documents": docs,
+            "total_documents": total
+        }))),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     }
 }
 
+/// Size and freshness of one source, for a source detail page
+///
+/// Document count, compressed size and last update come from SQLite; chunk
+/// count and uncompressed size from LanceDB.
+async fn handle_source_stats(
+    State(state): State<Arc<AppState>>,
+    Path(source_id): Path<String>,
+) -> impl IntoResponse {
+    // Read in a block to drop ContentStore before await
+    let stats = {
+        let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+            Ok(cs) => cs,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        };
+        content_store.source_stats(&source_id)
+    };
+    let stats = match stats {
+        Ok(Some(stats)) => stats,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Source '{}' not found or has no documents", source_id) })),
+            )
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    let db = state.db.read().await;
+    let docs = match db.list_documents(&source_id, Some(db::MAX_QUERY_LIMIT), None).await {
+        Ok(docs) => docs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    let chunk_count: u64 = docs.iter().map(|d| d.chunk_count as u64).sum();
+    let content_bytes: u64 = docs.iter().map(|d| d.content_length as u64).sum();
+
+    (StatusCode::OK, Json(json!({
+        "source_id": source_id,
+        "description": stats.description,
+        "document_count": stats.doc_count,
+        "chunk_count": chunk_count,
+        "content_bytes": content_bytes,
+        "compressed_bytes": stats.total_size,
+        "last_updated": stats.last_updated
+    })))
+}
+
+/// Chunks either side of `?chunk=` returned when `context` isn't given
+const DEFAULT_CHUNK_CONTEXT: usize = 2;
+
 async fn handle_get_doc(
     State(state): State<Arc<AppState>>,
     Path(doc_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
+    let context = match params.get("context").map(|v| v.parse::<usize>()) {
+        None => DEFAULT_CHUNK_CONTEXT,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "context must be a non-negative integer" }))),
+    };
+
     let db = state.db.read().await;
     let record = match db.get_document(&doc_id).await {
         Ok(Some(r)) => r,
@@ -458,10 +722,21 @@ async fn handle_get_doc(
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
     };
 
-    let content = match content_store.get_document(&doc_id) {
-        Ok(Some(c)) => c,
-        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Document content not found" }))),
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    // With `?chunk=`, only that chunk and its neighbours instead of the whole document
+    let (content, chunk_range) = match params.get("chunk") {
+        Some(chunk_id) => match content_store.chunk_window(&doc_id, chunk_id, context) {
+            Ok(Some(window)) => {
+                let texts: Vec<&str> = window.chunks.iter().map(|(_, text)| text.as_str()).collect();
+                (texts.join("\n\n"), Some(json!({ "start": window.first, "end": window.last })))
+            }
+            Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Chunk '{}' not found in document", chunk_id) }))),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        },
+        None => match content_store.get_document(&doc_id) {
+            Ok(Some(c)) => (c, None),
+            Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Document content not found" }))),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        },
     };
 
     let doc = eywa::Document {
@@ -474,7 +749,92 @@ async fn handle_get_doc(
         chunk_count: record.chunk_count,
     };
 
-    (StatusCode::OK, Json(json!(doc)))
+    let mut response = json!(doc);
+    if let Some(range) = chunk_range {
+        response["chunk_range"] = range;
+    }
+    (StatusCode::OK, Json(response))
+}
+
+async fn handle_similar_docs(
+    State(state): State<Arc<AppState>>,
+    Path(doc_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let limit = params.get("limit").and_then(|v| v.parse().ok()).unwrap_or(5);
+
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    let db = state.db.read().await;
+    match find_similar_documents(&state.embedder, &db, &content_store, &state.search_engine, &doc_id, limit).await {
+        Ok(Some(similar)) => (StatusCode::OK, Json(json!({
+            "document_id": doc_id,
+            "count": similar.len(),
+            "similar": similar
+        }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Document not found" }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+/// Request for POST /api/bookmarks
+#[derive(Deserialize)]
+struct AddBookmarkRequest {
+    document_id: String,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+async fn handle_list_bookmarks(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match content_store.list_bookmarks() {
+        Ok(bookmarks) => (StatusCode::OK, Json(json!({ "bookmarks": bookmarks }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+async fn handle_add_bookmark(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AddBookmarkRequest>,
+) -> impl IntoResponse {
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match content_store.get_document(&payload.document_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Document not found" }))),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+
+    match content_store.add_bookmark(&payload.document_id, payload.note.as_deref()) {
+        Ok(()) => (StatusCode::CREATED, Json(json!({ "bookmarked": payload.document_id }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+async fn handle_remove_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path(doc_id): Path<String>,
+) -> impl IntoResponse {
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match content_store.remove_bookmark(&doc_id) {
+        Ok(true) => (StatusCode::OK, Json(json!({ "removed": doc_id }))),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Bookmark not found" }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
 }
 
 async fn handle_delete_doc(
@@ -498,6 +858,60 @@ async fn handle_delete_doc(
     (StatusCode::OK, Json(json!({ "deleted": doc_id })))
 }
 
+/// Request for POST /api/docs/:id/pin (an empty object pins with the default boost)
+#[derive(Deserialize)]
+struct PinRequest {
+    #[serde(default = "default_pinned")]
+    pinned: bool,
+    #[serde(default)]
+    boost: Option<f32>,
+}
+
+fn default_pinned() -> bool {
+    true
+}
+
+async fn handle_pin_doc(
+    State(state): State<Arc<AppState>>,
+    Path(doc_id): Path<String>,
+    Json(payload): Json<PinRequest>,
+) -> impl IntoResponse {
+    let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
+        Ok(cs) => cs,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    match content_store.set_pinned(&doc_id, payload.pinned, payload.boost) {
+        Ok(true) => (StatusCode::OK, Json(json!({
+            "document_id": doc_id,
+            "pinned": payload.pinned,
+            "boost": payload.boost.filter(|_| payload.pinned)
+        }))),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Document not found" }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
+async fn handle_reembed_doc(
+    State(state): State<Arc<AppState>>,
+    Path(doc_id): Path<String>,
+) -> impl IntoResponse {
+    let data_dir = std::path::Path::new(&state.data_dir);
+    let mut db = state.db.write().await;
+    let pipeline = IngestPipeline::new(Arc::clone(&state.embedder), Arc::clone(&state.bm25_index))
+        .with_settings(&state.config);
+
+    match pipeline.reembed_document(&mut db, data_dir, &doc_id).await {
+        Ok(Some(result)) => (StatusCode::OK, Json(json!({
+            "document_id": doc_id,
+            "chunks_created": result.chunks_created,
+            "chunks_skipped": result.chunks_skipped
+        }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "error": "Document not found" }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
 async fn handle_sql_sources(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
         Ok(cs) => cs,
@@ -536,6 +950,11 @@ async fn handle_sql_source_docs(
 
 async fn handle_reset(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let mut db = state.db.write().await;
+    let cancelled = state.job_queue.lock().unwrap().cancel_all();
+    if let Err(e) = cancelled {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
+    }
+
     if let Err(e) = db.reset_all().await {
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
     }
@@ -556,6 +975,28 @@ async fn handle_reset(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     (StatusCode::OK, Json(json!({ "status": "reset complete" })))
 }
 
+/// POST /api/reindex - Queue a rebuild of LanceDB and BM25 from SQLite
+async fn handle_reindex(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let result = {
+        let mut queue = state.job_queue.lock().unwrap();
+        queue.active_reindex_job().and_then(|active| match active {
+            Some(job_id) => Ok((job_id, false)),
+            None => queue.queue_reindex().map(|job_id| (job_id, true)),
+        })
+    };
+    match result {
+        Ok((job_id, true)) => (StatusCode::ACCEPTED, Json(json!({
+            "job_id": job_id,
+            "status": "queued"
+        }))),
+        Ok((job_id, false)) => (StatusCode::CONFLICT, Json(json!({
+            "error": "A reindex is already queued or running",
+            "job_id": job_id
+        }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })))
+    }
+}
+
 async fn handle_export(State(state): State<Arc<AppState>>) -> Response {
     let content_store = match ContentStore::open(&std::path::Path::new(&state.data_dir).join("content.db")) {
         Ok(cs) => cs,
@@ -678,7 +1119,8 @@ async fn handle_fetch_preview(Json(payload): Json<serde_json::Value>) -> impl In
     };
 
     let content = extract_text_from_html(&html);
-    let title = extract_title_from_html(&html).unwrap_or_else(|| url.clone());
+    let strip_site_name = payload.get("strip_site_name").and_then(|v| v.as_bool()).unwrap_or(false);
+    let title = extract_title_from_html(&html, strip_site_name).unwrap_or_else(|| url.clone());
 
     if content.trim().is_empty() {
         return (StatusCode::BAD_REQUEST, Json(json!({ "error": "No text content found in page" })));
@@ -711,17 +1153,13 @@ async fn handle_fetch_url(
     };
 
     let content = extract_text_from_html(&html);
-    let title = extract_title_from_html(&html).unwrap_or_else(|| payload.url.clone());
+    let title = extract_title_from_html(&html, payload.strip_site_name).unwrap_or_else(|| payload.url.clone());
 
     if content.trim().is_empty() {
         return (StatusCode::BAD_REQUEST, Json(json!({ "error": "No text content found in page" })));
     }
 
     let source_id = payload.source_id.unwrap_or_else(|| "web".to_string());
-    let data_dir = std::path::Path::new(&state.data_dir);
-    let mut db = state.db.write().await;
-    let pipeline = IngestPipeline::new(Arc::clone(&state.embedder), Arc::clone(&state.bm25_index));
-
     let docs = vec![eywa::DocumentInput {
         content,
         title: Some(title.clone()),
@@ -729,6 +1167,28 @@ async fn handle_fetch_url(
         is_pdf: false,
     }];
 
+    // Async: fetch happened inline, chunking/embedding is left to the worker
+    if payload.run_async {
+        let result = {
+            let mut queue = state.job_queue.lock().unwrap();
+            queue.queue_documents(&source_id, docs)
+        };
+        return match result {
+            Ok(job_id) => (StatusCode::ACCEPTED, Json(json!({
+                "job_id": job_id,
+                "status": "queued",
+                "title": title,
+                "url": payload.url
+            }))),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+        };
+    }
+
+    let data_dir = std::path::Path::new(&state.data_dir);
+    let mut db = state.db.write().await;
+    let pipeline = IngestPipeline::new(Arc::clone(&state.embedder), Arc::clone(&state.bm25_index))
+        .with_settings(&state.config);
+
     match pipeline.ingest_documents(&mut db, data_dir, &source_id, docs).await {
         Ok(result) => (StatusCode::OK, Json(json!({
             "title": title,
@@ -1035,9 +1495,12 @@ async fn handle_start_download(
     let model_type = payload.model_type.clone();
     let model_id = payload.model_id.clone();
     let job_id_clone = job_id.clone();
+    let model_scan = Arc::clone(&state.model_scan);
 
     tokio::spawn(async move {
         run_download_task(downloads, job_id_clone, model_type, model_id).await;
+        // New files in the HuggingFace cache
+        model_scan.invalidate();
     });
 
     (
@@ -1212,6 +1675,7 @@ async fn handle_list_downloads(State(state): State<Arc<AppState>>) -> impl IntoR
 
 /// DELETE /api/models/cache/:model_type/:model_id - Delete a cached model
 async fn handle_delete_model_cache(
+    State(state): State<Arc<AppState>>,
     Path((model_type, model_id)): Path<(String, String)>,
 ) -> impl IntoResponse {
     // Get current config to check if model is selected
@@ -1277,16 +1741,406 @@ async fn handle_delete_model_cache(
     };
 
     match result {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({
-                "success": true,
-                "message": format!("Deleted cached model '{}'", model_name)
-            })),
-        ),
+        Ok(_) => {
+            // Files gone from the HuggingFace cache
+            state.model_scan.invalidate();
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "message": format!("Deleted cached model '{}'", model_name)
+                })),
+            )
+        }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "error": format!("Failed to delete model: {}", e) })),
         ),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn get_body(router: Router, uri: &str) -> String {
+        let response = router
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_index_stats_counts_fixtures() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+
+        let embedder = Arc::new(
+            eywa::Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(eywa::BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(data_path.to_str().unwrap()).await.unwrap();
+        let pipeline = IngestPipeline::new(embedder, bm25_index);
+
+        let doc = |content: &str| DocumentInput {
+            content: content.to_string(),
+            title: None,
+            file_path: None,
+            is_pdf: false,
+        };
+        pipeline
+            .ingest_documents(&mut db, data_path, "notes", vec![
+                    doc("Standup moved to 10am so the Berlin office can join. Keep updates short: yesterday, today, and anything that blocks you."),
+                    doc("Retro is on Fridays after the demo. Bring one thing that went well, one that didn't, and one experiment to try next sprint."),
+                ])
+            .await
+            .unwrap();
+        pipeline
+            .ingest_documents(&mut db, data_path, "wiki", vec![doc(
+                    "The wiki lives on the intranet. Every team owns a space there, and pages older than a year are flagged for review.",
+                )])
+            .await
+            .unwrap();
+
+        let stats = index_stats(&db, data_path).await;
+        // Counts only: nothing else (like cached models) is computed
+        assert_eq!(stats.as_object().unwrap().len(), 3);
+        assert_eq!(stats["source_count"], 2);
+        assert_eq!(stats["document_count"], 3);
+        assert_eq!(stats["chunk_count"], 3);
+    }
+
+    /// Server state over `data_path` with `contents` ingested into `source`
+    async fn state_with_docs(data_path: &std::path::Path, source: &str, contents: &[&str]) -> Arc<AppState> {
+        use crate::server::{create_download_tracker, ModelScanCache, RerankerCache, MODEL_SCAN_TTL};
+
+        let embedder = Arc::new(
+            eywa::Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(eywa::BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(data_path.to_str().unwrap()).await.unwrap();
+        let docs = contents
+            .iter()
+            .map(|content| DocumentInput {
+                content: content.to_string(),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            })
+            .collect();
+        IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+            .ingest_documents(&mut db, data_path, source, docs)
+            .await
+            .unwrap();
+
+        Arc::new(AppState {
+            embedder,
+            db: Arc::new(tokio::sync::RwLock::new(db)),
+            bm25_index,
+            search_engine: eywa::SearchEngine::new(),
+            job_queue: eywa::create_job_queue(&data_path.join("jobs.db")).unwrap(),
+            data_dir: data_path.to_string_lossy().to_string(),
+            downloads: create_download_tracker(),
+            model_scan: Arc::new(ModelScanCache::new(MODEL_SCAN_TTL)),
+            rerankers: RerankerCache::new(DevicePreference::Cpu),
+            config: Config::default(),
+        })
+    }
+
+    async fn post_search(state: &Arc<AppState>, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let request: SearchRequest = serde_json::from_value(body).unwrap();
+        let response = handle_search(State(Arc::clone(state)), Json(request)).await.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_rerank_model_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+            "The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock.",
+        ])
+        .await;
+
+        let model = RerankerModelConfig::default().id;
+        let (status, body) = post_search(&state, json!({ "query": "how do I bake sourdough bread", "rerank_model": model })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["reranked"], true);
+        assert_eq!(body["rerank_model"], json!(model));
+        assert!(body["results"][0]["content"].as_str().unwrap().starts_with("Sourdough"));
+
+        let (status, body) = post_search(&state, json!({ "query": "sourdough", "rerank_model": "someone/arbitrary-model" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("Unknown rerank_model"));
+        assert!(state.rerankers.get_or_load("someone/arbitrary-model").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_min_score_and_rerank_per_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+            "The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock.",
+        ])
+        .await;
+        let query = "how do I bake sourdough bread";
+
+        let (status, body) = post_search(&state, json!({ "query": query, "min_score": 0.0, "rerank": false })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["reranked"], false);
+        let scores: Vec<f64> = body["results"].as_array().unwrap().iter().map(|r| r["score"].as_f64().unwrap()).collect();
+        assert!(scores[0] >= scores[1], "Fused order is kept: {:?}", scores);
+
+        // A threshold above every vector match leaves only keyword hits
+        let (status, body) = post_search(&state, json!({ "query": query, "min_score": 1.0, "rerank": false })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["results"].as_array().unwrap().iter().all(|r| r["matched_by"] == json!(["bm25"])));
+
+        let (status, body) = post_search(&state, json!({ "query": query, "min_score": 0.0, "rerank": true })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["reranked"], true);
+        assert!(body["rerank_model"].is_string());
+
+        let (status, _) = post_search(&state, json!({ "query": query, "min_score": 1.5 })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = post_search(&state, json!({ "query": query, "rerank": false, "rerank_model": "x" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_async_fetch_is_queued_then_searchable() {
+        // A page to fetch, served locally
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let site = Router::new().route("/raft", get(|| async {
+            Html("<html><head><title>Raft</title></head><body><p>Raft elects a leader that replicates a log to followers. An entry is committed once a majority of the cluster has stored it.</p></body></html>")
+        }));
+        tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock.",
+        ])
+        .await;
+
+        let request = Request::post("/api/fetch-url")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "url": format!("http://{}/raft", addr), "async": true }).to_string()))
+            .unwrap();
+        let response = create_router(Arc::clone(&state)).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let job_id = body["job_id"].as_str().unwrap().to_string();
+        assert!(state.bm25_index.search("majority", 10).unwrap().is_empty());
+
+        // The queue worker embeds it
+        let timeout = std::time::Duration::from_secs(60);
+        let processed = crate::server::worker::process_next_doc(
+            &state.job_queue,
+            &state.embedder,
+            &state.db,
+            &state.bm25_index,
+            &state.data_dir,
+            &state.config,
+            timeout,
+        )
+        .await
+        .unwrap();
+        assert!(processed);
+
+        let job = state.job_queue.lock().unwrap().get_job(&job_id).unwrap().unwrap();
+        assert_eq!(job.completed, 1);
+        let (status, body) = post_search(&state, json!({
+            "query": "when is a log entry committed",
+            "source_id": "web",
+            "rerank": false,
+            "min_score": 0.0
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"][0]["title"], "Raft");
+    }
+
+    #[tokio::test]
+    async fn test_search_notes_clamped_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+        ])
+        .await;
+
+        let (status, body) = post_search(&state, json!({ "query": "sourdough", "limit": 1000 })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["note"].as_str().unwrap().contains("returning at most 100 results"));
+
+        let (_, body) = post_search(&state, json!({ "query": "sourdough", "limit": 5 })).await;
+        assert!(body.get("note").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_returns_keyword_hits_below_vector_floor() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+            "Ticket QZX-4471 tracks the printer that jams on heavy paper near the kitchen.",
+        ])
+        .await;
+
+        let (status, body) = post_search(&state, json!({ "query": "QZX", "min_score": 0.99, "rerank": false })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 1);
+        assert!(body["results"][0]["content"].as_str().unwrap().contains("QZX-4471"));
+        assert_eq!(body["results"][0]["matched_by"], json!(["bm25"]));
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_cli_top_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let embedder = {
+            let state = state_with_docs(dir.path(), "notes", &[
+                "Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours.",
+                "The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock.",
+                "Bread flour has more protein than plain flour, which gives a loaf its chew and helps it rise in the oven.",
+            ])
+            .await;
+            Arc::clone(&state.embedder)
+        };
+        // Reopen the stores the way `run_server` does, reranker included
+        let state = crate::server::build_state(dir.path().to_str().unwrap(), Config::default(), embedder)
+            .await
+            .unwrap();
+        let query = "how do I bake sourdough bread";
+
+        let (status, body) = post_search(&state, json!({ "query": query, "limit": 3 })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["reranked"], true);
+
+        let db = state.db.read().await;
+        let content_store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+        let engine = &state.search_engine;
+        let cli = crate::commands::search::reranked_search(
+            engine,
+            &state.embedder,
+            &db,
+            &state.bm25_index,
+            &content_store,
+            query,
+            None,
+            false,
+            engine.candidates_per_source,
+            0,
+            3,
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["results"][0]["id"], json!(cli[0].id));
+    }
+
+    #[tokio::test]
+    async fn test_get_doc_returns_chunk_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = state_with_docs(dir.path(), "notes", &[
+            "Deploys go out on Tuesdays and Thursdays. Tag the release, wait for CI to go green, then promote the build.",
+        ])
+        .await;
+        let doc_id = state.db.read().await.get_document_ids_for_source("notes").await.unwrap().remove(0);
+        let content_store = ContentStore::open(&dir.path().join("content.db")).unwrap();
+        let extra: Vec<(String, String, String)> = (1..=4)
+            .map(|i| (format!("w{}", i), doc_id.clone(), format!("Window chunk {}", i)))
+            .collect();
+        content_store.insert_chunks(&extra).unwrap();
+
+        let fetch = |query: &[(&str, &str)]| {
+            let state = Arc::clone(&state);
+            let doc_id = doc_id.clone();
+            let params: HashMap<String, String> = query.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            async move {
+                let response = handle_get_doc(State(state), Path(doc_id), Query(params)).await.into_response();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = fetch(&[("chunk", "w2"), ("context", "1")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["content"], "Window chunk 1\n\nWindow chunk 2\n\nWindow chunk 3");
+        assert_eq!(body["chunk_range"], json!({ "start": 1, "end": 3 }));
+
+        // Default context, clipped at the end of the document
+        let (status, body) = fetch(&[("chunk", "w4")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["chunk_range"], json!({ "start": 2, "end": 4 }));
+
+        // Without `chunk` the whole document comes back and there is no range
+        let (status, body) = fetch(&[]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["content"].as_str().unwrap().starts_with("Deploys go out"));
+        assert!(body.get("chunk_range").is_none());
+
+        let (status, _) = fetch(&[("chunk", "missing")]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = fetch(&[("chunk", "w2"), ("context", "-1")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_source_stats_combines_both_stores() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = [
+            "Deploys go out on Tuesdays and Thursdays. Tag the release, wait for CI to go green, then promote the build to production.",
+            "Rollbacks use the previous tag: redeploy it from the release page and post a note in the incident channel when it is live.",
+        ];
+        let state = state_with_docs(dir.path(), "runbooks", &contents).await;
+        let stats = |source: &str| {
+            let state = Arc::clone(&state);
+            let source = source.to_string();
+            async move {
+                let response = handle_source_stats(State(state), Path(source)).await.into_response();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = stats("runbooks").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["document_count"], 2);
+        assert_eq!(body["chunk_count"], 2);
+        assert_eq!(body["content_bytes"], contents.iter().map(|c| c.len()).sum::<usize>());
+        assert!(body["compressed_bytes"].as_u64().unwrap() > 0);
+        assert!(body["last_updated"].is_string());
+
+        let (status, body) = stats("missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].as_str().unwrap().contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_root_serves_configured_ui() {
+        assert_eq!(get_body(create_ui_routes(UiVersion::V1), "/").await, UI_V1_INDEX);
+        assert_eq!(get_body(create_ui_routes(UiVersion::V2), "/").await, UI_V2_INDEX);
+
+        // The full router takes the choice from the startup config
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = state_with_docs(dir.path(), "notes", &["The wiki lives on the intranet."]).await;
+        Arc::get_mut(&mut state).unwrap().config.default_ui = UiVersion::V1;
+        assert_eq!(get_body(create_router(state), "/").await, UI_V1_INDEX);
+    }
+
+    #[tokio::test]
+    async fn test_both_uis_reachable_at_explicit_paths() {
+        let router = create_ui_routes(UiVersion::V1);
+        assert_eq!(get_body(router.clone(), "/v1").await, UI_V1_INDEX);
+        assert_eq!(get_body(router, "/v2").await, UI_V2_INDEX);
+    }
+}
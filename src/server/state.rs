@@ -2,10 +2,13 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::RwLock;
-use eywa::{BM25Index, Embedder, SearchEngine, SharedJobQueue, VectorDB};
+use std::time::{Duration, Instant};
+use tokio::sync::{OnceCell, RwLock};
+use eywa::{BM25Index, Config, DevicePreference, Embedder, Reranker, RerankerModelConfig, SearchEngine, SharedJobQueue, VectorDB};
 use serde::Serialize;
 
+use crate::utils::CachedModel;
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Download Job Tracking
 // ─────────────────────────────────────────────────────────────────────────────
@@ -63,6 +66,87 @@ pub fn create_download_tracker() -> DownloadTracker {
     Arc::new(Mutex::new(HashMap::new()))
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Model Cache Scan
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// How long a HuggingFace cache scan is reused before walking it again
+pub const MODEL_SCAN_TTL: Duration = Duration::from_secs(60);
+
+/// Last HuggingFace cache scan, so polling `/api/info` doesn't re-walk model dirs
+pub struct ModelScanCache {
+    ttl: Duration,
+    last: Mutex<Option<(Instant, Vec<CachedModel>)>>,
+}
+
+impl ModelScanCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Cached models, running `scan` only if there is no fresh result
+    pub fn get_or_scan(&self, scan: impl FnOnce() -> Vec<CachedModel>) -> Vec<CachedModel> {
+        let mut last = self.last.lock().unwrap();
+        match last.as_ref() {
+            Some((at, models)) if at.elapsed() < self.ttl => models.clone(),
+            _ => {
+                let models = scan();
+                *last = Some((Instant::now(), models.clone()));
+                models
+            }
+        }
+    }
+
+    /// Drop the cached scan (e.g. after a model download)
+    pub fn invalidate(&self) {
+        *self.last.lock().unwrap() = None;
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Reranker Cache
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Rerankers loaded for per-request `rerank_model` overrides, keyed by model ID
+pub struct RerankerCache {
+    device: DevicePreference,
+    loaded: Mutex<HashMap<String, Arc<OnceCell<Arc<Reranker>>>>>,
+}
+
+impl RerankerCache {
+    pub fn new(device: DevicePreference) -> Self {
+        Self {
+            device,
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reranker for a curated model ID, loading it on first use
+    ///
+    /// Returns `Ok(None)` for IDs outside the curated set, so a request can
+    /// never make the server download an arbitrary repository. The load runs
+    /// on the blocking pool; concurrent requests for the same model wait for
+    /// it while other models stay available.
+    pub async fn get_or_load(&self, model_id: &str) -> anyhow::Result<Option<Arc<Reranker>>> {
+        let Some(model) = RerankerModelConfig::find_curated(model_id) else {
+            return Ok(None);
+        };
+        let cell = Arc::clone(self.loaded.lock().unwrap().entry(model.id.clone()).or_default());
+        let reranker = cell
+            .get_or_try_init(|| async {
+                let device = self.device.clone();
+                let reranker =
+                    tokio::task::spawn_blocking(move || Reranker::new_with_model(&model, &device, false)).await??;
+                anyhow::Ok(Arc::new(reranker))
+            })
+            .await?;
+        Ok(Some(Arc::clone(reranker)))
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // App State
 // ─────────────────────────────────────────────────────────────────────────────
@@ -76,4 +160,39 @@ pub struct AppState {
     pub job_queue: SharedJobQueue,
     pub data_dir: String,
     pub downloads: DownloadTracker,
+    pub model_scan: Arc<ModelScanCache>,
+    pub rerankers: RerankerCache,
+    /// Config as loaded at startup
+    pub config: Config,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_model_scan_is_cached_until_invalidated() {
+        let cache = ModelScanCache::new(MODEL_SCAN_TTL);
+        let walks = Cell::new(0);
+        let scan = || {
+            walks.set(walks.get() + 1);
+            vec![CachedModel { name: "org/model".to_string(), size_bytes: 42 }]
+        };
+
+        // Two rapid /info calls walk the cache once
+        assert_eq!(cache.get_or_scan(scan)[0].name, "org/model");
+        assert_eq!(cache.get_or_scan(scan)[0].size_bytes, 42);
+        assert_eq!(walks.get(), 1);
+
+        cache.invalidate();
+        cache.get_or_scan(scan);
+        assert_eq!(walks.get(), 2);
+
+        // Zero TTL: always rescan
+        let uncached = ModelScanCache::new(Duration::ZERO);
+        uncached.get_or_scan(scan);
+        uncached.get_or_scan(scan);
+        assert_eq!(walks.get(), 4);
+    }
 }
@@ -1,13 +1,18 @@
 //! Background queue worker for async document processing
 
 use anyhow::Result;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use eywa::{
-    BM25Index, DocumentInput, Embedder, IngestPipeline,
+    BM25Index, Config, ContentStore, DocumentInput, Embedder, IngestPipeline,
     PendingDoc, SharedJobQueue, VectorDB,
 };
 
+/// Default per-document embed timeout (overridden by `worker_timeout_secs`)
+pub const DEFAULT_DOC_TIMEOUT_SECS: u64 = 300;
+
 /// Background worker that processes the job queue
 /// Processes docs individually for granular status tracking
 pub async fn run_queue_worker(
@@ -16,19 +21,31 @@ pub async fn run_queue_worker(
     db: Arc<RwLock<VectorDB>>,
     bm25_index: Arc<BM25Index>,
     data_dir: String,
+    config: Config,
+    doc_timeout: Duration,
 ) {
     let mut cleanup_counter = 0u32;
 
     loop {
-        // Get next pending doc (already marked as processing by get_next_pending)
-        let doc_result = {
-            let mut queue = job_queue.lock().unwrap();
-            queue.get_next_pending()
-        };
+        // Reindex jobs run as a whole, ahead of per-document work
+        match process_next_reindex(&job_queue, &embedder, &db, &bm25_index, &data_dir, &config).await {
+            Ok(true) => {
+                cleanup_counter = 0;
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("Worker error getting reindex job: {}", e),
+        }
+
+        let result =
+            process_next_doc(&job_queue, &embedder, &db, &bm25_index, &data_dir, &config, doc_timeout).await;
 
-        let doc = match doc_result {
-            Ok(Some(d)) => d,
-            Ok(None) => {
+        match result {
+            Ok(true) => {
+                // Reset cleanup counter when we're doing work
+                cleanup_counter = 0;
+            }
+            Ok(false) => {
                 // No work, sleep a bit
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                 cleanup_counter += 1;
@@ -39,65 +56,388 @@ pub async fn run_queue_worker(
                         eprintln!("Error cleaning up old jobs: {}", e);
                     }
                 }
-                continue;
             }
             Err(e) => {
                 eprintln!("Worker error getting doc: {}", e);
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                continue;
             }
-        };
+        }
+    }
+}
 
-        // Process single document
-        let doc_id = doc.id.clone();
-        let result = process_single_document(&embedder, &db, &bm25_index, &data_dir, &doc).await;
+/// Take the next pending doc and run it through the ingest pipeline
+///
+/// Returns `Ok(false)` when there was nothing to do.
+pub(crate) async fn process_next_doc(
+    job_queue: &SharedJobQueue,
+    embedder: &Arc<Embedder>,
+    db: &Arc<RwLock<VectorDB>>,
+    bm25_index: &Arc<BM25Index>,
+    data_dir: &str,
+    config: &Config,
+    doc_timeout: Duration,
+) -> Result<bool> {
+    process_next(job_queue, |doc| {
+        process_single_document(job_queue, embedder, db, bm25_index, data_dir, config, doc_timeout, doc)
+    })
+    .await
+}
 
-        // Mark completed or failed
+/// Take the next pending doc and process it, marking it completed or failed
+///
+/// Returns `Ok(false)` when there was nothing to do.
+async fn process_next<F, Fut>(job_queue: &SharedJobQueue, process: F) -> Result<bool>
+where
+    F: FnOnce(PendingDoc) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    // Get next pending doc (already marked as processing by get_next_pending)
+    let doc = {
         let mut queue = job_queue.lock().unwrap();
-        match result {
-            Ok(_) => {
-                if let Err(e) = queue.mark_completed(&doc_id) {
-                    eprintln!("Error marking doc {} completed: {}", doc_id, e);
-                }
+        queue.get_next_pending()?
+    };
+    let Some(doc) = doc else {
+        return Ok(false);
+    };
+
+    let doc_id = doc.id.clone();
+    let result = process(doc).await;
+
+    // Mark completed or failed
+    let mut queue = job_queue.lock().unwrap();
+    match result {
+        Ok(_) => {
+            if let Err(e) = queue.mark_completed(&doc_id) {
+                eprintln!("Error marking doc {} completed: {}", doc_id, e);
             }
-            Err(e) => {
-                if let Err(err) = queue.mark_failed(&doc_id, &e.to_string()) {
-                    eprintln!("Error marking doc {} failed: {}", doc_id, err);
-                }
+        }
+        Err(e) => {
+            if let Err(err) = queue.mark_failed(&doc_id, &e.to_string()) {
+                eprintln!("Error marking doc {} failed: {}", doc_id, err);
             }
         }
+    }
+
+    Ok(true)
+}
+
+/// Run the next waiting reindex job, if any, and mark it done or failed
+///
+/// Returns `Ok(false)` when there was nothing to do.
+async fn process_next_reindex(
+    job_queue: &SharedJobQueue,
+    embedder: &Arc<Embedder>,
+    db_lock: &Arc<RwLock<VectorDB>>,
+    bm25_index: &Arc<BM25Index>,
+    data_dir: &str,
+    config: &Config,
+) -> Result<bool> {
+    let job_id = job_queue.lock().unwrap().next_reindex_job()?;
+    let Some(job_id) = job_id else {
+        return Ok(false);
+    };
+
+    let result = reindex(job_queue, &job_id, embedder, db_lock, bm25_index, data_dir, config).await;
+    let error = result.err().map(|e| e.to_string());
+    job_queue.lock().unwrap().finish_job(&job_id, error.as_deref())?;
+    Ok(true)
+}
+
+/// Rebuild LanceDB and BM25 from the documents in SQLite
+async fn reindex(
+    job_queue: &SharedJobQueue,
+    job_id: &str,
+    embedder: &Arc<Embedder>,
+    db_lock: &Arc<RwLock<VectorDB>>,
+    bm25_index: &Arc<BM25Index>,
+    data_dir: &str,
+    config: &Config,
+) -> Result<()> {
+    let data_path = std::path::Path::new(data_dir);
+    let content_db = data_path.join("content.db");
+    // Read in a block to drop ContentStore before await
+    let documents = {
+        let content_store = ContentStore::open(&content_db)?;
+        content_store.get_all_documents_with_metadata()?
+    };
+    let total = documents.len() as u32;
+    job_queue.lock().unwrap().set_job_progress(job_id, total, 0, None)?;
+
+    // Reset phase: hold the write lock so no ingest lands between the resets
+    {
+        let mut db = db_lock.write().await;
+        db.reset_all().await?;
+        bm25_index.reset()?;
+        ContentStore::open(&content_db)?.clear_index_model()?;
+    }
+
+    // Re-embed without the lock, then take it briefly per document (as for queued docs).
+    // Documents keep their IDs, so bookmarks, pins and tags stay attached.
+    let pipeline = Arc::new(
+        IngestPipeline::new(Arc::clone(embedder), Arc::clone(bm25_index)).with_settings(config),
+    );
+    for (i, doc) in documents.into_iter().enumerate() {
+        job_queue
+            .lock()
+            .unwrap()
+            .set_job_progress(job_id, total, i as u32, Some(&doc.title))?;
 
-        // Reset cleanup counter when we're doing work
-        cleanup_counter = 0;
+        let embedded_batch = {
+            let pipeline = Arc::clone(&pipeline);
+            let data_path = data_path.to_path_buf();
+            tokio::task::spawn_blocking(move || pipeline.prepare_and_embed_existing(&data_path, &doc))
+                .await??
+        };
+
+        let mut db = db_lock.write().await;
+        let response = pipeline.replace_embedded_batch(&mut db, embedded_batch).await?;
+        pipeline
+            .commit_barrier(&mut db, data_path, &response.document_ids)
+            .await?;
+    }
+
+    job_queue.lock().unwrap().set_job_progress(job_id, total, total, None)?;
+    Ok(())
+}
+
+/// Run a blocking embed step, giving up on it after `timeout`
+///
+/// Only the embed is bounded, so one pathological document can't stall the
+/// queue. Whatever follows it (the DB write) is not, and runs to completion.
+async fn embed_with_timeout<T, F>(timeout: Duration, embed: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(embed)).await {
+        Ok(joined) => joined?,
+        Err(_) => Err(anyhow::anyhow!(
+            "Processing timed out after {}s",
+            timeout.as_secs_f32()
+        )),
     }
 }
 
 /// Process a single document from the queue
+#[allow(clippy::too_many_arguments)]
 async fn process_single_document(
+    job_queue: &SharedJobQueue,
     embedder: &Arc<Embedder>,
     db_lock: &Arc<RwLock<VectorDB>>,
     bm25_index: &Arc<BM25Index>,
     data_dir: &str,
-    doc: &PendingDoc,
+    config: &Config,
+    doc_timeout: Duration,
+    doc: PendingDoc,
 ) -> Result<()> {
-    let pipeline = IngestPipeline::new(Arc::clone(embedder), Arc::clone(bm25_index));
+    let pipeline = Arc::new(
+        IngestPipeline::new(Arc::clone(embedder), Arc::clone(bm25_index)).with_settings(config),
+    );
     let data_path = std::path::Path::new(data_dir);
+    let doc_id = doc.id.clone();
 
     let input = DocumentInput {
-        content: doc.content.clone(),
-        title: doc.title.clone(),
-        file_path: doc.file_path.clone(),
+        content: doc.content,
+        title: doc.title,
+        file_path: doc.file_path,
         is_pdf: false,
     };
 
     // Step 1: Prepare + embed (slow) - NO LOCK HELD
-    let embedded_batch = pipeline.prepare_and_embed(&doc.source_id, data_path, vec![input])?;
+    // Runs on the blocking pool so the timeout can fire while it is busy
+    let embedded_batch = {
+        let pipeline = Arc::clone(&pipeline);
+        let source_id = pipeline.resolve_source_id(data_path, &doc.source_id)?;
+        let data_path = data_path.to_path_buf();
+        embed_with_timeout(doc_timeout, move || {
+            pipeline.prepare_and_embed(&source_id, &data_path, vec![input])
+        })
+        .await?
+    };
 
     // Step 2: Write to DB (fast) - lock held briefly
+    // Step 3: Commit barrier before the caller marks the doc completed
     {
         let mut db = db_lock.write().await;
-        pipeline.write_embedded_batch(&mut db, embedded_batch).await?;
+        // The source may have been deleted while this doc was embedding
+        let cancelled = !job_queue.lock().unwrap().is_active(&doc_id)?;
+        if cancelled {
+            return Ok(());
+        }
+        let response = pipeline.write_embedded_batch(&mut db, embedded_batch).await?;
+        pipeline
+            .commit_barrier(&mut db, data_path, &response.document_ids)
+            .await?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eywa::{create_job_queue, DevicePreference, DocStatus, EmbeddingModelConfig, JobKind, JobStatus};
+
+    fn doc(content: &str) -> DocumentInput {
+        DocumentInput {
+            content: content.to_string(),
+            title: Some(content.to_string()),
+            file_path: None,
+            is_pdf: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hanging_embed_times_out_and_queue_moves_on() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_queue = create_job_queue(&dir.path().join("jobs.db")).unwrap();
+        let job_id = job_queue
+            .lock()
+            .unwrap()
+            .queue_documents("docs", vec![doc("hangs"), doc("slow write")])
+            .unwrap();
+
+        // Mock worker: the first embed outlives the timeout; the second embeds
+        // quickly and then takes longer than the timeout to write
+        let timeout = Duration::from_millis(50);
+        let process = |doc: PendingDoc| async move {
+            let hangs = doc.content == "hangs";
+            embed_with_timeout(timeout, move || {
+                if hangs {
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+                Ok(())
+            })
+            .await?;
+            tokio::time::sleep(timeout * 3).await;
+            Ok(())
+        };
+
+        assert!(process_next(&job_queue, process).await.unwrap());
+        assert!(process_next(&job_queue, process).await.unwrap());
+        assert!(!process_next(&job_queue, process).await.unwrap());
+
+        let docs = job_queue.lock().unwrap().get_job_docs(&job_id).unwrap();
+        let hung = docs.iter().find(|d| d.title.as_deref() == Some("hangs")).unwrap();
+        let written = docs.iter().find(|d| d.title.as_deref() == Some("slow write")).unwrap();
+        assert_eq!(hung.status, DocStatus::Failed);
+        assert!(hung.error.as_deref().unwrap().contains("timed out"));
+        assert_eq!(written.status, DocStatus::Done);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_source_docs_are_not_processed() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let data_dir = data_path.to_string_lossy().to_string();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let db = Arc::new(RwLock::new(VectorDB::new(&data_dir).await.unwrap()));
+        let job_queue = create_job_queue(&data_path.join("jobs.db")).unwrap();
+        let job_id = job_queue
+            .lock()
+            .unwrap()
+            .queue_documents("doomed", vec![
+                doc("Sourdough needs a lively starter: feed it flour and water daily."),
+                doc("The office printer on the third floor jams on heavy paper."),
+            ])
+            .unwrap();
+
+        // Hold the write lock so the worker embeds and then waits to write
+        let db_guard = db.write().await;
+        let worker = {
+            let (job_queue, embedder, db, bm25_index, data_dir) =
+                (Arc::clone(&job_queue), Arc::clone(&embedder), Arc::clone(&db), Arc::clone(&bm25_index), data_dir.clone());
+            tokio::spawn(async move {
+                let timeout = Duration::from_secs(DEFAULT_DOC_TIMEOUT_SECS);
+                process_next_doc(&job_queue, &embedder, &db, &bm25_index, &data_dir, &Config::default(), timeout).await
+            })
+        };
+        loop {
+            let docs = job_queue.lock().unwrap().get_job_docs(&job_id).unwrap();
+            if docs.iter().any(|d| d.status == DocStatus::Processing) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // The source is deleted while the first doc is in flight
+        assert_eq!(job_queue.lock().unwrap().cancel_source("doomed").unwrap(), 2);
+        drop(db_guard);
+        assert!(worker.await.unwrap().unwrap());
+
+        // Nothing left to resurrect the source with
+        let config = Config::default();
+        let timeout = Duration::from_secs(DEFAULT_DOC_TIMEOUT_SECS);
+        assert!(!process_next_doc(&job_queue, &embedder, &db, &bm25_index, &data_dir, &config, timeout).await.unwrap());
+        assert!(db.read().await.list_sources().await.unwrap().is_empty());
+        let content_store = ContentStore::open(&data_path.join("content.db")).unwrap();
+        assert!(content_store.list_sources().unwrap().is_empty());
+        assert!(bm25_index.search("sourdough", 10).unwrap().is_empty());
+
+        let queue = job_queue.lock().unwrap();
+        let job = queue.get_job(&job_id).unwrap().unwrap();
+        assert_eq!((job.completed, job.failed), (0, 2));
+        assert_eq!(job.status, eywa::JobStatus::Failed);
+        for d in queue.get_job_docs(&job_id).unwrap() {
+            assert_eq!(d.status, DocStatus::Failed);
+            assert!(d.error.as_deref().unwrap().contains("deleted"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_job_rebuilds_index_and_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path();
+        let data_dir = data_path.to_string_lossy().to_string();
+        let embedder = Arc::new(
+            Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).unwrap(),
+        );
+        let bm25_index = Arc::new(BM25Index::open(data_path).unwrap());
+        let mut db = VectorDB::new(&data_dir).await.unwrap();
+        IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+            .ingest_documents(&mut db, data_path, "notes", vec![
+                doc("Sourdough needs a lively starter: feed it flour and water daily and bake once it doubles within six hours."),
+                doc("The office printer on the third floor jams on heavy paper, so use the one by the kitchen for card stock."),
+            ])
+            .await
+            .unwrap();
+        let chunks_before = db.list_sources().await.unwrap()[0].chunk_count;
+        let db = Arc::new(RwLock::new(db));
+        let document_ids = || {
+            let mut ids: Vec<String> = ContentStore::open(&data_path.join("content.db"))
+                .unwrap()
+                .get_all_documents_with_metadata()
+                .unwrap()
+                .into_iter()
+                .map(|d| d.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let ids_before = document_ids();
+
+        let job_queue = create_job_queue(&data_path.join("jobs.db")).unwrap();
+        let job_id = job_queue.lock().unwrap().queue_reindex().unwrap();
+        assert_eq!(job_queue.lock().unwrap().active_reindex_job().unwrap(), Some(job_id.clone()));
+
+        let config = Config::default();
+        assert!(process_next_reindex(&job_queue, &embedder, &db, &bm25_index, &data_dir, &config).await.unwrap());
+        assert!(!process_next_reindex(&job_queue, &embedder, &db, &bm25_index, &data_dir, &config).await.unwrap());
+
+        let job = job_queue.lock().unwrap().get_job(&job_id).unwrap().unwrap();
+        assert_eq!(job.kind, JobKind::Reindex);
+        assert_eq!(job.status, JobStatus::Done, "{:?}", job.error);
+        assert_eq!((job.completed, job.total), (2, 2));
+        assert!(job_queue.lock().unwrap().active_reindex_job().unwrap().is_none());
+
+        let sources = db.read().await.list_sources().await.unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].chunk_count, chunks_before);
+        // Rebuilt in place: no second SQLite row per document
+        assert_eq!(document_ids(), ids_before);
+        assert_eq!(ids_before.len(), 2);
+    }
+}
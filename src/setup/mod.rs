@@ -6,6 +6,7 @@ mod download;
 mod tui;
 
 use crate::config::Config;
+use crate::error::EywaError;
 use anyhow::Result;
 
 pub use download::{DownloadProgress, ModelDownloader, ModelInfo};
@@ -25,3 +26,17 @@ pub fn models_cached(config: &Config) -> bool {
     let downloader = ModelDownloader::new();
     downloader.is_cached(&config.embedding_model) && downloader.is_cached(&config.reranker_model)
 }
+
+/// Load config for a command that needs models, or explain how to get them
+///
+/// Checks the local cache only, so a fresh install gets a pointer to
+/// `eywa init` instead of a raw hf_hub download error.
+pub fn require_models() -> Result<Config> {
+    let Some(config) = Config::load()? else {
+        return Err(EywaError::NotInitialized.into());
+    };
+    if !models_cached(&config) {
+        return Err(EywaError::ModelsMissing.into());
+    }
+    Ok(config)
+}
@@ -0,0 +1,97 @@
+//! Related-document lookup
+//!
+//! Embeds a stored document, searches for nearby chunks, and groups them back
+//! into documents so callers get one entry per related document.
+
+use crate::content::ContentStore;
+use crate::db::VectorDB;
+use crate::embed::Embedder;
+use crate::search::SearchEngine;
+use crate::types::{SearchResult, SimilarDocument};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Find documents similar to `document_id`, best match first
+///
+/// Chunks from the document itself are never returned. Returns `Ok(None)` if
+/// the document doesn't exist.
+pub async fn find_similar_documents(
+    embedder: &Embedder,
+    db: &VectorDB,
+    content_store: &ContentStore,
+    search_engine: &SearchEngine,
+    document_id: &str,
+    limit: usize,
+) -> Result<Option<Vec<SimilarDocument>>> {
+    let source_content = match content_store.get_document(document_id)? {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+
+    let embedding = embedder.embed(&source_content)?;
+
+    // Several chunks usually belong to the same document, so over-fetch
+    let chunk_metas: Vec<_> = db
+        .search(&embedding, search_engine.candidate_count((limit + 5) * 2))
+        .await?
+        .into_iter()
+        .filter(|c| c.document_id != document_id)
+        .collect();
+
+    let document_ids: HashMap<String, String> = chunk_metas
+        .iter()
+        .map(|c| (c.id.clone(), c.document_id.clone()))
+        .collect();
+
+    let chunk_ids: Vec<&str> = chunk_metas.iter().map(|c| c.id.as_str()).collect();
+    let content_map: HashMap<String, String> = content_store.get_chunks(&chunk_ids)?.into_iter().collect();
+
+    let results: Vec<SearchResult> = chunk_metas
+        .into_iter()
+        .filter_map(|meta| {
+            let content = content_map.get(&meta.id)?.clone();
+            Some(SearchResult {
+                id: meta.id,
+                source_id: meta.source_id,
+                document_id: meta.document_id,
+                title: meta.title,
+                content,
+                file_path: meta.file_path,
+                line_start: meta.line_start,
+                line_end: meta.line_end,
+                page_start: meta.page_start,
+                page_end: meta.page_end,
+                section: meta.section,
+                has_code: meta.has_code,
+                score: meta.score,
+                matched_by: vec!["vector".to_string()],
+            })
+        })
+        .collect();
+
+    let candidates = results.len();
+    let results = search_engine.rerank(results, &source_content, candidates);
+
+    // Results are sorted, so the first chunk seen per document is its best
+    let mut similar: Vec<SimilarDocument> = Vec::new();
+    for result in results {
+        let Some(doc_id) = document_ids.get(&result.id) else {
+            continue;
+        };
+        if similar.iter().any(|d| &d.document_id == doc_id) {
+            continue;
+        }
+        similar.push(SimilarDocument {
+            document_id: doc_id.clone(),
+            source_id: result.source_id,
+            title: result.title,
+            file_path: result.file_path,
+            score: result.score,
+        });
+        if similar.len() >= limit {
+            break;
+        }
+    }
+
+    Ok(Some(similar))
+}
@@ -0,0 +1,140 @@
+//! Source-level operations that span all three stores
+//!
+//! LanceDB (vectors + metadata), SQLite (content), and Tantivy (BM25) each
+//! record a document's `source_id`, so moving documents between sources has
+//! to update all of them together.
+
+use crate::bm25::{BM25Index, ChunkInput};
+use crate::content::ContentStore;
+use crate::db::VectorDB;
+use crate::error::EywaError;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// Result of merging one source into another
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MergeStats {
+    /// Documents moved into the target source
+    pub documents_moved: u32,
+    /// Documents dropped because the target already had identical content
+    pub documents_deduped: u32,
+}
+
+/// Fingerprint a document by the sorted content hashes of its chunks
+fn chunk_fingerprint(content: &ContentStore, document_id: &str) -> Result<Vec<String>> {
+    let mut hashes: Vec<String> = content
+        .get_chunks_for_document(document_id)?
+        .iter()
+        .map(|(_, text)| format!("{:x}", md5::compute(text.as_bytes())))
+        .collect();
+    hashes.sort();
+    Ok(hashes)
+}
+
+/// Keep one chunk per distinct content in a document that absorbed a duplicate
+///
+/// Prefers the copy that has a vector, removes the rest from all three
+/// stores and brings the document's chunk count back in line.
+async fn drop_duplicate_chunks(
+    db: &VectorDB,
+    bm25_index: &BM25Index,
+    content: &ContentStore,
+    document_id: &str,
+) -> Result<()> {
+    let with_vectors = db.chunk_ids_with_vectors(document_id).await?;
+    let chunks = content.get_chunks_for_document(document_id)?;
+
+    let mut kept: HashMap<&str, &str> = HashMap::new();
+    for (id, text) in &chunks {
+        let kept_id = kept.entry(text.as_str()).or_insert(id.as_str());
+        if !with_vectors.contains(*kept_id) && with_vectors.contains(id) {
+            *kept_id = id.as_str();
+        }
+    }
+    let survivors: HashSet<&str> = kept.into_values().collect();
+    let extra: Vec<String> = chunks
+        .iter()
+        .filter(|(id, _)| !survivors.contains(id.as_str()))
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    content.delete_chunks(&extra)?;
+    bm25_index.delete_chunks(&extra)?;
+    db.delete_chunks(&extra).await?;
+    db.set_chunk_count(document_id, survivors.len() as u32).await?;
+    Ok(())
+}
+
+/// Merge all documents from `from` into `to`
+///
+/// Documents whose chunk hashes exactly match a document already in `to` are
+/// folded into that document instead of being duplicated. Everything else is
+/// moved as-is. The `from` source no longer exists afterwards.
+pub async fn merge_sources(
+    db: &VectorDB,
+    bm25_index: &BM25Index,
+    content: &ContentStore,
+    from: &str,
+    to: &str,
+) -> Result<MergeStats> {
+    if from == to {
+        anyhow::bail!("Cannot merge source '{}' into itself", from);
+    }
+
+    let from_ids = db.get_document_ids_for_source(from).await?;
+    if from_ids.is_empty() {
+        return Err(EywaError::SourceNotFound(from.to_string()).into());
+    }
+
+    // Index target documents by fingerprint
+    let mut target_by_fingerprint: HashMap<Vec<String>, String> = HashMap::new();
+    for doc_id in db.get_document_ids_for_source(to).await? {
+        let fingerprint = chunk_fingerprint(content, &doc_id)?;
+        if !fingerprint.is_empty() {
+            target_by_fingerprint.entry(fingerprint).or_insert(doc_id);
+        }
+    }
+
+    let mut stats = MergeStats::default();
+    let mut moved_ids: Vec<String> = Vec::new();
+    let mut reindex: Vec<ChunkInput> = Vec::new();
+
+    for doc_id in &from_ids {
+        let title = db.get_document(doc_id).await?.map(|r| r.title);
+        let fingerprint = chunk_fingerprint(content, doc_id)?;
+
+        let survivor = match target_by_fingerprint.get(&fingerprint) {
+            Some(target_id) if !fingerprint.is_empty() => {
+                // Keep the duplicate's chunks (they may hold the only vectors
+                // for this content) but attach them to the existing document
+                db.merge_document_into(doc_id, target_id, to).await?;
+                content.move_chunks(doc_id, target_id)?;
+                content.delete_document(doc_id)?;
+                drop_duplicate_chunks(db, bm25_index, content, target_id).await?;
+                stats.documents_deduped += 1;
+                target_id.clone()
+            }
+            _ => {
+                moved_ids.push(doc_id.clone());
+                stats.documents_moved += 1;
+                doc_id.clone()
+            }
+        };
+
+        for (chunk_id, text) in content.get_chunks_for_document(&survivor)? {
+            reindex.push(ChunkInput {
+                id: chunk_id,
+                source_id: to.to_string(),
+                content: text,
+                title: title.clone(),
+            });
+        }
+    }
+
+    let moved_refs: Vec<&str> = moved_ids.iter().map(|s| s.as_str()).collect();
+    db.move_documents_to_source(&moved_refs, to).await?;
+    content.move_documents_to_source(&moved_refs, to)?;
+    bm25_index.reindex_existing(&reindex)?;
+
+    Ok(stats)
+}
@@ -0,0 +1,244 @@
+//! Source summary
+//!
+//! Clusters a source's documents by their embeddings and picks the documents
+//! closest to each cluster's centre, giving a rough map of a large source.
+
+use crate::db::{VectorDB, MAX_QUERY_LIMIT};
+use crate::types::DocumentMeta;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// k-means stops after this many rounds even if assignments still change
+const MAX_ITERATIONS: usize = 25;
+
+/// A document standing in for its cluster
+#[derive(Debug, Clone)]
+pub struct RepresentativeDocument {
+    pub document_id: String,
+    pub title: String,
+    pub file_path: Option<String>,
+    /// Cosine similarity to the cluster centre
+    pub score: f32,
+}
+
+/// A group of related documents in a source
+#[derive(Debug, Clone)]
+pub struct SourceCluster {
+    /// Number of documents in the cluster
+    pub documents: usize,
+    /// Most central documents first
+    pub representatives: Vec<RepresentativeDocument>,
+}
+
+/// Group a source's documents into at most `clusters` topics, largest first
+///
+/// Each document is represented by the mean of its chunk vectors, so long
+/// documents don't outvote short ones. Returns an empty list for an empty
+/// or unknown source.
+pub async fn summarize_source(
+    db: &VectorDB,
+    source_id: &str,
+    clusters: usize,
+    per_cluster: usize,
+) -> Result<Vec<SourceCluster>> {
+    let (doc_ids, vectors) = document_vectors(db.chunk_vectors_for_source(source_id).await?);
+    if doc_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let documents: HashMap<String, DocumentMeta> = db
+        .list_documents(source_id, Some(MAX_QUERY_LIMIT), None)
+        .await?
+        .into_iter()
+        .map(|d| (d.id.clone(), d))
+        .collect();
+
+    let summary = kmeans(&vectors, clusters)
+        .into_iter()
+        .map(|members| SourceCluster {
+            documents: members.len(),
+            representatives: members
+                .into_iter()
+                .take(per_cluster)
+                .map(|(i, score)| {
+                    let meta = documents.get(&doc_ids[i]);
+                    RepresentativeDocument {
+                        document_id: doc_ids[i].clone(),
+                        title: meta.map(|m| m.title.clone()).unwrap_or_default(),
+                        file_path: meta.and_then(|m| m.file_path.clone()),
+                        score,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(summary)
+}
+
+/// Normalized mean of each document's chunk vectors, in first-seen order
+fn document_vectors(chunks: Vec<(String, Vec<f32>)>) -> (Vec<String>, Vec<Vec<f32>>) {
+    let mut ids: Vec<String> = Vec::new();
+    let mut sums: Vec<Vec<f32>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (doc_id, vector) in chunks {
+        let i = *index.entry(doc_id.clone()).or_insert_with(|| {
+            ids.push(doc_id);
+            sums.push(vec![0.0; vector.len()]);
+            ids.len() - 1
+        });
+        add(&mut sums[i], &vector);
+    }
+    sums.iter_mut().for_each(|v| normalize(v));
+
+    (ids, sums)
+}
+
+/// Cluster unit vectors by cosine similarity
+///
+/// Returns each non-empty cluster as (index, similarity to the centroid),
+/// most central first, largest cluster first. Seeding is deterministic: the
+/// vector closest to the overall mean, then repeatedly the vector farthest
+/// from every seed chosen so far.
+fn kmeans(vectors: &[Vec<f32>], k: usize) -> Vec<Vec<(usize, f32)>> {
+    let k = k.clamp(1, vectors.len());
+    let mut centroids = seed_centroids(vectors, k);
+    let mut assignment = vec![usize::MAX; vectors.len()];
+
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = vectors.iter().map(|v| nearest(&centroids, v).0).collect();
+        if next == assignment {
+            break;
+        }
+        assignment = next;
+
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0; centroid.len()];
+            let mut members = 0;
+            for (vector, _) in vectors.iter().zip(&assignment).filter(|(_, &a)| a == c) {
+                add(&mut sum, vector);
+                members += 1;
+            }
+            // An emptied cluster keeps its old centroid
+            if members > 0 {
+                normalize(&mut sum);
+                *centroid = sum;
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<(usize, f32)>> = vec![Vec::new(); k];
+    for (i, &c) in assignment.iter().enumerate() {
+        clusters[c].push((i, dot(&vectors[i], &centroids[c])));
+    }
+    clusters.retain(|members| !members.is_empty());
+    for members in &mut clusters {
+        members.sort_by(|a, b| b.1.total_cmp(&a.1));
+    }
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()));
+    clusters
+}
+
+/// Initial centroids for `kmeans`
+fn seed_centroids(vectors: &[Vec<f32>], k: usize) -> Vec<Vec<f32>> {
+    let mut mean = vec![0.0; vectors[0].len()];
+    for vector in vectors {
+        add(&mut mean, vector);
+    }
+    normalize(&mut mean);
+
+    let mut seeds = vec![vectors[nearest(vectors, &mean).0].clone()];
+    while seeds.len() < k {
+        let farthest = vectors
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, nearest(&seeds, v).1))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(i, _)| i);
+        seeds.push(vectors[farthest].clone());
+    }
+    seeds
+}
+
+/// Index of the candidate most similar to `vector`, with the similarity
+fn nearest(candidates: &[Vec<f32>], vector: &[f32]) -> (usize, f32) {
+    candidates
+        .iter()
+        .map(|c| dot(c, vector))
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn add(sum: &mut [f32], vector: &[f32]) {
+    for (s, v) in sum.iter_mut().zip(vector) {
+        *s += v;
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(v: &[f32]) -> Vec<f32> {
+        let mut v = v.to_vec();
+        normalize(&mut v);
+        v
+    }
+
+    #[test]
+    fn test_kmeans_separates_two_groups() {
+        let vectors = vec![
+            unit(&[1.0, 0.1, 0.0]),
+            unit(&[0.0, 1.0, 0.1]),
+            unit(&[0.9, 0.0, 0.1]),
+            unit(&[0.1, 0.9, 0.0]),
+            unit(&[1.0, 0.0, 0.0]),
+        ];
+
+        let clusters = kmeans(&vectors, 2);
+
+        let members = |c: usize| {
+            let mut ids: Vec<usize> = clusters[c].iter().map(|(i, _)| *i).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(members(0), vec![0, 2, 4]);
+        assert_eq!(members(1), vec![1, 3]);
+        // Most central first
+        assert!(clusters[0].windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_kmeans_caps_clusters_at_vector_count() {
+        let vectors = vec![unit(&[1.0, 0.0]), unit(&[0.0, 1.0])];
+        assert_eq!(kmeans(&vectors, 10).len(), 2);
+        assert_eq!(kmeans(&vectors, 0).len(), 1);
+    }
+
+    #[test]
+    fn test_document_vectors_average_chunks() {
+        let chunks = vec![
+            ("a".to_string(), vec![1.0, 0.0]),
+            ("b".to_string(), vec![0.0, 2.0]),
+            ("a".to_string(), vec![0.0, 1.0]),
+        ];
+        let (ids, vectors) = document_vectors(chunks);
+        assert_eq!(ids, vec!["a", "b"]);
+        assert!((vectors[0][0] - vectors[0][1]).abs() < 1e-6);
+        assert_eq!(vectors[1], vec![0.0, 1.0]);
+    }
+}
@@ -43,13 +43,71 @@ pub struct Chunk {
 pub struct SearchResult {
     pub id: String,
     pub source_id: String,
+    /// Document the chunk belongs to
+    #[serde(default)]
+    pub document_id: String,
     pub title: Option<String>,
     pub content: String,
     pub file_path: Option<String>,
+    /// First line of the chunk (empty for PDFs)
     pub line_start: Option<u32>,
+    /// Last line of the chunk (empty for PDFs)
+    #[serde(default)]
+    pub line_end: Option<u32>,
+    /// First PDF page the chunk covers
+    #[serde(default)]
+    pub page_start: Option<u32>,
+    /// Last PDF page the chunk covers
+    #[serde(default)]
+    pub page_end: Option<u32>,
+    /// Section header the chunk falls under (Markdown only)
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Whether the chunk contains a fenced code block
+    #[serde(default)]
+    pub has_code: bool,
+    pub score: f32,
+    /// Retrieval signals that found this chunk (`"vector"`, `"bm25"`)
+    #[serde(default)]
+    pub matched_by: Vec<String>,
+}
+
+/// A document related to another document, scored by its best matching chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarDocument {
+    pub document_id: String,
+    pub source_id: String,
+    pub title: Option<String>,
+    pub file_path: Option<String>,
+    pub score: f32,
+}
+
+/// Machine-readable reference to a search result
+///
+/// Returned alongside MCP search text (`format: "citations"`) so a client
+/// answering from the results can cite the exact chunks it was given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub document_id: String,
+    pub title: Option<String>,
+    pub source: String,
+    pub chunk_id: String,
     pub score: f32,
 }
 
+impl Citation {
+    /// Build a citation for a search result
+    pub fn from_result(result: &SearchResult) -> Self {
+        Self {
+            document_id: result.document_id.clone(),
+            title: result.title.clone(),
+            source: result.source_id.clone(),
+            chunk_id: result.id.clone(),
+            score: result.score,
+        }
+    }
+}
+
 /// Chunk metadata from vector search (content fetched separately from SQLite)
 #[derive(Debug, Clone)]
 pub struct ChunkMeta {
@@ -60,6 +118,10 @@ pub struct ChunkMeta {
     pub file_path: Option<String>,
     pub line_start: Option<u32>,
     pub line_end: Option<u32>,
+    pub page_start: Option<u32>,
+    pub page_end: Option<u32>,
+    pub section: Option<String>,
+    pub has_code: bool,
     pub score: f32,
 }
 
@@ -93,6 +155,8 @@ pub struct IngestResult {
     pub files_processed: u32,
     pub chunks_created: u32,
     pub chunks_skipped: u32,
+    #[serde(default)]
+    pub files_failed: Vec<FailedFile>,
 }
 
 /// API search request
@@ -102,6 +166,36 @@ pub struct SearchRequest {
     #[serde(default = "default_limit")]
     pub limit: usize,
     pub source_id: Option<String>,
+    /// Only return chunks containing code blocks
+    #[serde(default)]
+    pub code_only: bool,
+    /// Return only the number of matching documents
+    #[serde(default)]
+    pub count_only: bool,
+    /// Candidates guaranteed to each source before reranking
+    /// (overrides `rerank_candidates_per_source`)
+    #[serde(default)]
+    pub candidates_per_source: Option<usize>,
+    /// Drop results with less content than this many characters (0 = off)
+    #[serde(default)]
+    pub min_content_length: usize,
+    /// Drop vector hits below this similarity (0.0 - 1.0; server default 0.3).
+    /// Keyword (BM25) hits are kept regardless.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// `false` keeps the fused vector + keyword order; `true` or unset reranks with the server's
+    /// reranker (keyword boost if none could be loaded)
+    #[serde(default)]
+    pub rerank: Option<bool>,
+    /// Rerank with this curated reranker (by ID) instead of the server's default
+    #[serde(default)]
+    pub rerank_model: Option<String>,
+    /// Return a snippet around the matched query terms for each result
+    #[serde(default)]
+    pub highlight: bool,
+    /// Keep only the best-scoring chunk of each document
+    #[serde(default)]
+    pub dedup: bool,
 }
 
 fn default_limit() -> usize {
@@ -114,6 +208,33 @@ pub struct SearchResponse {
     pub query: String,
     pub results: Vec<SearchResult>,
     pub count: usize,
+    /// Whether results were scored by the neural reranker (false when degraded
+    /// to keyword reranking)
+    #[serde(default)]
+    pub reranked: bool,
+    /// Embedding model that built the index (None if nothing is indexed)
+    #[serde(default)]
+    pub index_model: Option<String>,
+    /// Embedding model that embedded the query
+    #[serde(default)]
+    pub query_model: Option<String>,
+    /// Set when the index and query models differ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    /// One per result, in order, when the request set `highlight`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<Highlight>>,
+}
+
+/// Where the query terms matched in a search result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Highlight {
+    /// Best-matching window of the result content (~200 chars)
+    pub snippet: String,
+    /// Byte offset of the snippet within the content
+    pub snippet_start: usize,
+    /// Byte ranges `[start, end)` of matched terms, relative to the snippet
+    pub matches: Vec<(usize, usize)>,
 }
 
 /// Input document for ingestion (from API/paste)
@@ -143,15 +264,41 @@ pub struct IngestResponse {
     pub documents_created: u32,
     pub chunks_created: u32,
     pub chunks_skipped: u32,
+    /// Files left alone because they're unchanged since the last ingest
+    #[serde(default)]
+    pub documents_skipped: u32,
+    /// Files skipped because they look binary (images, archives, executables, ...)
+    #[serde(default)]
+    pub files_skipped_binary: u32,
+    /// Files that couldn't be read or extracted; the rest of the ingest still ran
+    #[serde(default)]
+    pub files_failed: Vec<FailedFile>,
+    /// Files that weren't valid UTF-8; ingested with invalid bytes replaced by U+FFFD
+    #[serde(default)]
+    pub files_lossy_utf8: Vec<String>,
     pub document_ids: Vec<String>,
 }
 
+/// A file that failed to ingest, and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub file_path: String,
+    pub error: String,
+}
+
 /// API fetch URL request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchUrlRequest {
     pub url: String,
     #[serde(default)]
     pub source_id: Option<String>,
+    /// If true, queue the fetched page as a background job instead of
+    /// embedding it before responding
+    #[serde(default, rename = "async")]
+    pub run_async: bool,
+    /// Drop a trailing site name (e.g. " | Example Blog") from the page title
+    #[serde(default)]
+    pub strip_site_name: bool,
 }
 
 // ============================================================================
@@ -240,6 +387,37 @@ impl std::str::FromStr for JobStatus {
     }
 }
 
+/// What a queued job does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// Embed the documents queued with the job
+    #[default]
+    Ingest,
+    /// Rebuild LanceDB and BM25 from every document in SQLite
+    Reindex,
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobKind::Ingest => write!(f, "ingest"),
+            JobKind::Reindex => write!(f, "reindex"),
+        }
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ingest" => Ok(JobKind::Ingest),
+            "reindex" => Ok(JobKind::Reindex),
+            _ => Err(format!("Unknown job kind: {}", s)),
+        }
+    }
+}
+
 /// An ingestion job
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
@@ -266,6 +444,8 @@ pub struct QueueResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobProgress {
     pub job_id: String,
+    #[serde(default)]
+    pub kind: JobKind,
     pub source_id: String,
     pub status: JobStatus,
     pub total: u32,
@@ -274,6 +454,9 @@ pub struct JobProgress {
     pub current_doc: Option<String>,
     pub created_at: String,
     pub completed_at: Option<String>,
+    /// Why a job that runs as a whole (e.g. a reindex) failed
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 #[cfg(test)]
@@ -304,6 +487,16 @@ mod tests {
         assert!("invalid".parse::<JobStatus>().is_err());
     }
 
+    #[test]
+    fn test_fetch_url_async_flag() {
+        let req: FetchUrlRequest = serde_json::from_str(r#"{"url": "https://example.com"}"#).unwrap();
+        assert!(!req.run_async);
+
+        let req: FetchUrlRequest =
+            serde_json::from_str(r#"{"url": "https://example.com", "async": true}"#).unwrap();
+        assert!(req.run_async);
+    }
+
     #[test]
     fn test_default_limit() {
         let req: SearchRequest = serde_json::from_str(r#"{"query": "test"}"#).unwrap();
@@ -57,16 +57,154 @@ pub fn extract_text_from_html(html: &str) -> String {
 }
 
 /// Extract title from HTML
-pub fn extract_title_from_html(html: &str) -> Option<String> {
-    let lower = html.to_lowercase();
-    let start = lower.find("<title>")?;
-    let end = lower[start..].find("</title>")?;
-    let title = &html[start + 7..start + end];
-    let title = title.trim();
-    if title.is_empty() {
+///
+/// Prefers `og:title`, then `<title>`, then the first `<h1>`. With
+/// `strip_site_name`, a trailing site name like `" | Example Blog"` is
+/// dropped from the `<title>`. Callers fall back to the URL on `None`.
+pub fn extract_title_from_html(html: &str, strip_site_name: bool) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets aligned with the original
+    let lower = html.to_ascii_lowercase();
+
+    if let Some(title) = find_og_title(html, &lower) {
+        return Some(title);
+    }
+    if let Some(title) = find_tag_text(html, &lower, "title") {
+        return Some(if strip_site_name { strip_site_suffix(&title) } else { title });
+    }
+    find_tag_text(html, &lower, "h1")
+}
+
+/// Content of `<meta property="og:title" content="...">`, in any attribute order
+fn find_og_title(html: &str, lower: &str) -> Option<String> {
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find("<meta") {
+        let start = pos + offset + "<meta".len();
+        let (attributes, end) = tag_attributes(html, start)?;
+        let attribute = |name: &str| attributes.iter().find(|(n, _)| n == name).map(|(_, v)| *v);
+        let is_og_title = ["property", "name"]
+            .into_iter()
+            .any(|name| attribute(name).is_some_and(|v| v.eq_ignore_ascii_case("og:title")));
+        if is_og_title {
+            if let Some(content) = attribute("content") {
+                return non_empty(decode_entities(content));
+            }
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Attributes of a tag whose name ends at `start`, up to its closing `>`
+///
+/// Returns (lowercased name, raw value) pairs and the offset past the `>`.
+/// Quoted values may contain `>`. None if the tag is never closed.
+fn tag_attributes(html: &str, start: usize) -> Option<(Vec<(String, &str)>, usize)> {
+    let bytes = html.as_bytes();
+    let mut attributes = Vec::new();
+    let mut i = start;
+    loop {
+        while bytes.get(i)?.is_ascii_whitespace() || bytes[i] == b'/' {
+            i += 1;
+        }
+        if bytes[i] == b'>' {
+            return Some((attributes, i + 1));
+        }
+
+        let name_start = i;
+        while !matches!(bytes.get(i)?, b'=' | b'>' | b'/') && !bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let name = html[name_start..i].to_ascii_lowercase();
+        while bytes.get(i)?.is_ascii_whitespace() {
+            i += 1;
+        }
+        if bytes[i] != b'=' {
+            attributes.push((name, ""));
+            continue;
+        }
+        i += 1;
+        while bytes.get(i)?.is_ascii_whitespace() {
+            i += 1;
+        }
+
+        let value = match bytes[i] {
+            quote @ (b'"' | b'\'') => {
+                let value_start = i + 1;
+                let value_end = value_start + html[value_start..].find(quote as char)?;
+                i = value_end + 1;
+                &html[value_start..value_end]
+            }
+            _ => {
+                let value_start = i;
+                while bytes[i] != b'>' && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                    bytes.get(i)?;
+                }
+                &html[value_start..i]
+            }
+        };
+        attributes.push((name, value));
+    }
+}
+
+/// Text inside the first `<tag>` element, with nested markup removed
+fn find_tag_text(html: &str, lower: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let mut pos = 0;
+    let start = loop {
+        let start = pos + lower[pos..].find(&open)?;
+        // Skip tags that merely share the prefix (e.g. <h1x>, <titles>)
+        match lower.as_bytes().get(start + open.len()) {
+            Some(b'>') | Some(b' ') | Some(b'\t') | Some(b'\n') => break start,
+            _ => pos = start + open.len(),
+        }
+    };
+    let content_start = start + lower[start..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find(&format!("</{}", tag))?;
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html[content_start..content_end].chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    non_empty(decode_entities(&text))
+}
+
+/// Drop a trailing site name like `" | Example Blog"` or `" - Example"`
+fn strip_site_suffix(title: &str) -> String {
+    [" | ", " - ", " — ", " – ", " · ", " :: "]
+        .iter()
+        .filter_map(|sep| title.rfind(sep))
+        .max()
+        .map(|idx| title[..idx].trim())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(title)
+        .to_string()
+}
+
+/// Decode the handful of entities common in titles and collapse whitespace
+fn decode_entities(text: &str) -> String {
+    let decoded = text
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
         None
     } else {
-        Some(title.to_string())
+        Some(s)
     }
 }
 
@@ -154,3 +292,53 @@ pub fn scan_hf_cache() -> Vec<CachedModel> {
     models.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
     models
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_from_og_title_only() {
+        let html = r#"<html><head><meta content="Open Graph &amp; You" property="og:title"></head><body><p>Hi</p></body></html>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("Open Graph & You".to_string()));
+    }
+
+    #[test]
+    fn test_og_title_attributes_stay_inside_their_tag() {
+        // No content: fall back rather than read another tag's attribute
+        let html = r#"<head><meta property="og:title"><title>Fallback</title><meta name="description" content="Not a title"></head>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("Fallback".to_string()));
+
+        // A `>` inside a quoted value and a lookalike attribute name
+        let html = r#"<meta data-content="decoy" property='og:title' content="A > B"><title>Other</title>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("A > B".to_string()));
+
+        // og:title mentioned only in a value isn't an og:title tag
+        let html = r#"<meta name="note" content="og:title"><title>Real</title>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("Real".to_string()));
+    }
+
+    #[test]
+    fn test_og_title_preferred_over_title_tag() {
+        let html = r#"<head><title>Post | Blog</title><meta property="og:title" content="Post"></head>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("Post".to_string()));
+    }
+
+    #[test]
+    fn test_title_from_h1_only() {
+        let html = r#"<body><header>Nav</header><h1 class="hero">Getting <em>Started</em></h1><h1>Second</h1></body>"#;
+        assert_eq!(extract_title_from_html(html, false), Some("Getting Started".to_string()));
+    }
+
+    #[test]
+    fn test_title_site_suffix_stripped_optionally() {
+        let html = "<head><TITLE> Why Rust? | Example Blog </TITLE></head><body><h1>Ignored</h1></body>";
+        assert_eq!(extract_title_from_html(html, false), Some("Why Rust? | Example Blog".to_string()));
+        assert_eq!(extract_title_from_html(html, true), Some("Why Rust?".to_string()));
+    }
+
+    #[test]
+    fn test_no_title_falls_through() {
+        assert_eq!(extract_title_from_html("<body><p>no headings</p><title></title></body>", true), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Read-only health checks for the CLI (`eywa verify`)
+//!
+//! Checks that the stored index matches the configured embedding model, that
+//! the three stores agree on what they hold, and that models are usable.
+
+use crate::bm25::BM25Index;
+use crate::config::{Config, EmbeddingModelConfig, RerankerModelConfig};
+use crate::content::ContentStore;
+use crate::db::VectorDB;
+use crate::embed::{resolve_device, Embedder};
+use crate::rerank::Reranker;
+use crate::setup::ModelDownloader;
+use anyhow::Result;
+use std::path::Path;
+
+/// Outcome of a single check
+#[derive(Debug, Clone)]
+pub struct VerifyCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl VerifyCheck {
+    fn new(name: &str, passed: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// All checks from one verify run
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub checks: Vec<VerifyCheck>,
+}
+
+impl VerifyReport {
+    /// True if every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// Process exit code: 0 if healthy, 1 otherwise
+    pub fn exit_code(&self) -> u8 {
+        if self.passed() { 0 } else { 1 }
+    }
+}
+
+/// Check index dimensions and row counts across LanceDB, SQLite and Tantivy
+pub async fn verify_index(
+    config: &Config,
+    db: &VectorDB,
+    content: &ContentStore,
+    bm25_index: &BM25Index,
+) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+
+    let expected = config.embedding_model.dimensions;
+    report.checks.push(match db.stored_embedding_dim().await? {
+        Some(stored) if stored != expected => VerifyCheck::new(
+            "dimensions",
+            false,
+            format!(
+                "index has {} dims but {} produces {} (re-ingest or switch models)",
+                stored, config.embedding_model.name, expected
+            ),
+        ),
+        Some(stored) => VerifyCheck::new("dimensions", true, format!("{} dims", stored)),
+        None => VerifyCheck::new("dimensions", true, "no index yet"),
+    });
+
+    // SQLite keeps every document; LanceDB mirrors it one-to-one
+    let (lance_docs, lance_chunks) = db.row_counts().await?;
+    let stats = content.stats()?;
+    report.checks.push(VerifyCheck::new(
+        "documents",
+        lance_docs as u64 == stats.document_count,
+        format!("LanceDB {} / SQLite {}", lance_docs, stats.document_count),
+    ));
+
+    // Deduplicated chunks are indexed once in LanceDB and Tantivy, but SQLite
+    // stores a copy per document, so it can only ever have more
+    let bm25_chunks = bm25_index.num_docs()?;
+    report.checks.push(VerifyCheck::new(
+        "chunks",
+        lance_chunks as u64 == bm25_chunks && stats.chunk_count >= bm25_chunks,
+        format!(
+            "LanceDB {} / BM25 {} / SQLite {}",
+            lance_chunks, bm25_chunks, stats.chunk_count
+        ),
+    ));
+
+    Ok(report)
+}
+
+/// Warning for searches embedded by a different model than the index
+///
+/// Vectors from different models live in unrelated spaces, so scores are
+/// meaningless until the index is rebuilt. `None` when nothing is indexed.
+pub fn model_mismatch_warning(index_model: Option<&str>, query_model: &str) -> Option<String> {
+    let index_model = index_model?;
+    (index_model != query_model).then(|| {
+        format!(
+            "Index was built with {} but queries use {}. Run 'eywa reindex' to rebuild it.",
+            index_model, query_model
+        )
+    })
+}
+
+/// Check that the configured models are downloaded and load
+pub fn verify_models(config: &Config) -> VerifyReport {
+    let downloader = ModelDownloader::new();
+    let mut report = VerifyReport::default();
+
+    let embedder = if !downloader.is_cached(&config.embedding_model) {
+        VerifyCheck::new("embedding model", false, format!("{} not downloaded", config.embedding_model.name))
+    } else {
+        match Embedder::new_with_model(&config.embedding_model, &config.device, false) {
+            Ok(_) => VerifyCheck::new("embedding model", true, config.embedding_model.name.clone()),
+            Err(e) => VerifyCheck::new("embedding model", false, format!("failed to load: {}", e)),
+        }
+    };
+    report.checks.push(embedder);
+
+    let reranker = if !downloader.is_cached(&config.reranker_model) {
+        VerifyCheck::new("reranker model", false, format!("{} not downloaded", config.reranker_model.name))
+    } else {
+        match Reranker::new_with_model(&config.reranker_model, &config.device, false) {
+            Ok(_) => VerifyCheck::new("reranker model", true, config.reranker_model.name.clone()),
+            Err(e) => VerifyCheck::new("reranker model", false, format!("failed to load: {}", e)),
+        }
+    };
+    report.checks.push(reranker);
+
+    report
+}
+
+/// Validate a config file without loading models or touching any store
+///
+/// Checks that it parses, that curated models have their known dimensions,
+/// that custom models name a HuggingFace repo, and that the device exists.
+pub fn verify_config_file(path: &Path) -> VerifyReport {
+    let mut report = VerifyReport::default();
+
+    let config = match std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| toml::from_str::<Config>(&text).map_err(anyhow::Error::from))
+    {
+        Ok(config) => {
+            report.checks.push(VerifyCheck::new("config", true, path.display().to_string()));
+            config
+        }
+        Err(e) => {
+            report.checks.push(VerifyCheck::new("config", false, format!("{}: {}", path.display(), e)));
+            return report;
+        }
+    };
+
+    let model = &config.embedding_model;
+    report.checks.push(
+        match EmbeddingModelConfig::curated_models().into_iter().find(|m| m.id == model.id) {
+            Some(known) if known.dimensions != model.dimensions => VerifyCheck::new(
+                "embedding model",
+                false,
+                format!("{} produces {} dims, config says {}", known.name, known.dimensions, model.dimensions),
+            ),
+            Some(_) => VerifyCheck::new("embedding model", true, model.name.clone()),
+            None if !model.repo_id.contains('/') => VerifyCheck::new(
+                "embedding model",
+                false,
+                format!("unknown model '{}' (custom models need a repo_id like org/name)", model.id),
+            ),
+            None if model.dimensions == 0 => {
+                VerifyCheck::new("embedding model", false, format!("{} has 0 dimensions", model.repo_id))
+            }
+            None => VerifyCheck::new("embedding model", true, format!("{} (custom)", model.repo_id)),
+        },
+    );
+
+    let reranker = &config.reranker_model;
+    let known_reranker = RerankerModelConfig::curated_models().iter().any(|m| m.id == reranker.id);
+    report.checks.push(if known_reranker || reranker.repo_id.contains('/') {
+        VerifyCheck::new("reranker model", true, reranker.name.clone())
+    } else {
+        VerifyCheck::new(
+            "reranker model",
+            false,
+            format!("unknown model '{}' (custom models need a repo_id like org/name)", reranker.id),
+        )
+    });
+
+    report.checks.push(match config.validate() {
+        Ok(()) => VerifyCheck::new(
+            "chunk sizes",
+            true,
+            format!(
+                "target {}, overlap {}, min {}",
+                config.chunk_target_size, config.chunk_overlap_size, config.chunk_min_size
+            ),
+        ),
+        Err(e) => VerifyCheck::new("chunk sizes", false, e.to_string()),
+    });
+
+    report.checks.push(match resolve_device(&config.device) {
+        Ok(_) => VerifyCheck::new("device", true, config.device.name()),
+        Err(e) => VerifyCheck::new("device", false, format!("{} unavailable: {}", config.device.name(), e)),
+    });
+
+    report
+}
@@ -1,6 +1,6 @@
 //! Integration tests for Eywa
 
-use eywa::{BM25Index, ContentStore, DevicePreference, Embedder, EmbeddingModelConfig, IngestPipeline, Ingester, SearchEngine, VectorDB};
+use eywa::{embed_in_batches, explain_decisions, find_similar_documents, merge_sources, model_mismatch_warning, verify_index, BM25Index, Config, ContentStore, DevicePreference, Decision, Embedder, EmbeddingModelConfig, FileWalk, IngestManifest, IngestPipeline, Ingester, ManifestStatus, Reranker, RerankerModelConfig, SearchEngine, SkipReason, SourceNameCase, VectorDB, WalkOptions};
 use std::sync::Arc;
 use tempfile::tempdir;
 
@@ -35,6 +35,74 @@ fn test_embedder_batch() {
     }
 }
 
+#[test]
+fn test_embed_batch_matches_single_embeds() {
+    let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder");
+    // Different lengths, so the short texts are padded in the batch
+    let texts = vec![
+        "hi".to_string(),
+        "Rust is a systems programming language focused on safety and performance.".to_string(),
+        "Vector search finds the nearest chunks by cosine similarity".to_string(),
+    ];
+
+    let batch = embedder.embed_batch(&texts).expect("Failed to batch embed");
+
+    assert_eq!(batch.len(), texts.len());
+    for (text, batched) in texts.iter().zip(&batch) {
+        let single = embedder.embed(text).expect("Failed to embed");
+        let max_diff = single.iter().zip(batched).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+        assert!(max_diff < 1e-4, "Padding changed the embedding of '{}' by {}", text, max_diff);
+    }
+}
+
+#[test]
+fn test_concurrent_embedding_matches_sequential() {
+    let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder");
+    let texts: Vec<String> = (0..11)
+        .map(|i| format!("Passage {} about {}", i, ["rust", "tokio", "lancedb", "tantivy"][i % 4]))
+        .collect();
+
+    let sequential = embed_in_batches(&embedder, &texts, 3, 1).expect("Failed to embed sequentially");
+    let concurrent = embed_in_batches(&embedder, &texts, 3, 4).expect("Failed to embed concurrently");
+
+    assert_eq!(sequential.len(), texts.len());
+    assert_eq!(concurrent, sequential, "Concurrent embedding should give identical vectors in input order");
+}
+
+/// Times embedding a real folder with 1, 2 and one-per-core workers
+///
+/// Run with `cargo test --release --test integration bench_embed_workers -- --ignored --nocapture`;
+/// set `EYWA_BENCH_DIR` to embed a folder other than this crate's `src`.
+#[test]
+#[ignore]
+fn bench_embed_workers() {
+    let dir = std::env::var("EYWA_BENCH_DIR").unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/src").to_string());
+    let texts: Vec<String> = FileWalk::new(std::path::Path::new(&dir), &WalkOptions::default())
+        .expect("Failed to walk folder")
+        .filter_map(|f| std::fs::read_to_string(f).ok())
+        .flat_map(|content| {
+            content
+                .split("\n\n")
+                .map(str::trim)
+                .filter(|p| p.len() >= 100)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder");
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut workers = vec![1, 2, cores];
+    workers.sort();
+    workers.dedup();
+
+    println!("{} passages from {}", texts.len(), dir);
+    for threads in workers {
+        let start = std::time::Instant::now();
+        embed_in_batches(&embedder, &texts, 32, threads).expect("Failed to embed");
+        println!("{:>3} worker(s): {:.1}s", threads, start.elapsed().as_secs_f32());
+    }
+}
+
 #[test]
 fn test_embedder_similar_texts_have_high_similarity() {
     let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder");
@@ -60,20 +128,34 @@ fn test_search_engine_filters_by_score() {
         eywa::SearchResult {
             id: "1".to_string(),
             source_id: "test".to_string(),
+            document_id: "doc1".to_string(),
             title: Some("High".to_string()),
             content: "content".to_string(),
             file_path: None,
             line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score: 0.8,
+            matched_by: Vec::new(),
         },
         eywa::SearchResult {
             id: "2".to_string(),
             source_id: "test".to_string(),
+            document_id: "doc2".to_string(),
             title: Some("Low".to_string()),
             content: "content".to_string(),
             file_path: None,
             line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score: 0.2, // Below threshold of 0.3
+            matched_by: Vec::new(),
         },
     ];
 
@@ -90,20 +172,34 @@ fn test_search_engine_keyword_reranking() {
         eywa::SearchResult {
             id: "1".to_string(),
             source_id: "test".to_string(),
+            document_id: "doc1".to_string(),
             title: None,
             content: "This is about dogs and cats".to_string(),
             file_path: None,
             line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score: 0.7,
+            matched_by: Vec::new(),
         },
         eywa::SearchResult {
             id: "2".to_string(),
             source_id: "test".to_string(),
+            document_id: "doc2".to_string(),
             title: None,
             content: "Rust programming language is great".to_string(),
             file_path: None,
             line_start: None,
+            line_end: None,
+            page_start: None,
+            page_end: None,
+            section: None,
+            has_code: false,
             score: 0.75,
+            matched_by: Vec::new(),
         },
     ];
 
@@ -416,3 +512,1521 @@ async fn test_hybrid_search_combines_vector_and_bm25() {
     let total_unique: std::collections::HashSet<_> = vector_ids.iter().chain(bm25_ids.iter()).collect();
     assert!(total_unique.len() >= 2, "Hybrid search should cover multiple documents");
 }
+
+/// Pipeline, BM25 index and vector store for the commit barrier tests
+async fn barrier_fixture(data_path: &std::path::Path) -> (IngestPipeline, Arc<BM25Index>, VectorDB) {
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    (IngestPipeline::new(embedder, Arc::clone(&bm25_index)), bm25_index, db)
+}
+
+/// Write one document through every store and return its ID
+async fn write_wal_doc(pipeline: &IngestPipeline, db: &mut VectorDB, data_path: &std::path::Path) -> String {
+    let docs = vec![eywa::DocumentInput {
+        content: "Write-ahead logging ensures durability by recording changes before applying them. Databases replay the log after a crash to restore a consistent state.".to_string(),
+        title: Some("WAL".to_string()),
+        file_path: None,
+        is_pdf: false,
+    }];
+    let batch = pipeline.prepare_and_embed("docs", data_path, docs).expect("Failed to embed");
+    let response = pipeline.write_embedded_batch(db, batch).await.expect("Failed to write");
+    pipeline
+        .commit_barrier(db, data_path, &response.document_ids)
+        .await
+        .expect("Barrier should pass after a complete write");
+    response.document_ids[0].clone()
+}
+
+/// Assert a document has a vector and a BM25 entry for each of its chunks
+async fn assert_fully_indexed(db: &VectorDB, bm25_index: &BM25Index, doc_id: &str) {
+    assert!(db.get_document(doc_id).await.unwrap().is_some());
+    let with_vectors: Vec<String> = db.chunk_ids_with_vectors(doc_id).await.unwrap().into_iter().collect();
+    assert!(!with_vectors.is_empty(), "Document should have chunk vectors");
+    assert_eq!(bm25_index.indexed_chunk_ids(&with_vectors).unwrap().len(), with_vectors.len());
+}
+
+#[tokio::test]
+async fn test_commit_barrier_rejects_document_missing_from_content_store() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let (pipeline, _, mut db) = barrier_fixture(dir.path()).await;
+
+    // Crash before phase 1: nothing to rebuild from
+    let err = pipeline
+        .commit_barrier(&mut db, dir.path(), &["never-written".to_string()])
+        .await
+        .expect_err("Barrier should reject a document that never reached SQLite");
+    assert!(err.to_string().contains("content store"), "Unexpected error: {}", err);
+}
+
+#[tokio::test]
+async fn test_commit_barrier_repairs_crash_after_content_store() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let (pipeline, bm25_index, mut db) = barrier_fixture(data_path).await;
+
+    // Crash after phase 1: content in SQLite, nothing in LanceDB or BM25
+    {
+        let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+        content_store
+            .insert_document("orphan", "docs", "Orphan", None, "Checkpointing flushes dirty pages to disk so recovery replays less of the log.", "2024-01-01T00:00:00Z")
+            .expect("Failed to insert document");
+    }
+
+    pipeline
+        .commit_barrier(&mut db, data_path, &["orphan".to_string()])
+        .await
+        .expect("Barrier should rebuild the document from SQLite");
+
+    assert_fully_indexed(&db, &bm25_index, "orphan").await;
+    let results = bm25_index.search("checkpointing dirty pages", 10).unwrap();
+    assert!(!results.is_empty(), "Rebuilt document should be found by keyword search");
+}
+
+#[tokio::test]
+async fn test_commit_barrier_repairs_missing_chunk_vectors() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let (pipeline, bm25_index, mut db) = barrier_fixture(data_path).await;
+    let doc_id = write_wal_doc(&pipeline, &mut db, data_path).await;
+
+    // Crash part way through phase 2: a chunk vector never landed
+    let with_vectors: Vec<String> = db.chunk_ids_with_vectors(&doc_id).await.unwrap().into_iter().collect();
+    db.delete_chunks(&with_vectors[..1]).await.unwrap();
+
+    pipeline
+        .commit_barrier(&mut db, data_path, &[doc_id.clone()])
+        .await
+        .expect("Barrier should re-embed the missing chunk");
+
+    assert_fully_indexed(&db, &bm25_index, &doc_id).await;
+}
+
+#[tokio::test]
+async fn test_commit_barrier_repairs_crash_before_bm25() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let (pipeline, bm25_index, mut db) = barrier_fixture(data_path).await;
+    let doc_id = write_wal_doc(&pipeline, &mut db, data_path).await;
+
+    // Crash after phase 2: vectors stored, BM25 entries missing
+    let with_vectors: Vec<String> = db.chunk_ids_with_vectors(&doc_id).await.unwrap().into_iter().collect();
+    bm25_index.delete_chunks(&with_vectors).unwrap();
+    assert!(bm25_index.search("durability", 10).unwrap().is_empty());
+
+    pipeline
+        .commit_barrier(&mut db, data_path, &[doc_id.clone()])
+        .await
+        .expect("Barrier should restore the BM25 entries");
+
+    assert_fully_indexed(&db, &bm25_index, &doc_id).await;
+    assert!(!bm25_index.search("durability", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_reranker_batched_scores_match_single_batch() {
+    let documents: Vec<String> = vec![
+        "Rust guarantees memory safety without a garbage collector.",
+        "The borrow checker enforces ownership rules at compile time.",
+        "Bananas are rich in potassium.",
+        "Cargo is the Rust package manager and build tool.",
+        "Paris is the capital of France.",
+        "Lifetimes describe how long references remain valid.",
+        "Photosynthesis converts light into chemical energy.",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let query = "How does Rust manage memory?";
+
+    let single = Reranker::new_with_model(&RerankerModelConfig::default(), &DevicePreference::Cpu, false)
+        .expect("Failed to create reranker")
+        .with_batch_size(documents.len());
+    let batched = Reranker::new_with_model(&RerankerModelConfig::default(), &DevicePreference::Cpu, false)
+        .expect("Failed to create reranker")
+        .with_batch_size(3);
+
+    let single_scores = single.rerank(query, &documents).expect("Failed to rerank");
+    let batched_scores = batched.rerank(query, &documents).expect("Failed to rerank");
+
+    assert_eq!(single_scores.len(), documents.len());
+    assert_eq!(batched_scores.len(), documents.len());
+    for (i, (a, b)) in single_scores.iter().zip(batched_scores.iter()).enumerate() {
+        assert!((a - b).abs() < 1e-4, "Score {} differs across batch sizes: {} vs {}", i, a, b);
+    }
+}
+
+#[tokio::test]
+async fn test_merge_sources_dedupes_identical_documents() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let shared = eywa::DocumentInput {
+        content: "Consistent hashing distributes keys across nodes so that adding or removing a node only remaps a small fraction of keys. It is widely used in distributed caches.".to_string(),
+        title: Some("Hashing".to_string()),
+        file_path: None,
+        is_pdf: false,
+    };
+    let unique = eywa::DocumentInput {
+        content: "Vector clocks track causality between events in a distributed system by keeping one logical counter per process and merging them on message receipt.".to_string(),
+        title: Some("Clocks".to_string()),
+        file_path: None,
+        is_pdf: false,
+    };
+
+    pipeline.ingest_documents(&mut db, data_path, "team-a", vec![shared.clone(), unique]).await.expect("Failed to ingest");
+    let target = pipeline.ingest_documents(&mut db, data_path, "team-b", vec![shared]).await.expect("Failed to ingest").document_ids[0].clone();
+
+    let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let target_chunks = content_store.get_chunks_for_document(&target).unwrap().len();
+    let stats = merge_sources(&db, &bm25_index, &content_store, "team-a", "team-b")
+        .await
+        .expect("Failed to merge");
+
+    assert_eq!(stats.documents_moved, 1);
+    assert_eq!(stats.documents_deduped, 1);
+
+    let sources = db.list_sources().await.expect("Failed to list sources");
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].id, "team-b");
+    assert_eq!(db.get_document_ids_for_source("team-b").await.unwrap().len(), 2);
+
+    let (docs, total) = content_store.list_documents_by_source("team-b", None, None).unwrap();
+    assert_eq!(total, 2, "Identical document should not be duplicated: {:?}", docs);
+
+    // The folded-in copy's chunks don't pile up on the surviving document
+    assert_eq!(content_store.get_chunks_for_document(&target).unwrap().len(), target_chunks);
+    let record = db.get_document(&target).await.unwrap().expect("Target document should remain");
+    assert_eq!(record.chunk_count as usize, target_chunks);
+
+    // BM25 entries follow the merged source
+    assert!(bm25_index.search_source("causality", "team-a", 10).unwrap().is_empty());
+    assert!(!bm25_index.search_source("causality", "team-b", 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_list_documents_pages_with_offset() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+
+    let docs: Vec<eywa::DocumentInput> = ["Alpha", "Bravo", "Charlie"]
+        .iter()
+        .map(|name| eywa::DocumentInput {
+            content: format!("{} team runbook: restart the service, check the dashboards, and page the on-call engineer if errors persist.", name),
+            title: Some(name.to_string()),
+            file_path: None,
+            is_pdf: false,
+        })
+        .collect();
+    pipeline.ingest_documents(&mut db, data_path, "runbooks", docs).await.expect("Failed to ingest");
+
+    assert_eq!(db.count_documents("runbooks").await.unwrap(), 3);
+
+    let first = db.list_documents("runbooks", Some(2), Some(0)).await.unwrap();
+    let second = db.list_documents("runbooks", Some(2), Some(2)).await.unwrap();
+    assert_eq!(first.len(), 2);
+    assert_eq!(second.len(), 1);
+    let mut titles: Vec<&str> = first.iter().chain(&second).map(|d| d.title.as_str()).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Alpha", "Bravo", "Charlie"]);
+
+    assert!(db.list_documents("runbooks", Some(2), Some(10)).await.unwrap().is_empty());
+    assert_eq!(db.list_documents("runbooks", Some(eywa::db::MAX_QUERY_LIMIT), Some(1)).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_similar_documents_excludes_self() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs = vec![
+        eywa::DocumentInput {
+            content: "Rust ownership rules: each value has a single owner, and the value is dropped when the owner goes out of scope. Borrowing lets code use a value without taking ownership.".to_string(),
+            title: Some("Ownership".to_string()),
+            file_path: None,
+            is_pdf: false,
+        },
+        eywa::DocumentInput {
+            content: "The Rust borrow checker enforces that references never outlive the data they point to, and that mutable borrows are exclusive while shared borrows may be many.".to_string(),
+            title: Some("Borrowing".to_string()),
+            file_path: None,
+            is_pdf: false,
+        },
+        eywa::DocumentInput {
+            content: "Sourdough bread relies on a fermented starter of flour and water. Long proofing develops flavour and an open crumb before baking in a hot oven.".to_string(),
+            title: Some("Bread".to_string()),
+            file_path: None,
+            is_pdf: false,
+        },
+    ];
+    let response = pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+    let ownership_id = response.document_ids[0].clone();
+    let borrowing_id = response.document_ids[1].clone();
+
+    let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let engine = SearchEngine::new();
+
+    let similar = find_similar_documents(&embedder, &db, &content_store, &engine, &ownership_id, 5)
+        .await
+        .expect("Failed to find similar docs")
+        .expect("Document should exist");
+
+    assert!(!similar.is_empty());
+    assert!(similar.iter().all(|d| d.document_id != ownership_id), "Document must not be similar to itself");
+    assert_eq!(similar[0].document_id, borrowing_id);
+
+    // One entry per document
+    let mut ids: Vec<&str> = similar.iter().map(|d| d.document_id.as_str()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), similar.len());
+
+    let missing = find_similar_documents(&embedder, &db, &content_store, &engine, "missing", 5)
+        .await
+        .expect("Lookup should not error");
+    assert!(missing.is_none());
+}
+
+#[tokio::test]
+async fn test_source_summary_returns_representative_per_topic() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+
+    let doc = |title: &str, content: &str| eywa::DocumentInput {
+        content: content.to_string(),
+        title: Some(title.to_string()),
+        file_path: None,
+        is_pdf: false,
+    };
+    let docs = vec![
+        doc("bread-1", "Knead the bread dough for ten minutes until it is smooth and elastic, then let it rise in a warm kitchen until doubled."),
+        doc("rust-1", "The Rust borrow checker ensures references never outlive the data they point to, catching use-after-free bugs at compile time."),
+        doc("bread-2", "Preheat the oven with a baking stone inside, score the loaf with a sharp blade, and bake the sourdough until the crust is deep brown."),
+        doc("rust-2", "Rust traits define shared behaviour; generic functions take trait bounds so the compiler can monomorphize each call site."),
+        doc("bread-3", "A sourdough starter is flour and water fermented by wild yeast; feed it daily and use it at its peak to leaven the bread."),
+        doc("rust-3", "Cargo builds Rust crates, resolves dependencies from crates.io, and runs unit tests with cargo test across the workspace."),
+    ];
+    pipeline.ingest_documents(&mut db, data_path, "mixed", docs).await.expect("Failed to ingest");
+
+    let summary = eywa::summarize_source(&db, "mixed", 2, 2).await.expect("Failed to summarize");
+
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary.iter().map(|c| c.documents).sum::<usize>(), 6);
+    let topic = |cluster: &eywa::SourceCluster| -> Vec<String> {
+        cluster.representatives.iter().map(|d| d.title.split('-').next().unwrap().to_string()).collect()
+    };
+    let (first, second) = (topic(&summary[0]), topic(&summary[1]));
+    assert!(first.iter().all(|t| t == &first[0]), "Cluster mixes topics: {:?}", first);
+    assert!(second.iter().all(|t| t == &second[0]), "Cluster mixes topics: {:?}", second);
+    assert_ne!(first[0], second[0], "Both clusters cover the same topic");
+
+    assert!(eywa::summarize_source(&db, "missing", 2, 2).await.unwrap().is_empty());
+}
+
+#[test]
+fn test_query_expansion_finds_documents_raw_term_misses() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let bm25_index = BM25Index::open(dir.path()).expect("Failed to create BM25 index");
+    let chunk = |id: &str, content: &str| eywa::ChunkInput {
+        id: id.to_string(),
+        source_id: "docs".to_string(),
+        content: content.to_string(),
+        title: None,
+    };
+    bm25_index
+        .index_chunks(&[
+            chunk("sso", "Single sign-on authentication redirects users to the identity provider before they reach the dashboard."),
+            chunk("billing", "Invoices are generated on the first of the month and emailed to the account owner."),
+        ])
+        .expect("Failed to index");
+
+    assert!(bm25_index.search("auth", 10).unwrap().is_empty(), "Raw term should miss");
+
+    let synonyms = std::collections::HashMap::from([("auth".to_string(), vec!["authentication".to_string()])]);
+    let engine = SearchEngine::new().with_query_expansion(synonyms);
+    let results = bm25_index.search(&engine.expand_query("auth"), 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].chunk_id, "sso");
+
+    // Off by default
+    assert_eq!(SearchEngine::new().expand_query("auth"), "auth");
+}
+
+#[tokio::test]
+async fn test_embedding_only_search_is_cosine_ordered_without_reranking() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), bm25_index);
+
+    let docs = [
+        "Garbage collection in the JVM pauses application threads while the collector marks reachable objects and frees the rest.",
+        "Rust has no garbage collector: ownership and borrowing decide at compile time when memory is freed, with no runtime pauses.",
+        "The community garden meets on Saturdays to plant tomatoes, water the beds, and share seedlings with new members.",
+    ]
+    .iter()
+    .map(|content| eywa::DocumentInput {
+        content: content.to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    })
+    .collect();
+    pipeline.ingest_documents(&mut db, data_path, "notes", docs).await.expect("Failed to ingest");
+
+    let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let engine = SearchEngine::with_reranker_model(&RerankerModelConfig::default(), &DevicePreference::Cpu);
+    let query = "how does memory get freed without a garbage collector";
+    let results = engine
+        .search_embedding_only(&embedder, &db, &content_store, query, 3, None, false)
+        .await
+        .expect("Search failed");
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(engine.rerank_calls(), 0, "Embedding-only search must not rerank");
+    assert!(results.iter().all(|r| r.matched_by == vec!["vector".to_string()]));
+    assert!(results.iter().all(|r| !r.document_id.is_empty()), "Document IDs are carried through");
+
+    // Same order and scores as the raw cosine vector search, best first
+    let query_embedding = embedder.embed_query(query).unwrap();
+    let raw = db.search_with_filters(&query_embedding, 3, None, false).await.unwrap();
+    assert_eq!(
+        results.iter().map(|r| (r.id.as_str(), r.score)).collect::<Vec<_>>(),
+        raw.iter().map(|m| (m.id.as_str(), m.score)).collect::<Vec<_>>()
+    );
+    assert!(results.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+    // The regular path goes through the counter
+    engine.rerank(results, query, 3);
+    assert_eq!(engine.rerank_calls(), 1);
+}
+
+#[tokio::test]
+async fn test_code_only_search_returns_code_chunks() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs = vec![
+        eywa::DocumentInput {
+            content: "# Parsing\n\n## Reading config\n\nThe config loader reads a TOML file and parses it into a struct:\n\n```rust\nlet config: Config = toml::from_str(&text)?;\n```\n\nErrors are reported with the file path.\n".to_string(),
+            title: Some("Parsing".to_string()),
+            file_path: Some("parsing.md".to_string()),
+            is_pdf: false,
+        },
+        eywa::DocumentInput {
+            content: "# Config\n\n## Overview\n\nThe config file lives in the home directory and stores model choices. It is written in TOML and read on every start-up so changes apply immediately.\n".to_string(),
+            title: Some("Config".to_string()),
+            file_path: Some("config.md".to_string()),
+            is_pdf: false,
+        },
+    ];
+    pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+
+    let query = embedder.embed("how is the TOML config parsed").expect("Failed to embed");
+
+    let all = db.search_with_filters(&query, 10, None, false).await.expect("Search failed");
+    assert!(all.iter().any(|c| !c.has_code), "Expected some prose-only chunks");
+
+    let code = db.search_with_filters(&query, 10, None, true).await.expect("Search failed");
+    assert!(!code.is_empty());
+    assert!(code.iter().all(|c| c.has_code), "code_only must only return chunks with code");
+    assert!(code.iter().all(|c| c.line_end.is_some()));
+    assert_eq!(code[0].section.as_deref(), Some("Reading config"));
+}
+
+#[tokio::test]
+async fn test_linked_ingest_reads_content_from_disk() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let docs_dir = dir.path().join("docs");
+    std::fs::create_dir(&docs_dir).unwrap();
+    let file = docs_dir.join("guide.md");
+    std::fs::write(&file, "# Guide\n\n## Setup\n\nInstall the toolchain, clone the repository, and run the setup script to fetch dependencies before building.\n").unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index)).with_linked_files(true);
+
+    let response = pipeline
+        .ingest_files(&mut db, data_path, "docs", &[file.clone()])
+        .await
+        .expect("Failed to ingest");
+    let doc_id = &response.document_ids[0];
+
+    let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let chunks = content_store.get_chunks_for_document(doc_id).unwrap();
+    assert!(chunks.iter().any(|(_, text)| text.contains("Install the toolchain")));
+
+    std::fs::write(&file, "Rewritten on disk").unwrap();
+    assert_eq!(content_store.get_document(doc_id).unwrap().as_deref(), Some("Rewritten on disk"));
+
+    // Chunk text comes from the file too, so nothing is left once it's gone
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(content_store.get_document(doc_id).unwrap(), None);
+    assert!(content_store.get_chunks_for_document(doc_id).unwrap().is_empty());
+}
+
+#[test]
+fn test_embedding_prefix_override_is_applied() {
+    let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false)
+        .expect("Failed to create embedder")
+        .with_query_prefix("query: ")
+        .with_passage_prefix("passage: ");
+
+    let text = "How do I rotate an API key?";
+
+    let query = embedder.embed_query(text).unwrap();
+    assert_eq!(query, embedder.embed(&format!("query: {}", text)).unwrap());
+    assert_ne!(query, embedder.embed(text).unwrap());
+
+    let passages = embedder.embed_passages(&[text.to_string()]).unwrap();
+    assert_eq!(passages[0], embedder.embed(&format!("passage: {}", text)).unwrap());
+
+    // Empty prefix disables prefixing
+    let embedder = embedder.with_query_prefix("");
+    assert_eq!(embedder.embed_query(text).unwrap(), embedder.embed(text).unwrap());
+}
+
+#[tokio::test]
+async fn test_count_matches_counts_documents_above_min_score() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs: Vec<eywa::DocumentInput> = [
+        "Kubernetes schedules pods onto nodes and restarts containers that fail their health checks, keeping the desired state.",
+        "A Kubernetes deployment manages replica sets so that rolling updates replace pods gradually without downtime.",
+        "Watercolour painting layers transparent washes of pigment, working from light to dark tones on damp paper.",
+    ]
+    .iter()
+    .map(|c| eywa::DocumentInput {
+        content: c.to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    })
+    .collect();
+    pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+
+    let query = embedder.embed_query("kubernetes pods").unwrap();
+
+    // Everything matches with no threshold, nothing above a perfect score
+    assert_eq!(db.count_matches(&query, -1.0, None, false).await.unwrap(), 3);
+    assert_eq!(db.count_matches(&query, 1.01, None, false).await.unwrap(), 0);
+
+    // Agrees with a full search filtered by the same threshold
+    let min_score = SearchEngine::new().min_score;
+    let expected: std::collections::HashSet<String> = db
+        .search(&query, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.score >= min_score)
+        .map(|m| m.document_id)
+        .collect();
+    assert_eq!(db.count_matches(&query, min_score, None, false).await.unwrap(), expected.len());
+}
+
+#[tokio::test]
+async fn test_storage_report_tracks_fragments_across_optimize() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let chunk_fragments = |report: &[eywa::TableStorage]| {
+        report.iter().find(|t| t.name == "chunks_v2").map(|t| t.fragments).unwrap_or(0)
+    };
+
+    // Many small writes, each creating its own fragment
+    pipeline
+        .ingest_documents(&mut db, data_path, "docs", vec![eywa::DocumentInput {
+            content: "Document number 0 about storage fragments. Each small write to the chunks table lands in a fragment of its own.".to_string(),
+            title: None,
+            file_path: None,
+            is_pdf: false,
+        }])
+        .await
+        .expect("Failed to ingest");
+    let initial = chunk_fragments(&db.storage_report().await.unwrap());
+
+    for i in 1..6 {
+        pipeline
+            .ingest_documents(&mut db, data_path, "docs", vec![eywa::DocumentInput {
+                content: format!("Document number {} about storage fragments. Each small write to the chunks table lands in a fragment of its own.", i),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            }])
+            .await
+            .expect("Failed to ingest");
+    }
+
+    let report = db.storage_report().await.unwrap();
+    let fragmented = chunk_fragments(&report);
+    assert!(fragmented > initial, "fragments should grow: {} -> {}", initial, fragmented);
+    assert!(report.iter().any(|t| t.reclaimable_bytes > 0 || t.versions > 1));
+
+    db.optimize().await.expect("Failed to optimize");
+
+    let report = db.storage_report().await.unwrap();
+    let chunks = report.iter().find(|t| t.name == "chunks_v2").unwrap();
+    assert!(chunks.fragments < fragmented, "fragments should shrink: {} -> {}", fragmented, chunks.fragments);
+    assert_eq!(chunks.rows, 6);
+    assert_eq!(chunks.reclaimable_bytes, 0);
+}
+
+#[tokio::test]
+async fn test_per_source_tables_delete_touches_only_that_source() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::open(data_path.to_str().unwrap(), true).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    for source in ["alpha", "beta"] {
+        pipeline
+            .ingest_documents(&mut db, data_path, source, vec![eywa::DocumentInput {
+                content: format!("Notes kept in the {} source. With per-source tables every source writes its chunks to a table of its own.", source),
+                title: None,
+                file_path: None,
+                is_pdf: false,
+            }])
+            .await
+            .expect("Failed to ingest");
+    }
+
+    // "alpha" and "beta" hex-encoded
+    let table = |report: &[eywa::TableStorage], name: &str| {
+        report.iter().find(|t| t.name == name).cloned().expect("missing table")
+    };
+    let before = db.storage_report().await.unwrap();
+    assert!(before.iter().all(|t| t.name != "chunks_v2"));
+    let beta_before = table(&before, "chunks_src_62657461");
+    assert!(table(&before, "chunks_src_616c706861").rows > 0);
+
+    db.delete_source("alpha").await.expect("Failed to delete source");
+
+    let after = db.storage_report().await.unwrap();
+    assert_eq!(table(&after, "chunks_src_616c706861").rows, 0);
+    let beta_after = table(&after, "chunks_src_62657461");
+    assert_eq!(beta_after.rows, beta_before.rows);
+    assert_eq!(beta_after.versions, beta_before.versions, "beta's table must not be written");
+
+    // Unfiltered search fans out across the remaining tables
+    let query = embedder.embed("notes in a source").unwrap();
+    let results = db.search(&query, 10).await.unwrap();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.source_id == "beta"));
+}
+
+#[tokio::test]
+async fn test_verify_fails_on_dimension_mismatch() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs = vec![eywa::DocumentInput {
+        content: "Verify checks the index against the configured model: vector dimensions, row counts and the BM25 index must all agree.".to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    }];
+    pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+
+    // Matching model: healthy
+    let config = eywa::Config {
+        embedding_model: EmbeddingModelConfig::default(),
+        ..eywa::Config::default()
+    };
+    let report = verify_index(&config, &db, &content, &bm25_index).await.unwrap();
+    assert!(report.passed(), "{:?}", report.checks);
+    assert_eq!(report.exit_code(), 0);
+
+    // Configured model now disagrees with the stored vectors
+    let mut mismatched = config.clone();
+    mismatched.embedding_model.dimensions += 1;
+    let report = verify_index(&mismatched, &db, &content, &bm25_index).await.unwrap();
+    assert_ne!(report.exit_code(), 0);
+    let dims = report.checks.iter().find(|c| c.name == "dimensions").unwrap();
+    assert!(!dims.passed);
+}
+
+#[tokio::test]
+async fn test_large_batch_is_split_into_sub_batches() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let config = eywa::BatchConfig {
+        max_write_rows: 2,
+        ..eywa::BatchConfig::default()
+    };
+    let pipeline = IngestPipeline::with_config(Arc::clone(&embedder), Arc::clone(&bm25_index), config);
+
+    let topics = ["volcanoes", "sourdough", "chess openings", "tidal pools", "glaciers", "typography", "beekeeping"];
+    let docs: Vec<eywa::DocumentInput> = topics
+        .iter()
+        .map(|t| eywa::DocumentInput {
+            content: format!("A short note about {} and why they are interesting, written to give each topic a chunk of its own in the index.", t),
+            title: Some(t.to_string()),
+            file_path: None,
+            is_pdf: false,
+        })
+        .collect();
+    let response = pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+    assert!(response.chunks_created as usize > 2, "batch must exceed the sub-batch size");
+
+    // Every row landed, across several appends
+    let (_, chunk_rows) = db.row_counts().await.unwrap();
+    assert_eq!(chunk_rows, response.chunks_created as usize);
+    let report = db.storage_report().await.unwrap();
+    let chunks = report.iter().find(|t| t.name == "chunks_v2").unwrap();
+    assert!(chunks.fragments >= chunk_rows.div_ceil(2));
+
+    let query = embedder.embed_query("beekeeping").unwrap();
+    let results = db.search(&query, 1).await.unwrap();
+    assert_eq!(results[0].title.as_deref(), Some("beekeeping"));
+}
+
+#[tokio::test]
+async fn test_markdown_frontmatter_becomes_tags() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs = vec![eywa::DocumentInput {
+        content: "---\nauthor: Jane Doe\ntags: [networking, dns]\n---\n# Resolvers\n\nA recursive resolver walks the DNS hierarchy from the root servers down to the authoritative name server.\n".to_string(),
+        title: Some("Resolvers".to_string()),
+        file_path: Some("notes/resolvers.md".to_string()),
+        is_pdf: false,
+    }];
+    let response = pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+    let doc_id = &response.document_ids[0];
+
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let tags = content.get_document_tags(doc_id).unwrap();
+    assert_eq!(tags, vec!["author:Jane Doe", "networking", "dns"]);
+
+    let chunks = content.get_chunks_for_document(doc_id).unwrap();
+    assert!(!chunks.is_empty());
+    for (_, text) in &chunks {
+        assert!(!text.contains("author"));
+        assert!(!text.contains("networking"));
+    }
+}
+
+#[tokio::test]
+async fn test_reembed_document_rebuilds_chunks() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+
+    // Ingest with frontmatter kept in the chunks
+    let keep_frontmatter = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+        .with_settings(&Config { strip_frontmatter: false, ..Config::default() });
+    let docs = vec![eywa::DocumentInput {
+        content: "---\nreviewer: Quillfeather\nsummary: Notes from the storage team on cache write policies and their trade-offs\n---\n# Caching\n\nA write-through cache updates the backing store on every write, trading latency for consistency.\n".to_string(),
+        title: Some("Caching".to_string()),
+        file_path: Some("caching.md".to_string()),
+        is_pdf: false,
+    }];
+    let response = keep_frontmatter.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+    let doc_id = response.document_ids[0].clone();
+
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let old_chunks = content.get_chunks_for_document(&doc_id).unwrap();
+    assert!(old_chunks.iter().any(|(_, text)| text.contains("Quillfeather")));
+    assert!(!bm25_index.search("Quillfeather", 5).unwrap().is_empty());
+
+    // Re-embed with stripping on
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+        .with_settings(&Config::default());
+    let result = pipeline.reembed_document(&mut db, data_path, &doc_id).await.expect("Failed to re-embed");
+    assert!(result.is_some());
+    assert!(pipeline.reembed_document(&mut db, data_path, "missing").await.unwrap().is_none());
+
+    // Same document, new chunk set
+    let new_chunks = content.get_chunks_for_document(&doc_id).unwrap();
+    assert!(!new_chunks.is_empty());
+    assert!(new_chunks.iter().all(|(_, text)| !text.contains("Quillfeather")));
+    let old_ids: Vec<&String> = old_chunks.iter().map(|(id, _)| id).collect();
+    assert!(new_chunks.iter().all(|(id, _)| !old_ids.contains(&id)));
+    assert_eq!(db.get_document(&doc_id).await.unwrap().unwrap().chunk_count as usize, new_chunks.len());
+
+    // Search reflects the new chunks
+    assert!(bm25_index.search("Quillfeather", 5).unwrap().is_empty());
+    let query = embedder.embed_query("write-through cache consistency").unwrap();
+    let results = db.search(&query, 5).await.unwrap();
+    assert_eq!(results[0].document_id, doc_id);
+    let new_ids: Vec<&String> = new_chunks.iter().map(|(id, _)| id).collect();
+    assert!(results.iter().all(|r| new_ids.contains(&&r.id)));
+}
+
+#[test]
+fn test_quantized_embedder_loads_with_correct_dimensions() {
+    let model = EmbeddingModelConfig::default();
+    let full = Embedder::new_with_model(&model, &DevicePreference::Cpu, false).expect("Failed to create embedder");
+    let half = Embedder::new_with_options(&model, &DevicePreference::Cpu, eywa::EmbeddingQuantization::F16, false)
+        .expect("Failed to create f16 embedder");
+    assert_eq!(half.quantization(), eywa::EmbeddingQuantization::F16);
+
+    let text = "Quantized weights trade a little precision for memory.";
+    let a = full.embed(text).unwrap();
+    let b = half.embed(text).unwrap();
+    assert_eq!(b.len(), model.dimensions);
+
+    // Both are normalized, so the dot product is the cosine similarity
+    let cosine: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+    assert!(cosine > 0.99, "f16 vectors drifted too far: {}", cosine);
+
+    // No int8 BERT weights in Candle: falls back to f32
+    let int8 = Embedder::new_with_options(&model, &DevicePreference::Cpu, eywa::EmbeddingQuantization::Int8, false)
+        .expect("Failed to create embedder");
+    assert_eq!(int8.quantization(), eywa::EmbeddingQuantization::F32);
+    assert_eq!(int8.embed(text).unwrap(), a);
+}
+
+#[tokio::test]
+async fn test_dedup_scope_controls_cross_source_reuse() {
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let docs = vec![eywa::DocumentInput {
+        content: "Shared onboarding checklist that both teams keep in their own notes: laptop setup, accounts, and a first-week buddy.".to_string(),
+        title: Some("Checklist".to_string()),
+        file_path: None,
+        is_pdf: false,
+    }];
+
+    for (scope, expected_skipped) in [(eywa::DedupScope::Global, 1), (eywa::DedupScope::Source, 0)] {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let data_path = dir.path();
+        let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+        let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+        let pipeline = IngestPipeline::new(Arc::clone(&embedder), bm25_index).with_dedup_scope(scope);
+
+        let first = pipeline.ingest_documents(&mut db, data_path, "team-a", docs.clone()).await.unwrap();
+        let second = pipeline.ingest_documents(&mut db, data_path, "team-b", docs.clone()).await.unwrap();
+        assert_eq!(first.chunks_created, 1);
+        assert_eq!(second.chunks_skipped, expected_skipped, "{:?}", scope);
+        assert_eq!(second.chunks_created, 1 - expected_skipped, "{:?}", scope);
+
+        // Within a source, the same content is always a duplicate
+        let again = pipeline.ingest_documents(&mut db, data_path, "team-b", docs.clone()).await.unwrap();
+        assert_eq!(again.chunks_skipped, 1, "{:?}", scope);
+    }
+}
+
+#[test]
+fn test_pooling_strategies_produce_distinct_valid_vectors() {
+    // Curated models default to the pooling they were trained with
+    assert_eq!(EmbeddingModelConfig::default().pooling, eywa::PoolingStrategy::Mean);
+    assert!(EmbeddingModelConfig::curated_models()
+        .iter()
+        .all(|m| m.pooling == eywa::PoolingStrategy::Mean));
+
+    let text = "Pooling turns token embeddings into a single sentence vector.";
+    let mean_model = EmbeddingModelConfig::default();
+    let mean = Embedder::new_with_model(&mean_model, &DevicePreference::Cpu, false).expect("Failed to create embedder");
+    assert_eq!(mean.pooling(), eywa::PoolingStrategy::Mean);
+
+    let cls_model = EmbeddingModelConfig { pooling: eywa::PoolingStrategy::Cls, ..mean_model };
+    let cls = Embedder::new_with_model(&cls_model, &DevicePreference::Cpu, false).expect("Failed to create embedder");
+
+    let a = mean.embed(text).unwrap();
+    let b = cls.embed(text).unwrap();
+    assert_eq!(a.len(), cls_model.dimensions);
+    assert_eq!(b.len(), cls_model.dimensions);
+    for v in [&a, &b] {
+        let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-3, "vector not normalized: {}", norm);
+        assert!(v.iter().all(|x| x.is_finite()));
+    }
+
+    let cosine: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+    assert!(cosine < 0.999, "mean and CLS pooling should differ, cosine {}", cosine);
+}
+
+#[test]
+fn test_read_only_commands_work_without_models() {
+    // Fresh HOME: no config and an empty model cache
+    let home = tempdir().unwrap();
+    let data_dir = home.path().join("data");
+    let eywa = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_eywa"))
+            .env("HOME", home.path())
+            .arg("--data-dir")
+            .arg(&data_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = eywa(&["sources"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No sources found"));
+
+    // Commands that need models point at init instead of failing in hf_hub
+    let output = eywa(&["search", "anything"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("eywa init"));
+}
+
+#[tokio::test]
+async fn test_candidates_per_source_includes_small_sources() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    // A large source that matches the query closely...
+    let topics = ["moves", "borrows", "lifetimes", "drop order", "references", "clones", "slices", "boxes", "closures", "threads"];
+    let large: Vec<eywa::DocumentInput> = topics
+        .iter()
+        .map(|topic| eywa::DocumentInput {
+            content: format!(
+                "Rust ownership explained: how {} interact with the borrow checker, and which ownership rules the compiler enforces.",
+                topic
+            ),
+            title: Some(format!("Ownership and {}", topic)),
+            file_path: None,
+            is_pdf: false,
+        })
+        .collect();
+    pipeline.ingest_documents(&mut db, data_path, "rust-book", large).await.expect("Failed to ingest");
+
+    // ...and a small one that matches it loosely
+    let small = vec![eywa::DocumentInput {
+        content: "Team notes: we agreed to keep memory management simple in the new service and to revisit it once load testing is done.".to_string(),
+        title: Some("Team notes".to_string()),
+        file_path: None,
+        is_pdf: false,
+    }];
+    pipeline.ingest_documents(&mut db, data_path, "notes", small).await.expect("Failed to ingest");
+
+    let query = embedder.embed_query("rust ownership and the borrow checker").expect("Failed to embed");
+
+    let plain = db.search_with_source_quota(&query, 5, None, false, 0).await.expect("Search failed");
+    assert!(plain.iter().all(|c| c.source_id == "rust-book"), "large source should fill the plain pool");
+
+    // The plain top 5 with the small source's best chunk added, best first
+    let fair = db.search_with_source_quota(&query, 5, None, false, 1).await.expect("Search failed");
+    let notes: Vec<&str> = fair.iter().filter(|c| c.source_id == "notes").map(|c| c.id.as_str()).collect();
+    assert_eq!(notes.len(), 1, "small source should contribute exactly its quota");
+    let mut expected: Vec<&str> = plain.iter().map(|c| c.id.as_str()).collect();
+    expected.push(notes[0]);
+    assert_eq!(fair.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), expected);
+}
+
+#[tokio::test]
+async fn test_source_filter_returns_only_that_source() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let doc = |content: &str| eywa::DocumentInput {
+        content: content.to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    };
+    pipeline
+        .ingest_documents(&mut db, data_path, "backend", vec![doc("The API server retries failed database writes three times, waiting a little longer before each new attempt.")])
+        .await
+        .expect("Failed to ingest");
+    pipeline
+        .ingest_documents(&mut db, data_path, "frontend", vec![doc("The web client retries failed API requests with exponential backoff and shows a banner while it is offline.")])
+        .await
+        .expect("Failed to ingest");
+
+    let query = embedder.embed_query("how are failures retried").expect("Failed to embed");
+
+    let all = db.search_filtered(&query, 50, None).await.expect("Search failed");
+    assert!(all.iter().any(|c| c.source_id == "backend"));
+    assert!(all.iter().any(|c| c.source_id == "frontend"));
+
+    let filtered = db.search_filtered(&query, 50, Some("frontend")).await.expect("Search failed");
+    assert!(!filtered.is_empty());
+    assert!(filtered.iter().all(|c| c.source_id == "frontend"));
+
+    let missing = db.search_filtered(&query, 50, Some("nope")).await.expect("Search failed");
+    assert!(missing.is_empty());
+}
+
+#[tokio::test]
+async fn test_index_model_is_recorded_and_mismatch_warned() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    assert_eq!(content.index_model().unwrap(), None);
+
+    let docs = vec![eywa::DocumentInput {
+        content: "Indexes remember which model built them, so searches can warn when the query model no longer matches the vectors.".to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    }];
+    pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+
+    let index_model = content.index_model().unwrap();
+    assert_eq!(index_model.as_deref(), Some(embedder.model_id()));
+
+    // Same model: no warning; different query model: warn and point at eywa reindex
+    assert!(model_mismatch_warning(index_model.as_deref(), embedder.model_id()).is_none());
+    let warning = model_mismatch_warning(index_model.as_deref(), "bge-base-en-v1.5").expect("Expected a warning");
+    assert!(warning.contains(embedder.model_id()) && warning.contains("eywa reindex"));
+    assert!(model_mismatch_warning(None, "bge-base-en-v1.5").is_none());
+}
+
+#[tokio::test]
+async fn test_line_ranges_only_for_files() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+
+    let docs = vec![
+        eywa::DocumentInput {
+            content: "# Deploys\n\nDeploys run from the release branch after the full test suite passes on CI.\nRollbacks redeploy the previous tag and page the on-call engineer.\n".to_string(),
+            title: None,
+            file_path: Some("docs/deploys.md".to_string()),
+            is_pdf: false,
+        },
+        eywa::DocumentInput {
+            content: "Deploy guide fetched from the wiki. Deploys need two approvals before release and are frozen during the holidays.".to_string(),
+            title: Some("Wiki".to_string()),
+            file_path: Some("https://wiki.example.com/deploys".to_string()),
+            is_pdf: false,
+        },
+    ];
+    pipeline.ingest_documents(&mut db, data_path, "docs", docs).await.expect("Failed to ingest");
+
+    let query = embedder.embed_query("how do deploys work").expect("Failed to embed");
+    let results = db.search(&query, 10).await.expect("Search failed");
+
+    let file = results.iter().find(|c| c.file_path.as_deref() == Some("docs/deploys.md")).unwrap();
+    assert!(file.line_start.is_some());
+    assert!(file.line_end >= file.line_start);
+
+    let web = results.iter().find(|c| c.file_path.as_deref().is_some_and(|p| p.starts_with("https://"))).unwrap();
+    assert_eq!((web.line_start, web.line_end), (None, None));
+}
+
+#[tokio::test]
+async fn test_source_names_resolve_case_insensitively() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index))
+        .with_source_name_case(SourceNameCase::Insensitive);
+
+    let doc = |content: &str| eywa::DocumentInput {
+        content: content.to_string(),
+        title: None,
+        file_path: None,
+        is_pdf: false,
+    };
+    pipeline
+        .ingest_documents(&mut db, data_path, "Docs", vec![doc("First document, ingested as Docs. It describes how the team writes and reviews design documents before starting work.")])
+        .await
+        .expect("Failed to ingest");
+    pipeline
+        .ingest_documents(&mut db, data_path, "docs", vec![doc("Second document, ingested as docs. It lists the templates to use for meeting notes, incident reports and retrospectives.")])
+        .await
+        .expect("Failed to ingest");
+
+    // Both land in the source under the name it was first created with
+    let sources = db.list_sources().await.expect("Failed to list sources");
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].name, "Docs");
+    assert_eq!(sources[0].doc_count, 2);
+}
+
+#[tokio::test]
+async fn test_split_sections_creates_document_per_heading() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index)).with_split_sections(Some(1));
+
+    let file = data_path.join("handbook.md");
+    std::fs::write(
+        &file,
+        "# Onboarding\n\nNew hires get a laptop, a desk and accounts for email, chat and the code host on their first day.\n\n\
+         ## Accounts\n\nRequest access to anything else through the IT portal; approvals usually take a day.\n\n\
+         # Releases\n\nReleases ship every Tuesday after the code freeze on Monday evening, once the staging checks pass.\n\n\
+         # On-call\n\nThe on-call rotation changes weekly on Monday mornings and the handover happens in the team channel.\n",
+    )
+    .unwrap();
+
+    let result = pipeline
+        .ingest_files(&mut db, data_path, "handbook", &[file])
+        .await
+        .expect("Failed to ingest");
+    assert_eq!(result.documents_created, 3);
+
+    let docs = db.list_documents("handbook", None, None).await.expect("Failed to list documents");
+    let mut titles: Vec<&str> = docs.iter().map(|d| d.title.as_str()).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["On-call", "Onboarding", "Releases"]);
+}
+
+#[cfg(not(feature = "cuda"))]
+#[test]
+fn test_config_check_rejects_unavailable_device() {
+    let home = tempdir().unwrap();
+    let config_path = home.path().join("candidate.toml");
+    let config = eywa::Config {
+        device: DevicePreference::Cuda,
+        ..Default::default()
+    };
+    std::fs::write(&config_path, toml::to_string(&config).unwrap()).unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_eywa"))
+        .env("HOME", home.path())
+        .arg("--data-dir")
+        .arg(home.path().join("data"))
+        .args(["config", "--check"])
+        .arg(&config_path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("cuda unavailable"), "{}", stdout);
+    // Checking never writes the config
+    assert!(!home.path().join(".eywa/config.toml").exists());
+}
+
+#[tokio::test]
+async fn test_update_replaces_chunks_from_same_file() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index)).with_update(true);
+
+    let file = data_path.join("runbook.txt");
+    std::fs::write(
+        &file,
+        "Runbook: the Marigold cluster is restarted by draining one node at a time and waiting for replication to catch up.",
+    )
+    .unwrap();
+    let first = pipeline.ingest_files(&mut db, data_path, "ops", &[file.clone()]).await.expect("Failed to ingest");
+    assert_eq!(first.chunks_created, 1);
+
+    std::fs::write(
+        &file,
+        "Runbook: the cluster is restarted with a rolling deploy; the orchestrator drains nodes and waits for health checks.",
+    )
+    .unwrap();
+    let second = pipeline.ingest_files(&mut db, data_path, "ops", &[file.clone()]).await.expect("Failed to ingest");
+    assert_eq!(second.chunks_created, 1);
+
+    // Only the new version is left in every store
+    assert_eq!(db.row_counts().await.unwrap(), (1, 1));
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    assert_eq!(content.count_documents().unwrap(), 1);
+    assert!(content.get_document(&first.document_ids[0]).unwrap().is_none());
+    assert!(bm25_index.search("Marigold", 5).unwrap().is_empty());
+    assert!(!bm25_index.search("orchestrator", 5).unwrap().is_empty());
+
+    // Without --update the same file is added again
+    let append = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index));
+    std::fs::write(&file, "Runbook: restarts are now fully automated by the orchestrator and need no manual steps from whoever is on call this week.").unwrap();
+    append.ingest_files(&mut db, data_path, "ops", &[file]).await.expect("Failed to ingest");
+    assert_eq!(db.row_counts().await.unwrap(), (2, 2));
+}
+
+#[tokio::test]
+async fn test_unchanged_files_are_skipped_unless_forced() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let docs_dir = data_path.join("docs");
+    std::fs::create_dir(&docs_dir).unwrap();
+    let install = docs_dir.join("install.md");
+    let usage = docs_dir.join("usage.md");
+    std::fs::write(&install, "# Install\n\nDownload the release archive for your platform, unpack it, and put the binary somewhere on your PATH.\n").unwrap();
+    std::fs::write(&usage, "# Usage\n\nRun the tool from the project root; it reads its settings from the config file in your home directory.\n").unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index)).with_update(true);
+    let files = vec![install.clone(), usage.clone()];
+
+    let first = pipeline.ingest_files(&mut db, data_path, "docs", &files).await.expect("Failed to ingest");
+    assert_eq!((first.documents_created, first.documents_skipped), (2, 0));
+
+    // Nothing changed: nothing is read into the pipeline
+    let again = pipeline.ingest_files(&mut db, data_path, "docs", &files).await.expect("Failed to ingest");
+    assert_eq!((again.documents_created, again.documents_skipped), (0, 2));
+    assert_eq!(again.chunks_created + again.chunks_skipped, 0);
+
+    // Only the edited file is re-ingested
+    std::fs::write(&usage, "# Usage\n\nRun the tool from any directory; it looks for a config file next to the project before falling back to home.\n").unwrap();
+    let edited = pipeline.ingest_files(&mut db, data_path, "docs", &files).await.expect("Failed to ingest");
+    assert_eq!((edited.documents_created, edited.documents_skipped), (1, 1));
+
+    // Forced ingest bypasses the hash check
+    let forced = pipeline
+        .with_force(true)
+        .ingest_files(&mut db, data_path, "docs", &files)
+        .await
+        .expect("Failed to ingest");
+    assert_eq!((forced.documents_created, forced.documents_skipped), (2, 0));
+
+    let content = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    assert_eq!(content.count_documents().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_ingest_manifest_records_status_per_file() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let docs_dir = data_path.join("docs");
+    std::fs::create_dir(&docs_dir).unwrap();
+    let install = docs_dir.join("install.md");
+    let usage = docs_dir.join("usage.md");
+    let faq = docs_dir.join("faq.md");
+    let empty = docs_dir.join("empty.md");
+    std::fs::write(&install, "# Install\n\nDownload the release archive for your platform, unpack it, and put the binary somewhere on your PATH.\n").unwrap();
+    std::fs::write(&usage, "# Usage\n\nRun the tool from the project root; it reads its settings from the config file in your home directory.\n").unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(Arc::clone(&embedder), Arc::clone(&bm25_index)).with_update(true);
+    pipeline
+        .ingest_files(&mut db, data_path, "docs", &[install.clone(), usage.clone()])
+        .await
+        .expect("Failed to ingest");
+
+    // install unchanged, usage edited, faq new, empty has nothing to ingest
+    std::fs::write(&usage, "# Usage\n\nRun the tool from any directory; it looks for a config file next to the project before falling back to home.\n").unwrap();
+    std::fs::write(&faq, "# FAQ\n\nQ: Where is data stored? A: Everything lives under the data directory in your home folder, including the indexes.\n").unwrap();
+    std::fs::write(&empty, "   \n").unwrap();
+    let files = vec![install.clone(), usage.clone(), faq.clone(), empty.clone()];
+    let (_, manifest) = pipeline
+        .ingest_files_with_manifest(&mut db, data_path, "docs", &files)
+        .await
+        .expect("Failed to ingest");
+
+    let path = data_path.join("manifest.json");
+    manifest.write(&path).unwrap();
+    let manifest: IngestManifest = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+    let status_of = |file: &std::path::Path| {
+        let entries: Vec<_> = manifest.entries.iter().filter(|e| e.file_path == file.to_string_lossy()).collect();
+        assert_eq!(entries.len(), 1, "{}", file.display());
+        entries[0].clone()
+    };
+    assert_eq!(manifest.entries.len(), files.len());
+    assert_eq!(status_of(&install).status, ManifestStatus::Skipped);
+    assert!(status_of(&install).document_id.is_some());
+    assert_eq!(status_of(&usage).status, ManifestStatus::Updated);
+    assert_eq!(status_of(&faq).status, ManifestStatus::New);
+    assert!(status_of(&faq).chunk_count > 0);
+    assert_eq!(status_of(&faq).title.as_deref(), Some("faq.md"));
+    assert_eq!(status_of(&empty).status, ManifestStatus::Skipped);
+    assert!(status_of(&empty).document_id.is_none());
+    assert!(manifest.entries.iter().all(|e| e.source_id == "docs"));
+}
+
+#[tokio::test]
+async fn test_ingest_skips_binary_files() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let docs_dir = data_path.join("docs");
+    std::fs::create_dir(&docs_dir).unwrap();
+    let png_header: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+    let logo = docs_dir.join("logo.png");
+    let disguised = docs_dir.join("data.json");
+    let notes = docs_dir.join("notes.md");
+    std::fs::write(&logo, png_header).unwrap();
+    std::fs::write(&disguised, png_header).unwrap();
+    std::fs::write(&notes, "# Notes\n\nCafé au lait, naïve résumé, 知识库: plain UTF-8 text that should be indexed like any other document.\n").unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+    let result = pipeline
+        .ingest_files(&mut db, data_path, "docs", &[logo, disguised, notes])
+        .await
+        .expect("Failed to ingest");
+
+    assert_eq!(result.files_skipped_binary, 2);
+    assert_eq!(result.documents_created, 1);
+}
+
+#[tokio::test]
+async fn test_latin1_file_is_ingested_lossily_with_warning() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let log = data_path.join("server.txt");
+    // "café" and "naïve" encoded as latin-1: 0xE9 and 0xEF aren't valid UTF-8 here
+    let mut bytes = b"2024-05-01 12:00:00 INFO request served for caf\xe9 client, na\xefve retry policy applied; upstream latency 42ms, status 200.\n".to_vec();
+    bytes.extend_from_slice(b"2024-05-01 12:00:01 INFO cache warmed for all regions.\n");
+    std::fs::write(&log, &bytes).unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+    let result = pipeline
+        .ingest_files(&mut db, data_path, "logs", &[log.clone()])
+        .await
+        .expect("Failed to ingest");
+
+    assert_eq!(result.documents_created, 1);
+    assert_eq!(result.files_lossy_utf8, vec![log.to_string_lossy().to_string()]);
+
+    let content_store = ContentStore::open(&data_path.join("content.db")).expect("Failed to open content store");
+    let content = content_store.get_document(&result.document_ids[0]).unwrap().expect("Content should be stored");
+    assert!(content.contains("caf\u{FFFD} client"), "{}", content);
+    assert!(content.contains("cache warmed"));
+}
+
+#[tokio::test]
+async fn test_failed_pdf_is_recorded_without_aborting_ingest() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let broken = data_path.join("broken.pdf");
+    let notes = data_path.join("notes.md");
+    std::fs::write(&broken, b"%PDF-1.7\nthis is not really a PDF").unwrap();
+    std::fs::write(&notes, "# Notes\n\nThe deploy script reads its settings from the environment, then uploads the bundle to the release bucket.\n").unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+    let result = pipeline
+        .ingest_files(&mut db, data_path, "docs", &[broken.clone(), notes])
+        .await
+        .expect("A bad PDF shouldn't fail the whole ingest");
+
+    assert_eq!(result.documents_created, 1);
+    assert_eq!(result.files_failed.len(), 1);
+    assert_eq!(result.files_failed[0].file_path, broken.to_string_lossy());
+    assert!(!result.files_failed[0].error.is_empty());
+}
+
+#[tokio::test]
+async fn test_explain_ingest_reports_reason_per_file() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let docs = data_path.join("docs");
+    std::fs::create_dir_all(docs.join("build")).unwrap();
+    let text = "# Notes\n\nThe deploy script reads its settings from the environment, then uploads the bundle to the release bucket.\n";
+    std::fs::write(docs.join("kept.md"), text).unwrap();
+    std::fs::write(docs.join("stable.md"), text.replace("Notes", "Stable")).unwrap();
+    std::fs::write(docs.join("empty.md"), "  \n").unwrap();
+    std::fs::write(docs.join("data.json"), b"{\x00\x01\x02}").unwrap();
+    std::fs::write(docs.join("notes.draft.md"), text).unwrap();
+    std::fs::write(docs.join("photo.png"), b"\x89PNG").unwrap();
+    std::fs::write(docs.join("layout.xyz"), "grid 12\n").unwrap();
+    std::fs::write(docs.join(".secret.md"), text).unwrap();
+    std::fs::write(docs.join(".eywaignore"), "build/\n").unwrap();
+    std::fs::write(docs.join("build").join("out.md"), text).unwrap();
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let pipeline = IngestPipeline::new(embedder, bm25_index);
+    pipeline
+        .ingest_files(&mut db, data_path, "docs", &[docs.join("stable.md")])
+        .await
+        .expect("Failed to ingest");
+
+    let options = WalkOptions { exclude: vec!["*.draft.md".to_string()], explain: true, ..Default::default() };
+    let mut walk = FileWalk::new(&docs, &options).unwrap();
+    let (_, manifest) = pipeline
+        .ingest_file_stream(&mut db, data_path, "docs", &mut walk)
+        .await
+        .expect("Failed to ingest");
+    let decisions = explain_decisions(&walk.into_result(), &manifest);
+
+    let decision_for = |name: &str| {
+        let found: Vec<_> = decisions.iter().filter(|d| d.path.ends_with(name)).collect();
+        assert_eq!(found.len(), 1, "{}: {:?}", name, decisions);
+        found[0].decision.clone()
+    };
+    assert_eq!(decision_for("kept.md"), Decision::Ingested(ManifestStatus::New));
+    assert_eq!(decision_for("stable.md"), Decision::Skipped(SkipReason::Unchanged));
+    assert_eq!(decision_for("empty.md"), Decision::Skipped(SkipReason::Empty));
+    assert_eq!(decision_for("data.json"), Decision::Skipped(SkipReason::Binary));
+    assert_eq!(decision_for("notes.draft.md"), Decision::Skipped(SkipReason::Excluded("*.draft.md".to_string())));
+    assert_eq!(decision_for("photo.png"), Decision::Skipped(SkipReason::Binary));
+    assert_eq!(decision_for("layout.xyz"), Decision::Skipped(SkipReason::UnsupportedExtension));
+    assert_eq!(decision_for(".secret.md"), Decision::Skipped(SkipReason::Hidden));
+    assert_eq!(decision_for("build"), Decision::Skipped(SkipReason::Ignored));
+
+    let line = decisions.iter().find(|d| d.path.ends_with("stable.md")).unwrap().to_string();
+    assert!(line.contains("unchanged"), "{}", line);
+}
+
+#[tokio::test]
+async fn test_streaming_ingest_stays_within_memory_budget() {
+    let dir = tempdir().expect("Failed to create temp dir");
+    let data_path = dir.path();
+    let notes_dir = data_path.join("notes");
+    std::fs::create_dir(&notes_dir).unwrap();
+
+    let mut total_bytes = 0;
+    for i in 0..20 {
+        let content: String = (0..300)
+            .map(|line| format!("Note {} line {}: field observations about tides, weather and the shorebirds seen that day.\n", i, line))
+            .collect();
+        total_bytes += content.len();
+        std::fs::write(notes_dir.join(format!("note-{:02}.txt", i)), content).unwrap();
+    }
+
+    let embedder = Arc::new(Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false).expect("Failed to create embedder"));
+    let bm25_index = Arc::new(BM25Index::open(data_path).expect("Failed to create BM25 index"));
+    let mut db = VectorDB::new(data_path.to_str().unwrap()).await.expect("Failed to create db");
+    let config = eywa::BatchConfig {
+        max_memory_mb: 1,
+        ..eywa::BatchConfig::default()
+    };
+    let budget = config.max_memory_mb * 1024 * 1024;
+    // Large chunks keep the embedding work small
+    let sizes = eywa::chunking::ChunkSizes { target: 3000, overlap: 0, min: 100 };
+    let pipeline = IngestPipeline::with_config(Arc::clone(&embedder), Arc::clone(&bm25_index), config)
+        .with_chunker(eywa::chunking::ChunkerRegistry::with_settings(eywa::OverlapStrategy::None, sizes));
+
+    // Documents plus their chunks don't fit in one batch
+    assert!(2 * total_bytes > budget);
+
+    let walk = eywa::FileWalk::new(&notes_dir, &eywa::WalkOptions::default()).unwrap();
+    let (response, manifest) = pipeline
+        .ingest_file_stream(&mut db, data_path, "notes", walk)
+        .await
+        .expect("Failed to ingest");
+
+    assert_eq!(response.documents_created, 20);
+    assert_eq!(manifest.count(ManifestStatus::New), 20);
+    assert!(pipeline.peak_batch_memory() > 0);
+    assert!(pipeline.peak_batch_memory() <= budget, "peak {} over budget {}", pipeline.peak_batch_memory(), budget);
+    assert_eq!(db.row_counts().await.unwrap(), (20, response.chunks_created as usize));
+}
+
+#[test]
+fn test_token_limit_with_real_tokenizer() {
+    use eywa::chunking::{ChunkSizes, ChunkerRegistry, DocMetadata, TokenCounter};
+
+    let embedder = Embedder::new_with_model(&EmbeddingModelConfig::default(), &DevicePreference::Cpu, false)
+        .expect("Failed to create embedder");
+    // 512 positions less [CLS] and [SEP], then less the passage prefix
+    assert_eq!(embedder.max_passage_tokens(), 510);
+    let embedder = Arc::new(embedder.with_passage_prefix("Represent this passage for retrieval: "));
+    let prefix_tokens = embedder.count_tokens("Represent this passage for retrieval: ");
+    assert_eq!(embedder.max_passage_tokens(), 510 - prefix_tokens);
+
+    // Each text fits one character-sized chunk, so all splitting is by tokens
+    let max_tokens = 40;
+    let sizes = ChunkSizes { target: 4000, overlap: 0, min: 1 };
+    let chunker = ChunkerRegistry::with_settings(eywa::OverlapStrategy::default(), sizes)
+        .with_token_limit(Arc::clone(&embedder) as Arc<dyn TokenCounter>, max_tokens);
+    let metadata = DocMetadata {
+        document_id: "doc".to_string(),
+        source_id: "notes".to_string(),
+        file_path: Some("notes.txt".to_string()),
+    };
+
+    let ascii: String = (1..=30).map(|i| format!("Line {} says the quick brown fox jumps over the lazy dog.\n", i)).collect();
+    let cjk = "知识库把所有文档连接在一起并保存集体记忆。".repeat(40);
+    for text in [ascii.as_str(), cjk.as_str()] {
+        let chunks = chunker.chunk(text, Some("notes.txt"), &metadata);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(embedder.count_tokens(&chunk.content) <= max_tokens, "{:?}", chunk.content);
+        }
+    }
+
+    // Split pieces keep the line numbers of the text they came from
+    let lines: Vec<&str> = ascii.lines().collect();
+    for chunk in chunker.chunk(&ascii, Some("notes.txt"), &metadata) {
+        let (start, end) = (chunk.metadata.line_start as usize, chunk.metadata.line_end as usize);
+        let content = chunk.content.trim_end_matches('\n');
+        let first = content.lines().next().unwrap();
+        let last = content.lines().last().unwrap();
+        assert!(lines[start - 1].contains(first.trim()), "line {} vs {:?}", start, first);
+        assert!(lines[end - 1].contains(last.trim()), "line {} vs {:?}", end, last);
+    }
+}